@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use holochain_p2p::kitsune_p2p::KitsuneP2pConfig;
+use holochain_p2p::kitsune_p2p::LanDiscoveryConfig;
 use holochain_p2p::kitsune_p2p::TransportConfig;
 use holochain_types::prelude::InstalledAppId;
 use structopt::StructOpt;
@@ -60,6 +61,11 @@ pub struct Network {
     /// A bootstrap service can used for peers to discover each other without
     /// prior knowledge of each other.
     pub bootstrap: Option<Url2>,
+    #[structopt(long)]
+    /// Find peers on the local network.
+    /// Agents are announced to, and discovered from,
+    /// other conductors on the same network over udp multicast.
+    pub lan_discovery: bool,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -172,9 +178,13 @@ impl From<Network> for KitsuneP2pConfig {
         let Network {
             transport,
             bootstrap,
+            lan_discovery,
         } = n;
         let mut kit = KitsuneP2pConfig::default();
         kit.bootstrap_service = bootstrap;
+        if lan_discovery {
+            kit.lan_discovery = Some(LanDiscoveryConfig::default());
+        }
 
         match transport {
             NetworkType::Mem => (),
@@ -12,10 +12,12 @@ pub mod get_agent_activity;
 pub mod get_details;
 pub mod get_link_details;
 pub mod get_links;
+pub mod get_many;
 pub mod property;
 pub mod query;
 pub mod random_bytes;
 pub mod remote_signal;
+pub mod remote_signal_acknowledged;
 pub mod schedule;
 pub mod show_env;
 pub mod sign;
@@ -0,0 +1,24 @@
+use crate::prelude::*;
+
+/// Gets the elements for many entry or header hashes in one batch.
+///
+/// Each hash behaves exactly as it would passed to `get`, and the results are in the
+/// same order as the hashes, with `None` for any hash that has no live element.
+///
+/// Where `get` makes a network call per hash, `get_many` groups the hashes by the
+/// authorities holding them and asks each authority for all of its hashes at once,
+/// so e.g. rendering a feed of posts costs a few round trips rather than one per post.
+///
+/// Note: batching only applies to the default aggregation of the get options,
+///       with a quorum or deadline every hash is still fetched on its own.
+///
+/// @see get
+pub fn get_many<H>(hashes: Vec<H>, options: GetOptions) -> ExternResult<Vec<Option<Element>>>
+where
+    AnyDhtHash: From<H>,
+{
+    host_call::<GetManyInput, Vec<Option<Element>>>(
+        __get_many,
+        GetManyInput::new(hashes.into_iter().map(AnyDhtHash::from).collect(), options),
+    )
+}
@@ -13,6 +13,8 @@ use crate::prelude::*;
 /// as a send and forget operation.
 /// A log will be produced at `[remote_signal]=info` if the calls
 /// fail though (this may be removed in the future).
+/// Use [remote_signal_acknowledged] to wait and find out which
+/// agents got the signal.
 ///
 /// ### Usage
 /// Currently this requires the function `recv_remote_signal` be
@@ -39,6 +41,7 @@ where
         RemoteSignal {
             signal: ExternIO::encode(input)?,
             agents,
+            retry: None,
         },
     )
 }
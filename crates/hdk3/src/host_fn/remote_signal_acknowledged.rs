@@ -0,0 +1,35 @@
+//! Emit an app-defined Signal remotely and wait to hear how each delivery went.
+
+use crate::prelude::*;
+
+/// ## Remote Signal Acknowledged
+/// Send a signal to a list of other agents, the same as [remote_signal],
+/// but block until every agent has either handled the signal or could
+/// not be reached.
+///
+/// Returns a [RemoteSignalDelivery] for each agent, in the order given:
+/// - `Delivered` if the agent's `recv_remote_signal` handled the signal.
+/// - `Unreachable` if the agent could not be reached, even after retrying.
+/// - `Rejected` if the agent was reached but refused or failed to handle it.
+///
+/// Unreachable agents are retried with a doubling backoff according to
+/// `retry`, or tried once if it is `None`.
+///
+/// The same `recv_remote_signal` requirements as [remote_signal] apply.
+pub fn remote_signal_acknowledged<I>(
+    input: I,
+    agents: Vec<AgentPubKey>,
+    retry: Option<RemoteSignalRetry>,
+) -> ExternResult<RemoteSignalDeliveries>
+where
+    I: serde::Serialize + std::fmt::Debug,
+{
+    host_call::<RemoteSignal, RemoteSignalDeliveries>(
+        __remote_signal_acknowledged,
+        RemoteSignal {
+            signal: ExternIO::encode(input)?,
+            agents,
+            retry,
+        },
+    )
+}
@@ -29,10 +29,12 @@ pub use crate::host_fn::get_agent_activity::get_agent_activity;
 pub use crate::host_fn::get_details::get_details;
 pub use crate::host_fn::get_link_details::get_link_details;
 pub use crate::host_fn::get_links::get_links;
+pub use crate::host_fn::get_many::get_many;
 pub use crate::host_fn::query::query;
 pub use crate::host_fn::random_bytes::random_bytes;
 pub use crate::host_fn::random_bytes::*;
 pub use crate::host_fn::remote_signal::remote_signal;
+pub use crate::host_fn::remote_signal_acknowledged::remote_signal_acknowledged;
 pub use crate::host_fn::sign::sign;
 pub use crate::host_fn::sign::sign_raw;
 pub use crate::host_fn::sys_time::sys_time;
@@ -91,6 +93,7 @@ macro_rules! holochain_externs {
             __get_details,
             __get_links,
             __get_link_details,
+            __get_many,
             __get_agent_activity,
             __query,
             __call_remote,
@@ -98,6 +101,7 @@ macro_rules! holochain_externs {
             __create,
             __emit_signal,
             __remote_signal,
+            __remote_signal_acknowledged,
             __create_link,
             __delete_link,
             __update,
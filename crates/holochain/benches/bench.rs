@@ -82,6 +82,7 @@ pub fn wasm_call_n(c: &mut Criterion) {
                         fn_name: "echo_bytes".into(),
                         payload: ExternIO::encode(&bytes).unwrap(),
                         provenance: AGENT_KEY.lock().unwrap().clone(),
+                        read_only: false,
                     };
                     REAL_RIBOSOME
                         .lock()
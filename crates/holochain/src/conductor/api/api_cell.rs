@@ -4,8 +4,10 @@ use std::sync::Arc;
 
 use super::error::ConductorApiError;
 use super::error::ConductorApiResult;
+use crate::conductor::config::WasmLimits;
 use crate::conductor::entry_def_store::EntryDefBufferKey;
 use crate::conductor::interface::SignalBroadcaster;
+use crate::conductor::module_cache::ModuleCache;
 use crate::conductor::ConductorHandle;
 use crate::core::workflow::call_zome_workflow::call_zome_workspace_lock::CallZomeWorkspaceLock;
 use crate::core::workflow::ZomeCallResult;
@@ -107,6 +109,14 @@ impl CellConductorApiT for CellConductorApi {
         self.conductor_handle.get_entry_def(key).await
     }
 
+    fn wasm_limits(&self, dna_hash: &DnaHash) -> WasmLimits {
+        self.conductor_handle.wasm_limits(dna_hash)
+    }
+
+    fn module_cache(&self) -> Option<Arc<ModuleCache>> {
+        Some(self.conductor_handle.module_cache())
+    }
+
     fn into_call_zome_handle(self) -> CellConductorReadHandle {
         Arc::new(self)
     }
@@ -153,6 +163,12 @@ pub trait CellConductorApiT: Clone + Send + Sync + Sized {
     /// Get a [EntryDef] from the [EntryDefBuf]
     async fn get_entry_def(&self, key: &EntryDefBufferKey) -> Option<EntryDef>;
 
+    /// Get the wasm limits the conductor applies to calls into this Dna
+    fn wasm_limits(&self, dna_hash: &DnaHash) -> WasmLimits;
+
+    /// Get the conductor's compiled wasm module cache, if it has one
+    fn module_cache(&self) -> Option<Arc<ModuleCache>>;
+
     /// Turn this into a call zome handle
     fn into_call_zome_handle(self) -> CellConductorReadHandle;
 }
@@ -247,6 +247,28 @@ impl AdminInterfaceApi for RealAdminInterfaceApi {
                 let r = self.conductor_handle.get_agent_infos(cell_id).await?;
                 Ok(AdminResponse::AgentInfoRequested(r))
             }
+            ListZomeExports { dna_hash } => {
+                let exports = self.conductor_handle.list_zome_exports(&dna_hash).await?;
+                Ok(AdminResponse::ZomeExportsListed(exports))
+            }
+            DumpNetworkStats { dna_hash } => {
+                let stats = self.conductor_handle.network_stats(dna_hash).await?;
+                Ok(AdminResponse::NetworkStatsDumped(stats))
+            }
+            BlockPeer {
+                dna_hash,
+                agent,
+                duration_ms,
+            } => {
+                self.conductor_handle
+                    .block_peer(dna_hash, agent, duration_ms)
+                    .await?;
+                Ok(AdminResponse::PeerBlocked)
+            }
+            UnblockPeer { dna_hash, agent } => {
+                self.conductor_handle.unblock_peer(dna_hash, agent).await?;
+                Ok(AdminResponse::PeerUnblocked)
+            }
         }
     }
 }
@@ -298,7 +320,9 @@ mod test {
     use anyhow::Result;
     use holochain_lmdb::test_utils::test_environments;
     use holochain_types::app::InstallAppDnaPayload;
+    use holochain_types::app::InstalledCell;
     use holochain_types::test_utils::fake_agent_pubkey_1;
+    use holochain_types::test_utils::fake_agent_pubkey_2;
     use holochain_types::test_utils::fake_dna_file;
     use holochain_types::test_utils::fake_dna_zomes;
     use holochain_types::test_utils::write_fake_dna_file;
@@ -421,6 +445,118 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn list_zome_exports() -> Result<()> {
+        observability::test_run().ok();
+        let envs = test_environments();
+        let handle = Conductor::builder().test(&envs).await?;
+        let shutdown = handle.take_shutdown_handle().await.unwrap();
+        let admin_api = RealAdminInterfaceApi::new(handle.clone());
+        let dna = fake_dna_zomes(
+            &Uuid::new_v4().to_string(),
+            vec![(TestWasm::Foo.into(), TestWasm::Foo.into())],
+        );
+        let dna_hash = dna.dna_hash().clone();
+        handle.install_dna(dna).await?;
+
+        let response = admin_api
+            .handle_admin_request(AdminRequest::ListZomeExports {
+                dna_hash: dna_hash.clone(),
+            })
+            .await;
+        let expected = vec![ZomeExports {
+            zome_name: TestWasm::Foo.into(),
+            zome_fns: vec!["foo".into()],
+            callbacks: vec!["init".into()],
+            entry_defs: vec![],
+        }];
+        assert_matches!(response, AdminResponse::ZomeExportsListed(e) if e == expected);
+
+        // an unknown dna is an error
+        let response = admin_api
+            .handle_admin_request(AdminRequest::ListZomeExports {
+                dna_hash: fake_dna_file("other").dna_hash().clone(),
+            })
+            .await;
+        assert_matches!(response, AdminResponse::Error(_));
+
+        handle.shutdown().await;
+        tokio::time::timeout(std::time::Duration::from_secs(1), shutdown)
+            .await
+            .ok();
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn dump_network_stats() -> Result<()> {
+        observability::test_run().ok();
+        let envs = test_environments();
+        let handle = Conductor::builder().test(&envs).await?;
+        let shutdown = handle.take_shutdown_handle().await.unwrap();
+        let admin_api = RealAdminInterfaceApi::new(handle.clone());
+        let dna = fake_dna_zomes(
+            &Uuid::new_v4().to_string(),
+            vec![(TestWasm::Foo.into(), TestWasm::Foo.into())],
+        );
+        let dna_hash = dna.dna_hash().clone();
+        let agent = fake_agent_pubkey_1();
+        let cell_id = CellId::new(dna_hash.clone(), agent.clone());
+        crate::test_utils::install_app(
+            "test_app",
+            vec![(InstalledCell::new(cell_id, "nick".into()), None)],
+            vec![dna],
+            handle.clone(),
+        )
+        .await;
+
+        let response = admin_api
+            .handle_admin_request(AdminRequest::DumpNetworkStats { dna_hash: None })
+            .await;
+        let stats = unwrap_to::unwrap_to!(response => AdminResponse::NetworkStatsDumped).clone();
+        assert_eq!(1, stats.len());
+        assert_eq!(dna_hash, stats[0].dna_hash);
+        assert_eq!(vec![agent], stats[0].local_agents);
+        assert!(stats[0].peers.is_empty());
+
+        // a dna that isn't running is an error
+        let response = admin_api
+            .handle_admin_request(AdminRequest::DumpNetworkStats {
+                dna_hash: Some(fake_dna_file("other").dna_hash().clone()),
+            })
+            .await;
+        assert_matches!(response, AdminResponse::Error(_));
+
+        let response = admin_api
+            .handle_admin_request(AdminRequest::BlockPeer {
+                dna_hash: dna_hash.clone(),
+                agent: fake_agent_pubkey_2(),
+                duration_ms: None,
+            })
+            .await;
+        assert_matches!(response, AdminResponse::PeerBlocked);
+        let response = admin_api
+            .handle_admin_request(AdminRequest::UnblockPeer {
+                dna_hash,
+                agent: fake_agent_pubkey_2(),
+            })
+            .await;
+        assert_matches!(response, AdminResponse::PeerUnblocked);
+        let response = admin_api
+            .handle_admin_request(AdminRequest::BlockPeer {
+                dna_hash: fake_dna_file("other").dna_hash().clone(),
+                agent: fake_agent_pubkey_2(),
+                duration_ms: None,
+            })
+            .await;
+        assert_matches!(response, AdminResponse::Error(_));
+
+        handle.shutdown().await;
+        tokio::time::timeout(std::time::Duration::from_secs(1), shutdown)
+            .await
+            .ok();
+        Ok(())
+    }
+
     #[tokio::test(threaded_scheduler)]
     async fn install_list_dna_app() -> Result<()> {
         observability::test_run().ok();
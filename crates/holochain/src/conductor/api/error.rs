@@ -84,6 +84,10 @@ pub enum ConductorApiError {
 
     #[error(transparent)]
     SourceChainError(#[from] SourceChainError),
+
+    /// Ribosome error
+    #[error(transparent)]
+    RibosomeError(#[from] RibosomeError),
 }
 
 /// All the serialization errors that can occur
@@ -107,6 +111,15 @@ impl From<ConductorApiError> for ExternalApiWireError {
     fn from(err: ConductorApiError) -> Self {
         match err {
             ConductorApiError::DnaReadError(e) => ExternalApiWireError::DnaReadError(e),
+            ConductorApiError::RibosomeError(e) => e.into(),
+            ConductorApiError::CellError(CellError::WorkflowError(e))
+                if matches!(
+                    *e,
+                    WorkflowError::SourceChainError(SourceChainError::HeadMoved(_, _))
+                ) =>
+            {
+                ExternalApiWireError::ChainHeadMoved(e.to_string())
+            }
             e => ExternalApiWireError::internal(e),
         }
     }
@@ -4,14 +4,17 @@
 use super::CellConductorApiT;
 use super::ZomeCall;
 use crate::conductor::api::error::ConductorApiResult;
+use crate::conductor::config::WasmLimits;
 use crate::conductor::entry_def_store::EntryDefBufferKey;
 use crate::conductor::interface::SignalBroadcaster;
+use crate::conductor::module_cache::ModuleCache;
 use crate::core::workflow::ZomeCallResult;
 use async_trait::async_trait;
 use holo_hash::DnaHash;
 use holochain_keystore::KeystoreSender;
 use holochain_types::prelude::*;
 use mockall::mock;
+use std::sync::Arc;
 
 // Unfortunate workaround to get mockall to work with async_trait, due to the complexity of each.
 // The mock! expansion here creates mocks on a non-async version of the API, and then the actual trait is implemented
@@ -38,6 +41,8 @@ mock! {
         fn sync_get_this_dna(&self) -> ConductorApiResult<DnaFile>;
         fn sync_get_zome(&self, dna_hash: &DnaHash, zome_name: &ZomeName) -> ConductorApiResult<Zome>;
         fn sync_get_entry_def(&self, key: &EntryDefBufferKey) -> Option<EntryDef>;
+        fn mock_wasm_limits(&self, dna_hash: &DnaHash) -> WasmLimits;
+        fn mock_module_cache(&self) -> Option<Arc<ModuleCache>>;
         fn into_call_zome_handle(self) -> super::CellConductorReadHandle;
     }
 
@@ -92,6 +97,14 @@ impl CellConductorApiT for MockCellConductorApi {
         self.sync_get_entry_def(key)
     }
 
+    fn wasm_limits(&self, dna_hash: &DnaHash) -> WasmLimits {
+        self.mock_wasm_limits(dna_hash)
+    }
+
+    fn module_cache(&self) -> Option<Arc<ModuleCache>> {
+        self.mock_module_cache()
+    }
+
     fn into_call_zome_handle(self) -> super::CellConductorReadHandle {
         self.into_call_zome_handle()
     }
@@ -13,6 +13,7 @@ use crate::conductor::api::CellConductorApiT;
 use crate::conductor::cell::error::CellResult;
 use crate::conductor::entry_def_store::get_entry_def_from_ids;
 use crate::conductor::handle::ConductorHandle;
+use crate::conductor::metrics::CellQueueSizes;
 use crate::core::queue_consumer::spawn_queue_consumer_tasks;
 use crate::core::queue_consumer::InitialQueueTriggers;
 use crate::core::queue_consumer::QueueTriggers;
@@ -42,6 +43,7 @@ use holochain_lmdb::db::GetDb;
 use holochain_lmdb::env::EnvironmentRead;
 use holochain_lmdb::env::EnvironmentWrite;
 use holochain_lmdb::env::ReadManager;
+use holochain_lmdb::fresh_reader;
 use holochain_p2p::HolochainP2pCellT;
 use holochain_serialized_bytes::SerializedBytes;
 use holochain_state::prelude::*;
@@ -374,6 +376,25 @@ impl Cell {
                 .instrument(debug_span!("cell_handle_fetch_op_hashes_for_constraints"))
                 .await;
             }
+            FetchOpHashTimesForConstraints {
+                span_context: _,
+                respond,
+                dht_arc,
+                since,
+                until,
+                ..
+            } => {
+                async {
+                    let res = self
+                        .handle_fetch_op_hash_times_for_constraints(dht_arc, since, until)
+                        .map_err(holochain_p2p::HolochainP2pError::other);
+                    respond.respond(Ok(async move { res }.boxed().into()));
+                }
+                .instrument(debug_span!(
+                    "cell_handle_fetch_op_hash_times_for_constraints"
+                ))
+                .await;
+            }
             FetchOpHashData {
                 span_context: _,
                 respond,
@@ -418,16 +439,28 @@ impl Cell {
         _dht_hash: holo_hash::AnyDhtHash,
         ops: Vec<(holo_hash::DhtOpHash, holochain_types::dht_op::DhtOp)>,
     ) -> CellResult<()> {
-        incoming_dht_ops_workflow(
+        let counterfeit = incoming_dht_ops_workflow(
             &self.env,
             self.queue_triggers.sys_validation.clone(),
             ops,
-            Some(from_agent),
+            Some(from_agent.clone()),
         )
         .await
         .map_err(Box::new)
         .map_err(ConductorApiError::from)
         .map_err(Box::new)?;
+        if counterfeit > 0 {
+            // an honest node never passes on forged ops,
+            // so they count against the sender's reputation
+            if let Err(e) = self
+                .holochain_p2p_cell
+                .clone()
+                .report_invalid_data(from_agent)
+                .await
+            {
+                tracing::warn!(msg = "Failed to report invalid data", ?e);
+            }
+        }
         Ok(())
     }
 
@@ -567,11 +600,44 @@ impl Cell {
         let integrated_dht_ops = IntegratedDhtOpsBuf::new(self.env().clone().into())?;
         let result: Vec<DhtOpHash> = integrated_dht_ops
             .query(&reader, Some(since), Some(until), Some(dht_arc))?
+            // We don't keep the data of abandoned ops to serve
+            .filter(|(_, v)| Ok(v.validation_status != ValidationStatus::Abandoned))
             .map(|(k, _)| Ok(k))
             .collect()?;
         Ok(result)
     }
 
+    #[instrument(skip(self, dht_arc, since, until))]
+    /// the network module is requesting a list of dht op hashes
+    /// with the time each op's header was authored
+    fn handle_fetch_op_hash_times_for_constraints(
+        &self,
+        dht_arc: holochain_p2p::dht_arc::DhtArc,
+        since: Timestamp,
+        until: Timestamp,
+    ) -> CellResult<Vec<(DhtOpHash, Timestamp)>> {
+        let env_ref = self.env.guard();
+        let reader = env_ref.reader()?;
+        let integrated_dht_ops = IntegratedDhtOpsBuf::new(self.env().clone().into())?;
+        let vault = ElementBuf::vault(self.env.clone().into(), false)?;
+        let rejected = ElementBuf::rejected(self.env.clone().into())?;
+        let result: Vec<(DhtOpHash, Timestamp)> = integrated_dht_ops
+            .query(&reader, None, None, Some(dht_arc))?
+            .filter_map(|(k, v)| {
+                let header_hash = v.op.header_hash();
+                let header = match v.validation_status {
+                    ValidationStatus::Rejected => {
+                        rejected.get_header_with_reader(&reader, header_hash)?
+                    }
+                    _ => vault.get_header_with_reader(&reader, header_hash)?,
+                };
+                Ok(header.map(|h| (k, h.header().timestamp())))
+            })
+            .filter(|(_, t)| Ok(*t >= since && *t < until))
+            .collect()?;
+        Ok(result)
+    }
+
     #[instrument(skip(self, op_hashes))]
     /// The network module is requesting the content for dht ops
     async fn handle_fetch_op_hash_data(
@@ -598,7 +664,8 @@ impl Cell {
                         let cas = ElementBuf::rejected(self.env.clone().into())?;
                         light_to_op(val.op, &cas)?
                     }
-                    ValidationStatus::Abandoned => todo!("Add when abandoned store is added"),
+                    // We don't keep the data of abandoned ops
+                    ValidationStatus::Abandoned => continue,
                 };
                 let basis = full_op.dht_basis();
                 out.push((basis, op_hash, full_op));
@@ -640,6 +707,7 @@ impl Cell {
             payload,
             provenance: from_agent,
             fn_name,
+            read_only: false,
         };
         // double ? because
         // - ConductorApiResult
@@ -718,7 +786,9 @@ impl Cell {
         let dna_def = dna_file.dna_def().clone();
 
         // Get the ribosome
-        let ribosome = RealRibosome::new(dna_file);
+        let ribosome = RealRibosome::new(dna_file)
+            .with_wasm_limits(conductor_api.wasm_limits(id.dna_hash()))
+            .with_module_cache(conductor_api.module_cache());
 
         // Run the workflow
         let args = InitializeZomesWorkflowArgs { dna_def, ribosome };
@@ -757,7 +827,9 @@ impl Cell {
     // TODO: reevaluate once Workflows are fully implemented (after B-01567)
     pub(crate) async fn get_ribosome(&self) -> CellResult<RealRibosome> {
         match self.conductor_api.get_dna(self.dna_hash()).await {
-            Some(dna) => Ok(RealRibosome::new(dna)),
+            Some(dna) => Ok(RealRibosome::new(dna)
+                .with_wasm_limits(self.conductor_api.wasm_limits(self.dna_hash()))
+                .with_module_cache(self.conductor_api.module_cache())),
             None => Err(CellError::DnaMissing),
         }
     }
@@ -768,6 +840,35 @@ impl Cell {
         &self.env
     }
 
+    /// Count the ops waiting in this Cell's validation and integration queues.
+    /// The queues are walked on a blocking thread so a long queue
+    /// doesn't hold up the executor.
+    pub(crate) async fn queue_sizes(&self) -> CellResult<CellQueueSizes> {
+        let env: EnvironmentRead = self.env.clone().into();
+        tokio::task::spawn_blocking(move || Self::count_queues(env)).await?
+    }
+
+    fn count_queues(env: EnvironmentRead) -> CellResult<CellQueueSizes> {
+        let validation_limbo = ValidationLimboStore::new(env.clone())?;
+        let integration_limbo: IntegrationLimboStore =
+            KvBufFresh::new(env.clone(), env.get_db(&*INTEGRATION_LIMBO)?);
+        let mut sizes = CellQueueSizes::default();
+        fresh_reader!(env, |r| {
+            let mut iter = validation_limbo.iter(&r)?;
+            while let Some((_, vlv)) = iter.next()? {
+                match vlv.status {
+                    ValidationLimboStatus::Pending => sizes.sys_validation_pending += 1,
+                    ValidationLimboStatus::AwaitingSysDeps(_) => sizes.awaiting_sys_deps += 1,
+                    ValidationLimboStatus::SysValidated => sizes.app_validation_pending += 1,
+                    ValidationLimboStatus::AwaitingAppDeps(_) => sizes.awaiting_app_deps += 1,
+                }
+            }
+            sizes.integration_limbo = integration_limbo.iter(&r)?.count()?;
+            DatabaseResult::Ok(())
+        })?;
+        Ok(sizes)
+    }
+
     #[cfg(any(test, feature = "test_utils"))]
     /// Get the triggers for the cell
     /// Useful for testing when you want to
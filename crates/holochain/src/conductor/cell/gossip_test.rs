@@ -82,10 +82,16 @@ async fn gossip_test() {
 async fn signature_smoke_test() {
     observability::test_run().ok();
     let mut network_config = KitsuneP2pConfig::default();
-    network_config.transport_pool = vec![kitsune_p2p::TransportConfig::Mem {}];
-    // Hit an actual bootstrap service so it can blow up and return an error if we get our end of
+    network_config.transport_pool = vec![kitsune_p2p::TransportConfig::Mem {
+        simulated_network: None,
+    }];
+    // Hit an actual bootstrap server so it can blow up and return an error if we get our end of
     // things totally wrong.
-    network_config.bootstrap_service = Some(url2::url2!("{}", kitsune_p2p::BOOTSTRAP_SERVICE_DEV));
+    let (bootstrap, addr) = kitsune_p2p_bootstrap::run(([127, 0, 0, 1], 0))
+        .await
+        .unwrap();
+    tokio::task::spawn(bootstrap);
+    network_config.bootstrap_service = Some(url2::url2!("http://{}", addr));
     let zomes = vec![TestWasm::Anchor];
     let mut conductor_test =
         ConductorTestData::with_network_config(zomes.clone(), false, network_config.clone()).await;
@@ -97,7 +103,9 @@ async fn signature_smoke_test() {
 async fn agent_info_test() {
     observability::test_run().ok();
     let mut network_config = KitsuneP2pConfig::default();
-    network_config.transport_pool = vec![kitsune_p2p::TransportConfig::Mem {}];
+    network_config.transport_pool = vec![kitsune_p2p::TransportConfig::Mem {
+        simulated_network: None,
+    }];
     let zomes = vec![TestWasm::Anchor];
     let mut conductor_test =
         ConductorTestData::with_network_config(zomes.clone(), false, network_config.clone()).await;
@@ -12,6 +12,8 @@ use super::api::RealAdminInterfaceApi;
 use super::api::RealAppInterfaceApi;
 use super::config::AdminInterfaceConfig;
 use super::config::InterfaceDriver;
+use super::config::MetricsExporterConfig;
+use super::config::WasmLimits;
 use super::dna_store::DnaDefBuf;
 use super::dna_store::DnaStore;
 use super::dna_store::RealDnaStore;
@@ -32,9 +34,13 @@ use super::manager::spawn_task_manager;
 use super::manager::ManagedTaskAdd;
 use super::manager::ManagedTaskHandle;
 use super::manager::TaskManagerRunHandle;
+use super::metrics::spawn_metrics_exporter_task;
+use super::module_cache::ModuleCache;
+use super::module_cache::MODULE_CACHE_DIRECTORY;
 use super::p2p_store::all_agent_infos;
 use super::p2p_store::get_single_agent_info;
 use super::p2p_store::inject_agent_infos;
+use super::p2p_store::prune_expired_agent_infos_task;
 use super::paths::EnvironmentRootPath;
 use super::state::AppInterfaceId;
 use super::state::ConductorState;
@@ -308,6 +314,18 @@ where
         Ok(())
     }
 
+    pub(super) async fn add_metrics_exporter_via_handle(
+        &mut self,
+        config: MetricsExporterConfig,
+        handle: ConductorHandle,
+    ) -> ConductorResult<u16> {
+        let stop_rx = self.managed_task_stop_broadcaster.subscribe();
+        let (port, task) =
+            spawn_metrics_exporter_task(config.socket_addr(), handle, stop_rx).await?;
+        self.manage_task(ManagedTaskAdd::dont_handle(task)).await?;
+        Ok(port)
+    }
+
     pub(super) async fn add_app_interface_via_handle(
         &mut self,
         port: u16,
@@ -703,13 +721,14 @@ where
     pub(super) async fn put_wasm(
         &self,
         dna: DnaFile,
+        wasm_limits: WasmLimits,
     ) -> ConductorResult<Vec<(EntryDefBufferKey, EntryDef)>> {
         let environ = self.wasm_env.clone();
         let wasm = environ.get_db(&*holochain_lmdb::db::WASM)?;
         let dna_def_db = environ.get_db(&*holochain_lmdb::db::DNA_DEF)?;
         let entry_def_db = environ.get_db(&*holochain_lmdb::db::ENTRY_DEF)?;
 
-        let zome_defs = get_entry_defs(dna.clone())?;
+        let zome_defs = get_entry_defs(dna.clone(), wasm_limits)?;
 
         let mut entry_def_buf = EntryDefBuf::new(environ.clone().into(), entry_def_db)?;
 
@@ -742,6 +761,10 @@ where
         Ok(zome_defs)
     }
 
+    pub(super) fn running_cells(&self) -> Vec<Arc<Cell>> {
+        self.cells.values().map(|item| item.cell.clone()).collect()
+    }
+
     pub(super) async fn list_cell_ids(&self) -> ConductorResult<Vec<CellId>> {
         Ok(self.cells.keys().cloned().collect())
     }
@@ -985,6 +1008,8 @@ mod builder {
                     cert,
                     cert_priv_key,
                     cert_digest,
+                    // applied by kitsune from `network_config.allowed_client_digests`
+                    allowed_client_digests: None,
                 };
             let (holochain_p2p, p2p_evt) =
                 holochain_p2p::spawn_holochain_p2p(network_config, tls_config).await?;
@@ -1014,17 +1039,26 @@ mod builder {
             // Get data before handle
             let keystore = conductor.keystore.clone();
             let holochain_p2p = conductor.holochain_p2p.clone();
+            let p2p_env = conductor.p2p_env();
+            let module_cache = Arc::new(ModuleCache::new(
+                std::path::PathBuf::from(conductor.root_env_dir.clone())
+                    .join(MODULE_CACHE_DIRECTORY),
+                conductor_config.module_cache_max_bytes,
+            )?);
 
             // Create handle
             let handle: ConductorHandle = Arc::new(ConductorHandleImpl {
                 conductor: RwLock::new(conductor),
                 keystore,
                 holochain_p2p,
+                wasm_limits: conductor_config.wasm_limits.clone(),
+                module_cache,
             });
 
             handle.add_dnas().await?;
 
             tokio::task::spawn(p2p_event_task(p2p_evt, handle.clone()));
+            tokio::task::spawn(prune_expired_agent_infos_task(p2p_env));
 
             let cell_startup_errors = handle.clone().setup_cells().await?;
 
@@ -1044,6 +1078,10 @@ mod builder {
             // Create app interfaces
             handle.clone().startup_app_interfaces().await?;
 
+            if let Some(config) = conductor_config.metrics_exporter {
+                handle.clone().add_metrics_exporter(config).await?;
+            }
+
             handle.print_setup().await;
 
             Ok(handle)
@@ -108,6 +108,81 @@ async fn can_set_fake_state() {
     assert_eq!(state, conductor.get_state_from_handle().await.unwrap());
 }
 
+#[tokio::test(threaded_scheduler)]
+async fn serves_metrics() {
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+
+    let envs = test_environments();
+    let conductor = ConductorBuilder::new().test(&envs).await.unwrap();
+    let port = conductor
+        .clone()
+        .add_metrics_exporter(MetricsExporterConfig {
+            port: 0,
+            bind_address: None,
+        })
+        .await
+        .unwrap();
+
+    let get = |path: &'static str| async move {
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+            .await
+            .unwrap();
+        let mut res = String::new();
+        stream.read_to_string(&mut res).await.unwrap();
+        res
+    };
+
+    let res = get("/metrics").await;
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.contains("application/openmetrics-text"));
+    assert!(res.contains("\nholochain_cells 0\n"));
+    assert!(res.contains("# TYPE holochain_zome_call_duration_seconds histogram\n"));
+    assert!(res.contains("# TYPE kitsune_transport_write_bytes counter\n"));
+    assert!(res.ends_with("# EOF\n"));
+
+    assert!(get("/nope").await.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+    // connections that never send a request hold up the exporter
+    // only until they time out
+    let mut stalled = Vec::new();
+    for _ in 0..8 {
+        stalled.push(
+            tokio::net::TcpStream::connect(("127.0.0.1", port))
+                .await
+                .unwrap(),
+        );
+    }
+    tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
+    let mut refused = tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .unwrap();
+    let _ = refused
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await;
+    let mut res = String::new();
+    let _ = refused.read_to_string(&mut res).await;
+    assert_eq!("", res);
+    for mut stream in stalled {
+        let mut res = String::new();
+        tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            stream.read_to_string(&mut res),
+        )
+        .await
+        .expect("stalled metrics connection was never closed")
+        .unwrap();
+        assert_eq!("", res);
+    }
+    assert!(get("/metrics").await.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    conductor.shutdown().await;
+}
+
 #[tokio::test(threaded_scheduler)]
 async fn proxy_tls_with_test_keystore() {
     use ghost_actor::GhostControlSender;
@@ -140,6 +215,7 @@ async fn proxy_tls_inner(
         cert,
         cert_priv_key,
         cert_digest,
+        allowed_client_digests: None,
     };
 
     let (cert_digest, cert, cert_priv_key) = keystore2.get_or_create_first_tls_cert().await?;
@@ -148,6 +224,7 @@ async fn proxy_tls_inner(
         cert,
         cert_priv_key,
         cert_digest,
+        allowed_client_digests: None,
     };
 
     let proxy_config =
@@ -7,6 +7,7 @@ use crate::core::ribosome::real_ribosome::RealRibosome;
 use crate::core::ribosome::RibosomeT;
 
 use super::api::CellConductorApiT;
+use super::config::WasmLimits;
 use error::EntryDefStoreError;
 use error::EntryDefStoreResult;
 use fallible_iterator::FallibleIterator;
@@ -143,7 +144,7 @@ pub(crate) async fn get_entry_def(
     // If it's not found run the ribosome and get the entry defs
     match &entry_def {
         Some(_) => Ok(entry_def),
-        None => Ok(get_entry_defs(dna_file)?
+        None => Ok(get_entry_defs(dna_file, conductor_api.wasm_limits(dna_hash))?
             .get(entry_def_index.index())
             .map(|(_, v)| v.clone())),
     }
@@ -164,9 +165,11 @@ pub(crate) async fn get_entry_def_from_ids(
 }
 
 #[tracing::instrument(skip(dna))]
-/// Get all the [EntryDef] for this dna
+/// Get all the [EntryDef] for this dna,
+/// running its `entry_defs` callbacks within the given limits
 pub(crate) fn get_entry_defs(
     dna: DnaFile, // TODO: make generic
+    wasm_limits: WasmLimits,
 ) -> EntryDefStoreResult<Vec<(EntryDefBufferKey, EntryDef)>> {
     let invocation = EntryDefsInvocation;
 
@@ -179,7 +182,7 @@ pub(crate) fn get_entry_defs(
         .map(|(zome_name, zome)| (zome_name, zome))
         .collect::<HashMap<_, _>>();
 
-    let ribosome = RealRibosome::new(dna);
+    let ribosome = RealRibosome::new(dna).with_wasm_limits(wasm_limits);
     match ribosome.run_entry_defs(EntryDefsHostAccess, invocation)? {
         EntryDefsResult::Defs(map) => {
             // Turn the defs map into a vec of keys and entry defs
@@ -41,24 +41,42 @@
 //! types for testing. If we did not have a way of hiding this type genericity,
 //! code which interacted with the Conductor would also have to be highly generic.
 
+use super::api::error::ConductorApiError;
 use super::api::error::ConductorApiResult;
 use super::api::ZomeCall;
+use super::api::ZomeExports;
 use super::config::AdminInterfaceConfig;
+use super::config::MetricsExporterConfig;
+use super::config::WasmLimits;
+use super::config::WasmLimitsConfig;
 use super::dna_store::DnaStore;
 use super::entry_def_store::EntryDefBufferKey;
+use super::error::ConductorError;
 use super::error::ConductorResult;
 use super::error::CreateAppError;
 use super::interface::SignalBroadcaster;
 use super::manager::TaskManagerRunHandle;
+use super::metrics::observe_zome_call;
+use super::metrics::CellQueueSizes;
+use super::module_cache::ModuleCache;
 use super::p2p_store::get_agent_info_signed;
 use super::p2p_store::put_agent_info_signed;
 use super::p2p_store::query_agent_info_signed;
 use super::Cell;
 use super::Conductor;
+use crate::core::ribosome::error::RibosomeError;
+use crate::core::ribosome::guest_callback::entry_defs::EntryDefsHostAccess;
+use crate::core::ribosome::guest_callback::entry_defs::EntryDefsInvocation;
+use crate::core::ribosome::guest_callback::entry_defs::EntryDefsResult;
+use crate::core::ribosome::real_ribosome::RealRibosome;
+use crate::core::ribosome::RibosomeT;
+use crate::core::ribosome::ZomesToInvoke;
 use crate::core::workflow::CallZomeWorkspaceLock;
 use crate::core::workflow::ZomeCallResult;
 use derive_more::From;
 use futures::future::FutureExt;
+use holochain_p2p::actor::HolochainP2pSender;
+use holochain_p2p::actor::NetworkStats;
 use holochain_p2p::event::HolochainP2pEvent::*;
 use holochain_types::prelude::*;
 use kitsune_p2p::agent_store::AgentInfoSigned;
@@ -103,6 +121,12 @@ pub trait ConductorHandleT: Send + Sync {
     /// Add an app interface
     async fn add_app_interface(self: Arc<Self>, port: u16) -> ConductorResult<u16>;
 
+    /// Serve metrics over http, returning the bound port
+    async fn add_metrics_exporter(
+        self: Arc<Self>,
+        config: MetricsExporterConfig,
+    ) -> ConductorResult<u16>;
+
     /// Install a [Dna] in this Conductor
     async fn install_dna(&self, dna: DnaFile) -> ConductorResult<()>;
 
@@ -115,6 +139,15 @@ pub trait ConductorHandleT: Send + Sync {
     /// Get a [EntryDef] from the [EntryDefBuffer]
     async fn get_entry_def(&self, key: &EntryDefBufferKey) -> Option<EntryDef>;
 
+    /// Get the wasm limits that apply to calls into this [Dna]
+    fn wasm_limits(&self, hash: &DnaHash) -> WasmLimits;
+
+    /// Get the conductor's compiled wasm module cache
+    fn module_cache(&self) -> Arc<ModuleCache>;
+
+    /// List the zome fns, callbacks and entry defs of each zome in an installed [Dna]
+    async fn list_zome_exports(&self, hash: &DnaHash) -> ConductorApiResult<Vec<ZomeExports>>;
+
     /// Add the [DnaFile]s from the wasm and dna_def databases into memory
     async fn add_dnas(&self) -> ConductorResult<()>;
 
@@ -184,10 +217,32 @@ pub trait ConductorHandleT: Send + Sync {
     /// List Active AppIds
     async fn list_active_apps(&self) -> ConductorResult<Vec<InstalledAppId>>;
 
+    /// Count the ops waiting in each running Cell's workflow queues
+    async fn cell_queue_sizes(&self) -> ConductorApiResult<Vec<(CellId, CellQueueSizes)>>;
+
     /// Dump the cells state
     #[allow(clippy::ptr_arg)]
     async fn dump_cell_state(&self, cell_id: &CellId) -> ConductorApiResult<String>;
 
+    /// Get what the network knows about a running Dna,
+    /// or about every running Dna if `dna_hash` is None
+    async fn network_stats(
+        &self,
+        dna_hash: Option<DnaHash>,
+    ) -> ConductorApiResult<Vec<NetworkStats>>;
+
+    /// Stop contacting a remote agent on the network of a running Dna
+    /// for `duration_ms`, or the configured block duration if None
+    async fn block_peer(
+        &self,
+        dna_hash: DnaHash,
+        agent: AgentPubKey,
+        duration_ms: Option<u64>,
+    ) -> ConductorApiResult<()>;
+
+    /// Lift any block on a remote agent on the network of a running Dna
+    async fn unblock_peer(&self, dna_hash: DnaHash, agent: AgentPubKey) -> ConductorApiResult<()>;
+
     /// Access the broadcast Sender which will send a Signal across every
     /// attached app interface
     async fn signal_broadcaster(&self) -> SignalBroadcaster;
@@ -245,6 +300,8 @@ pub struct ConductorHandleImpl<DS: DnaStore + 'static> {
     pub(crate) conductor: RwLock<Conductor<DS>>,
     pub(crate) keystore: KeystoreSender,
     pub(crate) holochain_p2p: holochain_p2p::HolochainP2pRef,
+    pub(crate) wasm_limits: WasmLimitsConfig,
+    pub(crate) module_cache: Arc<ModuleCache>,
 }
 
 #[async_trait::async_trait]
@@ -276,6 +333,15 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
         lock.add_app_interface_via_handle(port, self.clone()).await
     }
 
+    async fn add_metrics_exporter(
+        self: Arc<Self>,
+        config: MetricsExporterConfig,
+    ) -> ConductorResult<u16> {
+        let mut lock = self.conductor.write().await;
+        lock.add_metrics_exporter_via_handle(config, self.clone())
+            .await
+    }
+
     async fn install_dna(&self, dna: DnaFile) -> ConductorResult<()> {
         let is_full_wasm_dna = dna
             .dna_def()
@@ -287,10 +353,24 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
 
         // Only install wasm if the DNA is composed purely of WasmZomes (no InlineZomes)
         if is_full_wasm_dna {
-            let entry_defs = lock.put_wasm(dna.clone()).await?;
+            // A hostile DNA mustn't be able to hang installation
+            let wasm_limits = self.wasm_limits.for_dna(dna.dna_hash());
+            let entry_defs = lock.put_wasm(dna.clone(), wasm_limits).await?;
             lock.dna_store_mut().add_entry_defs(entry_defs);
         }
 
+        // Compile the zomes in the background so the first call doesn't have to
+        if is_full_wasm_dna {
+            let module_cache = self.module_cache.clone();
+            let wasm_limits = self.wasm_limits.for_dna(dna.dna_hash());
+            let dna = dna.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = module_cache.warm(&dna, &wasm_limits) {
+                    error!(?e, dna_hash = ?dna.dna_hash(), "Failed to compile DNA wasm");
+                }
+            });
+        }
+
         lock.dna_store_mut().add_dna(dna);
 
         Ok(())
@@ -321,6 +401,46 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
         self.conductor.read().await.dna_store().get_entry_def(key)
     }
 
+    fn wasm_limits(&self, hash: &DnaHash) -> WasmLimits {
+        self.wasm_limits.for_dna(hash)
+    }
+
+    fn module_cache(&self) -> Arc<ModuleCache> {
+        self.module_cache.clone()
+    }
+
+    async fn list_zome_exports(&self, hash: &DnaHash) -> ConductorApiResult<Vec<ZomeExports>> {
+        let dna = self
+            .get_dna(hash)
+            .await
+            .ok_or_else(|| ConductorApiError::DnaMissing(hash.clone()))?;
+        let ribosome = RealRibosome::new(dna)
+            .with_wasm_limits(self.wasm_limits.for_dna(hash))
+            .with_module_cache(Some(self.module_cache.clone()));
+        let mut entry_defs =
+            match ribosome.run_entry_defs(EntryDefsHostAccess, EntryDefsInvocation)? {
+                EntryDefsResult::Defs(map) => map,
+                EntryDefsResult::Err(zome_name, msg) => {
+                    return Err(RibosomeError::EntryDefs(zome_name, msg).into())
+                }
+            };
+        let mut exports = Vec::new();
+        for zome in ribosome.zomes_to_invoke(ZomesToInvoke::All) {
+            // zomes without an entry_defs callback have no entry defs
+            let entry_defs = entry_defs
+                .remove(zome.zome_name())
+                .map(|defs| defs.into_iter().collect())
+                .unwrap_or_default();
+            exports.push(ZomeExports {
+                zome_name: zome.zome_name().clone(),
+                zome_fns: ribosome.list_zome_fns(&zome)?,
+                callbacks: ribosome.list_callbacks(&zome)?,
+                entry_defs,
+            });
+        }
+        Ok(exports)
+    }
+
     #[instrument(skip(self))]
     /// Warning: returning an error from this function kills the network for the conductor.
     async fn dispatch_holochain_p2p_event(
@@ -378,7 +498,10 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
 
     async fn call_zome(&self, call: ZomeCall) -> ConductorApiResult<ZomeCallResult> {
         let cell = self.cell_by_id(&call.cell_id).await?;
-        Ok(cell.call_zome(call, None).await?)
+        let start = std::time::Instant::now();
+        let result = cell.call_zome(call, None).await;
+        observe_zome_call(start.elapsed());
+        Ok(result?)
     }
 
     async fn call_zome_with_workspace(
@@ -509,10 +632,69 @@ impl<DS: DnaStore + 'static> ConductorHandleT for ConductorHandleImpl<DS> {
         self.conductor.read().await.list_active_apps().await
     }
 
+    async fn cell_queue_sizes(&self) -> ConductorApiResult<Vec<(CellId, CellQueueSizes)>> {
+        // Don't hold the conductor lock while reading the databases
+        let cells = self.conductor.read().await.running_cells();
+        let mut sizes = Vec::with_capacity(cells.len());
+        for cell in cells {
+            sizes.push((cell.id().clone(), cell.queue_sizes().await?));
+        }
+        Ok(sizes)
+    }
+
     async fn dump_cell_state(&self, cell_id: &CellId) -> ConductorApiResult<String> {
         self.conductor.read().await.dump_cell_state(cell_id).await
     }
 
+    async fn network_stats(
+        &self,
+        dna_hash: Option<DnaHash>,
+    ) -> ConductorApiResult<Vec<NetworkStats>> {
+        let running = self.running_dna_hashes().await?;
+        let dna_hashes = match dna_hash {
+            Some(dna_hash) if running.contains(&dna_hash) => vec![dna_hash],
+            Some(dna_hash) => return Err(ConductorApiError::DnaMissing(dna_hash)),
+            None => running,
+        };
+        let mut stats = Vec::with_capacity(dna_hashes.len());
+        for dna_hash in dna_hashes {
+            stats.push(
+                self.holochain_p2p
+                    .network_stats(dna_hash)
+                    .await
+                    .map_err(ConductorError::from)?,
+            );
+        }
+        Ok(stats)
+    }
+
+    async fn block_peer(
+        &self,
+        dna_hash: DnaHash,
+        agent: AgentPubKey,
+        duration_ms: Option<u64>,
+    ) -> ConductorApiResult<()> {
+        if !self.running_dna_hashes().await?.contains(&dna_hash) {
+            return Err(ConductorApiError::DnaMissing(dna_hash));
+        }
+        self.holochain_p2p
+            .block_peer(dna_hash, agent, duration_ms)
+            .await
+            .map_err(ConductorError::from)?;
+        Ok(())
+    }
+
+    async fn unblock_peer(&self, dna_hash: DnaHash, agent: AgentPubKey) -> ConductorApiResult<()> {
+        if !self.running_dna_hashes().await?.contains(&dna_hash) {
+            return Err(ConductorApiError::DnaMissing(dna_hash));
+        }
+        self.holochain_p2p
+            .unblock_peer(dna_hash, agent)
+            .await
+            .map_err(ConductorError::from)?;
+        Ok(())
+    }
+
     async fn signal_broadcaster(&self) -> SignalBroadcaster {
         self.conductor.read().await.signal_broadcaster()
     }
@@ -584,4 +766,15 @@ impl<DS: DnaStore + 'static> ConductorHandleImpl<DS> {
         let lock = self.conductor.read().await;
         Ok(lock.cell_by_id(cell_id)?)
     }
+
+    /// The hashes of the Dnas with at least one running cell
+    async fn running_dna_hashes(&self) -> ConductorApiResult<Vec<DnaHash>> {
+        let mut running = Vec::new();
+        for cell_id in self.conductor.read().await.list_cell_ids().await? {
+            if !running.contains(cell_id.dna_hash()) {
+                running.push(cell_id.dna_hash().clone());
+            }
+        }
+        Ok(running)
+    }
 }
@@ -0,0 +1,442 @@
+//! Export conductor and network metrics over http in the
+//! [OpenMetrics](https://openmetrics.io) text format, so Prometheus
+//! or a compatible scraper can collect them.
+//!
+//! Enabled by setting `metrics_exporter` in the [ConductorConfig].
+//! Every request for `/metrics` renders a fresh snapshot of:
+//! - the number of running cells,
+//! - the ops waiting in each cell's validation and integration queues,
+//! - a histogram of zome call latency,
+//! - the kitsune wire message and transport counters,
+//! - process and network usage.
+//!
+//! [ConductorConfig]: super::config::ConductorConfig
+
+use super::conductor::StopReceiver;
+use super::handle::ConductorHandle;
+use super::manager::ManagedTaskHandle;
+use super::manager::ManagedTaskResult;
+use holochain_types::prelude::*;
+use kitsune_p2p::dependencies::kitsune_p2p_types;
+use once_cell::sync::Lazy;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tracing::*;
+
+/// The content type of the OpenMetrics text format.
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Requests larger than this are refused.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Connections that don't send a full request within this are dropped.
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connections over this many at once are dropped without an answer.
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Upper bounds of the zome call latency histogram buckets, in seconds.
+const ZOME_CALL_LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The number of ops waiting in each of a cell's workflow queues.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CellQueueSizes {
+    /// Ops waiting for sys validation.
+    pub sys_validation_pending: usize,
+    /// Ops waiting on dependencies before sys validation.
+    pub awaiting_sys_deps: usize,
+    /// Ops waiting for app validation.
+    pub app_validation_pending: usize,
+    /// Ops waiting on dependencies before app validation.
+    pub awaiting_app_deps: usize,
+    /// Validated ops waiting to be integrated.
+    pub integration_limbo: usize,
+}
+
+impl CellQueueSizes {
+    /// The total number of ops in validation limbo.
+    pub fn validation_limbo(&self) -> usize {
+        self.sys_validation_pending
+            + self.awaiting_sys_deps
+            + self.app_validation_pending
+            + self.awaiting_app_deps
+    }
+}
+
+/// A histogram with fixed buckets that can be observed from any thread.
+struct Histogram {
+    bounds: &'static [f64],
+    buckets: Vec<AtomicU64>,
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: bounds.iter().map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bound, bucket) in self.bounds.iter().zip(self.buckets.iter()) {
+            if secs <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+static ZOME_CALL_LATENCY: Lazy<Histogram> = Lazy::new(|| Histogram::new(ZOME_CALL_LATENCY_BUCKETS));
+
+/// Record how long a zome call took.
+pub fn observe_zome_call(duration: Duration) {
+    ZOME_CALL_LATENCY.observe(duration);
+}
+
+/// Builds a metrics exposition in the OpenMetrics text format.
+#[derive(Default)]
+struct OpenMetrics(String);
+
+impl OpenMetrics {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        writeln!(self.0, "# TYPE {} {}", name, kind).unwrap();
+        writeln!(self.0, "# HELP {} {}", name, help).unwrap();
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                .collect::<Vec<_>>()
+                .join(",");
+            write!(self.0, "{{{}}}", labels).unwrap();
+        }
+        writeln!(self.0, " {}", value).unwrap();
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: impl std::fmt::Display) {
+        self.family(name, "counter", help);
+        self.sample(&format!("{}_total", name), &[], value);
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: impl std::fmt::Display) {
+        self.family(name, "gauge", help);
+        self.sample(name, &[], value);
+    }
+
+    fn histogram(&mut self, name: &str, help: &str, histogram: &Histogram) {
+        self.family(name, "histogram", help);
+        let bucket = format!("{}_bucket", name);
+        for (bound, count) in histogram.bounds.iter().zip(histogram.buckets.iter()) {
+            let le = format!("{:?}", bound);
+            self.sample(&bucket, &[("le", &le)], count.load(Ordering::Relaxed));
+        }
+        let count = histogram.count.load(Ordering::Relaxed);
+        self.sample(&bucket, &[("le", "+Inf")], count);
+        let sum = histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        self.sample(&format!("{}_sum", name), &[], sum);
+        self.sample(&format!("{}_count", name), &[], count);
+    }
+
+    fn finish(mut self) -> String {
+        self.0.push_str("# EOF\n");
+        self.0
+    }
+}
+
+fn escape_label(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render a snapshot of all the metrics.
+pub async fn render_metrics(handle: &ConductorHandle) -> String {
+    let mut out = OpenMetrics::default();
+
+    match handle.cell_queue_sizes().await {
+        Ok(cells) => {
+            out.gauge("holochain_cells", "Number of running cells.", cells.len());
+            out.family(
+                "holochain_cell_queue_ops",
+                "gauge",
+                "Ops waiting in each of a cell's workflow queues.",
+            );
+            for (cell_id, sizes) in &cells {
+                let dna = cell_id.dna_hash().to_string();
+                let agent = cell_id.agent_pubkey().to_string();
+                for (queue, size) in &[
+                    ("sys_validation", sizes.sys_validation_pending),
+                    ("awaiting_sys_deps", sizes.awaiting_sys_deps),
+                    ("app_validation", sizes.app_validation_pending),
+                    ("awaiting_app_deps", sizes.awaiting_app_deps),
+                    ("integration", sizes.integration_limbo),
+                ] {
+                    out.sample(
+                        "holochain_cell_queue_ops",
+                        &[("dna", &dna), ("agent", &agent), ("queue", queue)],
+                        size,
+                    );
+                }
+            }
+            out.family(
+                "holochain_cell_validation_limbo_ops",
+                "gauge",
+                "Ops in a cell's validation limbo.",
+            );
+            for (cell_id, sizes) in &cells {
+                let dna = cell_id.dna_hash().to_string();
+                let agent = cell_id.agent_pubkey().to_string();
+                out.sample(
+                    "holochain_cell_validation_limbo_ops",
+                    &[("dna", &dna), ("agent", &agent)],
+                    sizes.validation_limbo(),
+                );
+            }
+        }
+        Err(e) => warn!(?e, "Failed to read cell queue sizes for metrics"),
+    }
+
+    out.histogram(
+        "holochain_zome_call_duration_seconds",
+        "Time taken by zome calls made through the conductor.",
+        &ZOME_CALL_LATENCY,
+    );
+
+    use kitsune_p2p::metrics::KitsuneMetrics;
+    out.family(
+        "kitsune_wire_message_bytes",
+        "counter",
+        "Bytes of kitsune wire messages, by message type.",
+    );
+    for (metric, count) in KitsuneMetrics::iter() {
+        if let KitsuneMetrics::CompressionSaved = metric {
+            continue;
+        }
+        let message = format!("{:?}", metric);
+        out.sample(
+            "kitsune_wire_message_bytes_total",
+            &[("message", &message)],
+            count,
+        );
+    }
+    out.counter(
+        "kitsune_compression_saved_bytes",
+        "Bytes saved by compressing kitsune wire messages.",
+        KitsuneMetrics::get(KitsuneMetrics::CompressionSaved),
+    );
+
+    use kitsune_p2p_types::transport::KitsuneTransportMetrics;
+    for (name, help, metric) in vec![
+        (
+            "kitsune_transport_write_bytes",
+            "Bytes written to kitsune transport channels.",
+            KitsuneTransportMetrics::Write,
+        ),
+        (
+            "kitsune_transport_read_bytes",
+            "Bytes read from kitsune transport channels.",
+            KitsuneTransportMetrics::Read,
+        ),
+        (
+            "kitsune_transport_connections_opened",
+            "Kitsune transport connections opened.",
+            KitsuneTransportMetrics::ConnectionOpen,
+        ),
+        (
+            "kitsune_transport_connections_closed",
+            "Kitsune transport connections closed.",
+            KitsuneTransportMetrics::ConnectionClose,
+        ),
+        (
+            "kitsune_transport_connections_reused",
+            "Kitsune transport channels opened on an existing connection.",
+            KitsuneTransportMetrics::ConnectionReuse,
+        ),
+    ] {
+        out.counter(name, help, KitsuneTransportMetrics::get(metric));
+    }
+
+    out.gauge(
+        "kitsune_tasks",
+        "Number of running kitsune tasks.",
+        kitsune_p2p_types::metrics::metric_task_count(),
+    );
+
+    let sys_info = kitsune_p2p_types::metrics::get_sys_info();
+    out.gauge(
+        "process_memory_used_kilobytes",
+        "Memory used by this process.",
+        sys_info.used_mem_kb,
+    );
+    out.gauge(
+        "process_cpu_usage_percent",
+        "CPU used by this process.",
+        sys_info.proc_cpu_usage_pct_1000 as f64 / 1000.0,
+    );
+    out.gauge(
+        "network_transmit_bytes_per_second",
+        "Bytes transmitted per second on all network interfaces.",
+        sys_info.tx_bytes_per_sec,
+    );
+    out.gauge(
+        "network_receive_bytes_per_second",
+        "Bytes received per second on all network interfaces.",
+        sys_info.rx_bytes_per_sec,
+    );
+
+    out.finish()
+}
+
+/// Bind the metrics endpoint and spawn the task serving it.
+/// Returns the bound port along with the task.
+pub(crate) async fn spawn_metrics_exporter_task(
+    addr: SocketAddr,
+    handle: ConductorHandle,
+    mut stop_rx: StopReceiver,
+) -> std::io::Result<(u16, ManagedTaskHandle)> {
+    let mut listener = tokio::net::TcpListener::bind(addr).await?;
+    let port = listener.local_addr()?.port();
+    kitsune_p2p::metrics::enable();
+    kitsune_p2p_types::metrics::init_sys_info_poll();
+    info!(?addr, port, "Serving metrics");
+    let requests = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_REQUESTS));
+    let task = tokio::task::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = stop_rx.recv() => { break; },
+                res = listener.accept() => match res {
+                    Ok((stream, _)) => match requests.clone().try_acquire_owned() {
+                        Ok(permit) => {
+                            let handle = handle.clone();
+                            tokio::task::spawn(async move {
+                                serve_metrics(stream, handle).await;
+                                drop(permit);
+                            });
+                        }
+                        Err(_) => debug!("Too many metrics requests, dropping connection"),
+                    },
+                    Err(e) => warn!(?e, "Metrics exporter failed to accept a connection"),
+                },
+            }
+        }
+        ManagedTaskResult::Ok(())
+    });
+    Ok((port, task))
+}
+
+/// Answer a single http request.
+async fn serve_metrics(mut stream: tokio::net::TcpStream, handle: ConductorHandle) {
+    let request = match tokio::time::timeout(REQUEST_READ_TIMEOUT, read_request(&mut stream)).await
+    {
+        Ok(Some(request)) => request,
+        Ok(None) => return,
+        Err(_) => {
+            debug!("Timed out reading metrics request");
+            return;
+        }
+    };
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let path = request_line.next().unwrap_or("");
+    let res = match (method, path) {
+        ("GET", "/metrics") => {
+            let body = render_metrics(&handle).await;
+            respond(&mut stream, "200 OK", CONTENT_TYPE, &body).await
+        }
+        ("GET", _) => respond(&mut stream, "404 Not Found", "text/plain", "").await,
+        _ => respond(&mut stream, "405 Method Not Allowed", "text/plain", "").await,
+    };
+    if let Err(e) = res {
+        debug!(?e, "Failed to write metrics response");
+    }
+}
+
+/// Read up to the end of the request head,
+/// or None if the connection closed or the request was too large.
+async fn read_request(stream: &mut tokio::net::TcpStream) -> Option<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+        if request.len() > MAX_REQUEST_BYTES {
+            let _ = respond(stream, "413 Payload Too Large", "text/plain", "").await;
+            return None;
+        }
+    }
+    Some(request)
+}
+
+async fn respond(
+    stream: &mut tokio::net::TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown(std::net::Shutdown::Write)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_openmetrics_text() {
+        let histogram = Histogram::new(&[0.01, 0.1]);
+        histogram.observe(Duration::from_millis(5));
+        histogram.observe(Duration::from_millis(50));
+        histogram.observe(Duration::from_secs(1));
+
+        let mut out = OpenMetrics::default();
+        out.counter("test_bytes", "Some bytes.", 42);
+        out.sample("test_labelled", &[("name", "a \"b\"")], 1);
+        out.histogram("test_seconds", "Some durations.", &histogram);
+        let out = out.finish();
+
+        assert_eq!(
+            "# TYPE test_bytes counter\n\
+             # HELP test_bytes Some bytes.\n\
+             test_bytes_total 42\n\
+             test_labelled{name=\"a \\\"b\\\"\"} 1\n\
+             # TYPE test_seconds histogram\n\
+             # HELP test_seconds Some durations.\n\
+             test_seconds_bucket{le=\"0.01\"} 1\n\
+             test_seconds_bucket{le=\"0.1\"} 2\n\
+             test_seconds_bucket{le=\"+Inf\"} 3\n\
+             test_seconds_sum 1.055\n\
+             test_seconds_count 3\n\
+             # EOF\n",
+            out
+        );
+    }
+}
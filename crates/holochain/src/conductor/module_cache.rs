@@ -0,0 +1,377 @@
+//! # Compiled Module Cache
+//! Keeps compiled wasm modules on disk under the conductor's environment root
+//! so that zomes are not recompiled every time the conductor starts.
+//! Modules are keyed by [WasmHash] and the [WasmLimits] they were compiled
+//! with, as the limits are baked into the compiled code. The least recently
+//! used modules are dropped from memory once too many are loaded and evicted
+//! from disk once the cache grows past its size limit.
+
+use crate::conductor::config::WasmLimits;
+use crate::core::ribosome::error::RibosomeResult;
+use crate::core::ribosome::memory_limit::MemoryLimit;
+use holo_hash::WasmHash;
+use holochain_types::prelude::*;
+use holochain_wasmer_host::prelude::*;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use tracing::*;
+use wasmer_middleware_common::metering;
+use wasmer_runtime::cache::Artifact;
+use wasmer_runtime_core::backend::Compiler;
+use wasmer_runtime_core::codegen::MiddlewareChain;
+use wasmer_runtime_core::codegen::StreamingCompiler;
+use wasmer_singlepass_backend::ModuleCodeGenerator as SinglePassMCG;
+
+/// The directory under the environment root where compiled modules are stored
+pub const MODULE_CACHE_DIRECTORY: &str = "wasm-cache";
+
+/// The size limit used when none is configured (1GiB)
+pub const DEFAULT_MODULE_CACHE_MAX_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// How many compiled modules are held in memory at once
+pub const MODULE_CACHE_MAX_LOADED: usize = 128;
+
+/// Suffix of the files modules are written to before being renamed into place
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// Distinguishes the temporary files of concurrent writes
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A map that holds at most `capacity` values, dropping the least recently
+/// used one to make room for a new one.
+pub(crate) struct ModuleLru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (u64, V)>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> ModuleLru<K, V> {
+    /// Create an empty map holding at most `capacity` values
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Get a value, marking it as the most recently used
+    pub(crate) fn get(&mut self, key: &K) -> Option<V> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(key).map(|(last_used, value)| {
+            *last_used = tick;
+            value.clone()
+        })
+    }
+
+    /// Insert a value, dropping the least recently used ones if over capacity
+    pub(crate) fn insert(&mut self, key: K, value: V) {
+        self.tick += 1;
+        self.entries.insert(key, (self.tick, value));
+        while self.entries.len() > self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.keys()
+    }
+}
+
+/// A cache of compiled modules shared by every cell in a conductor.
+/// Recently used modules are held in memory and every module is persisted to
+/// disk when compiled.
+pub struct ModuleCache {
+    path: PathBuf,
+    max_bytes: u64,
+    modules: parking_lot::Mutex<ModuleLru<(WasmHash, WasmLimits), Module>>,
+}
+
+impl std::fmt::Debug for ModuleCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleCache")
+            .field("path", &self.path)
+            .field("max_bytes", &self.max_bytes)
+            .finish()
+    }
+}
+
+impl ModuleCache {
+    /// Open the cache in this directory, creating the directory if needed
+    pub fn new(path: PathBuf, max_bytes: Option<u64>) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&path)?;
+        Ok(Self {
+            path,
+            max_bytes: max_bytes.unwrap_or(DEFAULT_MODULE_CACHE_MAX_BYTES),
+            modules: parking_lot::Mutex::new(ModuleLru::new(MODULE_CACHE_MAX_LOADED)),
+        })
+    }
+
+    /// The directory the compiled modules are stored in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the compiled module for this wasm with these limits, loading it
+    /// from disk or compiling and storing it if it isn't cached yet.
+    pub fn get(
+        &self,
+        wasm_hash: &WasmHash,
+        wasm: &[u8],
+        wasm_limits: &WasmLimits,
+    ) -> RibosomeResult<Module> {
+        let key = (wasm_hash.clone(), wasm_limits.clone());
+        if let Some(module) = self.modules.lock().get(&key) {
+            return Ok(module);
+        }
+        let module = match self.load(wasm_hash, wasm_limits) {
+            Some(module) => module,
+            None => {
+                let module = compile(wasm, wasm_limits)?;
+                self.store(wasm_hash, wasm_limits, &module);
+                module
+            }
+        };
+        self.modules.lock().insert(key, module.clone());
+        Ok(module)
+    }
+
+    /// Compile every wasm in this [DnaFile] with these limits
+    /// ahead of its first call
+    pub fn warm(&self, dna: &DnaFile, wasm_limits: &WasmLimits) -> RibosomeResult<()> {
+        for (wasm_hash, dna_wasm) in dna.code() {
+            self.get(wasm_hash, &dna_wasm.code(), wasm_limits)?;
+        }
+        Ok(())
+    }
+
+    fn file_path(&self, wasm_hash: &WasmHash, wasm_limits: &WasmLimits) -> PathBuf {
+        if wasm_limits.is_unlimited() {
+            return self.path.join(wasm_hash.to_string());
+        }
+        let limit = |l: Option<u64>| l.map(|l| l.to_string()).unwrap_or_else(|| "none".into());
+        self.path.join(format!(
+            "{}.i{}.m{}",
+            wasm_hash,
+            limit(wasm_limits.max_instructions),
+            limit(wasm_limits.max_memory_bytes)
+        ))
+    }
+
+    fn load(&self, wasm_hash: &WasmHash, wasm_limits: &WasmLimits) -> Option<Module> {
+        let path = self.file_path(wasm_hash, wasm_limits);
+        let bytes = std::fs::read(&path).ok()?;
+        // Safe as long as the file was written by this cache, which also
+        // checks the wasmer version the artifact was compiled with.
+        let loaded = Artifact::deserialize(&bytes).and_then(|artifact| unsafe {
+            wasmer_runtime_core::load_cache_with(artifact, compiler(wasm_limits).as_ref())
+        });
+        match loaded {
+            Ok(module) => Some(module),
+            Err(e) => {
+                warn!(?e, ?path, "Discarding unreadable compiled module");
+                std::fs::remove_file(&path).ok();
+                None
+            }
+        }
+    }
+
+    fn store(&self, wasm_hash: &WasmHash, wasm_limits: &WasmLimits, module: &Module) {
+        let path = self.file_path(wasm_hash, wasm_limits);
+        let result = module
+            .cache()
+            .and_then(|artifact| artifact.serialize())
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|bytes| self.write(&path, &bytes).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            // Not fatal, the module will just be compiled again next time
+            warn!(?e, ?path, "Failed to store compiled module");
+        }
+        self.evict();
+    }
+
+    /// Write to a temporary file and rename it into place so a reader never
+    /// sees a partially written module.
+    fn write(&self, path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(format!(
+            ".{}.{}{}",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+            TEMP_SUFFIX
+        ));
+        let temp_path = PathBuf::from(temp_name);
+        let result =
+            std::fs::write(&temp_path, bytes).and_then(|_| std::fs::rename(&temp_path, path));
+        if result.is_err() {
+            std::fs::remove_file(&temp_path).ok();
+        }
+        result
+    }
+
+    /// Remove the oldest modules until the cache fits within its size limit.
+    /// Modules that are loaded in memory were used recently, so they are
+    /// removed last.
+    fn evict(&self) {
+        let loaded: HashSet<PathBuf> = self
+            .modules
+            .lock()
+            .keys()
+            .map(|(wasm_hash, wasm_limits)| self.file_path(wasm_hash, wasm_limits))
+            .collect();
+        let mut files: Vec<_> = match std::fs::read_dir(&self.path) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    // Another write may still be in progress, and a leftover
+                    // from a crash isn't a module
+                    if path.to_string_lossy().ends_with(TEMP_SUFFIX) {
+                        return None;
+                    }
+                    let metadata = entry.metadata().ok()?;
+                    Some((
+                        loaded.contains(&path),
+                        metadata.modified().ok()?,
+                        metadata.len(),
+                        path,
+                    ))
+                })
+                .collect(),
+            Err(_) => return,
+        };
+        let mut total: u64 = files.iter().map(|(_, _, len, _)| len).sum();
+        files.sort();
+        for (_, _, len, path) in files {
+            if total <= self.max_bytes {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                total -= len;
+            }
+        }
+    }
+}
+
+/// The compiler for modules with these limits. Limited modules are compiled
+/// with the metering middleware, which traps once the guest has executed more
+/// than `max_instructions`, and the memory limit middleware, which traps once
+/// it grows its memory past `max_memory_bytes`. The middleware requires the
+/// singlepass backend.
+fn compiler(wasm_limits: &WasmLimits) -> Box<dyn Compiler> {
+    if wasm_limits.is_unlimited() {
+        return Box::new(wasmer_runtime::default_compiler());
+    }
+    let WasmLimits {
+        max_instructions,
+        max_memory_bytes,
+    } = wasm_limits.clone();
+    let compiler: StreamingCompiler<SinglePassMCG, _, _, _, _> =
+        StreamingCompiler::new(move || {
+            let mut chain = MiddlewareChain::new();
+            if let Some(max_instructions) = max_instructions {
+                chain.push(metering::Metering::new(max_instructions));
+            }
+            if let Some(max_memory_bytes) = max_memory_bytes {
+                chain.push(MemoryLimit::new(max_memory_bytes));
+            }
+            chain
+        });
+    Box::new(compiler)
+}
+
+/// Compile a module with these limits
+pub(crate) fn compile(wasm: &[u8], wasm_limits: &WasmLimits) -> RibosomeResult<Module> {
+    Ok(
+        wasmer_runtime_core::compile_with(wasm, compiler(wasm_limits).as_ref())
+            .map_err(|e| WasmError::Compile(format!("{:?}", e)))?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use holochain_types::test_utils::fake_dna_zomes;
+    use holochain_wasm_test_utils::TestWasm;
+
+    #[test]
+    fn module_cache_persists_and_evicts() {
+        let tmp = tempdir::TempDir::new("module_cache").unwrap();
+        let dna = fake_dna_zomes(
+            "",
+            vec![
+                (TestWasm::Foo.into(), TestWasm::Foo.into()),
+                (TestWasm::HdkExtern.into(), TestWasm::HdkExtern.into()),
+            ],
+        );
+
+        let unlimited = WasmLimits::default();
+        let limited = WasmLimits {
+            max_instructions: Some(1_000_000),
+            max_memory_bytes: None,
+        };
+
+        let cache = ModuleCache::new(tmp.path().to_path_buf(), None).unwrap();
+        cache.warm(&dna, &unlimited).unwrap();
+        let files = std::fs::read_dir(tmp.path()).unwrap().count();
+        assert_eq!(files, 2);
+
+        // modules with limits are stored separately
+        cache.warm(&dna, &limited).unwrap();
+        let files = std::fs::read_dir(tmp.path()).unwrap().count();
+        assert_eq!(files, 4);
+
+        // a new cache loads the modules from disk
+        let cache = ModuleCache::new(tmp.path().to_path_buf(), None).unwrap();
+        for wasm_hash in dna.code().keys() {
+            assert!(cache.load(wasm_hash, &unlimited).is_some());
+            assert!(cache.load(wasm_hash, &limited).is_some());
+        }
+
+        // leftover temporary files don't count toward the size limit
+        let leftover = tmp.path().join(format!("leftover{}", TEMP_SUFFIX));
+        std::fs::write(&leftover, vec![0; 1024]).unwrap();
+        let size: u64 = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().metadata().unwrap().len())
+            .sum();
+        let cache = ModuleCache::new(tmp.path().to_path_buf(), Some(size - 1024)).unwrap();
+        cache.evict();
+        let files = std::fs::read_dir(tmp.path()).unwrap().count();
+        assert_eq!(files, 5);
+        std::fs::remove_file(&leftover).unwrap();
+
+        // a limit smaller than any module evicts them all
+        let cache = ModuleCache::new(tmp.path().to_path_buf(), Some(1)).unwrap();
+        cache.evict();
+        let files = std::fs::read_dir(tmp.path()).unwrap().count();
+        assert_eq!(files, 0);
+    }
+
+    #[test]
+    fn module_lru_drops_least_recently_used() {
+        let mut lru = ModuleLru::new(2);
+        lru.insert(1, "one");
+        lru.insert(2, "two");
+        // using 1 makes 2 the least recently used
+        assert_eq!(lru.get(&1), Some("one"));
+        lru.insert(3, "three");
+        assert_eq!(lru.get(&2), None);
+        assert_eq!(lru.get(&1), Some("one"));
+        assert_eq!(lru.get(&3), Some("three"));
+    }
+}
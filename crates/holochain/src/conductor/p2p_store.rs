@@ -256,10 +256,24 @@ pub fn query_agent_info_signed(
     environ: EnvironmentWrite,
     kitsune_space: Arc<kitsune_p2p::KitsuneSpace>,
 ) -> ConductorResult<Vec<AgentInfoSigned>> {
+    let mut out = Vec::new();
+    for_each_unexpired_agent_info(environ, |v, info, _| {
+        if info.as_space_ref() == kitsune_space.as_ref() {
+            out.push(v);
+        }
+    })?;
+    Ok(out)
+}
+
+/// Call `f` with each unexpired agent info in the store and the time it
+/// expires at, deleting the expired ones as we go.
+fn for_each_unexpired_agent_info<F>(environ: EnvironmentWrite, mut f: F) -> ConductorResult<()>
+where
+    F: FnMut(AgentInfoSigned, &kitsune_p2p::agent_store::AgentInfo, u64),
+{
     let p2p_kv = AgentKv::new(environ.clone().into())?;
     let env = environ.guard();
 
-    let mut out = Vec::new();
     env.with_commit(|writer| {
         let mut expired = Vec::new();
 
@@ -277,11 +291,7 @@ pub fn query_agent_info_signed(
                         let info = kitsune_p2p::agent_store::AgentInfo::try_from(&v)?;
                         let expires = info.signed_at_ms().checked_add(info.expires_after_ms());
                         match expires {
-                            Some(expires) if expires > now => {
-                                if info.as_space_ref() == kitsune_space.as_ref() {
-                                    out.push(v);
-                                }
-                            }
+                            Some(expires) if expires > now => f(v, &info, expires),
                             _ => expired.push(AgentKvKey::from(k)),
                         }
                     }
@@ -291,16 +301,12 @@ pub fn query_agent_info_signed(
             }
         }
 
-        if !expired.is_empty() {
-            for exp in expired {
-                p2p_kv.as_store_ref().delete(writer, &exp)?;
-            }
+        for exp in expired {
+            p2p_kv.as_store_ref().delete(writer, &exp)?;
         }
 
         ConductorResult::Ok(())
-    })?;
-
-    Ok(out)
+    })
 }
 
 /// Put single agent info into store
@@ -319,6 +325,42 @@ pub fn put_agent_info_signed(
     })?)
 }
 
+/// The longest we wait between checks for expired agent infos.
+const MAX_PRUNE_INTERVAL_MS: u64 = 1000 * 60 * 5;
+
+/// Remove every expired agent info from the store.
+/// Returns when the next remaining agent info expires, if there are any.
+pub fn prune_expired_agent_infos(environ: EnvironmentWrite) -> ConductorResult<Option<u64>> {
+    let mut next_expiry: Option<u64> = None;
+    for_each_unexpired_agent_info(environ, |_, _, expires| {
+        next_expiry = Some(std::cmp::min(expires, next_expiry.unwrap_or(u64::MAX)));
+    })?;
+    Ok(next_expiry)
+}
+
+/// Prune expired agent infos for as long as the conductor runs,
+/// waking when the next stored agent info is due to expire.
+pub async fn prune_expired_agent_infos_task(environ: EnvironmentWrite) {
+    loop {
+        let wait_ms = match prune_expired_agent_infos(environ.clone()) {
+            Ok(Some(next_expiry)) => {
+                let now: u64 = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis() as u64;
+                next_expiry.saturating_sub(now)
+            }
+            Ok(None) => MAX_PRUNE_INTERVAL_MS,
+            Err(e) => {
+                tracing::error!(msg = "Failed to prune expired agent infos", ?e);
+                MAX_PRUNE_INTERVAL_MS
+            }
+        };
+        let wait_ms = wait_ms.max(1000).min(MAX_PRUNE_INTERVAL_MS);
+        tokio::time::delay_for(std::time::Duration::from_millis(wait_ms)).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -330,6 +372,9 @@ mod tests {
     use holochain_lmdb::test_utils::test_p2p_env;
     use kitsune_p2p::fixt::AgentInfoFixturator;
     use kitsune_p2p::fixt::AgentInfoSignedFixturator;
+    use kitsune_p2p::fixt::KitsuneAgentFixturator;
+    use kitsune_p2p::fixt::KitsuneSignatureFixturator;
+    use kitsune_p2p::fixt::KitsuneSpaceFixturator;
     use kitsune_p2p::KitsuneBinType;
     use std::convert::TryInto;
 
@@ -413,4 +458,37 @@ mod tests {
 
         assert_eq!(expect, agents);
     }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn prune_expired_agent_infos_from_store() {
+        observability::test_run().ok();
+        let t_env = test_p2p_env();
+        let env = t_env.env();
+
+        let now: u64 = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let signed = |signed_at_ms, expires_after_ms| {
+            let agent = fixt!(KitsuneAgent);
+            let info = AgentInfo::new(
+                fixt!(KitsuneSpace),
+                agent.clone(),
+                vec![],
+                signed_at_ms,
+                expires_after_ms,
+            );
+            let mut data = Vec::new();
+            kitsune_p2p::dependencies::kitsune_p2p_types::codec::rmp_encode(&mut data, &info)
+                .unwrap();
+            AgentInfoSigned::try_new(agent, fixt!(KitsuneSignature), data).unwrap()
+        };
+        let live = signed(now, 1000 * 60);
+        let expired = signed(now - 1000 * 60, 1000);
+        inject_agent_infos(env.clone(), vec![live.clone(), expired]).unwrap();
+
+        let next_expiry = prune_expired_agent_infos(env.clone()).unwrap();
+        assert_eq!(next_expiry, Some(now + 1000 * 60));
+        assert_eq!(all_agent_infos(env.clone().into()).unwrap(), vec![live]);
+    }
 }
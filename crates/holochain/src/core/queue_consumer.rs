@@ -243,7 +243,7 @@ pub struct OneshotWriter(EnvironmentWrite);
 
 impl OneshotWriter {
     /// Create the writer and pass it into a closure.
-    pub fn with_writer<F>(self, f: F) -> Result<(), WorkspaceError>
+    pub fn with_writer<F>(&self, f: F) -> Result<(), WorkspaceError>
     where
         F: FnOnce(&mut Writer) -> Result<(), WorkspaceError> + Send,
     {
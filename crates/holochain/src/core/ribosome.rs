@@ -10,6 +10,7 @@
 pub mod error;
 pub mod guest_callback;
 pub mod host_fn;
+pub(crate) mod memory_limit;
 pub mod real_ribosome;
 
 use crate::conductor::api::CellConductorApi;
@@ -305,6 +306,9 @@ pub struct ZomeCallInvocation {
     /// The provenance of the call. Provenance means the 'source'
     /// so this expects the `AgentPubKey` of the agent calling the Zome function
     pub provenance: AgentPubKey,
+    /// Whether the call is denied write access to the workspace.
+    /// See [ZomeCall::read_only].
+    pub read_only: bool,
 }
 
 impl Invocation for ZomeCallInvocation {
@@ -329,6 +333,7 @@ impl ZomeCallInvocation {
             cap,
             payload,
             provenance,
+            read_only,
         } = call;
         let zome = conductor_api
             .get_zome(cell_id.dna_hash(), &zome_name)
@@ -341,6 +346,7 @@ impl ZomeCallInvocation {
             cap,
             payload,
             provenance,
+            read_only,
         }
     }
 }
@@ -354,6 +360,7 @@ impl From<ZomeCallInvocation> for ZomeCall {
             cap,
             payload,
             provenance,
+            read_only,
         } = inv;
         Self {
             cell_id,
@@ -362,6 +369,7 @@ impl From<ZomeCallInvocation> for ZomeCall {
             cap,
             payload,
             provenance,
+            read_only,
         }
     }
 }
@@ -377,6 +385,8 @@ pub struct ZomeCallHostAccess {
     // "resource" to give access to, but rather it's a bit of data that makes sense in
     // the context of zome calls, but not every CallContext
     pub cell_id: CellId,
+    /// Read-only calls can't write to the workspace
+    pub read_only: bool,
 }
 
 impl From<ZomeCallHostAccess> for HostAccess {
@@ -386,8 +396,12 @@ impl From<ZomeCallHostAccess> for HostAccess {
 }
 
 impl From<&ZomeCallHostAccess> for HostFnAccess {
-    fn from(_: &ZomeCallHostAccess) -> Self {
-        Self::all()
+    fn from(zome_call_host_access: &ZomeCallHostAccess) -> Self {
+        let mut access = Self::all();
+        if zome_call_host_access.read_only {
+            access.write_workspace = Permission::Deny;
+        }
+        access
     }
 }
 
@@ -437,18 +451,25 @@ pub trait RibosomeT: Sized + std::fmt::Debug {
         to_call: &FunctionName,
     ) -> Result<Option<ExternIO>, RibosomeError>;
 
-    /// @todo list out all the available callbacks and maybe cache them somewhere
-    fn list_callbacks(&self) {
-        unimplemented!()
-        // pseudocode
-        // self.instance().exports().filter(|e| e.is_callback())
+    /// List every function exported by a zome, both callbacks and zome fns.
+    fn zome_exports(&self, zome: &Zome) -> RibosomeResult<Vec<FunctionName>>;
+
+    /// List the callbacks a zome implements, e.g. `init` or `validate_create_entry`.
+    fn list_callbacks(&self, zome: &Zome) -> RibosomeResult<Vec<FunctionName>> {
+        Ok(self
+            .zome_exports(zome)?
+            .into_iter()
+            .filter(guest_callback::is_callback)
+            .collect())
     }
 
-    /// @todo list out all the available zome functions and maybe cache them somewhere
-    fn list_zome_fns(&self) {
-        unimplemented!()
-        // pseudocode
-        // self.instance().exports().filter(|e| !e.is_callback())
+    /// List the functions of a zome that can be called with a zome call.
+    fn list_zome_fns(&self, zome: &Zome) -> RibosomeResult<Vec<FunctionName>> {
+        Ok(self
+            .zome_exports(zome)?
+            .into_iter()
+            .filter(|f| !guest_callback::is_callback(f))
+            .collect())
     }
 
     fn run_init(
@@ -598,6 +619,27 @@ pub mod wasm_test {
 
         assert_eq!(fn_components.into_iter().collect::<Vec<String>>(), expected,);
     }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn zome_call_access() {
+        use crate::core::ribosome::ZomeCallHostAccess;
+        use crate::fixt::ZomeCallHostAccessFixturator;
+        use holochain_types::dna::zome::HostFnAccess;
+        use holochain_types::dna::zome::Permission;
+
+        let mut zome_call_host_access = ZomeCallHostAccessFixturator::new(::fixt::Unpredictable)
+            .next()
+            .unwrap();
+        assert_eq!(
+            HostFnAccess::from(&zome_call_host_access),
+            HostFnAccess::all()
+        );
+
+        zome_call_host_access.read_only = true;
+        let mut access = HostFnAccess::all();
+        access.write_workspace = Permission::Deny;
+        assert_eq!(HostFnAccess::from(&zome_call_host_access), access);
+    }
 }
 
 #[cfg(test)]
@@ -37,6 +37,11 @@ pub enum RibosomeError {
     #[error("Attempted to call a zome function that doesn't exist: Zome: {0} Fn {1}")]
     ZomeFnNotExists(ZomeName, FunctionName),
 
+    /// A zome fn or callback went over one of the conductor's wasm limits
+    /// and was aborted
+    #[error("Wasm limit exceeded calling Zome: {0} Fn {1}: used {2}")]
+    WasmLimitExceeded(ZomeName, FunctionName, String),
+
     /// a problem with entry defs
     #[error("An error with entry defs: {0}")]
     EntryDefs(ZomeName, String),
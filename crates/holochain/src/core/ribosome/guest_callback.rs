@@ -13,6 +13,50 @@ use crate::core::ribosome::RibosomeT;
 use fallible_iterator::FallibleIterator;
 use holochain_types::dna::zome::Zome;
 use holochain_zome_types::ExternIO;
+use holochain_zome_types::FunctionName;
+
+/// True if this is a function the conductor calls as a callback,
+/// rather than a zome fn intended to be called by clients.
+/// This matches the names each callback's invocation builds from its
+/// [FnComponents], so e.g. `validated_items` is not a callback.
+pub fn is_callback(fn_name: &FunctionName) -> bool {
+    match fn_name.0.as_str() {
+        "init" | "entry_defs" | "post_commit" => true,
+        "migrate_agent" | "migrate_agent_open" | "migrate_agent_close" => true,
+        "validate_create_link" | "validate_delete_link" => true,
+        name => {
+            if let Some(suffix) = name.strip_prefix("validation_package") {
+                is_validation_package_suffix(suffix)
+            } else if let Some(suffix) = name.strip_prefix("validate") {
+                is_validate_suffix(suffix)
+            } else {
+                false
+            }
+        }
+    }
+}
+
+/// `validation_package[_<zome id>]`
+fn is_validation_package_suffix(suffix: &str) -> bool {
+    suffix.is_empty()
+        || suffix
+            .strip_prefix('_')
+            .map_or(false, |zome_id| zome_id.parse::<u8>().is_ok())
+}
+
+/// `validate[_create|_update|_delete][_agent|_entry[_<entry def id>]]`
+fn is_validate_suffix(suffix: &str) -> bool {
+    let suffix = ["_create", "_update", "_delete"]
+        .iter()
+        .find_map(|header| suffix.strip_prefix(header))
+        .unwrap_or(suffix);
+    suffix.is_empty()
+        || suffix == "_agent"
+        || suffix == "_entry"
+        || suffix
+            .strip_prefix("_entry_")
+            .map_or(false, |entry_def_id| !entry_def_id.is_empty())
+}
 
 pub struct CallIterator<R: RibosomeT, I: Invocation> {
     host_access: HostAccess,
@@ -67,24 +111,26 @@ impl<R: RibosomeT, I: Invocation + 'static> FallibleIterator for CallIterator<R,
 }
 
 #[cfg(test)]
-#[cfg(feature = "slow_tests")]
 mod tests {
-    use super::CallIterator;
-    use crate::core::ribosome::FnComponents;
-    use crate::core::ribosome::MockInvocation;
-    use crate::core::ribosome::MockRibosomeT;
-    use crate::core::ribosome::ZomesToInvoke;
-    use crate::fixt::FnComponentsFixturator;
-    use crate::fixt::ZomeCallHostAccessFixturator;
-    use crate::fixt::ZomeFixturator;
-    use fallible_iterator::FallibleIterator;
-    use holochain_types::dna::zome::Zome;
-    use holochain_types::prelude::*;
-    use mockall::predicate::*;
-    use mockall::Sequence;
+    use super::is_callback;
 
     #[tokio::test(threaded_scheduler)]
+    #[cfg(feature = "slow_tests")]
     async fn call_iterator_iterates() {
+        use super::CallIterator;
+        use crate::core::ribosome::FnComponents;
+        use crate::core::ribosome::MockInvocation;
+        use crate::core::ribosome::MockRibosomeT;
+        use crate::core::ribosome::ZomesToInvoke;
+        use crate::fixt::FnComponentsFixturator;
+        use crate::fixt::ZomeCallHostAccessFixturator;
+        use crate::fixt::ZomeFixturator;
+        use fallible_iterator::FallibleIterator;
+        use holochain_types::dna::zome::Zome;
+        use holochain_types::prelude::*;
+        use mockall::predicate::*;
+        use mockall::Sequence;
+
         // stuff we need to test with
         let mut sequence = Sequence::new();
         let mut ribosome = MockRibosomeT::new();
@@ -153,4 +199,39 @@ mod tests {
         let output: Vec<(_, ExternIO)> = call_iterator.collect().unwrap();
         assert_eq!(output.len(), zomes.len() * fn_components.0.len());
     }
+
+    #[test]
+    fn callback_names() {
+        for name in &[
+            "init",
+            "entry_defs",
+            "post_commit",
+            "migrate_agent",
+            "migrate_agent_open",
+            "validation_package",
+            "validation_package_3",
+            "validate",
+            "validate_agent",
+            "validate_entry",
+            "validate_create",
+            "validate_update_entry",
+            "validate_delete_entry_post_comment",
+            "validate_create_link",
+            "validate_delete_link",
+        ] {
+            assert!(is_callback(&name.to_string().into()), "{}", name);
+        }
+        for name in &[
+            "initialize",
+            "validated_items",
+            "validate_everything",
+            "validate_entry_",
+            "validation_packages",
+            "validation_package_post",
+            "migrate_agent_data",
+            "post_commits",
+        ] {
+            assert!(!is_callback(&name.to_string().into()), "{}", name);
+        }
+    }
 }
@@ -108,6 +108,9 @@ host_fn_api_impls! {
     // Attempt to get a live entry from the cascade.
     fn get (zt::entry::GetInput) -> Option<zt::element::Element>;
 
+    // Attempt to get many live entries from the cascade in one batch.
+    fn get_many (zt::entry::GetManyInput) -> Vec<Option<zt::element::Element>>;
+
     fn get_agent_activity (zt::agent_info::GetAgentActivityInput) -> zt::query::AgentActivity;
 
     fn get_details (zt::entry::GetInput) -> Option<zt::metadata::Details>;
@@ -132,6 +135,9 @@ host_fn_api_impls! {
     // Remotely signal many agents without waiting for responses
     fn remote_signal (zt::signal::RemoteSignal) -> ();
 
+    // Remotely signal many agents and wait for each to acknowledge it
+    fn remote_signal_acknowledged (zt::signal::RemoteSignal) -> zt::signal::RemoteSignalDeliveries;
+
     // // @todo
     // fn send (()) -> ();
 
@@ -1,6 +1,8 @@
 use crate::core::ribosome::RibosomeT;
 use crate::core::ribosome::ZomeCall;
 use crate::core::ribosome::CallContext;
+use crate::core::ribosome::HostAccess;
+use crate::core::ribosome::ZomeCallHostAccess;
 use holochain_types::prelude::*;
 use std::sync::Arc;
 use holochain_wasmer_host::prelude::WasmError;
@@ -23,6 +25,13 @@ pub fn call(
 
     let zome_name = call.zome_name.clone();
 
+    // A read-only call can only make read-only calls, since the workspace
+    // it shares with them will never be committed
+    let read_only = match &host_access {
+        HostAccess::ZomeCall(ZomeCallHostAccess { read_only, .. }) => *read_only,
+        _ => false,
+    };
+
     // Create the invocation for this call
     let invocation = ZomeCall {
         cell_id,
@@ -31,6 +40,7 @@ pub fn call(
         fn_name: call.fn_name,
         payload: call.payload,
         provenance: call.provenance,
+        read_only,
     };
 
     // Make the call using this workspace
@@ -66,6 +76,7 @@ pub mod wasm_test {
     use crate::test_utils::install_app;
     use crate::test_utils::new_zome_call;
     use holochain_state::element_buf::ElementBuf;
+    use holochain_state::source_chain::SourceChain;
 
     #[tokio::test(threaded_scheduler)]
     async fn call_test() {
@@ -90,6 +101,7 @@ pub mod wasm_test {
                 fn_name: "set_access".into(),
                 payload: ExternIO::encode(()).unwrap(),
                 provenance: bob_agent_id.clone(),
+                read_only: false,
             })
             .await
             .unwrap();
@@ -106,6 +118,7 @@ pub mod wasm_test {
                     &bob_cell_id
                 ).unwrap(),
                 provenance: alice_agent_id.clone(),
+                read_only: false,
             })
             .await
             .unwrap()
@@ -160,6 +173,49 @@ pub mod wasm_test {
         conductor_test.shutdown_conductor().await;
     }
 
+    /// A read-only call can make read-only calls,
+    /// but can't write to the chain through them
+    #[tokio::test(threaded_scheduler)]
+    async fn read_only_call_cannot_write_through_call() {
+        observability::test_run().ok();
+
+        let zomes = vec![TestWasm::Create];
+        let mut conductor_test = ConductorTestData::two_agents(zomes, false).await;
+        let handle = conductor_test.handle();
+        let alice_call_data = conductor_test.alice_call_data();
+        let alice_cell_id = &alice_call_data.cell_id;
+        let chain_len = || {
+            SourceChain::new(alice_call_data.env.clone().into())
+                .unwrap()
+                .len()
+        };
+        let start_len = chain_len();
+
+        let mut invocation =
+            new_zome_call(&alice_cell_id, "call_get_entry", (), TestWasm::Create).unwrap();
+        invocation.read_only = true;
+        let result = handle.call_zome(invocation).await;
+        assert_matches!(result, Ok(Ok(ZomeCallResponse::Ok(_))));
+
+        let mut invocation =
+            new_zome_call(&alice_cell_id, "call_only_create_entry", (), TestWasm::Create)
+                .unwrap();
+        invocation.read_only = true;
+        let result = handle.call_zome(invocation).await;
+        assert_matches!(result, Ok(Err(_)));
+        assert_eq!(chain_len(), start_len);
+
+        // The same call succeeds when it's allowed to write
+        let invocation =
+            new_zome_call(&alice_cell_id, "call_only_create_entry", (), TestWasm::Create)
+                .unwrap();
+        let result = handle.call_zome(invocation).await;
+        assert_matches!(result, Ok(Ok(ZomeCallResponse::Ok(_))));
+        assert_eq!(chain_len(), start_len + 1);
+
+        conductor_test.shutdown_conductor().await;
+    }
+
     /// test calling a different zome
     /// in a different cell.
     #[tokio::test(threaded_scheduler)]
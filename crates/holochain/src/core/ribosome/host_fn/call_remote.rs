@@ -129,6 +129,7 @@ pub mod wasm_test {
                 fn_name: "set_access".into(),
                 payload: ExternIO::encode(()).unwrap(),
                 provenance: bob_agent_id.clone(),
+                read_only: false,
             })
             .await
             .unwrap();
@@ -145,6 +146,7 @@ pub mod wasm_test {
                     &bob_agent_id
                 ).unwrap(),
                 provenance: alice_agent_id,
+                read_only: false,
             })
             .await
             .unwrap()
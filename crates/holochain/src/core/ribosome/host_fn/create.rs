@@ -337,6 +337,7 @@ pub mod wasm_test {
                 fn_name: "create_entry_multiple".into(),
                 payload: ExternIO::encode(n).unwrap(),
                 provenance: alice_agent_id.clone(),
+                read_only: false,
             })
             .await
             .unwrap()
@@ -357,6 +358,7 @@ pub mod wasm_test {
                 fn_name: "get_entry_multiple".into(),
                 payload: ExternIO::encode(n).unwrap(),
                 provenance: alice_agent_id,
+                read_only: false,
             })
             .await
             .unwrap()
@@ -0,0 +1,35 @@
+use crate::core::ribosome::CallContext;
+use crate::core::ribosome::RibosomeT;
+use holochain_types::prelude::*;
+use std::sync::Arc;
+use holochain_wasmer_host::prelude::WasmError;
+
+#[allow(clippy::extra_unused_lifetimes)]
+pub fn get_many<'a>(
+    _ribosome: Arc<impl RibosomeT>,
+    call_context: Arc<CallContext>,
+    input: GetManyInput,
+) -> Result<Vec<Option<Element>>, WasmError> {
+    let GetManyInput{ any_dht_hashes, get_options } = input;
+
+    // Get the network from the context
+    let network = call_context.host_access.network().clone();
+
+    // timeouts must be handled by the network
+    tokio_safe_block_on::tokio_safe_block_forever_on(async move {
+        let maybe_elements = call_context
+            .host_access
+            .workspace()
+            .write()
+            .await
+            .cascade(network)
+            .dht_get_many(any_dht_hashes, get_options)
+            .await
+            .map_err(|cascade_error| WasmError::Host(cascade_error.to_string()))?;
+
+        Ok(maybe_elements)
+    })
+}
+
+// we are relying on the get tests to show the commit/get round trip
+// @see get.rs
@@ -1,12 +1,33 @@
 use crate::core::ribosome::CallContext;
 use crate::core::ribosome::RibosomeT;
+use holochain_p2p::kitsune_p2p::KitsuneP2pError;
+use holochain_p2p::HolochainP2pCell;
 use holochain_p2p::HolochainP2pCellT;
+use holochain_p2p::HolochainP2pError;
+use holochain_types::prelude::*;
+use holochain_wasmer_host::prelude::WasmError;
 use holochain_zome_types::signal::RemoteSignal;
+use holochain_zome_types::signal::RemoteSignalDelivery;
+use holochain_zome_types::signal::RemoteSignalRetry;
 use holochain_zome_types::zome::FunctionName;
 use holochain_zome_types::zome::ZomeName;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::Instrument;
-use holochain_wasmer_host::prelude::WasmError;
+
+/// The zome fn every agent handles remote signals with.
+pub(crate) const RECV_REMOTE_SIGNAL: &str = "recv_remote_signal";
+
+/// The most times a remote signal is sent to one agent,
+/// whatever `RemoteSignalRetry::max_attempts` asks for.
+pub(crate) const MAX_REMOTE_SIGNAL_ATTEMPTS: u8 = 10;
+
+/// The longest wait between two attempts to send a remote signal.
+pub(crate) const MAX_REMOTE_SIGNAL_BACKOFF_MS: u64 = 30_000;
+
+/// How long we keep trying to send a remote signal to one agent,
+/// including every retry.
+pub(crate) const MAX_REMOTE_SIGNAL_DURATION: Duration = Duration::from_secs(60);
 
 #[tracing::instrument(skip(_ribosome, call_context, input))]
 pub fn remote_signal(
@@ -14,31 +35,32 @@ pub fn remote_signal(
     call_context: Arc<CallContext>,
     input: RemoteSignal,
 ) -> Result<(), WasmError> {
-    const FN_NAME: &str = "recv_remote_signal";
     // Timeouts and errors are ignored,
     // this is a send and forget operation.
     let network = call_context.host_access().network().clone();
-    let RemoteSignal { agents, signal } = input;
+    let RemoteSignal {
+        agents,
+        signal,
+        retry,
+    } = input;
     let zome_name: ZomeName = call_context.zome().into();
-    let fn_name: FunctionName = FN_NAME.into();
     for agent in agents {
         tokio::task::spawn(
             {
-                let mut network = network.clone();
+                let network = network.clone();
                 let zome_name = zome_name.clone();
-                let fn_name = fn_name.clone();
                 let payload = signal.clone();
                 async move {
                     tracing::debug!("sending to {:?}", agent);
-                    let result = network
-                        .call_remote(agent.clone(), zome_name, fn_name, None, payload)
-                        .await;
+                    let delivery =
+                        deliver_remote_signal(network, agent.clone(), zome_name, payload, retry)
+                            .await;
                     tracing::debug!("sent to {:?}", agent);
-                    if let Err(e) = result {
+                    if delivery != RemoteSignalDelivery::Delivered {
                         tracing::info!(
                             "Failed to send remote signal to {:?} because of {:?}",
                             agent,
-                            e
+                            delivery
                         );
                     }
                 }
@@ -49,6 +71,87 @@ pub fn remote_signal(
     Ok(())
 }
 
+/// Send a remote signal to one agent, retrying while it can't be reached.
+/// Gives up after [MAX_REMOTE_SIGNAL_DURATION].
+pub(crate) async fn deliver_remote_signal(
+    mut network: HolochainP2pCell,
+    agent: AgentPubKey,
+    zome_name: ZomeName,
+    payload: ExternIO,
+    retry: Option<RemoteSignalRetry>,
+) -> RemoteSignalDelivery {
+    let fn_name: FunctionName = RECV_REMOTE_SIGNAL.into();
+    let max_attempts = retry
+        .map(|r| r.max_attempts.max(1).min(MAX_REMOTE_SIGNAL_ATTEMPTS))
+        .unwrap_or(1);
+    let mut backoff_ms = retry
+        .map(|r| r.initial_backoff_ms.min(MAX_REMOTE_SIGNAL_BACKOFF_MS))
+        .unwrap_or(0);
+    let deadline = tokio::time::Instant::now() + MAX_REMOTE_SIGNAL_DURATION;
+    let mut attempt = 1;
+    loop {
+        // the remote may ask us to wait longer than our backoff
+        let mut retry_after_ms = 0;
+        let call = network.call_remote(
+            agent.clone(),
+            zome_name.clone(),
+            fn_name.clone(),
+            None,
+            payload.clone(),
+        );
+        let response = match tokio::time::timeout_at(deadline, call).await {
+            Ok(response) => response,
+            Err(_) => {
+                return RemoteSignalDelivery::Unreachable(format!(
+                    "gave up after {:?}",
+                    MAX_REMOTE_SIGNAL_DURATION
+                ))
+            }
+        };
+        let delivery = match response {
+            Ok(response) => match ZomeCallResponse::try_from(response) {
+                Ok(ZomeCallResponse::Ok(_)) => RemoteSignalDelivery::Delivered,
+                Ok(ZomeCallResponse::Unauthorized(..)) => RemoteSignalDelivery::Rejected(format!(
+                    "not authorized to call {}",
+                    RECV_REMOTE_SIGNAL
+                )),
+                Ok(ZomeCallResponse::NetworkError(e)) => RemoteSignalDelivery::Unreachable(e),
+                Err(e) => RemoteSignalDelivery::Rejected(format!("invalid response: {}", e)),
+            },
+            // the agent got the signal, but its node refused it
+            Err(HolochainP2pError::OtherKitsuneP2pError(KitsuneP2pError::Rejected(e))) => {
+                RemoteSignalDelivery::Rejected(e.into())
+            }
+            Err(HolochainP2pError::OtherKitsuneP2pError(KitsuneP2pError::Busy {
+                retry_after_ms: ms,
+            })) => {
+                retry_after_ms = ms;
+                RemoteSignalDelivery::Unreachable(format!("busy, retry after {}ms", ms))
+            }
+            Err(e) => RemoteSignalDelivery::Unreachable(e.to_string()),
+        };
+        match delivery {
+            delivery @ RemoteSignalDelivery::Unreachable(_) if attempt < max_attempts => {
+                let wait = Duration::from_millis(
+                    backoff_ms
+                        .max(retry_after_ms)
+                        .min(MAX_REMOTE_SIGNAL_BACKOFF_MS),
+                );
+                // no point waiting if we'd give up before the next attempt
+                if tokio::time::Instant::now() + wait >= deadline {
+                    return delivery;
+                }
+                tokio::time::delay_for(wait).await;
+                backoff_ms = backoff_ms
+                    .saturating_mul(2)
+                    .min(MAX_REMOTE_SIGNAL_BACKOFF_MS);
+                attempt += 1;
+            }
+            delivery => return delivery,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::AtomicUsize;
@@ -57,21 +160,36 @@ mod tests {
     use super::*;
     use crate::test_utils::sweetest::SweetDnaFile;
     use crate::test_utils::sweetest::{SweetAgents, SweetConductorBatch};
+    use ::fixt::prelude::*;
     use futures::future;
     use hdk3::prelude::*;
+    use holo_hash::fixt::AgentPubKeyFixturator;
     use holochain_types::dna::zome::inline_zome::InlineZome;
     use holochain_zome_types::signal::AppSignal;
     use matches::assert_matches;
 
     fn zome(agents: Vec<AgentPubKey>, num_signals: Arc<AtomicUsize>) -> InlineZome {
         let entry_def = EntryDef::default_with_id("entrydef");
+        let acked_agents = agents.clone();
 
         InlineZome::new_unique(vec![entry_def.clone()])
+            .callback("signal_others_acknowledged", move |api, ()| {
+                let signal = RemoteSignal {
+                    agents: acked_agents.clone(),
+                    signal: ExternIO::encode("Hey").unwrap(),
+                    retry: Some(RemoteSignalRetry {
+                        max_attempts: 2,
+                        initial_backoff_ms: 10,
+                    }),
+                };
+                Ok(api.remote_signal_acknowledged(signal)?)
+            })
             .callback("signal_others", move |api, ()| {
                 let signal = ExternIO::encode("Hey").unwrap();
                 let signal = RemoteSignal {
                     agents: agents.clone(),
                     signal,
+                    retry: None,
                 };
                 tracing::debug!("sending signal to {:?}", agents);
                 api.remote_signal(signal)?;
@@ -94,8 +212,11 @@ mod tests {
                     access: ().into(),
                     functions,
                 };
-                api.create(EntryWithDefId::new(EntryDefId::CapGrant, Entry::CapGrant(cap_grant_entry)))
-                    .unwrap();
+                api.create(EntryWithDefId::new(
+                    EntryDefId::CapGrant,
+                    Entry::CapGrant(cap_grant_entry),
+                ))
+                .unwrap();
 
                 Ok(InitCallbackResult::Pass)
             })
@@ -149,4 +270,59 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test(threaded_scheduler)]
+    #[cfg(feature = "test_utils")]
+    async fn remote_signal_acknowledged_test() -> anyhow::Result<()> {
+        observability::test_run().ok();
+        const NUM_CONDUCTORS: usize = 3;
+
+        let num_signals = Arc::new(AtomicUsize::new(0));
+
+        let mut conductors = SweetConductorBatch::from_standard_config(NUM_CONDUCTORS).await;
+        let agents =
+            future::join_all(conductors.iter().map(|c| SweetAgents::one(c.keystore()))).await;
+
+        // An agent nobody is running can't be reached
+        let mut signalled = agents.clone();
+        signalled.push(fixt!(AgentPubKey));
+
+        let (dna_file, _) = SweetDnaFile::unique_from_inline_zome(
+            "zome1",
+            zome(signalled.clone(), num_signals.clone()),
+        )
+        .await
+        .unwrap();
+
+        let apps = conductors
+            .setup_app_for_zipped_agents("app", &agents, &[dna_file.clone().into()])
+            .await;
+
+        conductors.exchange_peer_info().await;
+
+        let cells: Vec<_> = apps.cells_flattened();
+
+        let deliveries: RemoteSignalDeliveries = conductors[0]
+            .call(&cells[0].zome("zome1"), "signal_others_acknowledged", ())
+            .await;
+
+        // The signal was handled before the call returned
+        assert_eq!(num_signals.load(Ordering::SeqCst), NUM_CONDUCTORS);
+        assert_eq!(
+            signalled,
+            deliveries
+                .iter()
+                .map(|(a, _)| a.clone())
+                .collect::<Vec<_>>()
+        );
+        for (_, delivery) in &deliveries[..NUM_CONDUCTORS] {
+            assert_eq!(*delivery, RemoteSignalDelivery::Delivered);
+        }
+        assert_matches!(
+            deliveries[NUM_CONDUCTORS].1,
+            RemoteSignalDelivery::Unreachable(_)
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,43 @@
+use crate::core::ribosome::host_fn::remote_signal::deliver_remote_signal;
+use crate::core::ribosome::host_fn::remote_signal::MAX_REMOTE_SIGNAL_DURATION;
+use crate::core::ribosome::CallContext;
+use crate::core::ribosome::RibosomeT;
+use holochain_wasmer_host::prelude::WasmError;
+use holochain_zome_types::signal::RemoteSignal;
+use holochain_zome_types::signal::RemoteSignalDeliveries;
+use holochain_zome_types::zome::ZomeName;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tracing::instrument(skip(_ribosome, call_context, input))]
+pub fn remote_signal_acknowledged(
+    _ribosome: Arc<impl RibosomeT>,
+    call_context: Arc<CallContext>,
+    input: RemoteSignal,
+) -> Result<RemoteSignalDeliveries, WasmError> {
+    let network = call_context.host_access().network().clone();
+    let RemoteSignal {
+        agents,
+        signal,
+        retry,
+    } = input;
+    let zome_name: ZomeName = call_context.zome().into();
+    // all the agents are signalled at once
+    let deliveries = agents.iter().map(|agent| {
+        deliver_remote_signal(
+            network.clone(),
+            agent.clone(),
+            zome_name.clone(),
+            signal.clone(),
+            retry,
+        )
+    });
+    // each delivery gives up by itself after MAX_REMOTE_SIGNAL_DURATION,
+    // the extra second is only there so we never block forever
+    let deliveries = tokio_safe_block_on::tokio_safe_block_on(
+        futures::future::join_all(deliveries),
+        MAX_REMOTE_SIGNAL_DURATION + Duration::from_secs(1),
+    )
+    .map_err(|e| WasmError::Host(format!("remote signal timed out: {:?}", e)))?;
+    Ok(agents.into_iter().zip(deliveries).collect())
+}
@@ -0,0 +1,68 @@
+//! A compiler middleware which traps as soon as a guest grows its memory
+//! past a limit, so a guest that never calls into the host still can't
+//! take more memory than it's allowed.
+
+use wasmer_runtime_core::codegen::Event;
+use wasmer_runtime_core::codegen::EventSink;
+use wasmer_runtime_core::codegen::FunctionMiddleware;
+use wasmer_runtime_core::codegen::InternalEvent;
+use wasmer_runtime_core::module::ModuleInfo;
+use wasmer_runtime_core::wasmparser::Operator;
+use wasmer_runtime_core::wasmparser::Type as WpType;
+use wasmer_runtime_core::wasmparser::TypeOrFuncType as WpTypeOrFuncType;
+
+/// The size of a wasm memory page in bytes
+const WASM_PAGE_SIZE: u64 = 65536;
+
+/// The error a guest traps with when it grows its memory past the limit.
+#[derive(Debug)]
+pub struct MemoryLimitExceededError;
+
+/// Checks the memory size after every `memory.grow` the guest makes.
+pub struct MemoryLimit {
+    max_pages: u32,
+}
+
+impl MemoryLimit {
+    /// Trap once the guest's memory is bigger than `max_memory_bytes`.
+    pub fn new(max_memory_bytes: u64) -> Self {
+        Self {
+            max_pages: (max_memory_bytes / WASM_PAGE_SIZE).min(u32::MAX as u64) as u32,
+        }
+    }
+}
+
+impl FunctionMiddleware for MemoryLimit {
+    type Error = String;
+
+    fn feed_event<'a, 'b: 'a>(
+        &mut self,
+        op: Event<'a, 'b>,
+        _module_info: &ModuleInfo,
+        sink: &mut EventSink<'a, 'b>,
+        _source_loc: u32,
+    ) -> Result<(), Self::Error> {
+        let grows_memory = matches!(
+            op,
+            Event::Wasm(Operator::MemoryGrow { .. })
+                | Event::WasmOwned(Operator::MemoryGrow { .. })
+        );
+        sink.push(op);
+        if grows_memory {
+            // if memory.size > max_pages { trap }
+            sink.push(Event::WasmOwned(Operator::MemorySize { reserved: 0 }));
+            sink.push(Event::WasmOwned(Operator::I32Const {
+                value: self.max_pages as i32,
+            }));
+            sink.push(Event::WasmOwned(Operator::I32GtU));
+            sink.push(Event::WasmOwned(Operator::If {
+                ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
+            }));
+            sink.push(Event::Internal(InternalEvent::Breakpoint(Box::new(|_| {
+                Err(Box::new(MemoryLimitExceededError))
+            }))));
+            sink.push(Event::WasmOwned(Operator::End));
+        }
+        Ok(())
+    }
+}
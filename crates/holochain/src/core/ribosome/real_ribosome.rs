@@ -8,6 +8,10 @@ use super::host_fn::get_agent_activity::get_agent_activity;
 use super::host_fn::HostFnApi;
 use super::HostAccess;
 use super::ZomeCallHostAccess;
+use crate::conductor::config::WasmLimits;
+use crate::conductor::module_cache;
+use crate::conductor::module_cache::ModuleCache;
+use crate::conductor::module_cache::ModuleLru;
 use crate::core::ribosome::error::RibosomeError;
 use crate::core::ribosome::error::RibosomeResult;
 use crate::core::ribosome::guest_callback::entry_defs::EntryDefsInvocation;
@@ -42,11 +46,13 @@ use crate::core::ribosome::host_fn::get::get;
 use crate::core::ribosome::host_fn::get_details::get_details;
 use crate::core::ribosome::host_fn::get_link_details::get_link_details;
 use crate::core::ribosome::host_fn::get_links::get_links;
+use crate::core::ribosome::host_fn::get_many::get_many;
 use crate::core::ribosome::host_fn::hash_entry::hash_entry;
 use crate::core::ribosome::host_fn::property::property;
 use crate::core::ribosome::host_fn::query::query;
 use crate::core::ribosome::host_fn::random_bytes::random_bytes;
 use crate::core::ribosome::host_fn::remote_signal::remote_signal;
+use crate::core::ribosome::host_fn::remote_signal_acknowledged::remote_signal_acknowledged;
 use crate::core::ribosome::host_fn::schedule::schedule;
 use crate::core::ribosome::host_fn::show_env::show_env;
 use crate::core::ribosome::host_fn::sign::sign;
@@ -68,11 +74,28 @@ use fallible_iterator::FallibleIterator;
 use holochain_types::prelude::*;
 
 use holochain_wasmer_host::prelude::*;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
 use std::sync::Arc;
+use wasmer_middleware_common::metering;
+use wasmer_runtime_core::module::ExportIndex;
 
 /// Path to the wasm cache path
 const WASM_CACHE_PATH_ENV: &str = "HC_WASM_CACHE_PATH";
 
+/// How many metered modules are held in memory at once
+const METERED_MODULES_MAX_LOADED: usize = 64;
+
+/// Modules compiled with instruction metering or a memory limit by ribosomes
+/// without a [ModuleCache], keyed by wasm cache key and limits.
+/// The limits are baked into the compiled code so each needs its own module.
+static METERED_MODULES: Lazy<parking_lot::Mutex<ModuleLru<(Vec<u8>, WasmLimits), Module>>> =
+    Lazy::new(|| parking_lot::Mutex::new(ModuleLru::new(METERED_MODULES_MAX_LOADED)));
+
+/// The function exports of each wasm, which never change for a given [WasmHash].
+static WASM_EXPORTS: Lazy<parking_lot::RwLock<HashMap<WasmHash, Vec<FunctionName>>>> =
+    Lazy::new(Default::default);
+
 /// The only RealRibosome is a Wasm ribosome.
 /// note that this is cloned on every invocation so keep clones cheap!
 #[derive(Clone, Debug)]
@@ -82,12 +105,33 @@ pub struct RealRibosome {
     //      - is already in the wasm cache, and only include the DnaDef portion
     //      - here in the ribosome.
     pub dna_file: DnaFile,
+    /// Instruction and memory limits applied to every wasm call.
+    pub wasm_limits: WasmLimits,
+    /// The conductor's compiled module cache. Without one, modules are only
+    /// cached on disk if `HC_WASM_CACHE_PATH` is set.
+    pub module_cache: Option<Arc<ModuleCache>>,
 }
 
 impl RealRibosome {
     /// Create a new instance
     pub fn new(dna_file: DnaFile) -> Self {
-        Self { dna_file }
+        Self {
+            dna_file,
+            wasm_limits: WasmLimits::default(),
+            module_cache: None,
+        }
+    }
+
+    /// Apply resource limits to all calls made through this ribosome
+    pub fn with_wasm_limits(mut self, wasm_limits: WasmLimits) -> Self {
+        self.wasm_limits = wasm_limits;
+        self
+    }
+
+    /// Load compiled modules from this cache rather than compiling them
+    pub fn with_module_cache(mut self, module_cache: Option<Arc<ModuleCache>>) -> Self {
+        self.module_cache = module_cache;
+        self
     }
 
     pub fn dna_file(&self) -> &DnaFile {
@@ -96,6 +140,18 @@ impl RealRibosome {
 
     pub fn module(&self, zome_name: &ZomeName) -> RibosomeResult<Module> {
         let wasm: Arc<Box<[u8]>> = self.dna_file.get_wasm_for_zome(zome_name)?.code();
+        if let Some(module_cache) = &self.module_cache {
+            let wasm_hash = self
+                .dna_file
+                .dna()
+                .get_wasm_zome(zome_name)?
+                .wasm_hash
+                .clone();
+            return module_cache.get(&wasm_hash, &wasm, &self.wasm_limits);
+        }
+        if !self.wasm_limits.is_unlimited() {
+            return self.metered_module(zome_name, &wasm);
+        }
         Ok(holochain_wasmer_host::instantiate::module(
             &self.wasm_cache_key(zome_name)?,
             &wasm,
@@ -103,6 +159,21 @@ impl RealRibosome {
         )?)
     }
 
+    /// Compile the zome within this ribosome's limits, for ribosomes without
+    /// a conductor's [ModuleCache] to keep the compiled module in.
+    fn metered_module(&self, zome_name: &ZomeName, wasm: &[u8]) -> RibosomeResult<Module> {
+        let key = (
+            self.wasm_cache_key(zome_name)?.to_vec(),
+            self.wasm_limits.clone(),
+        );
+        if let Some(module) = METERED_MODULES.lock().get(&key) {
+            return Ok(module);
+        }
+        let module = module_cache::compile(wasm, &self.wasm_limits)?;
+        METERED_MODULES.lock().insert(key, module.clone());
+        Ok(module)
+    }
+
     pub fn wasm_cache_key(&self, zome_name: &ZomeName) -> Result<&[u8], DnaError> {
         // TODO: make this actually the hash of the wasm once we can do that
         // watch out for cache misses in the tests that make things slooow if you change this!
@@ -117,8 +188,14 @@ impl RealRibosome {
 
     pub fn instance(&self, call_context: CallContext) -> RibosomeResult<Instance> {
         let zome_name = call_context.zome.zome_name().clone();
-        let wasm: Arc<Box<[u8]>> = self.dna_file.get_wasm_for_zome(&zome_name)?.code();
         let imports: ImportObject = Self::imports(self, call_context);
+        if !self.wasm_limits.is_unlimited() || self.module_cache.is_some() {
+            return Ok(self
+                .module(&zome_name)?
+                .instantiate(&imports)
+                .map_err(|e| WasmError::Compile(e.to_string()))?);
+        }
+        let wasm: Arc<Box<[u8]>> = self.dna_file.get_wasm_for_zome(&zome_name)?.code();
         Ok(holochain_wasmer_host::instantiate::instantiate(
             self.wasm_cache_key(&zome_name)?,
             &wasm,
@@ -129,16 +206,38 @@ impl RealRibosome {
 
     fn imports(&self, call_context: CallContext) -> ImportObject {
         let host_fn_access = (&call_context.host_access()).into();
+        // A read-only zome call can't write but can still call other zome fns,
+        // which `call` makes read-only in turn
+        let read_only_zome_call = matches!(
+            call_context.host_access(),
+            HostAccess::ZomeCall(ZomeCallHostAccess {
+                read_only: true,
+                ..
+            })
+        );
 
         // it is important that RealRibosome and ZomeCallInvocation are cheap to clone here
         let self_arc = std::sync::Arc::new((*self).clone());
         let call_context_arc = std::sync::Arc::new(call_context);
+        let max_memory_bytes = self.wasm_limits.max_memory_bytes;
 
         macro_rules! invoke_host_function {
             ( $host_function:ident ) => {{
                 let closure_self_arc = std::sync::Arc::clone(&self_arc);
                 let closure_call_context_arc = std::sync::Arc::clone(&call_context_arc);
                 move |ctx: &mut Ctx, guest_allocation_ptr: GuestPtr| -> Result<Len, WasmError> {
+                    // the memory limit middleware traps as soon as the guest grows past the
+                    // limit, but a module can also start out bigger than the limit, so host
+                    // calls are stopped here too and the overall call is failed by
+                    // `check_wasm_limits`
+                    if let Some(max_memory_bytes) = max_memory_bytes {
+                        if memory_bytes(ctx) > max_memory_bytes {
+                            return Err(WasmError::Host(format!(
+                                "Wasm memory exceeded the limit of {} bytes",
+                                max_memory_bytes
+                            )));
+                        }
+                    }
                     let result = match $crate::holochain_wasmer_host::guest::from_guest_ptr(
                         ctx,
                         guest_allocation_ptr,
@@ -297,6 +396,7 @@ impl RealRibosome {
         } = host_fn_access
         {
             ns.insert("__get", func!(invoke_host_function!(get)));
+            ns.insert("__get_many", func!(invoke_host_function!(get_many)));
             ns.insert("__get_details", func!(invoke_host_function!(get_details)));
             ns.insert("__get_links", func!(invoke_host_function!(get_links)));
             ns.insert(
@@ -310,6 +410,7 @@ impl RealRibosome {
             ns.insert("__query", func!(invoke_host_function!(query)));
         } else {
             ns.insert("__get", func!(invoke_host_function!(unreachable)));
+            ns.insert("__get_many", func!(invoke_host_function!(unreachable)));
             ns.insert("__get_details", func!(invoke_host_function!(unreachable)));
             ns.insert("__get_links", func!(invoke_host_function!(unreachable)));
             ns.insert(
@@ -333,9 +434,31 @@ impl RealRibosome {
                 "__remote_signal",
                 func!(invoke_host_function!(remote_signal)),
             );
+            ns.insert(
+                "__remote_signal_acknowledged",
+                func!(invoke_host_function!(remote_signal_acknowledged)),
+            );
         } else {
             ns.insert("__call_remote", func!(invoke_host_function!(unreachable)));
             ns.insert("__remote_signal", func!(invoke_host_function!(unreachable)));
+            ns.insert(
+                "__remote_signal_acknowledged",
+                func!(invoke_host_function!(unreachable)),
+            );
+        }
+
+        if read_only_zome_call
+            || matches!(
+                host_fn_access,
+                HostFnAccess {
+                    write_workspace: Permission::Allow,
+                    ..
+                }
+            )
+        {
+            ns.insert("__call", func!(invoke_host_function!(call)));
+        } else {
+            ns.insert("__call", func!(invoke_host_function!(unreachable)));
         }
 
         if let HostFnAccess {
@@ -343,7 +466,6 @@ impl RealRibosome {
             ..
         } = host_fn_access
         {
-            ns.insert("__call", func!(invoke_host_function!(call)));
             ns.insert("__create", func!(invoke_host_function!(create)));
             ns.insert("__emit_signal", func!(invoke_host_function!(emit_signal)));
             ns.insert("__create_link", func!(invoke_host_function!(create_link)));
@@ -352,7 +474,6 @@ impl RealRibosome {
             ns.insert("__delete", func!(invoke_host_function!(delete)));
             ns.insert("__schedule", func!(invoke_host_function!(schedule)));
         } else {
-            ns.insert("__call", func!(invoke_host_function!(unreachable)));
             ns.insert("__create", func!(invoke_host_function!(unreachable)));
             ns.insert("__emit_signal", func!(invoke_host_function!(unreachable)));
             ns.insert("__create_link", func!(invoke_host_function!(unreachable)));
@@ -365,6 +486,43 @@ impl RealRibosome {
 
         imports
     }
+
+    /// Fail the call if the instance went over any of the configured limits.
+    fn check_wasm_limits(
+        &self,
+        instance: &Instance,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> RibosomeResult<()> {
+        if let Some(max_instructions) = self.wasm_limits.max_instructions {
+            if metering::get_points_used(instance) > max_instructions {
+                return Err(RibosomeError::WasmLimitExceeded(
+                    zome_name.clone(),
+                    fn_name.clone(),
+                    format!("more than {} instructions", max_instructions),
+                ));
+            }
+        }
+        if let Some(max_memory_bytes) = self.wasm_limits.max_memory_bytes {
+            let used = memory_bytes(instance.context());
+            if used > max_memory_bytes {
+                return Err(RibosomeError::WasmLimitExceeded(
+                    zome_name.clone(),
+                    fn_name.clone(),
+                    format!(
+                        "{} bytes of memory with a limit of {}",
+                        used, max_memory_bytes
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The current size of the instance's linear memory in bytes
+fn memory_bytes(ctx: &Ctx) -> u64 {
+    ctx.memory(0).size().bytes().0 as u64
 }
 
 /// General purpose macro which relies heavily on various impls of the form:
@@ -427,6 +585,7 @@ impl RibosomeT for RealRibosome {
                         // @todo - is this a problem for large payloads like entries?
                         invocation.to_owned().host_input()?,
                     );
+                    self.check_wasm_limits(&instance, zome.zome_name(), to_call)?;
 
                     Ok(Some(result?))
                 } else {
@@ -444,6 +603,33 @@ impl RibosomeT for RealRibosome {
         }
     }
 
+    fn zome_exports(&self, zome: &Zome) -> RibosomeResult<Vec<FunctionName>> {
+        match zome.zome_def() {
+            ZomeDef::Wasm(WasmZome { wasm_hash }) => {
+                if let Some(exports) = WASM_EXPORTS.read().get(wasm_hash) {
+                    return Ok(exports.clone());
+                }
+                let mut exports: Vec<FunctionName> = self
+                    .module(zome.zome_name())?
+                    .info()
+                    .exports
+                    .iter()
+                    // skip the memory handling exports used by holochain_wasmer
+                    .filter(|(name, index)| {
+                        matches!(index, ExportIndex::Func(_)) && !name.starts_with("__")
+                    })
+                    .map(|(name, _)| FunctionName(name.clone()))
+                    .collect();
+                exports.sort();
+                WASM_EXPORTS
+                    .write()
+                    .insert(wasm_hash.clone(), exports.clone());
+                Ok(exports)
+            }
+            ZomeDef::Inline(zome) => Ok(zome.functions()),
+        }
+    }
+
     fn call_iterator<I: crate::core::ribosome::Invocation>(
         &self,
         access: HostAccess,
@@ -572,4 +758,46 @@ pub mod wasm_test {
 
         assert_eq!("foobar", bar_result.as_str());
     }
+
+    #[tokio::test(threaded_scheduler)]
+    /// A call that runs over the instruction limit is aborted with a distinct error.
+    async fn ribosome_wasm_limits_test() {
+        use crate::conductor::config::WasmLimits;
+        use crate::core::ribosome::error::RibosomeError;
+        use crate::core::ribosome::RibosomeT;
+        use crate::fixt::*;
+        use matches::assert_matches;
+
+        let host_access = fixt!(ZomeCallHostAccess, Predictable);
+        let ribosome = RealRibosomeFixturator::new(curve::Zomes(vec![TestWasm::HdkExtern]))
+            .next()
+            .unwrap()
+            .with_wasm_limits(WasmLimits {
+                max_instructions: Some(1),
+                max_memory_bytes: None,
+            });
+        let invocation = ZomeCallInvocationFixturator::new(NamedInvocation(
+            fixt!(CellId),
+            TestWasm::HdkExtern,
+            "foo".to_string(),
+            ExternIO::encode(()).unwrap(),
+        ))
+        .next()
+        .unwrap();
+
+        assert_matches!(
+            ribosome.call_zome_function(host_access.clone(), invocation.clone()),
+            Err(RibosomeError::WasmLimitExceeded(_, _, _))
+        );
+
+        // Every guest has at least a page of memory
+        let ribosome = ribosome.with_wasm_limits(WasmLimits {
+            max_instructions: None,
+            max_memory_bytes: Some(1),
+        });
+        assert_matches!(
+            ribosome.call_zome_function(host_access, invocation),
+            Err(RibosomeError::WasmLimitExceeded(_, _, _))
+        );
+    }
 }
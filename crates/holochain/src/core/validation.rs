@@ -2,7 +2,9 @@
 use std::convert::TryFrom;
 
 use derivative::Derivative;
+use holo_hash::AgentPubKey;
 use holo_hash::DhtOpHash;
+use holochain_p2p::HolochainP2pCellT;
 use holochain_types::dht_op::DhtOp;
 
 use super::workflow::error::WorkflowResult;
@@ -83,3 +85,67 @@ impl OutcomeOrError<ValidationOutcome, SysValidationError> {
         Err(SourceChainError::InvalidCommit(ValidationOutcome::try_from(self)?.to_string()).into())
     }
 }
+
+/// Count an op we rejected against the reputation of its author,
+/// if it came from the network. The agent that sent it to us may just
+/// be passing it on through gossip, so it isn't held responsible.
+pub(crate) async fn report_rejected_op<N: HolochainP2pCellT>(
+    network: &mut N,
+    op: &DhtOp,
+    from_agent: Option<&AgentPubKey>,
+) {
+    if from_agent.is_none() {
+        return;
+    }
+    let author = op.header().author().clone();
+    if author == network.from_agent() {
+        return;
+    }
+    if let Err(e) = network.report_invalid_data(author).await {
+        tracing::warn!(msg = "Failed to report invalid data", ?e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixt::AgentPubKeyFixturator;
+    use crate::fixt::DnaHashFixturator;
+    use crate::fixt::SignatureFixturator;
+    use ::fixt::prelude::*;
+    use holochain_p2p::MockHolochainP2pCellT;
+    use holochain_zome_types::header;
+    use holochain_zome_types::timestamp;
+    use mockall::predicate;
+
+    fn op_by(author: AgentPubKey) -> DhtOp {
+        let header = header::Header::Dna(header::Dna {
+            author,
+            timestamp: timestamp::now().into(),
+            hash: fixt!(DnaHash),
+        });
+        DhtOp::StoreElement(fixt!(Signature), header, None)
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn rejected_ops_count_against_the_author_not_the_relay() {
+        let us = fixt!(AgentPubKey);
+        let relay = fixt!(AgentPubKey);
+        let author = fixt!(AgentPubKey);
+
+        let mut network = MockHolochainP2pCellT::new();
+        network.expect_from_agent().return_const(us.clone());
+        // only ever the author, never the relay
+        network
+            .expect_report_invalid_data()
+            .with(predicate::eq(author.clone()))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        // relayed by gossip
+        report_rejected_op(&mut network, &op_by(author.clone()), Some(&relay)).await;
+        // our own op, or one we authored
+        report_rejected_op(&mut network, &op_by(author), None).await;
+        report_rejected_op(&mut network, &op_by(us), Some(&relay)).await;
+    }
+}
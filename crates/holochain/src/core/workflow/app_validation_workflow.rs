@@ -18,6 +18,7 @@ use crate::conductor::entry_def_store::get_entry_def;
 use crate::core::queue_consumer::OneshotWriter;
 use crate::core::queue_consumer::TriggerSender;
 use crate::core::queue_consumer::WorkComplete;
+use crate::core::ribosome::error::RibosomeError;
 use crate::core::ribosome::guest_callback::validate::ValidateHostAccess;
 use crate::core::ribosome::guest_callback::validate::ValidateInvocation;
 use crate::core::ribosome::guest_callback::validate::ValidateResult;
@@ -33,6 +34,7 @@ use crate::core::ribosome::real_ribosome::RealRibosome;
 use crate::core::ribosome::Invocation;
 use crate::core::ribosome::RibosomeT;
 use crate::core::ribosome::ZomesToInvoke;
+use crate::core::validation::report_rejected_op;
 use crate::core::validation::DhtOpOrder;
 use crate::core::validation::OrderedOp;
 use error::AppValidationResult;
@@ -144,7 +146,7 @@ async fn app_validation_workflow_inner(
         match &vlv.status {
             ValidationLimboStatus::AwaitingAppDeps(_) | ValidationLimboStatus::SysValidated => {
                 // Validate this op
-                let outcome = validate_op(
+                let outcome: AppValidationResult<Outcome> = validate_op(
                     op.clone(),
                     vlv.from_agent.clone(),
                     &conductor_api,
@@ -153,7 +155,27 @@ async fn app_validation_workflow_inner(
                 )
                 .await
                 // Get the outcome or return the error
-                .or_else(|outcome_or_err| outcome_or_err.try_into())?;
+                .or_else(|outcome_or_err| outcome_or_err.try_into());
+                let outcome = match outcome {
+                    // Every node sets its own wasm limits, so going over them can't make
+                    // an op invalid or honest nodes would disagree. Running it again would
+                    // only go over the same limits, so abandon it.
+                    Err(AppValidationError::RibosomeError(
+                        e @ RibosomeError::WasmLimitExceeded(..),
+                    )) => {
+                        warn!(
+                            ?hash,
+                            "Abandoning op whose validation went over the wasm limits: {}", e
+                        );
+                        let iv = IntegrationLimboValue {
+                            validation_status: ValidationStatus::Abandoned,
+                            op: vlv.op,
+                        };
+                        workspace.put_int_limbo(hash, iv, op)?;
+                        continue;
+                    }
+                    r => r?,
+                };
 
                 match outcome {
                     Outcome::Accepted => {
@@ -168,6 +190,8 @@ async fn app_validation_workflow_inner(
                         workspace.put_val_limbo(hash, vlv)?;
                     }
                     Outcome::Rejected(_) => {
+                        report_rejected_op(&mut network.clone(), &vlv.op, vlv.from_agent.as_ref())
+                            .await;
                         let iv = IntegrationLimboValue {
                             op: vlv.op,
                             validation_status: ValidationStatus::Rejected,
@@ -216,8 +240,12 @@ async fn validate_op(
         get_associated_entry_def(&element, dna_file.dna(), conductor_api, cascade).await?
     };
 
-    // Create the ribosome
-    let ribosome = RealRibosome::new(dna_file);
+    // Create the ribosome with the conductor's limits so a misbehaving
+    // validation callback can't hang the workflow
+    let wasm_limits = conductor_api.wasm_limits(conductor_api.cell_id().dna_hash());
+    let ribosome = RealRibosome::new(dna_file)
+        .with_wasm_limits(wasm_limits)
+        .with_module_cache(conductor_api.module_cache());
 
     // Get the validation package
     let validation_package = get_validation_package(
@@ -833,7 +861,7 @@ fn run_validation_callback_inner(
     workspace_lock: CallZomeWorkspaceLock,
     network: HolochainP2pCell,
 ) -> AppValidationResult<Outcome> {
-    let validate: ValidateResult = ribosome.run_validate(
+    let validate = ribosome.run_validate(
         ValidateHostAccess::new(workspace_lock, network),
         ValidateInvocation {
             zomes_to_invoke,
@@ -841,8 +869,8 @@ fn run_validation_callback_inner(
             validation_package,
             entry_def_id,
         },
-    )?;
-    match validate {
+    );
+    match validate? {
         ValidateResult::Valid => Ok(Outcome::Accepted),
         ValidateResult::Invalid(reason) => Ok(Outcome::Rejected(reason)),
         ValidateResult::UnresolvedDependencies(hashes) => Ok(Outcome::AwaitingDeps(hashes)),
@@ -887,8 +915,7 @@ pub fn run_link_validation_callback<I: Invocation + 'static>(
     network: HolochainP2pCell,
 ) -> AppValidationResult<Outcome> {
     let access = ValidateLinkHostAccess::new(workspace_lock, network);
-    let validate = ribosome.run_validate_link(access, invocation)?;
-    match validate {
+    match ribosome.run_validate_link(access, invocation)? {
         ValidateLinkResult::Valid => Ok(Outcome::Accepted),
         ValidateLinkResult::Invalid(reason) => Ok(Outcome::Rejected(reason)),
         ValidateLinkResult::UnresolvedDependencies(hashes) => Ok(Outcome::AwaitingDeps(hashes)),
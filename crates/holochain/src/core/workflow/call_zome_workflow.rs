@@ -1,5 +1,6 @@
 use super::app_validation_workflow;
 use super::error::WorkflowResult;
+use super::integrate_dht_ops_workflow::integrate_to_authored;
 use super::sys_validation_workflow::sys_validate_element;
 use crate::conductor::api::CellConductorApiT;
 use crate::conductor::interface::SignalBroadcaster;
@@ -24,6 +25,7 @@ use holochain_state::metadata::MetadataBufT;
 use holochain_state::source_chain::SourceChain;
 use holochain_state::source_chain::SourceChainError;
 use holochain_state::workspace::Workspace;
+use holochain_state::workspace::WorkspaceError;
 use holochain_state::workspace::WorkspaceResult;
 use holochain_zome_types::element::Element;
 
@@ -33,6 +35,10 @@ use tracing::instrument;
 
 pub mod call_zome_workspace_lock;
 
+/// How many times a zome call will be rebased onto a moved chain head
+/// before giving up and returning [SourceChainError::HeadMoved].
+pub const MAX_REBASE_ATTEMPTS: usize = 3;
+
 #[cfg(test)]
 mod validation_test;
 
@@ -61,26 +67,81 @@ pub async fn call_zome_workflow<
     Ribosome: RibosomeT + Send + 'static,
     C: CellConductorApiT,
 >(
-    workspace_lock: CallZomeWorkspaceLock,
+    mut workspace_lock: CallZomeWorkspaceLock,
     network: HolochainP2pCell,
     keystore: KeystoreSender,
     writer: OneshotWriter,
     args: CallZomeWorkflowArgs<Ribosome, C>,
     mut trigger_produce_dht_ops: TriggerSender,
 ) -> WorkflowResult<ZomeCallResult> {
-    let should_write = args.is_root_zome_call;
-    let result = call_zome_workflow_inner(workspace_lock.clone(), network, keystore, args).await?;
+    // Read-only calls can't have written anything, so there's nothing to commit
+    let read_only = args.invocation.read_only;
+    let should_write = args.is_root_zome_call && !read_only;
+    let zome = args.invocation.zome.clone();
+    let conductor_api = args.conductor_api.clone();
+    let mut chain_head_start_len = workspace_lock.read().await.source_chain.len();
+    let (ribosome, result) =
+        call_zome_workflow_inner(workspace_lock.clone(), network.clone(), keystore, args).await?;
 
     // --- END OF WORKFLOW, BEGIN FINISHER BOILERPLATE ---
 
     // commit the workspace
     if should_write {
-        let mut guard = workspace_lock.write().await;
-        let workspace = &mut guard;
-        writer.with_writer(|writer| Ok(workspace.flush_to_txn_ref(writer)?))?;
+        let mut rebase_attempts = 0;
+        loop {
+            let flushed = {
+                let mut guard = workspace_lock.write().await;
+                let workspace = &mut guard;
+                writer.with_writer(|writer| Ok(workspace.flush_to_txn_ref(writer)?))
+            };
+            match flushed {
+                Ok(()) => break,
+                // Another call moved the chain head while this one was running
+                // against its snapshot, so try to replay our elements on the new head.
+                Err(WorkspaceError::SourceChainError(SourceChainError::HeadMoved(
+                    bundle_head,
+                    current_head,
+                ))) => {
+                    let rebased = if rebase_attempts < MAX_REBASE_ATTEMPTS {
+                        rebase_attempts += 1;
+                        rebase_workspace(&workspace_lock, chain_head_start_len, &result).await?
+                    } else {
+                        None
+                    };
+                    match rebased {
+                        Some((rebased_lock, rebased_start_len)) => {
+                            tracing::debug!(
+                                rebase_attempts,
+                                "rebased zome call onto moved chain head"
+                            );
+                            validate_new_elements(
+                                &ribosome,
+                                &zome,
+                                rebased_start_len,
+                                rebased_lock.clone(),
+                                network.clone(),
+                                &conductor_api,
+                            )
+                            .await?;
+                            workspace_lock = rebased_lock;
+                            chain_head_start_len = rebased_start_len;
+                        }
+                        // Give up and let the client retry the whole call
+                        None => {
+                            return Err(
+                                SourceChainError::HeadMoved(bundle_head, current_head).into()
+                            )
+                        }
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
     }
 
-    trigger_produce_dht_ops.trigger();
+    if !read_only {
+        trigger_produce_dht_ops.trigger();
+    }
 
     Ok(result)
 }
@@ -94,7 +155,7 @@ async fn call_zome_workflow_inner<
     network: HolochainP2pCell,
     keystore: KeystoreSender,
     args: CallZomeWorkflowArgs<Ribosome, C>,
-) -> WorkflowResult<ZomeCallResult> {
+) -> WorkflowResult<(Ribosome, ZomeCallResult)> {
     let CallZomeWorkflowArgs {
         ribosome,
         invocation,
@@ -105,6 +166,7 @@ async fn call_zome_workflow_inner<
 
     let call_zome_handle = conductor_api.clone().into_call_zome_handle();
     let zome = invocation.zome.clone();
+    let read_only = invocation.read_only;
 
     // Get the current head
     let chain_head_start_len = workspace_lock.read().await.source_chain.len();
@@ -122,6 +184,7 @@ async fn call_zome_workflow_inner<
                 signal_tx,
                 call_zome_handle,
                 invocation.cell_id.clone(),
+                read_only,
             );
             let result = ribosome.call_zome_function(host_access, invocation);
             (ribosome, result)
@@ -130,6 +193,33 @@ async fn call_zome_workflow_inner<
     .await?;
     tracing::trace!(line = line!());
 
+    // A read-only call was denied the host functions which write to the chain,
+    // so skip validation rather than contending for the workspace write lock
+    if !read_only {
+        validate_new_elements(
+            &ribosome,
+            &zome,
+            chain_head_start_len,
+            workspace_lock,
+            network,
+            &conductor_api,
+        )
+        .await?;
+    }
+
+    Ok((ribosome, result))
+}
+
+/// Run sys and app validation on every element added to the source chain
+/// since `chain_head_start_len`.
+async fn validate_new_elements<Ribosome: RibosomeT + Send + 'static, C: CellConductorApiT>(
+    ribosome: &Ribosome,
+    zome: &Zome,
+    chain_head_start_len: usize,
+    workspace_lock: CallZomeWorkspaceLock,
+    network: HolochainP2pCell,
+    conductor_api: &C,
+) -> WorkflowResult<()> {
     let to_app_validate = {
         let mut workspace = workspace_lock.write().await;
         // Get the new head
@@ -144,7 +234,7 @@ async fn call_zome_workflow_inner<
             // Loop forwards through all the new elements
             let mut i = chain_head_start_len;
             while let Some(element) = workspace.source_chain.get_at_index(i as u32)? {
-                sys_validate_element(&element, &mut workspace, network.clone(), &conductor_api)
+                sys_validate_element(&element, &mut workspace, network.clone(), conductor_api)
                     .await
                     // If the was en error exit
                     // If the validation failed, exit with an InvalidCommit
@@ -200,7 +290,7 @@ async fn call_zome_workflow_inner<
                             link_add,
                             base,
                             target,
-                            &ribosome,
+                            ribosome,
                             workspace_lock.clone(),
                             network.clone(),
                         )?,
@@ -210,7 +300,7 @@ async fn call_zome_workflow_inner<
                     app_validation_workflow::run_delete_link_validation_callback(
                         zome.clone(),
                         delete_link.clone(),
-                        &ribosome,
+                        ribosome,
                         workspace_lock.clone(),
                         network.clone(),
                     )?,
@@ -219,10 +309,10 @@ async fn call_zome_workflow_inner<
                     app_validation_workflow::run_validation_callback_direct(
                         zome.clone(),
                         chain_element,
-                        &ribosome,
+                        ribosome,
                         workspace_lock.clone(),
                         network.clone(),
-                        &conductor_api,
+                        conductor_api,
                     )
                     .await?,
                 ),
@@ -257,7 +347,74 @@ async fn call_zome_workflow_inner<
         }
     }
 
-    Ok(result)
+    Ok(())
+}
+
+/// Replay the elements a zome call added to its snapshot on top of the
+/// current persisted chain head, and carry over everything else the call
+/// would have flushed.
+///
+/// Returns the rebased workspace and its new chain start length, or None if
+/// the call can't be transparently rebased because its response or one of its
+/// new entries refers to a header whose hash the rebase would change.
+async fn rebase_workspace(
+    workspace_lock: &CallZomeWorkspaceLock,
+    chain_head_start_len: usize,
+    result: &ZomeCallResult,
+) -> WorkflowResult<Option<(CallZomeWorkspaceLock, usize)>> {
+    let mut workspace = workspace_lock.write().await;
+    let mut rebased_workspace = CallZomeWorkspace::new(workspace.env().clone())?;
+    let rebased_start_len = rebased_workspace.source_chain.len();
+    let rebased = workspace
+        .source_chain
+        .rebase_onto(chain_head_start_len, &mut rebased_workspace.source_chain)
+        .await?;
+
+    // Zome call outputs and app entries are msgpack, so any header hash the
+    // guest put in them appears as that hash's own msgpack encoding
+    let encoded_hashes = rebased
+        .keys()
+        .map(holochain_serialized_bytes::encode)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(SourceChainError::from)?;
+    let refers_to_rebased = |bytes: &[u8]| {
+        encoded_hashes
+            .iter()
+            .any(|hash| contains_encoded(bytes, hash))
+    };
+    if let Ok(ZomeCallResponse::Ok(output)) = result {
+        if refers_to_rebased(&output.0) {
+            return Ok(None);
+        }
+    }
+    for i in rebased_start_len..rebased_workspace.source_chain.len() {
+        let element = rebased_workspace
+            .source_chain
+            .get_at_index(i as u32)?
+            .ok_or_else(|| SourceChainError::ElementMissing(format!("index {}", i)))?;
+        if let Some(Entry::App(app_entry)) = element.entry().as_option() {
+            if refers_to_rebased(app_entry.as_ref().bytes()) {
+                return Ok(None);
+            }
+        }
+        integrate_to_authored(
+            &element,
+            rebased_workspace.source_chain.elements(),
+            &mut rebased_workspace.meta_authored,
+        )?;
+    }
+    // Anything the call cached from the network doesn't depend on the chain head
+    std::mem::swap(
+        &mut rebased_workspace.element_cache,
+        &mut workspace.element_cache,
+    );
+    std::mem::swap(&mut rebased_workspace.meta_cache, &mut workspace.meta_cache);
+    Ok(Some((rebased_workspace.into(), rebased_start_len)))
+}
+
+/// Whether msgpack `bytes` contain the msgpack encoded value `encoded`.
+fn contains_encoded(bytes: &[u8], encoded: &[u8]) -> bool {
+    bytes.windows(encoded.len()).any(|window| window == encoded)
 }
 
 pub struct CallZomeWorkspace {
@@ -338,17 +495,25 @@ impl Workspace for CallZomeWorkspace {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::conductor::api::error::ConductorApiError;
     use crate::conductor::api::CellConductorApi;
+    use crate::conductor::api::ZomeCall;
+    use crate::conductor::handle::ConductorHandleT;
     use crate::conductor::handle::MockConductorHandleT;
+    use crate::conductor::CellError;
     use crate::core::ribosome::MockRibosomeT;
     use crate::core::workflow::error::WorkflowError;
     use crate::core::workflow::genesis_workflow::tests::fake_genesis;
     use crate::fixt::*;
+    use crate::test_utils::sweetest::SweetConductor;
+    use crate::test_utils::sweetest::SweetDnaFile;
+    use crate::test_utils::sweetest::SweetZome;
     use ::fixt::prelude::*;
 
     use holochain_lmdb::env::ReadManager;
     use holochain_lmdb::test_utils::test_cell_env;
     use holochain_p2p::HolochainP2pCellFixturator;
+    use holochain_types::dna::zome::inline_zome::InlineZome;
     use holochain_types::test_utils::fake_agent_pubkey_1;
     use holochain_wasm_test_utils::TestWasm;
     use holochain_zome_types::cell::CellId;
@@ -356,6 +521,7 @@ pub mod tests {
     use holochain_zome_types::ExternIO;
     use matches::assert_matches;
     use observability;
+    use std::sync::Barrier;
 
     #[derive(Debug, serde::Serialize, serde::Deserialize, SerializedBytes)]
     struct Payload {
@@ -379,7 +545,9 @@ pub mod tests {
             conductor_api,
             is_root_zome_call: true,
         };
-        call_zome_workflow_inner(workspace.into(), network, keystore, args).await
+        call_zome_workflow_inner(workspace.into(), network, keystore, args)
+            .await
+            .map(|(_, result)| result)
     }
 
     // 1.  Check if there is a Capability token secret in the parameters.
@@ -553,4 +721,138 @@ pub mod tests {
             .unwrap();
         // TODO: Check the workspace has changes
     }
+
+    /// A zome which can commit while another call holds a stale snapshot.
+    /// The slow calls wait on `created` once they have committed to their
+    /// snapshot, then on `head_moved` until the test has moved the head.
+    fn racing_zome(created: Arc<Barrier>, head_moved: Arc<Barrier>) -> InlineZome {
+        let entry_def = EntryDef::default_with_id("payload");
+        let entry = |a: u32| {
+            EntryWithDefId::new(
+                "payload".into(),
+                Entry::app(Payload { a }.try_into().unwrap()).unwrap(),
+            )
+        };
+        InlineZome::new_unique(vec![entry_def])
+            .callback("create", move |api, a: u32| {
+                api.create(entry(a))?;
+                Ok(())
+            })
+            // Hold the call's snapshot open until "create" has moved the head
+            .callback("create_slowly", {
+                let created = created.clone();
+                let head_moved = head_moved.clone();
+                move |api, a: u32| {
+                    api.create(entry(a))?;
+                    created.wait();
+                    head_moved.wait();
+                    Ok(())
+                }
+            })
+            .callback("create_slowly_returning_hash", move |api, a: u32| {
+                let hash = api.create(entry(a))?;
+                created.wait();
+                head_moved.wait();
+                Ok(hash)
+            })
+            .callback("chain_len", |api, ()| {
+                Ok(api.query(ChainQueryFilter::new())?.0.len())
+            })
+    }
+
+    /// Wait on a barrier shared with a zome call without blocking the runtime
+    async fn wait(barrier: &Arc<Barrier>) {
+        let barrier = barrier.clone();
+        tokio::task::spawn_blocking(move || {
+            barrier.wait();
+        })
+        .await
+        .unwrap();
+    }
+
+    fn zome_call(zome: &SweetZome, fn_name: &str, a: u32) -> ZomeCall {
+        ZomeCall {
+            cell_id: zome.cell_id().clone(),
+            zome_name: zome.name().clone(),
+            fn_name: fn_name.into(),
+            cap: None,
+            provenance: zome.cell_id().agent_pubkey().clone(),
+            payload: ExternIO::encode(a).unwrap(),
+            read_only: false,
+        }
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    #[cfg(feature = "test_utils")]
+    async fn stale_call_is_rebased_onto_moved_head() {
+        observability::test_run().ok();
+        let created = Arc::new(Barrier::new(2));
+        let head_moved = Arc::new(Barrier::new(2));
+        let (dna_file, _) = SweetDnaFile::unique_from_inline_zome(
+            "zome1",
+            racing_zome(created.clone(), head_moved.clone()),
+        )
+        .await
+        .unwrap();
+        let mut conductor = SweetConductor::from_standard_config().await;
+        let app = conductor.setup_app("app", &[dna_file]).await;
+        let zome = app.cells()[0].zome("zome1");
+        let start_len: usize = conductor.call(&zome, "chain_len", ()).await;
+
+        let slow = tokio::task::spawn({
+            let handle = conductor.as_ref().clone();
+            let call = zome_call(&zome, "create_slowly", 1);
+            async move { handle.call_zome(call).await }
+        });
+        wait(&created).await;
+        let _: () = conductor.call(&zome, "create", 2).await;
+        wait(&head_moved).await;
+
+        // The slow call was rebased rather than failing
+        let response = slow.await.unwrap().unwrap().unwrap();
+        assert_matches!(response, ZomeCallResponse::Ok(_));
+        let end_len: usize = conductor.call(&zome, "chain_len", ()).await;
+        assert_eq!(end_len, start_len + 2);
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    #[cfg(feature = "test_utils")]
+    async fn stale_call_returning_rebased_hash_fails() {
+        observability::test_run().ok();
+        let created = Arc::new(Barrier::new(2));
+        let head_moved = Arc::new(Barrier::new(2));
+        let (dna_file, _) = SweetDnaFile::unique_from_inline_zome(
+            "zome1",
+            racing_zome(created.clone(), head_moved.clone()),
+        )
+        .await
+        .unwrap();
+        let mut conductor = SweetConductor::from_standard_config().await;
+        let app = conductor.setup_app("app", &[dna_file]).await;
+        let zome = app.cells()[0].zome("zome1");
+        let start_len: usize = conductor.call(&zome, "chain_len", ()).await;
+
+        let slow = tokio::task::spawn({
+            let handle = conductor.as_ref().clone();
+            let call = zome_call(&zome, "create_slowly_returning_hash", 1);
+            async move { handle.call_zome(call).await }
+        });
+        wait(&created).await;
+        let _: () = conductor.call(&zome, "create", 2).await;
+        wait(&head_moved).await;
+
+        // The hash the caller got back would be wrong after a rebase,
+        // so the call fails for the client to retry
+        match slow.await.unwrap() {
+            Err(ConductorApiError::CellError(CellError::WorkflowError(e))) => {
+                assert_matches!(
+                    *e,
+                    WorkflowError::SourceChainError(SourceChainError::HeadMoved(_, _))
+                );
+            }
+            r => panic!("expected the chain head to have moved, got {:?}", r),
+        }
+        let end_len: usize = conductor.call(&zome, "chain_len", ()).await;
+        assert_eq!(end_len, start_len + 1);
+    }
 }
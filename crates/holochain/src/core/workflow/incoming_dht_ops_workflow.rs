@@ -28,14 +28,17 @@ use tracing::instrument;
 mod test;
 
 #[instrument(skip(state_env, sys_validation_trigger, ops))]
+/// Add ops from the network to the validation limbo, returning how many
+/// were dropped for failing counterfeit checks.
 pub async fn incoming_dht_ops_workflow(
     state_env: &EnvironmentWrite,
     mut sys_validation_trigger: TriggerSender,
     ops: Vec<(holo_hash::DhtOpHash, holochain_types::dht_op::DhtOp)>,
     from_agent: Option<AgentPubKey>,
-) -> WorkflowResult<()> {
+) -> WorkflowResult<usize> {
     // set up our workspace
     let mut workspace = IncomingDhtOpsWorkspace::new(state_env.clone().into())?;
+    let mut counterfeit = 0;
 
     // add incoming ops to the validation limbo
     for (hash, op) in ops {
@@ -48,6 +51,7 @@ pub async fn incoming_dht_ops_workflow(
                     msg = "Dropping op because it failed counterfeit checks",
                     ?op
                 );
+                counterfeit += 1;
             }
         }
     }
@@ -60,7 +64,7 @@ pub async fn incoming_dht_ops_workflow(
     // trigger validation of queued ops
     sys_validation_trigger.trigger();
 
-    Ok(())
+    Ok(counterfeit)
 }
 
 #[instrument(skip(op))]
@@ -175,6 +175,7 @@ async fn sys_validation_workflow_inner(
                 workspace.put_val_limbo(op_hash, vlv)?;
             }
             Outcome::Rejected => {
+                report_rejected_op(&mut network.clone(), &vlv.op, vlv.from_agent.as_ref()).await;
                 let iv = IntegrationLimboValue {
                     op: vlv.op,
                     validation_status: ValidationStatus::Rejected,
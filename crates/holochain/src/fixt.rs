@@ -303,9 +303,28 @@ fixturator!(
     vanilla fn make_call_zome_handle(CellId);
 );
 
+fn make_zome_call_host_access(
+    workspace: CallZomeWorkspaceLock,
+    keystore: KeystoreSender,
+    network: HolochainP2pCell,
+    signal_tx: SignalBroadcaster,
+    call_zome_handle: CellConductorReadHandle,
+    cell_id: CellId,
+) -> ZomeCallHostAccess {
+    ZomeCallHostAccess::new(
+        workspace,
+        keystore,
+        network,
+        signal_tx,
+        call_zome_handle,
+        cell_id,
+        false,
+    )
+}
+
 fixturator!(
     ZomeCallHostAccess;
-    constructor fn new(CallZomeWorkspaceLock, KeystoreSender, HolochainP2pCell, SignalBroadcaster, CellConductorReadHandle, CellId);
+    vanilla fn make_zome_call_host_access(CallZomeWorkspaceLock, KeystoreSender, HolochainP2pCell, SignalBroadcaster, CellConductorReadHandle, CellId);
 );
 
 fixturator!(
@@ -450,6 +469,7 @@ fixturator!(
         fn_name: FunctionNameFixturator::new(Empty).next().unwrap(),
         payload: ExternIoFixturator::new(Empty).next().unwrap(),
         provenance: AgentPubKeyFixturator::new(Empty).next().unwrap(),
+        read_only: false,
     };
     curve Unpredictable ZomeCallInvocation {
         cell_id: CellIdFixturator::new(Unpredictable).next().unwrap(),
@@ -458,6 +478,7 @@ fixturator!(
         fn_name: FunctionNameFixturator::new(Unpredictable).next().unwrap(),
         payload: ExternIoFixturator::new(Unpredictable).next().unwrap(),
         provenance: AgentPubKeyFixturator::new(Unpredictable).next().unwrap(),
+        read_only: false,
     };
     curve Predictable ZomeCallInvocation {
         cell_id: CellIdFixturator::new_indexed(Predictable, get_fixt_index!())
@@ -478,6 +499,7 @@ fixturator!(
         provenance: AgentPubKeyFixturator::new_indexed(Predictable, get_fixt_index!())
             .next()
             .unwrap(),
+        read_only: false,
     };
 );
 
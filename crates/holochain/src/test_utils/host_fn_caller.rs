@@ -175,6 +175,7 @@ impl HostFnCaller {
             signal_tx,
             call_zome_handle,
             cell_id,
+            false,
         );
         let ribosome = Arc::new(ribosome);
         let zome = ribosome.dna_def().get_zome(&zome_name).unwrap();
@@ -453,6 +453,7 @@ impl SweetConductorHandle {
             cap,
             provenance: provenance.clone(),
             payload,
+            read_only: false,
         };
         let response = self.0.call_zome(call).await.unwrap().unwrap();
         unwrap_to!(response => ZomeCallResponse::Ok)
@@ -161,6 +161,7 @@ impl TestConductorHandle {
             cap,
             provenance,
             payload,
+            read_only: false,
         };
         let response = self.0.call_zome(call).await.unwrap().unwrap();
         unwrap_to!(response => ZomeCallResponse::Ok)
@@ -218,6 +219,7 @@ where
             cap,
             provenance,
             payload,
+            read_only: false,
         }
     }
 }
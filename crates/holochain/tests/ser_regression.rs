@@ -125,6 +125,7 @@ async fn ser_regression_test() {
         fn_name: "create_channel".into(),
         payload: ExternIO::encode(channel).unwrap(),
         provenance: alice_agent_id.clone(),
+        read_only: false,
     };
 
     let request = Box::new(invocation.clone());
@@ -154,6 +155,7 @@ async fn ser_regression_test() {
         fn_name: "create_message".into(),
         payload: ExternIO::encode(message).unwrap(),
         provenance: alice_agent_id.clone(),
+        read_only: false,
     };
 
     let request = Box::new(invocation.clone());
@@ -222,6 +222,7 @@ async fn speed_test(n: Option<usize>) -> TestEnvironments {
             fn_name: func.into(),
             payload: ExternIO::encode(payload)?,
             provenance: cell_id.agent_pubkey().clone(),
+            read_only: false,
         })
     }
 
@@ -86,6 +86,9 @@ fn create_config(port: u16, environment_path: PathBuf) -> ConductorConfig {
         }),
         keystore_path: None,
         use_dangerous_test_keystore: true,
+        wasm_limits: Default::default(),
+        module_cache_max_bytes: None,
+        metrics_exporter: None,
     }
 }
 
@@ -428,6 +431,7 @@ async fn remote_signals() {
             RemoteSignal {
                 signal: signal.clone(),
                 agents: all_agents,
+                retry: None,
             },
         )
         .await;
@@ -486,41 +486,39 @@ where
     }
 
     #[instrument(skip(self, hashes, options))]
-    /// Exactly the same as fetch_elements_via_entry
-    /// except the network is cloned and a task is spawned
-    /// for each entry.
+    /// Fetch many elements by header hash,
+    /// batching the requests by authority.
     async fn fetch_elements_via_header_parallel<I: IntoIterator<Item = HeaderHash>>(
         &mut self,
         hashes: I,
         options: NetworkGetOptions,
     ) -> CascadeResult<()> {
-        // Network needs mut access for calls which we can't share across
-        // threads so we need to clone.
-        let network = ok_or_return!(self.network.clone());
-
-        // Spawn a task to run in parallel for each entry.
-        // This works because we don't need to use self and therefor
-        // don't need to share the &mut to our databases across threads.
-        let tasks = hashes.into_iter().map(|hash| {
-            tokio::task::spawn({
-                let mut network = network.clone();
-                let options = options.clone();
-                async move {
-                    network
-                        .get(hash.clone().into(), options)
-                        .instrument(debug_span!("fetch_element_via_entry::network_get"))
-                        .await
-                }
-            })
-        });
+        self.fetch_many(hashes.into_iter().map(AnyDhtHash::from).collect(), options)
+            .await
+    }
 
-        // try waiting on all the gets but exit if any fail
-        let all_responses = futures::future::try_join_all(tasks).await?;
+    /// Fetch many elements or entries in one batch and put
+    /// the data from every authority that responded into the cache.
+    async fn fetch_many(
+        &mut self,
+        hashes: Vec<AnyDhtHash>,
+        options: NetworkGetOptions,
+    ) -> CascadeResult<()> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+        let network = ok_or_return!(self.network.as_mut());
+        let all_responses = network
+            .get_many(hashes, options)
+            .instrument(debug_span!("fetch_many::network_get_many"))
+            .await?;
 
-        // Put the data into the cache from every authority that responded
-        for responses in all_responses {
-            for response in responses? {
-                self.put_element_in_cache(response)?;
+        for (hash, responses) in all_responses {
+            for response in responses {
+                match *hash.hash_type() {
+                    AnyDht::Entry => self.put_entry_in_cache(response)?,
+                    AnyDht::Header => self.put_element_in_cache(response)?,
+                }
             }
         }
         Ok(())
@@ -582,44 +580,15 @@ where
     }
 
     #[instrument(skip(self, hashes, options))]
-    /// Exactly the same as fetch_elements_via_entry
-    /// except the network is cloned and a task is spawned
-    /// for each entry.
+    /// Fetch many elements by entry hash,
+    /// batching the requests by authority.
     async fn fetch_elements_via_entry_parallel<I: IntoIterator<Item = EntryHash>>(
         &mut self,
         hashes: I,
         options: NetworkGetOptions,
     ) -> CascadeResult<()> {
-        // Network needs mut access for calls which we can't share across
-        // threads so we need to clone.
-        let network = ok_or_return!(self.network.clone());
-
-        // Spawn a task to run in parallel for each entry.
-        // This works because we don't need to use self and therefor
-        // don't need to share the &mut to our databases across threads.
-        let tasks = hashes.into_iter().map(|hash| {
-            tokio::task::spawn({
-                let mut network = network.clone();
-                let options = options.clone();
-                async move {
-                    network
-                        .get(hash.clone().into(), options)
-                        .instrument(debug_span!("fetch_element_via_entry::network_get"))
-                        .await
-                }
-            })
-        });
-
-        // try waiting on all the gets but exit if any fail
-        let all_responses = futures::future::try_join_all(tasks).await?;
-
-        // Put the data into the cache from every authority that responded
-        for responses in all_responses {
-            for response in responses? {
-                self.put_entry_in_cache(response)?;
-            }
-        }
-        Ok(())
+        self.fetch_many(hashes.into_iter().map(AnyDhtHash::from).collect(), options)
+            .await
     }
 
     #[instrument(skip(self, options))]
@@ -1509,6 +1478,62 @@ where
         }
     }
 
+    #[instrument(skip(self, hashes, options))]
+    /// Same as [Cascade::dht_get] for many hashes, but every hash that
+    /// needs the network is fetched in one batch, with a single request
+    /// per authority rather than one per hash.
+    /// Results are in the same order as the hashes.
+    pub async fn dht_get_many(
+        &mut self,
+        hashes: Vec<AnyDhtHash>,
+        options: GetOptions,
+    ) -> CascadeResult<Vec<Option<Element>>> {
+        let mut to_fetch = Vec::new();
+        for hash in hashes.iter() {
+            if self.am_i_authoring(hash).await? {
+                continue;
+            }
+            if self.am_i_an_authority(hash.clone()).await? {
+                self.update_cache_from_integrated(hash.clone(), options.clone().into())?;
+                continue;
+            }
+            // If the caller only needs the content and we have
+            // the content locally we can avoid the network call
+            if let GetStrategy::Content = options.strategy {
+                let found = match *hash.hash_type() {
+                    AnyDht::Entry => self
+                        .get_element_local_raw_via_entry(&hash.clone().into())?
+                        .is_some(),
+                    AnyDht::Header => self.get_element_local_raw(&hash.clone().into())?.is_some(),
+                };
+                if found {
+                    continue;
+                }
+            }
+            to_fetch.push(hash.clone());
+        }
+        self.fetch_many(to_fetch, options.clone().into()).await?;
+
+        // Everything we could fetch is now in the cache,
+        // so answer each get without going back to the network.
+        let network = self.network.take();
+        let out = self.dht_get_many_local(hashes, options).await;
+        self.network = network;
+        out
+    }
+
+    async fn dht_get_many_local(
+        &mut self,
+        hashes: Vec<AnyDhtHash>,
+        options: GetOptions,
+    ) -> CascadeResult<Vec<Option<Element>>> {
+        let mut out = Vec::with_capacity(hashes.len());
+        for hash in hashes {
+            out.push(self.dht_get(hash, options.clone()).await?);
+        }
+        Ok(out)
+    }
+
     #[instrument(skip(self))]
     pub async fn get_details(
         &mut self,
@@ -1,4 +1,5 @@
 use holo_hash::*;
+use holochain_p2p::actor::NetworkStats;
 use holochain_types::prelude::*;
 use holochain_zome_types::cell::CellId;
 use kitsune_p2p::agent_store::AgentInfoSigned;
@@ -177,6 +178,64 @@ pub enum AdminRequest {
         /// Optionally choose a specific agent info
         cell_id: Option<CellId>,
     },
+    /// List the zome functions, callbacks and entry defs of every zome
+    /// in an installed `Dna`.
+    ///
+    /// Will be responded to with an [`AdminResponse::ZomeExportsListed`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`AdminResponse::ZomeExportsListed`]: enum.AdminResponse.html#variant.ZomeExportsListed
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    ListZomeExports {
+        /// The hash of the installed `Dna` to inspect
+        dna_hash: DnaHash,
+    },
+    /// Dump what this conductor knows about the network of each running `Dna`:
+    /// the peers in its peer store and whether it is connected to them,
+    /// its last gossip with each agent and how its requests of
+    /// remote agents turned out.
+    ///
+    /// Will be responded to with an [`AdminResponse::NetworkStatsDumped`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`AdminResponse::NetworkStatsDumped`]: enum.AdminResponse.html#variant.NetworkStatsDumped
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    DumpNetworkStats {
+        /// Only dump the network of this `Dna`, or leave as None
+        /// for the networks of all running `Dna`s
+        dna_hash: Option<DnaHash>,
+    },
+    /// Stop contacting a remote agent on the network of a running `Dna`,
+    /// e.g. one that keeps sending invalid data. The conductor already
+    /// blocks peers for a while when they misbehave; this blocks one by hand.
+    ///
+    /// Will be responded to with an [`AdminResponse::PeerBlocked`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`AdminResponse::PeerBlocked`]: enum.AdminResponse.html#variant.PeerBlocked
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    BlockPeer {
+        /// The `Dna` whose network the agent should be blocked on
+        dna_hash: DnaHash,
+        /// The remote agent to block
+        agent: AgentPubKey,
+        /// How long to block the agent for in milliseconds, or leave as None
+        /// for the `peer_block_duration_ms` tuning param
+        duration_ms: Option<u64>,
+    },
+    /// Lift any block on a remote agent on the network of a running `Dna`.
+    ///
+    /// Will be responded to with an [`AdminResponse::PeerUnblocked`]
+    /// or an [`AdminResponse::Error`]
+    ///
+    /// [`AdminResponse::PeerUnblocked`]: enum.AdminResponse.html#variant.PeerUnblocked
+    /// [`AdminResponse::Error`]: enum.AppResponse.html#variant.Error
+    UnblockPeer {
+        /// The `Dna` whose network the agent should be unblocked on
+        dna_hash: DnaHash,
+        /// The remote agent to unblock
+        agent: AgentPubKey,
+    },
 }
 
 /// Represents the possible responses to an [`AdminRequest`]
@@ -288,6 +347,41 @@ pub enum AdminResponse {
     ///
     /// [`AdminRequest::RequestAgentInfo`]: enum.AdminRequest.html#variant.RequestAgentInfo
     AgentInfoRequested(Vec<AgentInfoSigned>),
+    /// The succesful response to an [`AdminRequest::ListZomeExports`].
+    ///
+    /// Contains the exports of each zome in the `Dna`, in zome order.
+    ///
+    /// [`AdminRequest::ListZomeExports`]: enum.AdminRequest.html#variant.ListZomeExports
+    ZomeExportsListed(Vec<ZomeExports>),
+    /// The succesful response to an [`AdminRequest::DumpNetworkStats`].
+    ///
+    /// Contains the network stats of each requested `Dna`.
+    ///
+    /// [`AdminRequest::DumpNetworkStats`]: enum.AdminRequest.html#variant.DumpNetworkStats
+    NetworkStatsDumped(Vec<NetworkStats>),
+    /// The succesful response to an [`AdminRequest::BlockPeer`].
+    ///
+    /// This means the agent won't be contacted until the block ends.
+    ///
+    /// [`AdminRequest::BlockPeer`]: enum.AdminRequest.html#variant.BlockPeer
+    PeerBlocked,
+    /// The succesful response to an [`AdminRequest::UnblockPeer`].
+    ///
+    /// [`AdminRequest::UnblockPeer`]: enum.AdminRequest.html#variant.UnblockPeer
+    PeerUnblocked,
+}
+
+/// What a single zome of a `Dna` exports
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ZomeExports {
+    /// The name of the zome
+    pub zome_name: ZomeName,
+    /// The functions which can be called with a [`ZomeCall`](crate::ZomeCall)
+    pub zome_fns: Vec<FunctionName>,
+    /// The callbacks the conductor will call, e.g. `init` or `validate_create_entry`
+    pub callbacks: Vec<FunctionName>,
+    /// The entry defs of the zome, in entry def index order
+    pub entry_defs: Vec<EntryDef>,
 }
 
 /// Error type that goes over the websocket wire.
@@ -311,6 +405,9 @@ pub enum ExternalApiWireError {
     ActivateApp(String),
     /// The zome call is unauthorized
     ZomeCallUnauthorized(String),
+    /// The zome call's writes could not be applied because the source chain
+    /// head moved while it was running. The call can be safely retried.
+    ChainHeadMoved(String),
 }
 
 impl ExternalApiWireError {
@@ -107,6 +107,13 @@ pub struct ZomeCall {
     /// is making this ZomeCall over this interface. Until we do, the caller simply
     /// provides this data and Holochain trusts them.
     pub provenance: AgentPubKey,
+    /// Run the call against a read-only view of the source chain.
+    ///
+    /// Read-only calls are denied access to host functions which write to the
+    /// workspace, and their workspace is never validated or committed, so they
+    /// don't contend with writing calls to the same cell.
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 #[allow(missing_docs)]
@@ -9,8 +9,10 @@ mod admin_interface_config;
 mod dpki_config;
 #[allow(missing_docs)]
 mod error;
+mod metrics_exporter_config;
 mod passphrase_service_config;
 pub mod paths;
+mod wasm_limits_config;
 //mod logger_config;
 //mod signal_config;
 pub use paths::EnvironmentRootPath;
@@ -19,7 +21,10 @@ pub use super::*;
 pub use dpki_config::DpkiConfig;
 //pub use logger_config::LoggerConfig;
 pub use error::*;
+pub use metrics_exporter_config::MetricsExporterConfig;
 pub use passphrase_service_config::PassphraseServiceConfig;
+pub use wasm_limits_config::WasmLimits;
+pub use wasm_limits_config::WasmLimitsConfig;
 //pub use signal_config::SignalConfig;
 use std::path::Path;
 use std::path::PathBuf;
@@ -75,6 +80,21 @@ pub struct ConductorConfig {
 
     /// Config options for the network module. Optional.
     pub network: Option<holochain_p2p::kitsune_p2p::KitsuneP2pConfig>,
+
+    /// Instruction and memory limits for wasm calls, with optional per DNA overrides.
+    /// If omitted, wasm calls are unlimited.
+    #[serde(default)]
+    pub wasm_limits: WasmLimitsConfig,
+
+    /// The maximum size in bytes of the compiled wasm modules the conductor keeps
+    /// on disk under the environment path. Defaults to 1GiB.
+    #[serde(default)]
+    pub module_cache_max_bytes: Option<u64>,
+
+    /// Optionally serve conductor and network metrics over http
+    /// for Prometheus or compatible scrapers.
+    #[serde(default)]
+    pub metrics_exporter: Option<MetricsExporterConfig>,
     //
     //
     // /// Which signals to emit
@@ -150,6 +170,9 @@ pub mod tests {
                 keystore_path: None,
                 admin_interfaces: None,
                 use_dangerous_test_keystore: false,
+                wasm_limits: Default::default(),
+                module_cache_max_bytes: None,
+                metrics_exporter: None,
             }
         );
     }
@@ -239,6 +262,9 @@ pub mod tests {
                     driver: InterfaceDriver::Websocket { port: 1234 }
                 }]),
                 network: Some(network_config),
+                wasm_limits: Default::default(),
+                module_cache_max_bytes: None,
+                metrics_exporter: None,
             }
         );
     }
@@ -270,6 +296,41 @@ pub mod tests {
                 keystore_path: Some(PathBuf::from("/path/to/keystore").into()),
                 admin_interfaces: None,
                 use_dangerous_test_keystore: true,
+                wasm_limits: Default::default(),
+                module_cache_max_bytes: None,
+                metrics_exporter: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_config_wasm_limits() {
+        let dna_hash = holo_hash::DnaHash::from_raw_36(vec![0xdb; 36]);
+        let other_dna_hash = holo_hash::DnaHash::from_raw_36(vec![0xdc; 36]);
+        let yaml = format!(
+            r#"---
+    environment_path: /path/to/env
+    wasm_limits:
+      max_instructions: 1000
+      dna_overrides:
+        {}:
+          max_memory_bytes: 65536
+    "#,
+            dna_hash
+        );
+        let result: ConductorConfig = config_from_yaml(&yaml).unwrap();
+        assert_eq!(
+            result.wasm_limits.for_dna(&dna_hash),
+            WasmLimits {
+                max_instructions: None,
+                max_memory_bytes: Some(65536),
+            }
+        );
+        assert_eq!(
+            result.wasm_limits.for_dna(&other_dna_hash),
+            WasmLimits {
+                max_instructions: Some(1000),
+                max_memory_bytes: None,
             }
         );
     }
@@ -0,0 +1,35 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+
+/// Serve the conductor and network metrics over http in the
+/// OpenMetrics text format, for Prometheus or compatible scrapers.
+///
+/// ```yaml
+/// metrics_exporter:
+///   port: 9100
+///   bind_address: 0.0.0.0
+/// ```
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub struct MetricsExporterConfig {
+    /// The port to serve `/metrics` on. 0 picks a free port.
+    pub port: u16,
+
+    /// The address to bind to. Defaults to 127.0.0.1,
+    /// so the metrics are only visible on this machine.
+    #[serde(default)]
+    pub bind_address: Option<IpAddr>,
+}
+
+impl MetricsExporterConfig {
+    /// The socket address to serve the metrics on.
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(
+            self.bind_address
+                .unwrap_or_else(|| IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            self.port,
+        )
+    }
+}
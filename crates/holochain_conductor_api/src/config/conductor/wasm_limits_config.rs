@@ -0,0 +1,62 @@
+use holo_hash::DnaHash;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Resource limits applied to every wasm call made by the conductor,
+/// including zome calls and callbacks such as `validate` and `init`.
+///
+/// The top level limits apply to all DNAs and can be overridden for
+/// individual DNAs in `dna_overrides`.
+///
+/// ```yaml
+/// wasm_limits:
+///   max_instructions: 100000000
+///   max_memory_bytes: 67108864
+///   dna_overrides:
+///     uhC0kAbCd...:
+///       max_instructions: 500000000
+/// ```
+#[derive(Clone, Deserialize, Serialize, Default, Debug, PartialEq)]
+pub struct WasmLimitsConfig {
+    /// The limits used for any DNA without an override.
+    #[serde(flatten)]
+    pub default: WasmLimits,
+
+    /// Per DNA limits, keyed by the base64 encoded [DnaHash].
+    /// These replace the default limits entirely for that DNA.
+    #[serde(default)]
+    pub dna_overrides: HashMap<String, WasmLimits>,
+}
+
+impl WasmLimitsConfig {
+    /// Get the limits that apply to calls into this DNA.
+    pub fn for_dna(&self, dna_hash: &DnaHash) -> WasmLimits {
+        self.dna_overrides
+            .get(&dna_hash.to_string())
+            .cloned()
+            .unwrap_or_else(|| self.default.clone())
+    }
+}
+
+/// The limits for a single wasm call.
+/// A `None` value means that resource is not limited.
+#[derive(Clone, Deserialize, Serialize, Default, Debug, PartialEq, Eq, Hash)]
+pub struct WasmLimits {
+    /// The maximum number of metered wasm instructions a single call can execute
+    /// before it is aborted.
+    #[serde(default)]
+    pub max_instructions: Option<u64>,
+
+    /// The maximum size in bytes that a single instance's linear memory can grow to.
+    /// The guest traps as soon as it grows its memory past this.
+    #[serde(default)]
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl WasmLimits {
+    /// True if no limits are set.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_instructions.is_none() && self.max_memory_bytes.is_none()
+    }
+}
@@ -47,6 +47,16 @@ pub trait HolochainP2pCellT {
         payload: ExternIO,
     ) -> actor::HolochainP2pResult<SerializedBytes>;
 
+    /// Invoke a zome function on a remote node, streaming the serialized response.
+    async fn call_remote_stream(
+        &mut self,
+        to_agent: AgentPubKey,
+        zome_name: ZomeName,
+        fn_name: FunctionName,
+        cap: Option<CapSecret>,
+        payload: ExternIO,
+    ) -> actor::HolochainP2pResult<actor::ResponseStream>;
+
     /// Publish data to the correct neighborhood.
     #[allow(clippy::ptr_arg)]
     async fn publish(
@@ -71,6 +81,15 @@ pub trait HolochainP2pCellT {
         options: actor::GetOptions,
     ) -> actor::HolochainP2pResult<Vec<GetElementResponse>>;
 
+    /// Get many entries from the DHT, batching the hashes by authority.
+    async fn get_many(
+        &mut self,
+        dht_hashes: Vec<holo_hash::AnyDhtHash>,
+        options: actor::GetOptions,
+    ) -> actor::HolochainP2pResult<
+        std::collections::HashMap<holo_hash::AnyDhtHash, Vec<GetElementResponse>>,
+    >;
+
     /// Get metadata from the DHT.
     async fn get_meta(
         &mut self,
@@ -93,12 +112,23 @@ pub trait HolochainP2pCellT {
         options: actor::GetActivityOptions,
     ) -> actor::HolochainP2pResult<Vec<AgentActivityResponse>>;
 
+    /// Get agent activity from the DHT, streaming the encoded response.
+    async fn get_agent_activity_stream(
+        &mut self,
+        agent: AgentPubKey,
+        query: ChainQueryFilter,
+        options: actor::GetActivityOptions,
+    ) -> actor::HolochainP2pResult<actor::ResponseStream>;
+
     /// Send a validation receipt to a remote node.
     async fn send_validation_receipt(
         &mut self,
         to_agent: AgentPubKey,
         receipt: SerializedBytes,
     ) -> actor::HolochainP2pResult<()>;
+
+    /// Report that a remote agent sent us data that failed validation.
+    async fn report_invalid_data(&mut self, agent: AgentPubKey) -> actor::HolochainP2pResult<()>;
 }
 
 /// A wrapper around HolochainP2pSender that partially applies the dna_hash / agent_pub_key.
@@ -158,6 +188,28 @@ impl HolochainP2pCellT for HolochainP2pCell {
             .await
     }
 
+    /// Invoke a zome function on a remote node, streaming the serialized response.
+    async fn call_remote_stream(
+        &mut self,
+        to_agent: AgentPubKey,
+        zome_name: ZomeName,
+        fn_name: FunctionName,
+        cap: Option<CapSecret>,
+        payload: ExternIO,
+    ) -> actor::HolochainP2pResult<actor::ResponseStream> {
+        self.sender
+            .call_remote_stream(
+                (*self.dna_hash).clone(),
+                (*self.from_agent).clone(),
+                to_agent,
+                zome_name,
+                fn_name,
+                cap,
+                payload,
+            )
+            .await
+    }
+
     /// Publish data to the correct neighborhood.
     async fn publish(
         &mut self,
@@ -211,6 +263,25 @@ impl HolochainP2pCellT for HolochainP2pCell {
             .await
     }
 
+    /// Get many entries from the DHT, batching the hashes by authority.
+    async fn get_many(
+        &mut self,
+        dht_hashes: Vec<holo_hash::AnyDhtHash>,
+        options: actor::GetOptions,
+    ) -> actor::HolochainP2pResult<
+        std::collections::HashMap<holo_hash::AnyDhtHash, Vec<GetElementResponse>>,
+    > {
+        self.sender
+            .get_many(
+                (*self.dna_hash).clone(),
+                (*self.from_agent).clone(),
+                dht_hashes,
+                options,
+            )
+            .instrument(tracing::debug_span!("HolochainP2p::get_many"))
+            .await
+    }
+
     /// Get metadata from the DHT.
     async fn get_meta(
         &mut self,
@@ -261,6 +332,24 @@ impl HolochainP2pCellT for HolochainP2pCell {
             .await
     }
 
+    /// Get agent activity from the DHT, streaming the encoded response.
+    async fn get_agent_activity_stream(
+        &mut self,
+        agent: AgentPubKey,
+        query: ChainQueryFilter,
+        options: actor::GetActivityOptions,
+    ) -> actor::HolochainP2pResult<actor::ResponseStream> {
+        self.sender
+            .get_agent_activity_stream(
+                (*self.dna_hash).clone(),
+                (*self.from_agent).clone(),
+                agent,
+                query,
+                options,
+            )
+            .await
+    }
+
     /// Send a validation receipt to a remote node.
     async fn send_validation_receipt(
         &mut self,
@@ -276,6 +365,13 @@ impl HolochainP2pCellT for HolochainP2pCell {
             )
             .await
     }
+
+    /// Report that a remote agent sent us data that failed validation.
+    async fn report_invalid_data(&mut self, agent: AgentPubKey) -> actor::HolochainP2pResult<()> {
+        self.sender
+            .report_invalid_data((*self.dna_hash).clone(), agent)
+            .await
+    }
 }
 
 pub use kitsune_p2p::dht_arc;
@@ -3,6 +3,8 @@ use crate::event::*;
 use crate::*;
 
 use futures::future::FutureExt;
+use futures::stream::StreamExt;
+use futures::stream::TryStreamExt;
 
 use crate::types::AgentPubKeyExt;
 
@@ -12,10 +14,17 @@ use ghost_actor::dependencies::tracing_futures::Instrument;
 use holochain_zome_types::zome::FunctionName;
 use kitsune_p2p::actor::KitsuneP2pSender;
 use kitsune_p2p::agent_store::AgentInfoSigned;
+use kitsune_p2p::request_quota::RequestBackoff;
+use kitsune_p2p::request_quota::RequestQuotas;
+use std::collections::HashMap;
 
 pub(crate) struct HolochainP2pActor {
     evt_sender: futures::channel::mpsc::Sender<HolochainP2pEvent>,
     kitsune_p2p: ghost_actor::GhostSender<kitsune_p2p::actor::KitsuneP2p>,
+    /// Limits on the requests we serve each remote node.
+    request_quotas: RequestQuotas,
+    /// Remote agents that refused our requests as busy.
+    backoff: RequestBackoff,
 }
 
 impl ghost_actor::GhostControlHandler for HolochainP2pActor {}
@@ -28,6 +37,7 @@ impl HolochainP2pActor {
         channel_factory: ghost_actor::actor_builder::GhostActorChannelFactory<Self>,
         evt_sender: futures::channel::mpsc::Sender<HolochainP2pEvent>,
     ) -> HolochainP2pResult<Self> {
+        let request_quotas = RequestQuotas::new(config.tuning_params.clone());
         let (kitsune_p2p, kitsune_p2p_events) =
             kitsune_p2p::spawn_kitsune_p2p(config, tls_config).await?;
 
@@ -36,9 +46,28 @@ impl HolochainP2pActor {
         Ok(Self {
             evt_sender,
             kitsune_p2p,
+            request_quotas,
+            backoff: RequestBackoff::default(),
         })
     }
 
+    /// Count an incoming request against the quota of the remote node
+    /// that sent it. Requests from agents on this node aren't limited.
+    fn start_request(
+        &self,
+        from_node: Option<kitsune_p2p::dependencies::url2::Url2>,
+        request: &crate::wire::WireMessage,
+    ) -> kitsune_p2p::actor::KitsuneP2pResult<Option<kitsune_p2p::request_quota::RequestPermit>>
+    {
+        match from_node {
+            Some(from_node) => self
+                .request_quotas
+                .try_start(from_node, request.request_type())
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// receiving an incoming request from a remote node
     #[allow(clippy::too_many_arguments)]
     fn handle_incoming_call_remote(
@@ -92,6 +121,51 @@ impl HolochainP2pActor {
         .into())
     }
 
+    /// receiving an incoming get_many request from a remote node
+    #[tracing::instrument(skip(self, dna_hash, to_agent, dht_hashes, options), level = "trace")]
+    fn handle_incoming_get_many(
+        &mut self,
+        dna_hash: DnaHash,
+        to_agent: AgentPubKey,
+        dht_hashes: Vec<holo_hash::AnyDhtHash>,
+        options: event::GetOptions,
+    ) -> kitsune_p2p::actor::KitsuneP2pHandlerResult<Vec<u8>> {
+        if dht_hashes.len() > crate::wire::MAX_GET_MANY_HASHES {
+            return Err(format!(
+                "get_many asked for {} hashes, the limit is {}",
+                dht_hashes.len(),
+                crate::wire::MAX_GET_MANY_HASHES
+            )
+            .into());
+        }
+        let evt_sender = self.evt_sender.clone();
+        Ok(async move {
+            let gets = dht_hashes.into_iter().map(|dht_hash| {
+                let get = evt_sender.get(
+                    dna_hash.clone(),
+                    to_agent.clone(),
+                    dht_hash.clone(),
+                    options.clone(),
+                );
+                async move { (dht_hash, get.await) }
+            });
+            let mut out: crate::wire::WireGetManyResponse = Vec::new();
+            for (dht_hash, res) in futures::future::join_all(gets).await {
+                match res {
+                    Ok(response) => out.push((dht_hash, response)),
+                    // leave it to the requester to get elsewhere
+                    Err(e) => tracing::debug!(msg = "incoming get_many failed", ?dht_hash, ?e),
+                }
+            }
+            holochain_serialized_bytes::encode(&out)
+                .map_err(HolochainP2pError::from)
+                .map_err(kitsune_p2p::KitsuneP2pError::from)
+        }
+        .instrument(tracing::debug_span!("incoming_get_many_task"))
+        .boxed()
+        .into())
+    }
+
     /// receiving an incoming get_meta request from a remote node
     fn handle_incoming_get_meta(
         &mut self,
@@ -298,16 +372,18 @@ impl kitsune_p2p::event::KitsuneP2pEventHandler for HolochainP2pActor {
         space: Arc<kitsune_p2p::KitsuneSpace>,
         to_agent: Arc<kitsune_p2p::KitsuneAgent>,
         from_agent: Arc<kitsune_p2p::KitsuneAgent>,
+        from_node: Option<kitsune_p2p::dependencies::url2::Url2>,
         payload: Vec<u8>,
     ) -> kitsune_p2p::event::KitsuneP2pEventHandlerResult<Vec<u8>> {
+        let request =
+            crate::wire::WireMessage::decode(payload.as_ref()).map_err(HolochainP2pError::from)?;
+        let permit = self.start_request(from_node, &request)?;
+
         let space = DnaHash::from_kitsune(&space);
         let to_agent = AgentPubKey::from_kitsune(&to_agent);
         let from_agent = AgentPubKey::from_kitsune(&from_agent);
 
-        let request =
-            crate::wire::WireMessage::decode(payload.as_ref()).map_err(HolochainP2pError::from)?;
-
-        match request {
+        let response = match request {
             crate::wire::WireMessage::CallRemote {
                 zome_name,
                 fn_name,
@@ -319,6 +395,10 @@ impl kitsune_p2p::event::KitsuneP2pEventHandler for HolochainP2pActor {
             crate::wire::WireMessage::Get { dht_hash, options } => {
                 self.handle_incoming_get(space, to_agent, dht_hash, options)
             }
+            crate::wire::WireMessage::GetMany {
+                dht_hashes,
+                options,
+            } => self.handle_incoming_get_many(space, to_agent, dht_hashes, options),
             crate::wire::WireMessage::GetMeta { dht_hash, options } => {
                 self.handle_incoming_get_meta(space, to_agent, dht_hash, options)
             }
@@ -344,7 +424,15 @@ impl kitsune_p2p::event::KitsuneP2pEventHandler for HolochainP2pActor {
             crate::wire::WireMessage::GetValidationPackage { header_hash } => {
                 self.handle_incoming_get_validation_package(space, to_agent, header_hash)
             }
+        }?;
+        // hold the permit until the request has been served
+        Ok(async move {
+            let response = response.await;
+            drop(permit);
+            response
         }
+        .boxed()
+        .into())
     }
 
     #[tracing::instrument(skip(self), level = "trace")]
@@ -353,19 +441,22 @@ impl kitsune_p2p::event::KitsuneP2pEventHandler for HolochainP2pActor {
         space: Arc<kitsune_p2p::KitsuneSpace>,
         to_agent: Arc<kitsune_p2p::KitsuneAgent>,
         from_agent: Arc<kitsune_p2p::KitsuneAgent>,
+        from_node: Option<kitsune_p2p::dependencies::url2::Url2>,
         payload: Vec<u8>,
     ) -> kitsune_p2p::event::KitsuneP2pEventHandlerResult<()> {
+        let request =
+            crate::wire::WireMessage::decode(payload.as_ref()).map_err(HolochainP2pError::from)?;
+        let permit = self.start_request(from_node, &request)?;
+
         let space = DnaHash::from_kitsune(&space);
         let to_agent = AgentPubKey::from_kitsune(&to_agent);
         let from_agent = AgentPubKey::from_kitsune(&from_agent);
 
-        let request =
-            crate::wire::WireMessage::decode(payload.as_ref()).map_err(HolochainP2pError::from)?;
-
-        match request {
+        let response = match request {
             // error on these call type messages
             crate::wire::WireMessage::CallRemote { .. }
             | crate::wire::WireMessage::Get { .. }
+            | crate::wire::WireMessage::GetMany { .. }
             | crate::wire::WireMessage::GetMeta { .. }
             | crate::wire::WireMessage::GetLinks { .. }
             | crate::wire::WireMessage::GetAgentActivity { .. }
@@ -388,7 +479,14 @@ impl kitsune_p2p::event::KitsuneP2pEventHandler for HolochainP2pActor {
                 dht_hash,
                 ops,
             ),
+        }?;
+        Ok(async move {
+            let response = response.await;
+            drop(permit);
+            response
         }
+        .boxed()
+        .into())
     }
 
     #[tracing::instrument(skip(self), level = "trace")]
@@ -447,6 +545,37 @@ impl kitsune_p2p::event::KitsuneP2pEventHandler for HolochainP2pActor {
         .into())
     }
 
+    #[tracing::instrument(skip(self), level = "trace")]
+    fn handle_fetch_op_hash_times_for_constraints(
+        &mut self,
+        input: kitsune_p2p::event::FetchOpHashTimesForConstraintsEvt,
+    ) -> kitsune_p2p::event::KitsuneP2pEventHandlerResult<Vec<(Arc<kitsune_p2p::KitsuneOpHash>, i64)>>
+    {
+        let kitsune_p2p::event::FetchOpHashTimesForConstraintsEvt {
+            space,
+            agent,
+            dht_arc,
+            since_utc_epoch_s,
+            until_utc_epoch_s,
+        } = input;
+        let space = DnaHash::from_kitsune(&space);
+        let agent = AgentPubKey::from_kitsune(&agent);
+        let since = Timestamp(since_utc_epoch_s, 0);
+        let until = Timestamp(until_utc_epoch_s, 0);
+
+        let evt_sender = self.evt_sender.clone();
+        Ok(async move {
+            Ok(evt_sender
+                .fetch_op_hash_times_for_constraints(space, agent, dht_arc, since, until)
+                .await?
+                .into_iter()
+                .map(|(h, t)| (h.into_kitsune(), t.0))
+                .collect())
+        }
+        .boxed()
+        .into())
+    }
+
     #[allow(clippy::needless_collect)]
     #[tracing::instrument(skip(self), level = "trace")]
     fn handle_fetch_op_hash_data(
@@ -559,15 +688,62 @@ impl HolochainP2pHandler for HolochainP2pActor {
         let to_agent = to_agent.into_kitsune();
         let from_agent = from_agent.into_kitsune();
 
-        let req =
-            crate::wire::WireMessage::call_remote(zome_name, fn_name, cap, payload).encode()?;
+        let req = crate::wire::WireMessage::call_remote(zome_name, fn_name, cap, payload);
 
         let kitsune_p2p = self.kitsune_p2p.clone();
+        let backoff = self.backoff.clone();
         Ok(async move {
-            let result: Vec<u8> = kitsune_p2p
-                .rpc_single(space, to_agent, from_agent, req, None)
-                .await?;
-            Ok(UnsafeBytes::from(result).into())
+            // streamed so large responses don't time out,
+            // kitsune fails a stream larger than a remote may send
+            let chunks: Vec<Vec<u8>> = rpc_stream_to(
+                kitsune_p2p,
+                backoff,
+                space,
+                to_agent,
+                from_agent,
+                &req,
+                None,
+            )
+            .await?
+            .try_collect()
+            .await?;
+            Ok(UnsafeBytes::from(chunks.concat()).into())
+        }
+        .boxed()
+        .into())
+    }
+
+    #[tracing::instrument(skip(self), level = "trace")]
+    fn handle_call_remote_stream(
+        &mut self,
+        dna_hash: DnaHash,
+        from_agent: AgentPubKey,
+        to_agent: AgentPubKey,
+        zome_name: ZomeName,
+        fn_name: FunctionName,
+        cap: Option<CapSecret>,
+        payload: ExternIO,
+    ) -> HolochainP2pHandlerResult<actor::ResponseStream> {
+        let space = dna_hash.into_kitsune();
+        let to_agent = to_agent.into_kitsune();
+        let from_agent = from_agent.into_kitsune();
+
+        let req = crate::wire::WireMessage::call_remote(zome_name, fn_name, cap, payload);
+
+        let kitsune_p2p = self.kitsune_p2p.clone();
+        let backoff = self.backoff.clone();
+        Ok(async move {
+            let chunks = rpc_stream_to(
+                kitsune_p2p,
+                backoff,
+                space,
+                to_agent,
+                from_agent,
+                &req,
+                None,
+            )
+            .await?;
+            Ok(chunks.map_err(HolochainP2pError::from).boxed())
         }
         .boxed()
         .into())
@@ -617,13 +793,21 @@ impl HolochainP2pHandler for HolochainP2pActor {
         let to_agent = input.request_from.into_kitsune();
         let from_agent = input.agent_pub_key.into_kitsune();
 
-        let req = crate::wire::WireMessage::get_validation_package(input.header_hash).encode()?;
+        let req = crate::wire::WireMessage::get_validation_package(input.header_hash);
 
         let kitsune_p2p = self.kitsune_p2p.clone();
+        let backoff = self.backoff.clone();
         Ok(async move {
-            let response = kitsune_p2p
-                .rpc_single(space, to_agent, from_agent, req, None)
-                .await?;
+            let response = rpc_single_to(
+                kitsune_p2p,
+                backoff,
+                space,
+                to_agent,
+                from_agent,
+                &req,
+                None,
+            )
+            .await?;
             let response = SerializedBytes::from(UnsafeBytes::from(response)).try_into()?;
             Ok(response)
         }
@@ -647,28 +831,140 @@ impl HolochainP2pHandler for HolochainP2pActor {
         let payload = crate::wire::WireMessage::get(dht_hash, r_options).encode()?;
 
         let kitsune_p2p = self.kitsune_p2p.clone();
-        Ok(async move {
-            let result = kitsune_p2p
-                .rpc_multi(kitsune_p2p::actor::RpcMulti {
-                    space,
-                    from_agent,
-                    basis,
-                    remote_agent_count: options.remote_agent_count,
-                    timeout_ms: options.timeout_ms,
-                    as_race: options.as_race,
-                    race_timeout_ms: options.race_timeout_ms,
-                    payload,
-                })
-                .instrument(tracing::debug_span!("rpc_multi"))
-                .await?;
+        Ok(
+            rpc_multi_get(kitsune_p2p, space, from_agent, basis, options, payload)
+                .boxed()
+                .into(),
+        )
+    }
 
-            let mut out = Vec::new();
-            for item in result {
-                let kitsune_p2p::actor::RpcMultiResponse { response, .. } = item;
-                out.push(SerializedBytes::from(UnsafeBytes::from(response)).try_into()?);
+    #[tracing::instrument(skip(self, dna_hash, from_agent, dht_hashes, options), level = "trace")]
+    fn handle_get_many(
+        &mut self,
+        dna_hash: DnaHash,
+        from_agent: AgentPubKey,
+        dht_hashes: Vec<holo_hash::AnyDhtHash>,
+        options: actor::GetOptions,
+    ) -> HolochainP2pHandlerResult<HashMap<holo_hash::AnyDhtHash, Vec<GetElementResponse>>> {
+        let space = dna_hash.into_kitsune();
+        let from_agent = from_agent.into_kitsune();
+        let r_options: event::GetOptions = (&options).into();
+
+        let mut by_basis = HashMap::new();
+        for dht_hash in dht_hashes {
+            by_basis.insert(dht_hash.to_kitsune(), dht_hash);
+        }
+
+        let kitsune_p2p = self.kitsune_p2p.clone();
+        let backoff = self.backoff.clone();
+        Ok(async move {
+            let mut out = HashMap::new();
+
+            // only a plain get can be answered by a single authority per hash
+            if let kitsune_p2p::actor::RpcMultiStrategy::FirstN = options.strategy {
+                let groups = match kitsune_p2p
+                    .group_bases_by_authority(
+                        space.clone(),
+                        from_agent.clone(),
+                        by_basis.keys().cloned().collect(),
+                    )
+                    .await
+                {
+                    Ok(groups) => groups.groups,
+                    // every hash falls back to a regular get
+                    Err(e) => {
+                        tracing::debug!(msg = "get_many could not group hashes", ?e);
+                        Vec::new()
+                    }
+                };
+                let timeout_ms = options.timeout_ms;
+                let batches = groups.into_iter().flat_map(|(to_agent, bases)| {
+                    let dht_hashes: Vec<_> = bases
+                        .iter()
+                        .filter_map(|b| by_basis.get(b).cloned())
+                        .collect();
+                    dht_hashes
+                        .chunks(crate::wire::MAX_GET_MANY_HASHES)
+                        .map(|chunk| (to_agent.clone(), chunk.to_vec()))
+                        .collect::<Vec<_>>()
+                });
+                let group_gets = batches.map(|(to_agent, dht_hashes)| {
+                    let req = crate::wire::WireMessage::get_many(dht_hashes, r_options.clone());
+                    let kitsune_p2p = kitsune_p2p.clone();
+                    let backoff = backoff.clone();
+                    let space = space.clone();
+                    let from_agent = from_agent.clone();
+                    async move {
+                        let response = rpc_single_to(
+                            kitsune_p2p,
+                            backoff,
+                            space,
+                            to_agent,
+                            from_agent,
+                            &req,
+                            timeout_ms,
+                        )
+                        .await?;
+                        let response: crate::wire::WireGetManyResponse =
+                            holochain_serialized_bytes::decode(&response)?;
+                        HolochainP2pResult::Ok(response)
+                    }
+                });
+                for result in futures::future::join_all(group_gets).await {
+                    match result {
+                        Ok(responses) => {
+                            for (dht_hash, response) in responses {
+                                // an authority that holds nothing for a hash
+                                // may just not have it yet, so ask around
+                                if !is_empty_get_response(&response) {
+                                    out.insert(dht_hash, vec![response]);
+                                }
+                            }
+                        }
+                        // the hashes of this group fall back to a regular get,
+                        // as do those of an authority that's busy with get_many
+                        Err(e) => tracing::debug!(msg = "get_many group failed", ?e),
+                    }
+                }
             }
 
-            Ok(out)
+            let gets = by_basis
+                .into_iter()
+                .filter(|(_, dht_hash)| !out.contains_key(dht_hash))
+                .map(|(basis, dht_hash)| {
+                    let payload =
+                        crate::wire::WireMessage::get(dht_hash.clone(), r_options.clone()).encode();
+                    let kitsune_p2p = kitsune_p2p.clone();
+                    let space = space.clone();
+                    let from_agent = from_agent.clone();
+                    let options = options.clone();
+                    async move {
+                        let responses =
+                            rpc_multi_get(kitsune_p2p, space, from_agent, basis, options, payload?)
+                                .await?;
+                        HolochainP2pResult::Ok((dht_hash, responses))
+                    }
+                });
+            // a hash that can't be got is left out,
+            // unless nothing could be got at all
+            let mut first_err = None;
+            for result in futures::future::join_all(gets).await {
+                match result {
+                    Ok((dht_hash, responses)) => {
+                        if !responses.is_empty() {
+                            out.insert(dht_hash, responses);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::debug!(msg = "get_many fallback get failed", ?e);
+                        first_err.get_or_insert(e);
+                    }
+                }
+            }
+            match first_err {
+                Some(e) if out.is_empty() => Err(e),
+                _ => Ok(out),
+            }
         }
         .boxed()
         .into())
@@ -700,6 +996,8 @@ impl HolochainP2pHandler for HolochainP2pActor {
                     timeout_ms: options.timeout_ms,
                     as_race: options.as_race,
                     race_timeout_ms: options.race_timeout_ms,
+                    strategy: kitsune_p2p::actor::RpcMultiStrategy::FirstN,
+                    quorum_key: None,
                     payload,
                 })
                 .await?;
@@ -733,7 +1031,7 @@ impl HolochainP2pHandler for HolochainP2pActor {
 
         let kitsune_p2p = self.kitsune_p2p.clone();
         Ok(async move {
-            // TODO - We're just targeting a single remote node for now
+            // TODO - We're just targeting a single remote node by default
             //        without doing any pagination / etc...
             //        Setting up RpcMulti to act like RpcSingle
             let result = kitsune_p2p
@@ -741,10 +1039,12 @@ impl HolochainP2pHandler for HolochainP2pActor {
                     space,
                     from_agent,
                     basis,
-                    remote_agent_count: Some(1),
+                    remote_agent_count: Some(options.remote_agent_count.unwrap_or(1)),
                     timeout_ms: options.timeout_ms,
                     as_race: false,
                     race_timeout_ms: options.timeout_ms,
+                    strategy: options.strategy,
+                    quorum_key: None,
                     payload,
                 })
                 .await?;
@@ -778,39 +1078,93 @@ impl HolochainP2pHandler for HolochainP2pActor {
         let basis = agent_hash.to_kitsune();
         let r_options: event::GetActivityOptions = (&options).into();
 
-        let payload =
-            crate::wire::WireMessage::get_agent_activity(agent, query, r_options).encode()?;
+        let req = crate::wire::WireMessage::get_agent_activity(agent, query, r_options);
 
         let kitsune_p2p = self.kitsune_p2p.clone();
+        let backoff = self.backoff.clone();
         Ok(async move {
-            // TODO - We're just targeting a single remote node for now
-            //        without doing any pagination / etc...
-            //        Setting up RpcMulti to act like RpcSingle
-            let result = kitsune_p2p
-                .rpc_multi(kitsune_p2p::actor::RpcMulti {
-                    space,
-                    from_agent,
-                    basis,
-                    remote_agent_count: Some(1),
-                    timeout_ms: options.timeout_ms,
-                    as_race: false,
-                    race_timeout_ms: options.timeout_ms,
-                    payload,
-                })
-                .await?;
+            // streamed from an authority so a long chain doesn't time out
+            if let Some(chunks) = agent_activity_from_authority(
+                kitsune_p2p.clone(),
+                backoff,
+                space.clone(),
+                from_agent.clone(),
+                basis.clone(),
+                &req,
+                options.timeout_ms,
+            )
+            .await?
+            {
+                match chunks.try_collect::<Vec<Vec<u8>>>().await {
+                    Ok(chunks) => {
+                        let response = SerializedBytes::from(UnsafeBytes::from(chunks.concat()));
+                        return Ok(vec![response.try_into()?]);
+                    }
+                    Err(e) => tracing::debug!(msg = "agent activity stream failed", ?e),
+                }
+            }
 
             let mut out = Vec::new();
-            for item in result {
-                let kitsune_p2p::actor::RpcMultiResponse { response, .. } = item;
+            for response in
+                rpc_multi_agent_activity(kitsune_p2p, space, from_agent, basis, &options, &req)
+                    .await?
+            {
                 out.push(SerializedBytes::from(UnsafeBytes::from(response)).try_into()?);
             }
-
             Ok(out)
         }
         .boxed()
         .into())
     }
 
+    #[tracing::instrument(skip(self), level = "trace")]
+    fn handle_get_agent_activity_stream(
+        &mut self,
+        dna_hash: DnaHash,
+        from_agent: AgentPubKey,
+        agent: AgentPubKey,
+        query: ChainQueryFilter,
+        options: actor::GetActivityOptions,
+    ) -> HolochainP2pHandlerResult<actor::ResponseStream> {
+        let space = dna_hash.into_kitsune();
+        let from_agent = from_agent.into_kitsune();
+        // Convert the agent key to an any dht hash so it can be used
+        // as the basis for sending this request
+        let agent_hash: AnyDhtHash = agent.clone().into();
+        let basis = agent_hash.to_kitsune();
+        let r_options: event::GetActivityOptions = (&options).into();
+
+        let req = crate::wire::WireMessage::get_agent_activity(agent, query, r_options);
+
+        let kitsune_p2p = self.kitsune_p2p.clone();
+        let backoff = self.backoff.clone();
+        Ok(async move {
+            if let Some(chunks) = agent_activity_from_authority(
+                kitsune_p2p.clone(),
+                backoff,
+                space.clone(),
+                from_agent.clone(),
+                basis.clone(),
+                &req,
+                options.timeout_ms,
+            )
+            .await?
+            {
+                return Ok(chunks.map_err(HolochainP2pError::from).boxed());
+            }
+
+            // a stream holds one response, so the first to arrive wins
+            let response =
+                rpc_multi_agent_activity(kitsune_p2p, space, from_agent, basis, &options, &req)
+                    .await?
+                    .into_iter()
+                    .next();
+            Ok(futures::stream::iter(response.map(Ok)).boxed())
+        }
+        .boxed()
+        .into())
+    }
+
     #[tracing::instrument(skip(self), level = "trace")]
     fn handle_send_validation_receipt(
         &mut self,
@@ -823,16 +1177,313 @@ impl HolochainP2pHandler for HolochainP2pActor {
         let to_agent = to_agent.into_kitsune();
         let from_agent = from_agent.into_kitsune();
 
-        let req = crate::wire::WireMessage::validation_receipt(receipt).encode()?;
+        let req = crate::wire::WireMessage::validation_receipt(receipt);
 
         let kitsune_p2p = self.kitsune_p2p.clone();
+        let backoff = self.backoff.clone();
         Ok(async move {
-            kitsune_p2p
-                .rpc_single(space, to_agent, from_agent, req, None)
-                .await?;
+            rpc_single_to(
+                kitsune_p2p,
+                backoff,
+                space,
+                to_agent,
+                from_agent,
+                &req,
+                None,
+            )
+            .await?;
             Ok(())
         }
         .boxed()
         .into())
     }
+
+    #[tracing::instrument(skip(self), level = "trace")]
+    fn handle_network_stats(
+        &mut self,
+        dna_hash: DnaHash,
+    ) -> HolochainP2pHandlerResult<actor::NetworkStats> {
+        let space = dna_hash.clone().into_kitsune();
+
+        let kitsune_p2p = self.kitsune_p2p.clone();
+        Ok(async move {
+            let stats = kitsune_p2p.space_stats(space).await?;
+            Ok(actor::NetworkStats::from_kitsune(dna_hash, stats))
+        }
+        .boxed()
+        .into())
+    }
+
+    #[tracing::instrument(skip(self), level = "trace")]
+    fn handle_report_invalid_data(
+        &mut self,
+        dna_hash: DnaHash,
+        agent: AgentPubKey,
+    ) -> HolochainP2pHandlerResult<()> {
+        let space = dna_hash.into_kitsune();
+        let agent = agent.into_kitsune();
+
+        let kitsune_p2p = self.kitsune_p2p.clone();
+        Ok(
+            async move { Ok(kitsune_p2p.report_invalid_data(space, agent).await?) }
+                .boxed()
+                .into(),
+        )
+    }
+
+    #[tracing::instrument(skip(self), level = "trace")]
+    fn handle_block_peer(
+        &mut self,
+        dna_hash: DnaHash,
+        agent: AgentPubKey,
+        duration_ms: Option<u64>,
+    ) -> HolochainP2pHandlerResult<()> {
+        let space = dna_hash.into_kitsune();
+        let agent = agent.into_kitsune();
+
+        let kitsune_p2p = self.kitsune_p2p.clone();
+        Ok(
+            async move { Ok(kitsune_p2p.block_peer(space, agent, duration_ms).await?) }
+                .boxed()
+                .into(),
+        )
+    }
+
+    #[tracing::instrument(skip(self), level = "trace")]
+    fn handle_unblock_peer(
+        &mut self,
+        dna_hash: DnaHash,
+        agent: AgentPubKey,
+    ) -> HolochainP2pHandlerResult<()> {
+        let space = dna_hash.into_kitsune();
+        let agent = agent.into_kitsune();
+
+        let kitsune_p2p = self.kitsune_p2p.clone();
+        Ok(
+            async move { Ok(kitsune_p2p.unblock_peer(space, agent).await?) }
+                .boxed()
+                .into(),
+        )
+    }
+}
+
+/// True if the response is an authority saying it holds nothing for the hash.
+fn is_empty_get_response(response: &GetElementResponse) -> bool {
+    matches!(
+        response,
+        GetElementResponse::GetHeader(None) | GetElementResponse::GetEntryFull(None)
+    )
+}
+
+/// Get responses agree if they hold the same element, or the same entry,
+/// whatever deletes, updates and validation status each authority has seen.
+/// An element only agrees if its entry does too, as an entry isn't
+/// covered by the header hash unless it was checked against the header.
+fn get_quorum_key() -> kitsune_p2p::actor::QuorumKey {
+    kitsune_p2p::actor::QuorumKey(Arc::new(|response: &[u8]| {
+        let decoded: Result<GetElementResponse, _> =
+            SerializedBytes::from(UnsafeBytes::from(response.to_vec())).try_into();
+        // tagged so a hash can't agree with a different kind of response
+        let (tag, bytes) = match decoded {
+            Ok(GetElementResponse::GetHeader(Some(element))) => {
+                let mut bytes = element.header_hash().into_inner();
+                if let Some(entry) = element.entry() {
+                    bytes.extend(EntryHash::with_data_sync(entry).into_inner());
+                }
+                (1, bytes)
+            }
+            Ok(GetElementResponse::GetEntryFull(Some(entry))) => {
+                (2, EntryHash::with_data_sync(&entry.entry).into_inner())
+            }
+            Ok(_) => (0, Vec::new()),
+            Err(_) => (3, response.to_vec()),
+        };
+        let mut key = vec![tag];
+        key.extend(bytes);
+        key
+    }))
+}
+
+/// Send a request to a remote agent with `rpc_single`, unless it
+/// asked us to back off from this type of request for now.
+async fn rpc_single_to(
+    kitsune_p2p: ghost_actor::GhostSender<kitsune_p2p::actor::KitsuneP2p>,
+    backoff: RequestBackoff,
+    space: Arc<kitsune_p2p::KitsuneSpace>,
+    to_agent: Arc<kitsune_p2p::KitsuneAgent>,
+    from_agent: Arc<kitsune_p2p::KitsuneAgent>,
+    request: &crate::wire::WireMessage,
+    timeout_ms: Option<u64>,
+) -> HolochainP2pResult<Vec<u8>> {
+    let request_type = request.request_type();
+    backoff.check(&to_agent, request_type)?;
+    let payload = request.encode()?;
+    let result = kitsune_p2p
+        .rpc_single(space, to_agent.clone(), from_agent, payload, timeout_ms)
+        .await;
+    backoff.record(&to_agent, request_type, &result);
+    Ok(result?)
+}
+
+/// Send a request to a remote agent with `rpc_stream`, unless it
+/// asked us to back off from this type of request for now.
+async fn rpc_stream_to(
+    kitsune_p2p: ghost_actor::GhostSender<kitsune_p2p::actor::KitsuneP2p>,
+    backoff: RequestBackoff,
+    space: Arc<kitsune_p2p::KitsuneSpace>,
+    to_agent: Arc<kitsune_p2p::KitsuneAgent>,
+    from_agent: Arc<kitsune_p2p::KitsuneAgent>,
+    request: &crate::wire::WireMessage,
+    timeout_ms: Option<u64>,
+) -> HolochainP2pResult<kitsune_p2p::actor::RpcStream> {
+    let request_type = request.request_type();
+    backoff.check(&to_agent, request_type)?;
+    let payload = request.encode()?;
+    let result = kitsune_p2p
+        .rpc_stream(space, to_agent.clone(), from_agent, payload, timeout_ms)
+        .await;
+    backoff.record(&to_agent, request_type, &result);
+    Ok(result?)
+}
+
+/// Start streaming agent activity from the authority we know holds it.
+/// `None` if we know of no authority, or it refused to stream it,
+/// so the activity should be asked of the neighborhood instead.
+async fn agent_activity_from_authority(
+    kitsune_p2p: ghost_actor::GhostSender<kitsune_p2p::actor::KitsuneP2p>,
+    backoff: RequestBackoff,
+    space: Arc<kitsune_p2p::KitsuneSpace>,
+    from_agent: Arc<kitsune_p2p::KitsuneAgent>,
+    basis: Arc<kitsune_p2p::KitsuneBasis>,
+    request: &crate::wire::WireMessage,
+    timeout_ms: Option<u64>,
+) -> HolochainP2pResult<Option<kitsune_p2p::actor::RpcStream>> {
+    let authorities = kitsune_p2p
+        .group_bases_by_authority(space.clone(), from_agent.clone(), vec![basis])
+        .await?;
+    let to_agent = match authorities.groups.into_iter().next() {
+        Some((to_agent, _)) => to_agent,
+        None => return Ok(None),
+    };
+    match rpc_stream_to(
+        kitsune_p2p,
+        backoff,
+        space,
+        to_agent,
+        from_agent,
+        request,
+        timeout_ms,
+    )
+    .await
+    {
+        Ok(chunks) => Ok(Some(chunks)),
+        Err(e) => {
+            tracing::debug!(msg = "agent activity authority failed", ?e);
+            Ok(None)
+        }
+    }
+}
+
+/// Get agent activity from the neighborhood of the agent with `rpc_multi`,
+/// as many responses as the options ask for.
+async fn rpc_multi_agent_activity(
+    kitsune_p2p: ghost_actor::GhostSender<kitsune_p2p::actor::KitsuneP2p>,
+    space: Arc<kitsune_p2p::KitsuneSpace>,
+    from_agent: Arc<kitsune_p2p::KitsuneAgent>,
+    basis: Arc<kitsune_p2p::KitsuneBasis>,
+    options: &actor::GetActivityOptions,
+    request: &crate::wire::WireMessage,
+) -> HolochainP2pResult<Vec<Vec<u8>>> {
+    let payload = request.encode()?;
+    let result = kitsune_p2p
+        .rpc_multi(kitsune_p2p::actor::RpcMulti {
+            space,
+            from_agent,
+            basis,
+            remote_agent_count: Some(options.remote_agent_count.unwrap_or(1)),
+            timeout_ms: options.timeout_ms,
+            as_race: options.as_race,
+            race_timeout_ms: options.race_timeout_ms,
+            strategy: kitsune_p2p::actor::RpcMultiStrategy::FirstN,
+            quorum_key: None,
+            payload,
+        })
+        .await?;
+    Ok(result.into_iter().map(|r| r.response).collect())
+}
+
+/// Get a hash from the neighborhood of its basis with `rpc_multi`.
+async fn rpc_multi_get(
+    kitsune_p2p: ghost_actor::GhostSender<kitsune_p2p::actor::KitsuneP2p>,
+    space: Arc<kitsune_p2p::KitsuneSpace>,
+    from_agent: Arc<kitsune_p2p::KitsuneAgent>,
+    basis: Arc<kitsune_p2p::KitsuneBasis>,
+    options: actor::GetOptions,
+    payload: Vec<u8>,
+) -> HolochainP2pResult<Vec<GetElementResponse>> {
+    let result = kitsune_p2p
+        .rpc_multi(kitsune_p2p::actor::RpcMulti {
+            space,
+            from_agent,
+            basis,
+            remote_agent_count: options.remote_agent_count,
+            timeout_ms: options.timeout_ms,
+            as_race: options.as_race,
+            race_timeout_ms: options.race_timeout_ms,
+            strategy: options.strategy,
+            quorum_key: Some(get_quorum_key()),
+            payload,
+        })
+        .instrument(tracing::debug_span!("rpc_multi"))
+        .await?;
+
+    let mut out = Vec::new();
+    for item in result {
+        let kitsune_p2p::actor::RpcMultiResponse { response, .. } = item;
+        out.push(SerializedBytes::from(UnsafeBytes::from(response)).try_into()?);
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::fixt::prelude::*;
+    use holochain_zome_types::HeaderHashed;
+    use holochain_zome_types::ValidationStatus;
+
+    fn get_header_response(header: Header, entry: Option<Entry>) -> Vec<u8> {
+        let response = GetElementResponse::GetHeader(Some(Box::new(WireElement::from_element(
+            ElementStatus::new(
+                Element::new(
+                    SignedHeaderHashed::with_presigned(
+                        HeaderHashed::from_content_sync(header),
+                        fixt!(Signature),
+                    ),
+                    entry,
+                ),
+                ValidationStatus::Valid,
+            ),
+            vec![],
+            vec![],
+        ))));
+        UnsafeBytes::from(SerializedBytes::try_from(response).unwrap()).into()
+    }
+
+    #[test]
+    fn get_header_quorum_key_covers_the_entry() {
+        let key = get_quorum_key();
+        let header = fixt!(Header);
+        let entry = fixt!(Entry);
+
+        let honest = get_header_response(header.clone(), Some(entry.clone()));
+        assert_eq!(
+            (key.0)(&honest),
+            (key.0)(&get_header_response(header.clone(), Some(entry)))
+        );
+        // the same header with a swapped entry doesn't agree
+        let lying = get_header_response(header, Some(fixt!(Entry)));
+        assert_ne!((key.0)(&honest), (key.0)(&lying));
+    }
 }
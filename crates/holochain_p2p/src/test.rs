@@ -40,6 +40,18 @@ impl HolochainP2pHandler for StubNetwork {
     ) -> HolochainP2pHandlerResult<SerializedBytes> {
         Err("stub".into())
     }
+    fn handle_call_remote_stream(
+        &mut self,
+        dna_hash: DnaHash,
+        from_agent: AgentPubKey,
+        to_agent: AgentPubKey,
+        zome_name: ZomeName,
+        fn_name: FunctionName,
+        cap: Option<CapSecret>,
+        payload: ExternIO,
+    ) -> HolochainP2pHandlerResult<actor::ResponseStream> {
+        Err("stub".into())
+    }
     fn handle_publish(
         &mut self,
         dna_hash: DnaHash,
@@ -66,6 +78,17 @@ impl HolochainP2pHandler for StubNetwork {
     ) -> HolochainP2pHandlerResult<Vec<GetElementResponse>> {
         Err("stub".into())
     }
+    fn handle_get_many(
+        &mut self,
+        dna_hash: DnaHash,
+        from_agent: AgentPubKey,
+        dht_hashes: Vec<holo_hash::AnyDhtHash>,
+        options: actor::GetOptions,
+    ) -> HolochainP2pHandlerResult<
+        std::collections::HashMap<holo_hash::AnyDhtHash, Vec<GetElementResponse>>,
+    > {
+        Err("stub".into())
+    }
     fn handle_get_meta(
         &mut self,
         dna_hash: DnaHash,
@@ -94,6 +117,16 @@ impl HolochainP2pHandler for StubNetwork {
     ) -> HolochainP2pHandlerResult<Vec<AgentActivityResponse>> {
         Err("stub".into())
     }
+    fn handle_get_agent_activity_stream(
+        &mut self,
+        dna_hash: DnaHash,
+        from_agent: AgentPubKey,
+        agent: AgentPubKey,
+        query: ChainQueryFilter,
+        options: actor::GetActivityOptions,
+    ) -> HolochainP2pHandlerResult<actor::ResponseStream> {
+        Err("stub".into())
+    }
     fn handle_send_validation_receipt(
         &mut self,
         dna_hash: DnaHash,
@@ -103,6 +136,34 @@ impl HolochainP2pHandler for StubNetwork {
     ) -> HolochainP2pHandlerResult<()> {
         Err("stub".into())
     }
+    fn handle_network_stats(
+        &mut self,
+        dna_hash: DnaHash,
+    ) -> HolochainP2pHandlerResult<actor::NetworkStats> {
+        Err("stub".into())
+    }
+    fn handle_report_invalid_data(
+        &mut self,
+        dna_hash: DnaHash,
+        agent: AgentPubKey,
+    ) -> HolochainP2pHandlerResult<()> {
+        Err("stub".into())
+    }
+    fn handle_block_peer(
+        &mut self,
+        dna_hash: DnaHash,
+        agent: AgentPubKey,
+        duration_ms: Option<u64>,
+    ) -> HolochainP2pHandlerResult<()> {
+        Err("stub".into())
+    }
+    fn handle_unblock_peer(
+        &mut self,
+        dna_hash: DnaHash,
+        agent: AgentPubKey,
+    ) -> HolochainP2pHandlerResult<()> {
+        Err("stub".into())
+    }
 }
 
 /// Spawn a stub network that doesn't respond to any messages.
@@ -231,6 +292,66 @@ mod tests {
         r_task.await.unwrap();
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn test_call_remote_stream_workflow() {
+        let (dna, a1, a2, _) = test_setup();
+
+        let (p2p, mut evt) = spawn_holochain_p2p(
+            KitsuneP2pConfig::default(),
+            TlsConfig::new_ephemeral().await.unwrap(),
+        )
+        .await
+        .unwrap();
+
+        // bigger than a stream chunk
+        let big: Vec<u8> = (0..200_000).map(|i| i as u8).collect();
+        let big_clone = big.clone();
+        let r_task = tokio::task::spawn(async move {
+            use tokio::stream::StreamExt;
+            while let Some(evt) = evt.next().await {
+                use crate::types::event::HolochainP2pEvent::*;
+                match evt {
+                    CallRemote { respond, .. } => {
+                        let big = big_clone.clone();
+                        respond.r(Ok(async move { Ok(UnsafeBytes::from(big).into()) }
+                            .boxed()
+                            .into()));
+                    }
+                    SignNetworkData { respond, .. } => {
+                        respond.r(Ok(async move { Ok(vec![0; 64].into()) }.boxed().into()));
+                    }
+                    PutAgentInfoSigned { respond, .. } => {
+                        respond.r(Ok(async move { Ok(()) }.boxed().into()));
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        p2p.join(dna.clone(), a1.clone()).await.unwrap();
+        p2p.join(dna.clone(), a2.clone()).await.unwrap();
+
+        let chunks: Vec<Vec<u8>> = futures::stream::TryStreamExt::try_collect(
+            p2p.call_remote_stream(
+                dna,
+                a1,
+                a2,
+                "".into(),
+                "".into(),
+                None,
+                ExternIO::encode(b"yippo").unwrap(),
+            )
+            .await
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(big, chunks.concat());
+
+        p2p.ghost_actor_shutdown().await.unwrap();
+        r_task.await.unwrap();
+    }
+
     #[tokio::test(threaded_scheduler)]
     async fn test_send_validation_receipt_workflow() {
         let (dna, a1, a2, _) = test_setup();
@@ -425,6 +546,100 @@ mod tests {
         r_task.await.unwrap();
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn test_get_many_workflow() {
+        let (dna, a1, a2, _a3) = test_setup();
+
+        let (p2p, mut evt) = spawn_holochain_p2p(
+            KitsuneP2pConfig::default(),
+            TlsConfig::new_ephemeral().await.unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let test_1 = GetElementResponse::GetHeader(None);
+
+        let r_task = tokio::task::spawn({
+            let test_1 = test_1.clone();
+            async move {
+                use tokio::stream::StreamExt;
+                while let Some(evt) = evt.next().await {
+                    use crate::types::event::HolochainP2pEvent::*;
+                    match evt {
+                        Get { respond, .. } => {
+                            let resp = test_1.clone();
+                            respond.r(Ok(async move { Ok(resp) }.boxed().into()));
+                        }
+                        SignNetworkData { respond, .. } => {
+                            respond.r(Ok(async move { Ok(vec![0; 64].into()) }.boxed().into()));
+                        }
+                        PutAgentInfoSigned { respond, .. } => {
+                            respond.r(Ok(async move { Ok(()) }.boxed().into()));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        });
+
+        p2p.join(dna.clone(), a1.clone()).await.unwrap();
+        p2p.join(dna.clone(), a2.clone()).await.unwrap();
+
+        let hashes = vec![
+            holo_hash::AnyDhtHash::from_raw_36_and_type(
+                b"eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee".to_vec(),
+                holo_hash::hash_type::AnyDht::Header,
+            ),
+            holo_hash::AnyDhtHash::from_raw_36_and_type(
+                b"ffffffffffffffffffffffffffffffffffff".to_vec(),
+                holo_hash::hash_type::AnyDht::Entry,
+            ),
+        ];
+
+        let res = p2p
+            .get_many(dna, a1, hashes.clone(), actor::GetOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(2, res.len());
+        for hash in hashes {
+            let responses = res.get(&hash).unwrap();
+            assert!(!responses.is_empty());
+            assert!(responses.iter().all(|r| *r == test_1));
+        }
+
+        p2p.ghost_actor_shutdown().await.unwrap();
+        r_task.await.unwrap();
+    }
+
+    #[test]
+    fn test_get_options_from_zome_aggregation() {
+        use holochain_zome_types::entry::GetAggregation;
+        use kitsune_p2p::actor::RpcMultiStrategy;
+
+        let zome_options = holochain_zome_types::entry::GetOptions::latest();
+        let options: actor::GetOptions = zome_options.clone().into();
+        assert_eq!(RpcMultiStrategy::FirstN, options.strategy);
+        assert!(options.as_race);
+
+        let options: actor::GetOptions = zome_options
+            .clone()
+            .with_aggregation(GetAggregation::Quorum(3))
+            .into();
+        assert_eq!(RpcMultiStrategy::Quorum(3), options.strategy);
+        assert!(!options.as_race);
+
+        let options: actor::GetOptions = zome_options
+            .with_aggregation(GetAggregation::AllWithDeadline {
+                count: Some(5),
+                timeout_ms: Some(1000),
+            })
+            .into();
+        assert_eq!(RpcMultiStrategy::AllWithDeadline, options.strategy);
+        assert_eq!(Some(5), options.remote_agent_count);
+        assert_eq!(Some(1000), options.timeout_ms);
+    }
+
     #[tokio::test(threaded_scheduler)]
     async fn test_get_links_workflow() {
         let (dna, a1, a2, _) = test_setup();
@@ -485,4 +700,86 @@ mod tests {
         p2p.ghost_actor_shutdown().await.unwrap();
         r_task.await.unwrap();
     }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_get_agent_activity_workflow() {
+        let (dna, a1, a2, a3) = test_setup();
+
+        let (p2p, mut evt) = spawn_holochain_p2p(
+            KitsuneP2pConfig::default(),
+            TlsConfig::new_ephemeral().await.unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let test_1 = AgentActivityResponse {
+            agent: a3.clone(),
+            valid_activity: holochain_types::activity::ChainItems::NotRequested,
+            rejected_activity: holochain_types::activity::ChainItems::NotRequested,
+            status: holochain_zome_types::query::ChainStatus::Empty,
+            highest_observed: None,
+        };
+
+        let test_1_clone = test_1.clone();
+        let r_task = tokio::task::spawn(async move {
+            use tokio::stream::StreamExt;
+            while let Some(evt) = evt.next().await {
+                let test_1_clone = test_1_clone.clone();
+                use crate::types::event::HolochainP2pEvent::*;
+                match evt {
+                    GetAgentActivity { respond, .. } => {
+                        respond.r(Ok(async move { Ok(test_1_clone) }.boxed().into()));
+                    }
+                    SignNetworkData { respond, .. } => {
+                        respond.r(Ok(async move { Ok(vec![0; 64].into()) }.boxed().into()));
+                    }
+                    PutAgentInfoSigned { respond, .. } => {
+                        respond.r(Ok(async move { Ok(()) }.boxed().into()));
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        p2p.join(dna.clone(), a1.clone()).await.unwrap();
+        p2p.join(dna.clone(), a2.clone()).await.unwrap();
+
+        // every response the neighborhood sends is returned
+        let res = p2p
+            .get_agent_activity(
+                dna.clone(),
+                a1.clone(),
+                a3.clone(),
+                ChainQueryFilter::new(),
+                actor::GetActivityOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert!(!res.is_empty());
+        for r in res {
+            assert_eq!(r, test_1);
+        }
+
+        // the stream is the first response, encoded
+        let chunks: Vec<Vec<u8>> = futures::stream::TryStreamExt::try_collect(
+            p2p.get_agent_activity_stream(
+                dna,
+                a1,
+                a3,
+                ChainQueryFilter::new(),
+                actor::GetActivityOptions::default(),
+            )
+            .await
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+        let res: AgentActivityResponse = SerializedBytes::from(UnsafeBytes::from(chunks.concat()))
+            .try_into()
+            .unwrap();
+        assert_eq!(test_1, res);
+
+        p2p.ghost_actor_shutdown().await.unwrap();
+        r_task.await.unwrap();
+    }
 }
@@ -63,6 +63,7 @@ impl From<HolochainP2pError> for kitsune_p2p::KitsuneP2pError {
     fn from(e: HolochainP2pError) -> Self {
         use HolochainP2pError::*;
         match e {
+            GhostError(e) => Self::GhostError(e),
             RoutingDnaError(dna) => Self::RoutingSpaceError(dna.to_kitsune()),
             RoutingAgentError(agent) => Self::RoutingAgentError(agent.to_kitsune()),
             OtherKitsuneP2pError(e) => e,
@@ -1,8 +1,10 @@
 //! Module containing the HolochainP2p actor definition.
 #![allow(clippy::too_many_arguments)]
 
+use crate::types::AgentPubKeyExt;
 use crate::*;
 use holochain_types::activity::AgentActivityResponse;
+use kitsune_p2p::actor::RpcMultiStrategy;
 
 /// Request a validation package.
 #[derive(Clone, Debug)]
@@ -47,6 +49,10 @@ pub struct GetOptions {
     /// Set to `None` for a default "best-effort" race.
     pub race_timeout_ms: Option<u64>,
 
+    /// [Network]
+    /// How the responses of the remote nodes are aggregated.
+    pub strategy: RpcMultiStrategy,
+
     /// [Remote]
     /// Whether the remote-end should follow redirects or just return the
     /// requested entry.
@@ -65,6 +71,7 @@ impl Default for GetOptions {
             timeout_ms: None,
             as_race: true,
             race_timeout_ms: None,
+            strategy: RpcMultiStrategy::default(),
             follow_redirects: true,
             all_live_headers_with_metadata: false,
         }
@@ -72,8 +79,25 @@ impl Default for GetOptions {
 }
 
 impl From<holochain_zome_types::entry::GetOptions> for GetOptions {
-    fn from(_: holochain_zome_types::entry::GetOptions) -> Self {
-        Self::default()
+    fn from(options: holochain_zome_types::entry::GetOptions) -> Self {
+        use holochain_zome_types::entry::GetAggregation;
+        let mut out = Self::default();
+        match options.aggregation {
+            GetAggregation::FirstN(remote_agent_count) => {
+                out.remote_agent_count = remote_agent_count;
+            }
+            GetAggregation::Quorum(quorum) => {
+                out.as_race = false;
+                out.strategy = RpcMultiStrategy::Quorum(quorum);
+            }
+            GetAggregation::AllWithDeadline { count, timeout_ms } => {
+                out.as_race = false;
+                out.remote_agent_count = count;
+                out.timeout_ms = timeout_ms;
+                out.strategy = RpcMultiStrategy::AllWithDeadline;
+            }
+        }
+        out
     }
 }
 
@@ -130,17 +154,30 @@ impl Default for GetMetaOptions {
 /// Fields tagged with `[Remote]` are controls that will be forwarded to the
 /// remote agent processing this `GetLinks` request.
 pub struct GetLinksOptions {
+    /// [Network]
+    /// How many remote nodes should we make requests of / aggregate.
+    /// Set to `None` to ask a single remote node.
+    pub remote_agent_count: Option<u8>,
+
     /// [Network]
     /// Timeout to await responses for aggregation.
     /// Set to `None` for a default "best-effort".
     /// Note - if all requests time-out you will receive an empty result,
     /// not a timeout error.
     pub timeout_ms: Option<u64>,
+
+    /// [Network]
+    /// How the responses of the remote nodes are aggregated.
+    pub strategy: RpcMultiStrategy,
 }
 
 impl Default for GetLinksOptions {
     fn default() -> Self {
-        Self { timeout_ms: None }
+        Self {
+            remote_agent_count: None,
+            timeout_ms: None,
+            strategy: RpcMultiStrategy::default(),
+        }
     }
 }
 
@@ -150,12 +187,27 @@ impl Default for GetLinksOptions {
 /// Fields tagged with `[Remote]` are controls that will be forwarded to the
 /// remote agent processing this `GetLinks` request.
 pub struct GetActivityOptions {
+    /// [Network]
+    /// How many remote nodes should we make requests of / aggregate,
+    /// when no authority is known to hold the activity.
+    /// Set to `None` to ask a single node.
+    pub remote_agent_count: Option<u8>,
     /// [Network]
     /// Timeout to await responses for aggregation.
     /// Set to `None` for a default "best-effort".
     /// Note - if all requests time-out you will receive an empty result,
     /// not a timeout error.
     pub timeout_ms: Option<u64>,
+    /// [Network]
+    /// We are interested in speed. If `true` and we have any results
+    /// when `race_timeout_ms` is expired, those results will be returned.
+    /// After `race_timeout_ms` and before `timeout_ms` the first result
+    /// received will be returned.
+    pub as_race: bool,
+    /// [Network]
+    /// See `as_race` for details.
+    /// Set to `None` for a default "best-effort" race.
+    pub race_timeout_ms: Option<u64>,
     /// Number of times to retry getting elements in parallel.
     /// For a small dht a large parallel get can overwhelm a single
     /// agent and it can be worth retrying the elements that didn't
@@ -178,7 +230,10 @@ pub struct GetActivityOptions {
 impl Default for GetActivityOptions {
     fn default() -> Self {
         Self {
+            remote_agent_count: None,
             timeout_ms: None,
+            as_race: false,
+            race_timeout_ms: None,
             retry_gets: 0,
             include_valid_activity: true,
             include_rejected_activity: false,
@@ -187,6 +242,119 @@ impl Default for GetActivityOptions {
     }
 }
 
+/// What we know about the network of one dna, for debugging.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NetworkStats {
+    /// The dna these stats are for.
+    pub dna_hash: DnaHash,
+    /// The agents running this dna on this conductor.
+    pub local_agents: Vec<AgentPubKey>,
+    /// The remote agents in our peer store for this dna.
+    pub peers: Vec<PeerNetworkStats>,
+    /// The agents we have gossiped with, most recent first.
+    pub gossip_partners: Vec<GossipPartnerStats>,
+    /// Outcomes of all the requests we made of remote agents.
+    pub requests: RequestStats,
+}
+
+/// What we know about one remote agent.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerNetworkStats {
+    /// The remote agent.
+    pub agent: AgentPubKey,
+    /// The urls the agent can be reached at.
+    pub urls: Vec<String>,
+    /// When the agent signed its agent info, in ms since the unix epoch.
+    pub signed_at_ms: u64,
+    /// When the agent info expires, in ms since the unix epoch.
+    pub expires_at_ms: u64,
+    /// Whether we have a transport connection to the agent.
+    pub connected: bool,
+    /// Outcomes of the requests we made of this agent.
+    pub requests: RequestStats,
+    /// How well the agent has answered us lately, positive is good.
+    pub score: f64,
+    /// Whether we are currently refusing to contact the agent.
+    pub blocked: bool,
+}
+
+/// The last gossip round with one agent.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GossipPartnerStats {
+    /// The agent we gossiped with.
+    pub agent: AgentPubKey,
+    /// When we last compared op hashes, in ms since the unix epoch.
+    pub last_gossip_at_ms: u64,
+    /// How many op hashes we had when we last compared.
+    pub local_op_count: u64,
+    /// How many op hashes they had when we last compared.
+    pub remote_op_count: u64,
+}
+
+/// Counts of how requests of remote agents turned out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RequestStats {
+    /// Requests that got a response.
+    pub succeeded: u64,
+    /// Requests that got no response within their timeout.
+    pub timed_out: u64,
+    /// Requests that failed, or got a failure response.
+    pub failed: u64,
+}
+
+impl From<kitsune_p2p::actor::RequestStats> for RequestStats {
+    fn from(s: kitsune_p2p::actor::RequestStats) -> Self {
+        Self {
+            succeeded: s.succeeded,
+            timed_out: s.timed_out,
+            failed: s.failed,
+        }
+    }
+}
+
+impl NetworkStats {
+    /// Convert the stats kitsune keeps for the space of this dna.
+    pub fn from_kitsune(dna_hash: DnaHash, stats: kitsune_p2p::actor::SpaceStats) -> Self {
+        let kitsune_p2p::actor::SpaceStats {
+            local_agents,
+            peers,
+            gossip_partners,
+            requests,
+        } = stats;
+        Self {
+            dna_hash,
+            local_agents: local_agents.iter().map(AgentPubKey::from_kitsune).collect(),
+            peers: peers
+                .into_iter()
+                .map(|p| PeerNetworkStats {
+                    agent: AgentPubKey::from_kitsune(&p.agent),
+                    urls: p.urls.iter().map(|u| u.to_string()).collect(),
+                    signed_at_ms: p.signed_at_ms,
+                    expires_at_ms: p.expires_at_ms,
+                    connected: p.connected,
+                    requests: p.requests.into(),
+                    score: p.score,
+                    blocked: p.blocked,
+                })
+                .collect(),
+            gossip_partners: gossip_partners
+                .into_iter()
+                .map(|g| GossipPartnerStats {
+                    agent: AgentPubKey::from_kitsune(&g.agent),
+                    last_gossip_at_ms: g.last_gossip_at_ms,
+                    local_op_count: g.local_op_count,
+                    remote_op_count: g.remote_op_count,
+                })
+                .collect(),
+            requests: requests.into(),
+        }
+    }
+}
+
+/// A response as the chunks it arrives in, see `call_remote_stream`.
+/// Concatenated, the chunks are the encoded response.
+pub type ResponseStream = futures::stream::BoxStream<'static, HolochainP2pResult<Vec<u8>>>;
+
 ghost_actor::ghost_chan! {
     /// The HolochainP2pSender struct allows controlling the HolochainP2p
     /// actor instance.
@@ -208,6 +376,19 @@ ghost_actor::ghost_chan! {
             payload: ExternIO,
         ) -> SerializedBytes;
 
+        /// Invoke a zome function on a remote node, streaming the serialized
+        /// response in chunks. Each chunk has its own timeout, so large
+        /// responses don't need to arrive all at once.
+        fn call_remote_stream(
+            dna_hash: DnaHash,
+            from_agent: AgentPubKey,
+            to_agent: AgentPubKey,
+            zome_name: ZomeName,
+            fn_name: FunctionName,
+            cap: Option<CapSecret>,
+            payload: ExternIO,
+        ) -> ResponseStream;
+
         /// Publish data to the correct neighborhood.
         fn publish(
             dna_hash: DnaHash,
@@ -229,6 +410,19 @@ ghost_actor::ghost_chan! {
             options: GetOptions,
         ) -> Vec<GetElementResponse>;
 
+        /// Get many entries from the DHT, asking each authority for all the
+        /// hashes it holds in a single request rather than one request per hash.
+        /// Hashes no authority answered for, or held nothing for, fall back to
+        /// a regular `get`, as do all hashes if `options.strategy` isn't `FirstN`.
+        /// Hashes nobody answered for are missing from the result,
+        /// which is only an error if nothing could be got at all.
+        fn get_many(
+            dna_hash: DnaHash,
+            from_agent: AgentPubKey,
+            dht_hashes: Vec<holo_hash::AnyDhtHash>,
+            options: GetOptions,
+        ) -> std::collections::HashMap<holo_hash::AnyDhtHash, Vec<GetElementResponse>>;
+
         /// Get metadata from the DHT.
         fn get_meta(
             dna_hash: DnaHash,
@@ -254,8 +448,34 @@ ghost_actor::ghost_chan! {
             options: GetActivityOptions,
         ) -> Vec<AgentActivityResponse>;
 
+        /// Get agent activity from an authority holding it, streaming the
+        /// encoded `AgentActivityResponse` in chunks. If that authority
+        /// can't be reached, the first response from the agent's
+        /// neighborhood is sent in one chunk. The stream is empty if
+        /// nobody responded.
+        fn get_agent_activity_stream(
+            dna_hash: DnaHash,
+            from_agent: AgentPubKey,
+            agent: AgentPubKey,
+            query: ChainQueryFilter,
+            options: GetActivityOptions,
+        ) -> ResponseStream;
+
         /// Send a validation receipt to a remote node.
         fn send_validation_receipt(dna_hash: DnaHash, to_agent: AgentPubKey, from_agent: AgentPubKey, receipt: SerializedBytes) -> ();
+
+        /// Get what we know about the network of a dna we have joined.
+        fn network_stats(dna_hash: DnaHash) -> NetworkStats;
+
+        /// Report that a remote agent sent us data for this dna that failed validation.
+        fn report_invalid_data(dna_hash: DnaHash, agent: AgentPubKey) -> ();
+
+        /// Stop contacting a remote agent for this dna for `duration_ms`,
+        /// or the `peer_block_duration_ms` tuning param if None.
+        fn block_peer(dna_hash: DnaHash, agent: AgentPubKey, duration_ms: Option<u64>) -> ();
+
+        /// Lift any block on a remote agent for this dna.
+        fn unblock_peer(dna_hash: DnaHash, agent: AgentPubKey) -> ();
     }
 }
 
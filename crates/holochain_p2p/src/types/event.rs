@@ -6,7 +6,7 @@ use holochain_zome_types::signature::Signature;
 use kitsune_p2p::agent_store::AgentInfoSigned;
 
 /// Get options help control how the get is processed at various levels.
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct GetOptions {
     /// Whether the remote-end should follow redirects or just return the
     /// requested entry.
@@ -159,6 +159,16 @@ ghost_actor::ghost_chan! {
             until: holochain_types::Timestamp,
         ) -> Vec<holo_hash::DhtOpHash>;
 
+        /// The p2p module wishes to query our DhtOpHash store for ops
+        /// authored within a time span, with the time each was authored.
+        fn fetch_op_hash_times_for_constraints(
+            dna_hash: DnaHash,
+            to_agent: AgentPubKey,
+            dht_arc: kitsune_p2p::dht_arc::DhtArc,
+            since: holochain_types::Timestamp,
+            until: holochain_types::Timestamp,
+        ) -> Vec<(holo_hash::DhtOpHash, holochain_types::Timestamp)>;
+
         /// The p2p module needs access to the content for a given set of DhtOpHashes.
         fn fetch_op_hash_data(
             dna_hash: DnaHash,
@@ -191,6 +201,7 @@ macro_rules! match_p2p_evt {
             HolochainP2pEvent::GetAgentActivity { $i, .. } => { $($t)* }
             HolochainP2pEvent::ValidationReceiptReceived { $i, .. } => { $($t)* }
             HolochainP2pEvent::FetchOpHashesForConstraints { $i, .. } => { $($t)* }
+            HolochainP2pEvent::FetchOpHashTimesForConstraints { $i, .. } => { $($t)* }
             HolochainP2pEvent::FetchOpHashData { $i, .. } => { $($t)* }
             HolochainP2pEvent::SignNetworkData { $i, .. } => { $($t)* }
             HolochainP2pEvent::PutAgentInfoSigned { $i, .. } => { $($t)* }
@@ -42,6 +42,10 @@ pub(crate) enum WireMessage {
         dht_hash: holo_hash::AnyDhtHash,
         options: event::GetOptions,
     },
+    GetMany {
+        dht_hashes: Vec<holo_hash::AnyDhtHash>,
+        options: event::GetOptions,
+    },
     GetMeta {
         dht_hash: holo_hash::AnyDhtHash,
         options: event::GetMetaOptions,
@@ -105,6 +109,16 @@ impl WireMessage {
         Self::Get { dht_hash, options }
     }
 
+    pub fn get_many(
+        dht_hashes: Vec<holo_hash::AnyDhtHash>,
+        options: event::GetOptions,
+    ) -> WireMessage {
+        Self::GetMany {
+            dht_hashes,
+            options,
+        }
+    }
+
     pub fn get_meta(
         dht_hash: holo_hash::AnyDhtHash,
         options: event::GetMetaOptions,
@@ -130,4 +144,28 @@ impl WireMessage {
     pub fn get_validation_package(header_hash: HeaderHash) -> WireMessage {
         Self::GetValidationPackage { header_hash }
     }
+
+    /// The name requests of this kind are limited by in the
+    /// `peer_request_*` kitsune tuning params.
+    pub fn request_type(&self) -> &'static str {
+        match self {
+            Self::CallRemote { .. } => "call_remote",
+            Self::Publish { .. } => "publish",
+            Self::ValidationReceipt { .. } => "validation_receipt",
+            Self::Get { .. } => "get",
+            Self::GetMany { .. } => "get_many",
+            Self::GetMeta { .. } => "get_meta",
+            Self::GetLinks { .. } => "get_links",
+            Self::GetAgentActivity { .. } => "get_agent_activity",
+            Self::GetValidationPackage { .. } => "get_validation_package",
+        }
+    }
 }
+
+/// A [WireMessage::GetMany] asks for at most this many hashes,
+/// bigger batches are split over several requests.
+pub(crate) const MAX_GET_MANY_HASHES: usize = 64;
+
+/// The responses to a [WireMessage::GetMany], for the hashes
+/// the remote agent managed to get.
+pub(crate) type WireGetManyResponse = Vec<(holo_hash::AnyDhtHash, GetElementResponse)>;
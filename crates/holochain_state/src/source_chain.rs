@@ -13,6 +13,7 @@ use holochain_lmdb::prelude::*;
 use holochain_types::prelude::*;
 use shrinkwraprs::Shrinkwrap;
 pub use source_chain_buffer::*;
+use std::collections::HashMap;
 use std::collections::HashSet;
 
 mod error;
@@ -79,6 +80,41 @@ impl SourceChain {
         self.put(header_builder, Some(entry)).await
     }
 
+    /// Replay every element this chain has added since `from_len` on top of
+    /// the head of `onto`, re-signing each rebuilt header.
+    ///
+    /// This is how a zome call which ran against a stale snapshot catches up
+    /// with a chain head that moved underneath it. References between the
+    /// replayed elements (updates, deletes and link deletes of elements
+    /// created in the same call) are rewritten to point at the rebased headers.
+    ///
+    /// Returns the mapping from each original header hash to its rebased hash.
+    pub async fn rebase_onto(
+        &self,
+        from_len: usize,
+        onto: &mut SourceChain,
+    ) -> SourceChainResult<HashMap<HeaderHash, HeaderHash>> {
+        let mut rebased = HashMap::new();
+        for i in from_len..self.len() {
+            let element = self
+                .get_at_index(i as u32)?
+                .ok_or_else(|| SourceChainError::ElementMissing(format!("index {}", i)))?;
+            let (signed_header, entry) = element.into_inner();
+            let old_header_address = signed_header.header_address().clone();
+            let mut header = signed_header.into_header_and_signature().0.into_content();
+            rebase_header(
+                &mut header,
+                onto.chain_head()?.to_owned(),
+                onto.len() as u32,
+                timestamp::now(),
+                &rebased,
+            )?;
+            let new_header_address = onto.put_raw(header, entry.into_option()).await?;
+            rebased.insert(old_header_address, new_header_address);
+        }
+        Ok(rebased)
+    }
+
     /// Fetch a relevant CapGrant from the private entries.
     ///
     /// If a function has an Unrestricted grant against it, this may be returned.
@@ -300,6 +336,52 @@ impl SourceChain {
     }
 }
 
+/// Move a header to a new position in the chain, rewriting any references to
+/// headers which were themselves rebased.
+fn rebase_header(
+    header: &mut Header,
+    new_prev_header: HeaderHash,
+    new_header_seq: u32,
+    new_timestamp: Timestamp,
+    rebased: &HashMap<HeaderHash, HeaderHash>,
+) -> SourceChainResult<()> {
+    let remap = |hash: &mut HeaderHash| {
+        if let Some(new_hash) = rebased.get(hash) {
+            *hash = new_hash.clone();
+        }
+    };
+    let (prev_header, header_seq, timestamp) = match header {
+        // The Dna header is always first so can never sit on a new head
+        Header::Dna(_) => {
+            return Err(SourceChainError::InvalidStructure(
+                ChainInvalidReason::MalformedGenesisData,
+            ))
+        }
+        Header::Update(h) => {
+            remap(&mut h.original_header_address);
+            (&mut h.prev_header, &mut h.header_seq, &mut h.timestamp)
+        }
+        Header::Delete(h) => {
+            remap(&mut h.deletes_address);
+            (&mut h.prev_header, &mut h.header_seq, &mut h.timestamp)
+        }
+        Header::DeleteLink(h) => {
+            remap(&mut h.link_add_address);
+            (&mut h.prev_header, &mut h.header_seq, &mut h.timestamp)
+        }
+        Header::AgentValidationPkg(h) => (&mut h.prev_header, &mut h.header_seq, &mut h.timestamp),
+        Header::InitZomesComplete(h) => (&mut h.prev_header, &mut h.header_seq, &mut h.timestamp),
+        Header::CreateLink(h) => (&mut h.prev_header, &mut h.header_seq, &mut h.timestamp),
+        Header::OpenChain(h) => (&mut h.prev_header, &mut h.header_seq, &mut h.timestamp),
+        Header::CloseChain(h) => (&mut h.prev_header, &mut h.header_seq, &mut h.timestamp),
+        Header::Create(h) => (&mut h.prev_header, &mut h.header_seq, &mut h.timestamp),
+    };
+    *prev_header = new_prev_header;
+    *header_seq = new_header_seq;
+    *timestamp = new_timestamp;
+    Ok(())
+}
+
 impl From<SourceChainBuf> for SourceChain {
     fn from(buffer: SourceChainBuf) -> Self {
         Self(buffer)
@@ -324,6 +406,7 @@ pub mod tests {
     use holochain_types::test_utils::fake_dna_hash;
     use holochain_zome_types::capability::CapAccess;
     use holochain_zome_types::capability::ZomeCallCapGrant;
+    use matches::assert_matches;
 
     use std::collections::HashSet;
 
@@ -484,6 +567,92 @@ pub mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn rebase_onto_moved_head() -> SourceChainResult<()> {
+        let test_env = test_cell_env();
+        let env = test_env.env();
+        let alice = AgentPubKeyFixturator::new(Predictable).next().unwrap();
+        {
+            let mut store = SourceChainBuf::new(env.clone().into())?;
+            store.genesis(fake_dna_hash(1), alice.clone(), None).await?;
+            env.guard()
+                .with_commit(|writer| store.flush_to_txn(writer))?;
+        }
+
+        let grant_entry = |tag: &str| {
+            let grant = ZomeCallCapGrant::new(tag.into(), CapAccess::Unrestricted, HashSet::new());
+            EntryHashed::from_content_sync(Entry::CapGrant(grant)).into_inner()
+        };
+
+        // Two snapshots of the same chain
+        let mut stale = SourceChain::new(env.clone().into())?;
+        let mut racer = SourceChain::new(env.clone().into())?;
+        let start_len = stale.len();
+
+        // The stale snapshot creates and then updates an entry
+        let (entry, entry_hash) = grant_entry("a");
+        let create_address = stale
+            .put(
+                builder::Create {
+                    entry_type: EntryType::CapGrant,
+                    entry_hash: entry_hash.clone(),
+                },
+                Some(entry),
+            )
+            .await?;
+        let (entry, updated_entry_hash) = grant_entry("b");
+        stale
+            .put(
+                builder::Update {
+                    entry_type: EntryType::CapGrant,
+                    entry_hash: updated_entry_hash,
+                    original_header_address: create_address.clone(),
+                    original_entry_address: entry_hash,
+                },
+                Some(entry),
+            )
+            .await?;
+
+        // Meanwhile the racer moves the persisted head
+        let (entry, entry_hash) = grant_entry("c");
+        racer
+            .put(
+                builder::Create {
+                    entry_type: EntryType::CapGrant,
+                    entry_hash,
+                },
+                Some(entry),
+            )
+            .await?;
+        env.guard()
+            .with_commit(|writer| racer.flush_to_txn(writer))?;
+
+        let result = env
+            .guard()
+            .with_commit(|writer| stale.flush_to_txn_ref(writer));
+        assert_matches!(result, Err(SourceChainError::HeadMoved(_, _)));
+
+        let mut rebased = SourceChain::new(env.clone().into())?;
+        let rebased_start_len = rebased.len();
+        let hashes = stale.rebase_onto(start_len, &mut rebased).await?;
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(rebased.len(), rebased_start_len + 2);
+
+        // The update now points at the rebased create
+        let update = rebased.get_at_index(rebased_start_len as u32 + 1)?.unwrap();
+        match update.header() {
+            Header::Update(update) => {
+                assert_eq!(update.original_header_address, hashes[&create_address]);
+                assert_eq!(update.header_seq as usize, rebased_start_len + 1);
+            }
+            _ => panic!("expected an update header"),
+        }
+
+        env.guard()
+            .with_commit(|writer| rebased.flush_to_txn(writer))?;
+        Ok(())
+    }
+
     // @todo bring all this back when we want to administer cap claims better
     // #[tokio::test(threaded_scheduler)]
     // async fn test_get_cap_claim() -> SourceChainResult<()> {
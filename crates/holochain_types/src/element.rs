@@ -365,6 +365,16 @@ impl WireElement {
             .entry_data()
             .map(|(hash, _)| hash)
     }
+
+    /// The entry of this element, if it came with one
+    pub fn entry(&self) -> Option<&Entry> {
+        self.maybe_entry.as_ref()
+    }
+
+    /// Hash the header of this element
+    pub fn header_hash(&self) -> HeaderHash {
+        HeaderHash::with_data_sync(self.signed_header.header())
+    }
 }
 
 #[cfg(test)]
@@ -40,6 +40,10 @@ pub struct GetOptions {
     /// If it is false you will get whatever is locally
     /// available on this conductor.
     pub strategy: GetStrategy,
+    /// How the responses of the authorities are combined
+    /// when the get goes to the network.
+    #[serde(default)]
+    pub aggregation: GetAggregation,
 }
 
 impl GetOptions {
@@ -54,6 +58,7 @@ impl GetOptions {
     pub fn latest() -> Self {
         Self {
             strategy: GetStrategy::Latest,
+            aggregation: GetAggregation::default(),
         }
     }
     /// Gets the content but does not
@@ -66,8 +71,17 @@ impl GetOptions {
     pub fn content() -> Self {
         Self {
             strategy: GetStrategy::Content,
+            aggregation: GetAggregation::default(),
         }
     }
+
+    /// Combine the responses of the authorities this way.
+    /// e.g. `GetOptions::latest().with_aggregation(GetAggregation::Quorum(3))`
+    /// only returns data that three authorities agree on.
+    pub fn with_aggregation(mut self, aggregation: GetAggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
 }
 
 impl Default for GetOptions {
@@ -91,6 +105,35 @@ pub enum GetStrategy {
     Content,
 }
 
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+/// How the responses of the authorities asked for some data
+/// are combined when a get goes to the network.
+pub enum GetAggregation {
+    /// Use the first responses to arrive, from up to this many
+    /// authorities or the network default if `None`.
+    FirstN(Option<u8>),
+    /// Only return data that this many remote authorities agree on,
+    /// the same element or entry whatever metadata each has seen.
+    /// The get fails if they don't agree in time. At most 5 authorities
+    /// are asked, so a larger quorum is lowered to 5.
+    Quorum(u8),
+    /// Wait for this many authorities to respond, or the network
+    /// default if `None`, using whatever has arrived by the deadline.
+    AllWithDeadline {
+        /// How many authorities to wait for.
+        count: Option<u8>,
+        /// How long to wait in milliseconds,
+        /// or the network default if `None`.
+        timeout_ms: Option<u64>,
+    },
+}
+
+impl Default for GetAggregation {
+    fn default() -> Self {
+        GetAggregation::FirstN(None)
+    }
+}
+
 /// Structure holding the entry portion of a chain element.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, SerializedBytes)]
 #[serde(tag = "entry_type", content = "entry")]
@@ -218,6 +261,28 @@ impl GetInput {
     }
 }
 
+/// Zome IO inner for get_many calls.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct GetManyInput {
+    /// The DHT hashes to get, any mix of entry and header hashes.
+    pub any_dht_hashes: Vec<holo_hash::AnyDhtHash>,
+    /// Options for every get.
+    pub get_options: crate::entry::GetOptions,
+}
+
+impl GetManyInput {
+    /// Constructor.
+    pub fn new(
+        any_dht_hashes: Vec<holo_hash::AnyDhtHash>,
+        get_options: crate::entry::GetOptions,
+    ) -> Self {
+        Self {
+            any_dht_hashes,
+            get_options,
+        }
+    }
+}
+
 /// Zome IO inner for update.
 #[derive(PartialEq, Debug, Deserialize, Serialize, Clone)]
 pub struct UpdateInput {
@@ -16,11 +16,44 @@ impl AppSignal {
     }
 }
 
-/// Remote signal many agents without waiting for responses.
+/// Remote signal many agents, either without waiting for responses
+/// (`remote_signal`) or waiting for each agent to acknowledge it
+/// (`remote_signal_acknowledged`).
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, SerializedBytes)]
 pub struct RemoteSignal {
     /// Agents to send the signal to.
     pub agents: Vec<AgentPubKey>,
     /// The signal to send.
     pub signal: crate::ExternIO,
+    /// Retry agents the signal couldn't reach.
+    /// None to only try each agent once.
+    #[serde(default)]
+    pub retry: Option<RemoteSignalRetry>,
 }
+
+/// How to retry sending a remote signal to an agent it couldn't reach.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RemoteSignalRetry {
+    /// How many times to try each agent in all, at most 10.
+    pub max_attempts: u8,
+    /// How long to wait before the second attempt.
+    /// The wait doubles for each attempt after that, up to 30 seconds.
+    /// Retries stop a minute after the first attempt.
+    pub initial_backoff_ms: u64,
+}
+
+/// Whether a remote signal reached an agent.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum RemoteSignalDelivery {
+    /// The agent's `recv_remote_signal` handled the signal.
+    Delivered,
+    /// We couldn't reach the agent, even after any retries.
+    Unreachable(String),
+    /// The agent got the signal but didn't accept it, e.g. because
+    /// `recv_remote_signal` failed or we may not call it.
+    Rejected(String),
+}
+
+/// How a remote signal was delivered to each agent,
+/// in the order the agents were given.
+pub type RemoteSignalDeliveries = Vec<(AgentPubKey, RemoteSignalDelivery)>;
@@ -135,6 +135,9 @@ wasm_io_types! {
     // Attempt to get a live entry from the cascade.
     fn get (zt::entry::GetInput) -> Option<zt::element::Element>;
 
+    // Attempt to get many live entries from the cascade in one batch.
+    fn get_many (zt::entry::GetManyInput) -> Vec<Option<zt::element::Element>>;
+
     // Hash an entry on the host.
     fn hash_entry (zt::entry::Entry) -> holo_hash::EntryHash;
 
@@ -150,6 +153,9 @@ wasm_io_types! {
     // Remotely signal many agents without waiting for responses
     fn remote_signal (zt::signal::RemoteSignal) -> ();
 
+    // Remotely signal many agents and wait for each to acknowledge it
+    fn remote_signal_acknowledged (zt::signal::RemoteSignal) -> zt::signal::RemoteSignalDeliveries;
+
     // // @todo
     // fn send (()) -> ();
 
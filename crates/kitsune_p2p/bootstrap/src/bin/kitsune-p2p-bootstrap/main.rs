@@ -0,0 +1,22 @@
+use structopt::StructOpt;
+
+/// Option Parsing
+#[derive(structopt::StructOpt, Debug)]
+#[structopt(name = "kitsune-p2p-bootstrap")]
+struct Opt {
+    /// To which network interface / port should we bind?
+    #[structopt(short = "b", long, default_value = "0.0.0.0:0")]
+    bind_to: std::net::SocketAddr,
+}
+
+#[tokio::main]
+async fn main() {
+    let opt = Opt::from_args();
+    match kitsune_p2p_bootstrap::run(opt.bind_to).await {
+        Ok((driver, addr)) => {
+            println!("http://{}", addr);
+            driver.await;
+        }
+        Err(e) => eprintln!("{:?}", e),
+    }
+}
@@ -0,0 +1,125 @@
+#![deny(missing_docs)]
+//! A bootstrap server for kitsune-p2p peer discovery.
+//!
+//! Implements the same `put` / `random` / `now` api as the default
+//! remote bootstrap service, so private networks and tests can run
+//! without it. Agent infos are checked against their signatures on `put`
+//! and dropped once they expire. The number of spaces, the agents per space
+//! and the request size are capped, when full the infos expiring soonest
+//! make room for new ones.
+//!
+//! Each request is a POST with the op in the `X-Op` header
+//! and a messagepack encoded body.
+//!
+//! ```no_run
+//! # async fn example() -> std::io::Result<()> {
+//! let (driver, addr) = kitsune_p2p_bootstrap::run(([127, 0, 0, 1], 0)).await?;
+//! tokio::task::spawn(driver);
+//! println!("bootstrap service at http://{}", addr);
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::future::BoxFuture;
+use futures::future::FutureExt;
+use hyper::body::HttpBody;
+use hyper::Body;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
+use kitsune_p2p_types::dependencies::ghost_actor::dependencies::tracing;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+mod store;
+use store::*;
+
+/// The HTTP header name for the op on POST requests.
+const OP_HEADER: &str = "X-Op";
+/// Put a signed agent info.
+const OP_PUT: &str = "put";
+/// Return the server's opinion of 'now' in milliseconds.
+const OP_NOW: &str = "now";
+/// Return a random set of agents in a space.
+const OP_RANDOM: &str = "random";
+
+/// The largest request body we read, a signed agent info is well below this.
+const MAX_BODY_BYTES: usize = 1024 * 16;
+
+/// The future that drives a bootstrap server.
+/// The server stops when this is dropped.
+pub type BootstrapDriver = BoxFuture<'static, ()>;
+
+/// Bind a bootstrap server to this address.
+/// Returns the future driving the server, which must be spawned or awaited,
+/// along with the address it is bound to (useful when binding to port 0).
+pub async fn run(addr: impl Into<SocketAddr>) -> std::io::Result<(BootstrapDriver, SocketAddr)> {
+    let store = Arc::new(Store::default());
+    let make_service = hyper::service::make_service_fn(move |_| {
+        let store = store.clone();
+        async move {
+            Ok::<_, Infallible>(hyper::service::service_fn(move |req| {
+                handle(store.clone(), req)
+            }))
+        }
+    });
+    let server = hyper::Server::try_bind(&addr.into())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
+        .serve(make_service);
+    let addr = server.local_addr();
+    let driver = async move {
+        if let Err(e) = server.await {
+            tracing::error!(?e, "bootstrap server error");
+        }
+    }
+    .boxed();
+    Ok((driver, addr))
+}
+
+async fn handle(store: Arc<Store>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.method() != hyper::Method::POST {
+        return Ok(error_response(StatusCode::METHOD_NOT_ALLOWED, "POST only"));
+    }
+    let op = req
+        .headers()
+        .get(OP_HEADER)
+        .and_then(|op| op.to_str().ok())
+        .map(|op| op.to_string());
+    let body = match read_body(req.into_body()).await {
+        Ok(body) => body,
+        Err((status, e)) => return Ok(error_response(status, &e)),
+    };
+    let res = match op.as_deref() {
+        Some(OP_PUT) => store.put(&body).await,
+        Some(OP_NOW) => store.now(),
+        Some(OP_RANDOM) => store.random(&body),
+        _ => Err(format!("unknown op: {:?}", op)),
+    };
+    Ok(match res {
+        Ok(data) => Response::new(Body::from(data)),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, &e),
+    })
+}
+
+/// Read a request body, giving up once it is over `MAX_BODY_BYTES`.
+async fn read_body(mut body: Body) -> Result<Vec<u8>, (StatusCode, String)> {
+    let mut out = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        if out.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("request body over {} bytes", MAX_BODY_BYTES),
+            ));
+        }
+        out.extend_from_slice(&chunk);
+    }
+    Ok(out)
+}
+
+fn error_response(status: StatusCode, msg: &str) -> Response<Body> {
+    let mut res = Response::new(Body::from(msg.to_string()));
+    *res.status_mut() = status;
+    res
+}
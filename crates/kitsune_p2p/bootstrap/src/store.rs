@@ -0,0 +1,382 @@
+//! In memory store of the agent infos put to this bootstrap server.
+
+use kitsune_p2p::agent_store::AgentInfo;
+use kitsune_p2p::agent_store::AgentInfoSigned;
+use kitsune_p2p::KitsuneAgent;
+use kitsune_p2p::KitsuneBinType;
+use kitsune_p2p::KitsuneSpace;
+use lair_keystore_api::internal::sign_ed25519::SignEd25519PubKey;
+use lair_keystore_api::internal::sign_ed25519::SignEd25519Signature;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// How far in the future an agent info can be signed,
+/// to allow for some clock drift between agents and the server.
+const MAX_CLOCK_DRIFT_MS: u64 = 1000 * 60;
+
+/// The longest an agent info can ask to be kept for.
+const MAX_EXPIRES_AFTER_MS: u64 = 1000 * 60 * 60;
+
+/// The most spaces the store holds agent infos for.
+const MAX_SPACES: usize = 1024;
+
+/// The most agent infos the store holds in any one space.
+const MAX_AGENTS_PER_SPACE: usize = 1024;
+
+/// Input to the `random` op, matching the kitsune `RandomQuery`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RandomQuery {
+    space: Arc<KitsuneSpace>,
+    limit: u32,
+}
+
+struct Entry {
+    info_signed: AgentInfoSigned,
+    signed_at_ms: u64,
+    expires_at_ms: u64,
+}
+
+type Spaces = HashMap<KitsuneSpace, HashMap<KitsuneAgent, Entry>>;
+
+/// Agent infos by space and agent.
+/// When a space or the store is full, the infos expiring soonest
+/// make room for infos that will be kept for longer.
+pub(crate) struct Store {
+    spaces: parking_lot::Mutex<Spaces>,
+    max_spaces: usize,
+    max_agents_per_space: usize,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new(MAX_SPACES, MAX_AGENTS_PER_SPACE)
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("system time before unix epoch")
+        .as_millis() as u64
+}
+
+/// Drop expired agent infos and any spaces left empty.
+fn prune_expired(spaces: &mut Spaces, now: u64) {
+    spaces.retain(|_, agents| {
+        agents.retain(|_, entry| entry.expires_at_ms > now);
+        !agents.is_empty()
+    });
+}
+
+fn encode<T: serde::Serialize>(item: T) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    kitsune_p2p_types::codec::rmp_encode(&mut out, item).map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// When the last agent info in a space expires.
+fn space_expires_at_ms(agents: &HashMap<KitsuneAgent, Entry>) -> u64 {
+    agents
+        .values()
+        .map(|entry| entry.expires_at_ms)
+        .max()
+        .unwrap_or(0)
+}
+
+impl Store {
+    pub(crate) fn new(max_spaces: usize, max_agents_per_space: usize) -> Self {
+        Self {
+            spaces: parking_lot::Mutex::new(HashMap::new()),
+            max_spaces,
+            max_agents_per_space,
+        }
+    }
+
+    /// Make room for a new space kept until `expires_at_ms`
+    /// by evicting the space expiring soonest, if it expires before then.
+    fn make_room_for_space(&self, spaces: &mut Spaces, expires_at_ms: u64) -> Result<(), String> {
+        if spaces.len() < self.max_spaces {
+            return Ok(());
+        }
+        let soonest = spaces
+            .iter()
+            .map(|(space, agents)| (space_expires_at_ms(agents), space))
+            .min_by_key(|(expires_at_ms, _)| *expires_at_ms)
+            .map(|(space_expires_at_ms, space)| (space_expires_at_ms, space.clone()));
+        match soonest {
+            Some((space_expires_at_ms, space)) if space_expires_at_ms < expires_at_ms => {
+                spaces.remove(&space);
+                Ok(())
+            }
+            _ => Err("bootstrap store is full".into()),
+        }
+    }
+
+    /// Make room for a new agent kept until `expires_at_ms`
+    /// by evicting the agent expiring soonest, if it expires before then.
+    fn make_room_for_agent(
+        &self,
+        agents: &mut HashMap<KitsuneAgent, Entry>,
+        expires_at_ms: u64,
+    ) -> Result<(), String> {
+        if agents.len() < self.max_agents_per_space {
+            return Ok(());
+        }
+        let soonest = agents
+            .iter()
+            .min_by_key(|(_, entry)| entry.expires_at_ms)
+            .map(|(agent, entry)| (entry.expires_at_ms, agent.clone()));
+        match soonest {
+            Some((agent_expires_at_ms, agent)) if agent_expires_at_ms < expires_at_ms => {
+                agents.remove(&agent);
+                Ok(())
+            }
+            _ => Err("space is full".into()),
+        }
+    }
+
+    /// The `put` op. Checks the signature and times on an agent info
+    /// before storing it, replacing any older info for the same agent.
+    pub(crate) async fn put(&self, body: &[u8]) -> Result<Vec<u8>, String> {
+        let info_signed: AgentInfoSigned =
+            kitsune_p2p_types::codec::rmp_decode(&mut &*body).map_err(|e| e.to_string())?;
+        let info = AgentInfo::try_from(&info_signed).map_err(|e| e.to_string())?;
+
+        if info.as_agent_ref() != info_signed.as_agent_ref() {
+            return Err("agent info is for a different agent than the signer".into());
+        }
+
+        let pub_key: SignEd25519PubKey = info_signed.as_agent_ref().get_bytes().to_vec().into();
+        let signature: SignEd25519Signature = info_signed.as_signature_ref().0.clone().into();
+        let valid = pub_key
+            .verify(
+                Arc::new(info_signed.as_agent_info_ref().to_vec()),
+                signature,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        if !valid {
+            return Err("invalid agent info signature".into());
+        }
+
+        let now = now_ms();
+        let signed_at_ms = info.signed_at_ms();
+        if signed_at_ms > now + MAX_CLOCK_DRIFT_MS {
+            return Err("agent info is signed in the future".into());
+        }
+        if info.expires_after_ms() > MAX_EXPIRES_AFTER_MS {
+            return Err(format!(
+                "agent info expiry must be at most {}ms",
+                MAX_EXPIRES_AFTER_MS
+            ));
+        }
+        let expires_at_ms = signed_at_ms + info.expires_after_ms();
+        if expires_at_ms <= now {
+            return Err("agent info has expired".into());
+        }
+
+        let mut spaces = self.spaces.lock();
+        prune_expired(&mut spaces, now);
+        if !spaces.contains_key(info.as_space_ref()) {
+            self.make_room_for_space(&mut spaces, expires_at_ms)?;
+        }
+        let agents = spaces.entry(info.as_space_ref().clone()).or_default();
+        let is_newer = match agents.get(info.as_agent_ref()) {
+            Some(entry) => entry.signed_at_ms < signed_at_ms,
+            None => {
+                self.make_room_for_agent(agents, expires_at_ms)?;
+                true
+            }
+        };
+        if is_newer {
+            agents.insert(
+                info.as_agent_ref().clone(),
+                Entry {
+                    info_signed,
+                    signed_at_ms,
+                    expires_at_ms,
+                },
+            );
+        }
+        encode(())
+    }
+
+    /// The `now` op.
+    pub(crate) fn now(&self) -> Result<Vec<u8>, String> {
+        encode(now_ms())
+    }
+
+    /// The `random` op. Returns up to `limit` unexpired agent infos
+    /// from the space in a random order.
+    pub(crate) fn random(&self, body: &[u8]) -> Result<Vec<u8>, String> {
+        let query: RandomQuery =
+            kitsune_p2p_types::codec::rmp_decode(&mut &*body).map_err(|e| e.to_string())?;
+        let now = now_ms();
+        let mut spaces = self.spaces.lock();
+        prune_expired(&mut spaces, now);
+        let agents = match spaces.get(&*query.space) {
+            Some(agents) => agents,
+            None => return encode(Vec::<serde_bytes::ByteBuf>::new()),
+        };
+        let mut out = rand::seq::IteratorRandom::choose_multiple(
+            agents.values(),
+            &mut rand::thread_rng(),
+            query.limit as usize,
+        )
+        .into_iter()
+        .map(|entry| encode(&entry.info_signed).map(serde_bytes::ByteBuf::from))
+        .collect::<Result<Vec<_>, _>>()?;
+        // choose_multiple doesn't randomize the order
+        rand::seq::SliceRandom::shuffle(&mut out[..], &mut rand::thread_rng());
+        encode(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kitsune_p2p::KitsuneSignature;
+    use lair_keystore_api::internal::sign_ed25519::sign_ed25519_keypair_new_from_entropy;
+
+    async fn signed_info(space: &KitsuneSpace, signed_at_ms: u64) -> Vec<u8> {
+        let keypair = sign_ed25519_keypair_new_from_entropy().await.unwrap();
+        let agent = KitsuneAgent::new((*keypair.pub_key.0).clone());
+        let info = AgentInfo::new(
+            space.clone(),
+            agent.clone(),
+            vec![],
+            signed_at_ms,
+            MAX_EXPIRES_AFTER_MS,
+        );
+        let data = encode(&info).unwrap();
+        let signature = keypair.sign(Arc::new(data.clone())).await.unwrap();
+        let info_signed =
+            AgentInfoSigned::try_new(agent, KitsuneSignature((*signature.0).clone()), data)
+                .unwrap();
+        encode(&info_signed).unwrap()
+    }
+
+    fn random(store: &Store, space: &KitsuneSpace) -> Vec<serde_bytes::ByteBuf> {
+        let query = encode(RandomQuery {
+            space: Arc::new(space.clone()),
+            limit: 10,
+        })
+        .unwrap();
+        let out = store.random(&query).unwrap();
+        kitsune_p2p_types::codec::rmp_decode(&mut &*out).unwrap()
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn put_and_random() {
+        let store = Store::default();
+        let space = KitsuneSpace::new(vec![0; 36]);
+        let alice = signed_info(&space, now_ms()).await;
+        let bob = signed_info(&space, now_ms()).await;
+        store.put(&alice).await.unwrap();
+        store.put(&bob).await.unwrap();
+
+        let mut found = random(&store, &space)
+            .into_iter()
+            .map(|b| b.into_vec())
+            .collect::<Vec<_>>();
+        found.sort();
+        let mut expected = vec![alice, bob];
+        expected.sort();
+        assert_eq!(expected, found);
+
+        let other_space = KitsuneSpace::new(vec![1; 36]);
+        assert!(random(&store, &other_space).is_empty());
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn put_rejects_bad_infos() {
+        let store = Store::default();
+        let space = KitsuneSpace::new(vec![0; 36]);
+
+        // a corrupted signature
+        let mut info_signed: AgentInfoSigned =
+            kitsune_p2p_types::codec::rmp_decode(&mut &*signed_info(&space, now_ms()).await)
+                .unwrap();
+        let mut signature = info_signed.as_signature_ref().0.clone();
+        signature[0] ^= 1;
+        info_signed = AgentInfoSigned::try_new(
+            info_signed.as_agent_ref().clone(),
+            KitsuneSignature(signature),
+            info_signed.as_agent_info_ref().to_vec(),
+        )
+        .unwrap();
+        assert!(store.put(&encode(&info_signed).unwrap()).await.is_err());
+
+        // signed too far in the future
+        let future = signed_info(&space, now_ms() + MAX_CLOCK_DRIFT_MS * 2).await;
+        assert!(store.put(&future).await.is_err());
+
+        // already expired
+        let expired = signed_info(&space, now_ms() - MAX_EXPIRES_AFTER_MS - 1).await;
+        assert!(store.put(&expired).await.is_err());
+
+        assert!(random(&store, &space).is_empty());
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn put_prunes_expired_infos() {
+        let store = Store::default();
+        let space = KitsuneSpace::new(vec![0; 36]);
+        let other_space = KitsuneSpace::new(vec![1; 36]);
+        let alice = signed_info(&other_space, now_ms()).await;
+        store.put(&alice).await.unwrap();
+
+        // age alice's info until it has expired
+        for agents in store.spaces.lock().values_mut() {
+            for entry in agents.values_mut() {
+                entry.expires_at_ms = now_ms() - 1;
+            }
+        }
+
+        // a put to any space prunes it
+        let bob = signed_info(&space, now_ms()).await;
+        store.put(&bob).await.unwrap();
+        assert!(!store.spaces.lock().contains_key(&other_space));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn put_evicts_soonest_expiring_when_full() {
+        let store = Store::new(1, 2);
+        let space = KitsuneSpace::new(vec![0; 36]);
+        let now = now_ms();
+        let alice = signed_info(&space, now - 1000).await;
+        let bob = signed_info(&space, now - 500).await;
+        store.put(&alice).await.unwrap();
+        store.put(&bob).await.unwrap();
+
+        // carol expires last so alice makes room for her
+        let carol = signed_info(&space, now).await;
+        store.put(&carol).await.unwrap();
+        let mut found = random(&store, &space)
+            .into_iter()
+            .map(|b| b.into_vec())
+            .collect::<Vec<_>>();
+        found.sort();
+        let mut expected = vec![bob, carol];
+        expected.sort();
+        assert_eq!(expected, found);
+
+        // dave would expire first so there is no room for him
+        let dave = signed_info(&space, now - 2000).await;
+        assert!(store.put(&dave).await.is_err());
+
+        // a space expiring after all of the first space replaces it
+        let other_space = KitsuneSpace::new(vec![1; 36]);
+        let eve = signed_info(&other_space, now + 1000).await;
+        store.put(&eve).await.unwrap();
+        assert!(random(&store, &space).is_empty());
+        assert_eq!(1, random(&store, &other_space).len());
+
+        // but one expiring sooner is refused
+        let third_space = KitsuneSpace::new(vec![2; 36]);
+        let frank = signed_info(&third_space, now - 5000).await;
+        assert!(store.put(&frank).await.is_err());
+        assert!(random(&store, &third_space).is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use ghost_actor::dependencies::tracing;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use url2::Url2;
 
@@ -23,6 +24,60 @@ pub struct KitsuneP2pTuningParams {
     pub default_rpc_multi_remote_agent_count: u32,
     pub default_rpc_multi_timeout_ms: u32,
     pub agent_info_expires_after_ms: u32,
+    pub wire_compression_threshold_bytes: u32,
+    pub transport_max_connections: u32,
+    pub transport_connection_idle_timeout_ms: u32,
+    pub peer_block_score_threshold: u32,
+    pub peer_block_duration_ms: u32,
+    pub gossip_bucket_width_s: u32,
+    pub peer_request_rate_limit: u32,
+    pub peer_request_concurrency_limit: u32,
+    /// Per request type overrides of the above, keyed by
+    /// e.g. `peer_request_rate_limit.get_agent_activity`.
+    pub peer_request_limit_overrides: BTreeMap<String, u32>,
+    pub rpc_stream_chunk_size_bytes: u32,
+    pub rpc_stream_window: u32,
+}
+
+impl KitsuneP2pTuningParams {
+    /// The limits on connections held open by each transport.
+    pub fn connection_limits(&self) -> kitsune_p2p_types::connection_lru::ConnectionLimits {
+        kitsune_p2p_types::connection_lru::ConnectionLimits {
+            max_connections: self.transport_max_connections as usize,
+            idle_timeout_ms: self.transport_connection_idle_timeout_ms as u64,
+        }
+    }
+
+    /// The limits on requests of one type we serve each remote agent.
+    pub fn peer_request_limits(&self, request_type: &str) -> PeerRequestLimits {
+        let limit = |param: &str, default: u32| {
+            self.peer_request_limit_overrides
+                .get(&format!("{}.{}", param, request_type))
+                .copied()
+                .unwrap_or(default)
+        };
+        PeerRequestLimits {
+            rate_per_s: limit(PEER_REQUEST_RATE_LIMIT, self.peer_request_rate_limit),
+            max_concurrent: limit(
+                PEER_REQUEST_CONCURRENCY_LIMIT,
+                self.peer_request_concurrency_limit,
+            ),
+        }
+    }
+}
+
+const PEER_REQUEST_RATE_LIMIT: &str = "peer_request_rate_limit";
+const PEER_REQUEST_CONCURRENCY_LIMIT: &str = "peer_request_concurrency_limit";
+
+/// The limits on requests of one type we serve each remote agent,
+/// from the `peer_request_*` tuning params.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PeerRequestLimits {
+    /// Requests allowed per second, with bursts of up to this many.
+    /// 0 = unlimited.
+    pub rate_per_s: u32,
+    /// Requests allowed in flight at once. 0 = unlimited.
+    pub max_concurrent: u32,
 }
 
 impl Default for KitsuneP2pTuningParams {
@@ -35,6 +90,38 @@ impl Default for KitsuneP2pTuningParams {
             default_rpc_multi_remote_agent_count: 2,
             default_rpc_multi_timeout_ms: 2000,
             agent_info_expires_after_ms: 1000 * 60 * 20, // 20 minutes
+            // deflate messages to remotes that support it over 1KiB,
+            // 0 = never compress
+            wire_compression_threshold_bytes: 1024,
+            // close the least recently used connection past this,
+            // 0 = unlimited
+            transport_max_connections: 256,
+            // 0 = never close idle connections
+            transport_connection_idle_timeout_ms: 1000 * 60 * 2, // 2 minutes
+            // block peers whose score falls to minus this,
+            // 0 = never block automatically
+            peer_block_score_threshold: 10,
+            peer_block_duration_ms: 1000 * 60 * 5, // 5 minutes
+            // gossip compares summaries of ops authored in time buckets,
+            // exchanging hashes for mismatched buckets this wide,
+            // 0 = always exchange full hash lists
+            gossip_bucket_width_s: 60 * 60, // 1 hour
+            // requests of each type a remote agent may make of us
+            // per second and at once, 0 = unlimited
+            peer_request_rate_limit: 100,
+            peer_request_concurrency_limit: 16,
+            // agent activity is expensive to serve
+            peer_request_limit_overrides: vec![(
+                format!("{}.get_agent_activity", PEER_REQUEST_RATE_LIMIT),
+                10,
+            )]
+            .into_iter()
+            .collect(),
+            // streamed responses are sent in chunks this big,
+            // each chunk has the rpc_single timeout
+            rpc_stream_chunk_size_bytes: 64 * 1024, // 64KiB
+            // chunks of a streamed response requested at once
+            rpc_stream_window: 4,
         }
     }
 }
@@ -74,6 +161,46 @@ impl serde::Serialize for KitsuneP2pTuningParams {
             "agent_info_expires_after_ms",
             &format!("{}", self.agent_info_expires_after_ms),
         )?;
+        m.serialize_entry(
+            "wire_compression_threshold_bytes",
+            &format!("{}", self.wire_compression_threshold_bytes),
+        )?;
+        m.serialize_entry(
+            "transport_max_connections",
+            &format!("{}", self.transport_max_connections),
+        )?;
+        m.serialize_entry(
+            "transport_connection_idle_timeout_ms",
+            &format!("{}", self.transport_connection_idle_timeout_ms),
+        )?;
+        m.serialize_entry(
+            "peer_block_score_threshold",
+            &format!("{}", self.peer_block_score_threshold),
+        )?;
+        m.serialize_entry(
+            "peer_block_duration_ms",
+            &format!("{}", self.peer_block_duration_ms),
+        )?;
+        m.serialize_entry(
+            "gossip_bucket_width_s",
+            &format!("{}", self.gossip_bucket_width_s),
+        )?;
+        m.serialize_entry(
+            PEER_REQUEST_RATE_LIMIT,
+            &format!("{}", self.peer_request_rate_limit),
+        )?;
+        m.serialize_entry(
+            PEER_REQUEST_CONCURRENCY_LIMIT,
+            &format!("{}", self.peer_request_concurrency_limit),
+        )?;
+        for (k, v) in self.peer_request_limit_overrides.iter() {
+            m.serialize_entry(k, &format!("{}", v))?;
+        }
+        m.serialize_entry(
+            "rpc_stream_chunk_size_bytes",
+            &format!("{}", self.rpc_stream_chunk_size_bytes),
+        )?;
+        m.serialize_entry("rpc_stream_window", &format!("{}", self.rpc_stream_window))?;
         m.end()
     }
 }
@@ -115,6 +242,56 @@ impl<'de> serde::Deserialize<'de> for KitsuneP2pTuningParams {
                     Ok(v) => out.agent_info_expires_after_ms = v,
                     Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
                 },
+                "wire_compression_threshold_bytes" => match v.parse::<u32>() {
+                    Ok(v) => out.wire_compression_threshold_bytes = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                "transport_max_connections" => match v.parse::<u32>() {
+                    Ok(v) => out.transport_max_connections = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                "transport_connection_idle_timeout_ms" => match v.parse::<u32>() {
+                    Ok(v) => out.transport_connection_idle_timeout_ms = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                "peer_block_score_threshold" => match v.parse::<u32>() {
+                    Ok(v) => out.peer_block_score_threshold = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                "peer_block_duration_ms" => match v.parse::<u32>() {
+                    Ok(v) => out.peer_block_duration_ms = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                "gossip_bucket_width_s" => match v.parse::<u32>() {
+                    Ok(v) => out.gossip_bucket_width_s = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                PEER_REQUEST_RATE_LIMIT => match v.parse::<u32>() {
+                    Ok(v) => out.peer_request_rate_limit = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                PEER_REQUEST_CONCURRENCY_LIMIT => match v.parse::<u32>() {
+                    Ok(v) => out.peer_request_concurrency_limit = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                "rpc_stream_chunk_size_bytes" => match v.parse::<u32>() {
+                    Ok(v) => out.rpc_stream_chunk_size_bytes = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                "rpc_stream_window" => match v.parse::<u32>() {
+                    Ok(v) => out.rpc_stream_window = v,
+                    Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                },
+                _ if k.starts_with(&format!("{}.", PEER_REQUEST_RATE_LIMIT))
+                    || k.starts_with(&format!("{}.", PEER_REQUEST_CONCURRENCY_LIMIT)) =>
+                {
+                    match v.parse::<u32>() {
+                        Ok(v) => {
+                            out.peer_request_limit_overrides.insert(k, v);
+                        }
+                        Err(e) => tracing::warn!("failed to parse {}: {}", k, e),
+                    }
+                }
                 _ => tracing::warn!("INVALID TUNING PARAM: '{}'", k),
             }
         }
@@ -136,6 +313,17 @@ pub struct KitsuneP2pConfig {
     /// a warning will be printed in the tracing log.
     #[serde(default)]
     pub tuning_params: KitsuneP2pTuningParams,
+    /// Announce our agents to, and discover peers from,
+    /// other nodes on the local network.
+    /// Default: None = no local network discovery.
+    #[serde(default)]
+    pub lan_discovery: Option<LanDiscoveryConfig>,
+    /// Only accept incoming tls connections from nodes presenting
+    /// a certificate with one of these digests
+    /// (base64 url encoded, as in their proxy urls).
+    /// Default: None = accept any node.
+    #[serde(default)]
+    pub allowed_client_digests: Option<Vec<String>>,
 }
 
 impl Default for KitsuneP2pConfig {
@@ -144,6 +332,57 @@ impl Default for KitsuneP2pConfig {
             transport_pool: Vec::new(),
             bootstrap_service: None,
             tuning_params: KitsuneP2pTuningParams::default(),
+            lan_discovery: None,
+            allowed_client_digests: None,
+        }
+    }
+}
+
+impl KitsuneP2pConfig {
+    /// Decode `allowed_client_digests`.
+    pub fn allowed_client_digests(
+        &self,
+    ) -> crate::KitsuneP2pResult<Option<Vec<lair_keystore_api::actor::CertDigest>>> {
+        match &self.allowed_client_digests {
+            None => Ok(None),
+            Some(digests) => Ok(Some(
+                digests
+                    .iter()
+                    .map(|d| kitsune_p2p_proxy::decode_cert_digest(d.as_str()))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+        }
+    }
+}
+
+/// Configure discovery of peers on the local network.
+/// Signed agent infos of our joined agents are multicast over udp,
+/// and those multicast by other nodes in spaces we have joined
+/// are added to our peer store.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LanDiscoveryConfig {
+    /// The ipv4 multicast group to announce to and listen on.
+    /// Default: 239.255.42.98
+    pub multicast_group: std::net::Ipv4Addr,
+    /// The udp port to announce to and listen on.
+    /// Default: 49642
+    pub port: u16,
+    /// The local interface to join the multicast group on.
+    /// Default: 0.0.0.0 = let the os choose.
+    pub interface: std::net::Ipv4Addr,
+    /// How often to announce our agent infos again.
+    /// Default: 10 seconds.
+    pub announce_interval_ms: u64,
+}
+
+impl Default for LanDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            multicast_group: std::net::Ipv4Addr::new(239, 255, 42, 98),
+            port: 49642,
+            interface: std::net::Ipv4Addr::UNSPECIFIED,
+            announce_interval_ms: 10 * 1000,
         }
     }
 }
@@ -154,7 +393,13 @@ impl Default for KitsuneP2pConfig {
 pub enum TransportConfig {
     /// A transport that uses the local memory transport protocol
     /// (this is mainly for testing).
-    Mem {},
+    Mem {
+        /// Join a simulated network with latency, loss, bandwidth limits
+        /// and partitions applied between its listeners.
+        /// Default: None = perfect links.
+        #[serde(default)]
+        simulated_network: Option<kitsune_p2p_types::transport_mem_sim::MemNetworkConfig>,
+    },
     /// A transport that uses the QUIC protocol
     Quic {
         /// To which network interface / port should we bind?
@@ -172,6 +417,25 @@ pub enum TransportConfig {
         /// Default: None = use NIC port.
         override_port: Option<u16>,
     },
+    /// A transport that uses TCP, for networks that block UDP.
+    /// This does not encrypt anything itself, so it can only be used as
+    /// the sub_transport of a Proxy.
+    Tcp {
+        /// To which network interface / port should we bind?
+        /// Default: "kitsune-tcp://0.0.0.0:0".
+        bind_to: Option<Url2>,
+
+        /// If you have port-forwarding set up,
+        /// or wish to apply a vanity domain name,
+        /// you may need to override the local NIC ip.
+        /// Default: None = use NIC ip.
+        override_host: Option<String>,
+
+        /// If you have port-forwarding set up,
+        /// you may need to override the local NIC port.
+        /// Default: None = use NIC port.
+        override_port: Option<u16>,
+    },
     /// A transport that tls tunnels through a sub-transport (ALPN kitsune-proxy/0)
     Proxy {
         /// The 'Proxy' transport is a wrapper around a sub-transport
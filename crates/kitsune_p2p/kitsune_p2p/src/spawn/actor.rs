@@ -2,6 +2,7 @@
 
 use crate::actor;
 use crate::actor::*;
+use crate::agent_store::AgentInfo;
 use crate::event::*;
 use crate::gossip::*;
 use crate::metrics::KitsuneMetrics;
@@ -9,10 +10,12 @@ use crate::*;
 use futures::future::FutureExt;
 use futures::stream::StreamExt;
 use kitsune_p2p_types::async_lazy::AsyncLazy;
+use kitsune_p2p_types::connection_lru::ConnectionLimits;
 use kitsune_p2p_types::transport::*;
 use kitsune_p2p_types::transport_pool::*;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::sync::Arc;
 
 /// The bootstrap service is much more thoroughly documented in the default service implementation.
@@ -20,7 +23,13 @@ use std::sync::Arc;
 mod bootstrap;
 mod discover;
 mod gossip;
+mod lan_discovery;
+mod network_stats;
+mod reputation;
+mod rpc_stream;
 mod space;
+mod wire_compression;
+mod wire_handshake;
 use ghost_actor::dependencies::must_future;
 use ghost_actor::dependencies::tracing;
 use space::*;
@@ -38,12 +47,19 @@ pub(crate) struct KitsuneP2pActor {
     evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
     transport: ghost_actor::GhostSender<TransportListener>,
     spaces: HashMap<Arc<KitsuneSpace>, AsyncLazy<ghost_actor::GhostSender<KitsuneP2p>>>,
+    wire_protocols: wire_handshake::WireProtocols,
+    lan_discovery: Option<lan_discovery::LanDiscovery>,
+    reputation: reputation::PeerReputation,
     config: Arc<KitsuneP2pConfig>,
 }
 
+/// `behind_proxy` is true when building the sub_transport of a proxy,
+/// which is the only place unencrypted transports are allowed.
 fn build_transport(
     t_conf: TransportConfig,
     tls_config: Arc<kitsune_p2p_proxy::TlsConfig>,
+    connection_limits: ConnectionLimits,
+    behind_proxy: bool,
 ) -> must_future::MustBoxFuture<
     'static,
     TransportResult<(
@@ -53,9 +69,17 @@ fn build_transport(
 > {
     must_future::MustBoxFuture::new(async move {
         match t_conf {
-            TransportConfig::Mem {} => {
-                Ok(kitsune_p2p_types::transport_mem::spawn_bind_transport_mem().await?)
-            }
+            TransportConfig::Mem { simulated_network } => match simulated_network {
+                Some(config) => {
+                    let network =
+                        kitsune_p2p_types::transport_mem_sim::MemNetwork::from_config(&config)?;
+                    Ok(
+                        kitsune_p2p_types::transport_mem::spawn_bind_transport_mem_sim(network)
+                            .await?,
+                    )
+                }
+                None => Ok(kitsune_p2p_types::transport_mem::spawn_bind_transport_mem().await?),
+            },
             TransportConfig::Quic {
                 bind_to,
                 override_host,
@@ -64,15 +88,32 @@ fn build_transport(
                 let sub_conf = kitsune_p2p_transport_quic::ConfigListenerQuic::default()
                     .set_bind_to(bind_to)
                     .set_override_host(override_host)
-                    .set_override_port(override_port);
+                    .set_override_port(override_port)
+                    .set_connection_limits(connection_limits);
                 Ok(kitsune_p2p_transport_quic::spawn_transport_listener_quic(sub_conf).await?)
             }
+            TransportConfig::Tcp { .. } if !behind_proxy => Err(
+                "the tcp transport is unencrypted, use it as the sub_transport of a proxy".into(),
+            ),
+            TransportConfig::Tcp {
+                bind_to,
+                override_host,
+                override_port,
+            } => {
+                let sub_conf = kitsune_p2p_transport_tcp::ConfigListenerTcp::default()
+                    .set_bind_to(bind_to)
+                    .set_override_host(override_host)
+                    .set_override_port(override_port)
+                    .set_connection_limits(connection_limits);
+                Ok(kitsune_p2p_transport_tcp::spawn_transport_listener_tcp(sub_conf).await?)
+            }
             TransportConfig::Proxy {
                 sub_transport,
                 proxy_config,
             } => {
                 let (sub_lstn, sub_evt) =
-                    build_transport(*sub_transport, tls_config.clone()).await?;
+                    build_transport(*sub_transport, tls_config.clone(), connection_limits, true)
+                        .await?;
                 let sub_conf = match proxy_config {
                     ProxyConfig::RemoteProxyClient { proxy_url } => {
                         kitsune_p2p_proxy::ProxyConfig::remote_proxy_client(
@@ -112,34 +153,111 @@ impl KitsuneP2pActor {
         evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
     ) -> KitsuneP2pResult<Self> {
         crate::types::metrics::init();
+        let tls_config = match config.allowed_client_digests()? {
+            Some(digests) => tls_config.with_allowed_client_digests(digests),
+            None => tls_config,
+        };
         let tls_config = Arc::new(tls_config);
         let (t_pool, transport, t_event) = spawn_transport_pool().await?;
+        let connection_limits = config.tuning_params.connection_limits();
         for t_conf in config.transport_pool.clone() {
-            let (l, e) = build_transport(t_conf, tls_config.clone()).await?;
+            let (l, e) =
+                build_transport(t_conf, tls_config.clone(), connection_limits, false).await?;
             t_pool.push_sub_transport(l, e).await?;
         }
+        let wire_protocols = wire_handshake::WireProtocols::new(connection_limits.idle_timeout_ms);
+        let compression_threshold =
+            wire_compression::threshold(config.tuning_params.wire_compression_threshold_bytes);
+        let transport = wire_handshake::spawn_wire_handshake(
+            transport,
+            wire_protocols.clone(),
+            compression_threshold,
+        )
+        .await?;
+
+        let lan_discovery = match config.lan_discovery.clone() {
+            Some(lan_config) => {
+                Some(lan_discovery::spawn_lan_discovery(lan_config, evt_sender.clone()).await?)
+            }
+            None => None,
+        };
+
+        let reputation = reputation::PeerReputation::new(
+            config.tuning_params.peer_block_score_threshold,
+            config.tuning_params.peer_block_duration_ms as u64,
+        );
 
+        let outgoing_streams = rpc_stream::OutgoingStreams::new(
+            crate::request_quota::RequestQuotas::new(config.tuning_params.clone()),
+            wire_protocols.clone(),
+        );
         tokio::task::spawn({
             let evt_sender = evt_sender.clone();
+            let wire_protocols = wire_protocols.clone();
+            let reputation = reputation.clone();
             t_event.for_each_concurrent(/* limit */ 10, move |event| {
                 let evt_sender = evt_sender.clone();
+                let outgoing_streams = outgoing_streams.clone();
+                let wire_protocols = wire_protocols.clone();
+                let reputation = reputation.clone();
                 async move {
                     let evt_sender = &evt_sender;
                     match event {
-                        TransportEvent::IncomingChannel(_url, mut write, read) => {
+                        TransportEvent::IncomingChannel(url, mut write, read) => {
                             let read = read.read_to_end().await;
                             use kitsune_p2p_types::codec::Codec;
-                            let read = match wire::Wire::decode_ref(&read) {
-                                Err(err) => {
-                                    let reason = format!("{:?}", err);
-                                    let fail = wire::Wire::failure(reason).encode_vec().unwrap();
+                            let (read, compressed) = match wire_compression::decode_incoming(&read)
+                            {
+                                Err(fail) => {
+                                    let fail = fail.encode_vec().unwrap();
                                     KitsuneMetrics::count(KitsuneMetrics::Fail, fail.len());
                                     let _ = write.write_and_close(fail).await;
                                     return;
                                 }
-                                Ok((_, r)) => r,
+                                Ok(r) => r,
+                            };
+                            // compress our response if the remote can inflate it
+                            let can_inflate = compressed
+                                || wire_protocols.has_feature(&url, wire::WIRE_FEATURE_DEFLATE);
+                            let failure_kinds =
+                                wire_protocols.has_feature(&url, wire::WIRE_FEATURE_FAILURE_KINDS);
+                            let mut write = match compression_threshold {
+                                Some(threshold) if can_inflate => {
+                                    wire_compression::compress_write(write, threshold)
+                                }
+                                _ => write,
                             };
+                            // refuse anything from an agent we've blocked
+                            if let Some(from_agent) = read.from_agent() {
+                                if reputation.is_blocked(from_agent) {
+                                    let fail = wire::Wire::failure(format!(
+                                        "peer is blocked: {:?}",
+                                        from_agent
+                                    ))
+                                    .encode_vec()
+                                    .unwrap();
+                                    KitsuneMetrics::count(KitsuneMetrics::Fail, fail.len());
+                                    let _ = write.write_and_close(fail).await;
+                                    return;
+                                }
+                            }
                             match read {
+                                wire::Wire::Hello(wire::Hello {
+                                    protocol_version,
+                                    min_protocol_version,
+                                    features,
+                                }) => {
+                                    if let Ok(protocol) = wire::WireProtocol::negotiate(
+                                        protocol_version,
+                                        min_protocol_version,
+                                        &features,
+                                    ) {
+                                        wire_protocols.remote_hello(url, protocol);
+                                    }
+                                    let resp = wire::Wire::local_hello_resp().encode_vec().unwrap();
+                                    KitsuneMetrics::count(KitsuneMetrics::HelloResp, resp.len());
+                                    let _ = write.write_and_close(resp).await;
+                                }
                                 wire::Wire::Call(wire::Call {
                                     space,
                                     from_agent,
@@ -148,13 +266,14 @@ impl KitsuneP2pActor {
                                     ..
                                 }) => {
                                     let res = match evt_sender
-                                        .call(space, to_agent, from_agent, data.into())
+                                        .call(space, to_agent, from_agent, Some(url), data.into())
                                         .await
                                     {
                                         Err(err) => {
-                                            let reason = format!("{:?}", err);
                                             let fail =
-                                                wire::Wire::failure(reason).encode_vec().unwrap();
+                                                wire::Wire::handler_failure(&err, failure_kinds)
+                                                    .encode_vec()
+                                                    .unwrap();
                                             KitsuneMetrics::count(KitsuneMetrics::Fail, fail.len());
                                             let _ = write.write_and_close(fail).await;
                                             return;
@@ -166,6 +285,55 @@ impl KitsuneP2pActor {
                                     KitsuneMetrics::count(KitsuneMetrics::CallResp, resp.len());
                                     let _ = write.write_and_close(resp).await;
                                 }
+                                wire::Wire::CallStream(wire::CallStream {
+                                    space,
+                                    from_agent,
+                                    to_agent,
+                                    data,
+                                    chunk_size,
+                                }) => {
+                                    let res = match evt_sender
+                                        .call(
+                                            space,
+                                            to_agent,
+                                            from_agent,
+                                            Some(url.clone()),
+                                            data.into(),
+                                        )
+                                        .await
+                                    {
+                                        Err(err) => {
+                                            let fail =
+                                                wire::Wire::handler_failure(&err, failure_kinds)
+                                                    .encode_vec()
+                                                    .unwrap();
+                                            KitsuneMetrics::count(KitsuneMetrics::Fail, fail.len());
+                                            let _ = write.write_and_close(fail).await;
+                                            return;
+                                        }
+                                        Ok(r) => r,
+                                    };
+                                    let resp = outgoing_streams.start(url, res, chunk_size);
+                                    let metric = if resp.is_failure() {
+                                        KitsuneMetrics::Fail
+                                    } else {
+                                        KitsuneMetrics::StreamChunk
+                                    };
+                                    let resp = resp.encode_vec().unwrap();
+                                    KitsuneMetrics::count(metric, resp.len());
+                                    let _ = write.write_and_close(resp).await;
+                                }
+                                wire::Wire::StreamNext(wire::StreamNext { stream_id, seq }) => {
+                                    let resp = outgoing_streams.next(url, stream_id, seq);
+                                    let metric = if resp.is_failure() {
+                                        KitsuneMetrics::Fail
+                                    } else {
+                                        KitsuneMetrics::StreamChunk
+                                    };
+                                    let resp = resp.encode_vec().unwrap();
+                                    KitsuneMetrics::count(metric, resp.len());
+                                    let _ = write.write_and_close(resp).await;
+                                }
                                 wire::Wire::Notify(wire::Notify {
                                     space,
                                     from_agent,
@@ -174,12 +342,12 @@ impl KitsuneP2pActor {
                                     ..
                                 }) => {
                                     if let Err(err) = evt_sender
-                                        .notify(space, to_agent, from_agent, data.into())
+                                        .notify(space, to_agent, from_agent, Some(url), data.into())
                                         .await
                                     {
-                                        let reason = format!("{:?}", err);
-                                        let fail =
-                                            wire::Wire::failure(reason).encode_vec().unwrap();
+                                        let fail = wire::Wire::handler_failure(&err, failure_kinds)
+                                            .encode_vec()
+                                            .unwrap();
                                         KitsuneMetrics::count(KitsuneMetrics::Fail, fail.len());
                                         let _ = write.write_and_close(fail).await;
                                         return;
@@ -245,6 +413,51 @@ impl KitsuneP2pActor {
                                     );
                                     let _ = write.write_and_close(resp).await;
                                 }
+                                wire::Wire::FetchOpHashSummary(wire::FetchOpHashSummary {
+                                    space,
+                                    from_agent,
+                                    to_agent,
+                                    dht_arc,
+                                    windows,
+                                    include_hashes,
+                                }) => {
+                                    let input = ReqOpHashSummaryEvt::new(
+                                        from_agent,
+                                        to_agent,
+                                        dht_arc,
+                                        windows,
+                                        include_hashes,
+                                    );
+                                    let (buckets, peer_hashes) =
+                                        match local_req_op_hash_summary(&evt_sender, space, input)
+                                            .await
+                                        {
+                                            Err(err) => {
+                                                let reason = format!("{:?}", err);
+                                                let fail = wire::Wire::failure(reason)
+                                                    .encode_vec()
+                                                    .unwrap();
+                                                KitsuneMetrics::count(
+                                                    KitsuneMetrics::Fail,
+                                                    fail.len(),
+                                                );
+                                                let _ = write.write_and_close(fail).await;
+                                                return;
+                                            }
+                                            Ok(r) => r,
+                                        };
+                                    let resp = wire::Wire::fetch_op_hash_summary_response(
+                                        buckets,
+                                        peer_hashes,
+                                    )
+                                    .encode_vec()
+                                    .expect("This encoding should never fail");
+                                    KitsuneMetrics::count(
+                                        KitsuneMetrics::FetchOpHashSummaryResp,
+                                        resp.len(),
+                                    );
+                                    let _ = write.write_and_close(resp).await;
+                                }
                                 wire::Wire::FetchOpData(wire::FetchOpData {
                                     space,
                                     from_agent,
@@ -335,7 +548,13 @@ impl KitsuneP2pActor {
                                     KitsuneMetrics::count(KitsuneMetrics::GossipResp, resp.len());
                                     let _ = write.write_and_close(resp).await;
                                 }
-                                _ => unimplemented!("{:?}", read),
+                                _ => {
+                                    let reason =
+                                        format!("unexpected kitsune wire message: {:?}", read);
+                                    let fail = wire::Wire::failure(reason).encode_vec().unwrap();
+                                    KitsuneMetrics::count(KitsuneMetrics::Fail, fail.len());
+                                    let _ = write.write_and_close(fail).await;
+                                }
                             }
                         }
                     }
@@ -349,6 +568,9 @@ impl KitsuneP2pActor {
             evt_sender,
             transport,
             spaces: HashMap::new(),
+            wire_protocols,
+            lan_discovery,
+            reputation,
             config: Arc::new(config),
         })
     }
@@ -377,13 +599,33 @@ async fn agent_info_query(
         } else {
             Ok(vec![])
         }
-    } else if let Some(_by_basis_arc) = by_basis_arc {
-        Ok(evt_sender
+    } else if let Some((basis, arc)) = by_basis_arc {
+        // return agents holding the basis,
+        // or any whose arc is centered within the requested arc
+        let basis_loc = basis.get_loc();
+        let mut list = evt_sender
             .query_agent_info_signed(QueryAgentInfoSignedEvt {
                 space,
                 agent: to_agent,
             })
-            .await?)
+            .await?
+            .into_iter()
+            .filter_map(|signed| {
+                let info = AgentInfo::try_from(&signed).ok()?;
+                let info_arc = info.dht_arc();
+                if info_arc.contains(basis_loc) || arc.contains(info_arc.center_loc) {
+                    Some((signed, info))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        discover::sort_near_basis(basis_loc, &mut list, |(_, info)| info);
+        Ok(list
+            .into_iter()
+            .take(discover::BASIS_QUERY_LIMIT)
+            .map(|(signed, _)| signed)
+            .collect())
     } else {
         Err("must specify by_agent or by_basis_arc".into())
     }
@@ -437,9 +679,12 @@ impl KitsuneP2pEventHandler for KitsuneP2pActor {
         space: Arc<KitsuneSpace>,
         to_agent: Arc<KitsuneAgent>,
         from_agent: Arc<KitsuneAgent>,
+        from_node: Option<url2::Url2>,
         payload: Vec<u8>,
     ) -> KitsuneP2pEventHandlerResult<Vec<u8>> {
-        Ok(self.evt_sender.call(space, to_agent, from_agent, payload))
+        Ok(self
+            .evt_sender
+            .call(space, to_agent, from_agent, from_node, payload))
     }
 
     fn handle_notify(
@@ -447,9 +692,12 @@ impl KitsuneP2pEventHandler for KitsuneP2pActor {
         space: Arc<KitsuneSpace>,
         to_agent: Arc<KitsuneAgent>,
         from_agent: Arc<KitsuneAgent>,
+        from_node: Option<url2::Url2>,
         payload: Vec<u8>,
     ) -> KitsuneP2pEventHandlerResult<()> {
-        Ok(self.evt_sender.notify(space, to_agent, from_agent, payload))
+        Ok(self
+            .evt_sender
+            .notify(space, to_agent, from_agent, from_node, payload))
     }
 
     fn handle_gossip(
@@ -472,6 +720,13 @@ impl KitsuneP2pEventHandler for KitsuneP2pActor {
         Ok(self.evt_sender.fetch_op_hashes_for_constraints(input))
     }
 
+    fn handle_fetch_op_hash_times_for_constraints(
+        &mut self,
+        input: FetchOpHashTimesForConstraintsEvt,
+    ) -> KitsuneP2pEventHandlerResult<Vec<(Arc<KitsuneOpHash>, i64)>> {
+        Ok(self.evt_sender.fetch_op_hash_times_for_constraints(input))
+    }
+
     fn handle_fetch_op_hash_data(
         &mut self,
         input: FetchOpHashDataEvt,
@@ -511,13 +766,23 @@ impl KitsuneP2pHandler for KitsuneP2pActor {
         let internal_sender = self.internal_sender.clone();
         let space2 = space.clone();
         let transport = self.transport.clone();
+        let wire_protocols = self.wire_protocols.clone();
+        let lan_discovery = self.lan_discovery.clone();
+        let reputation = self.reputation.clone();
         let config = Arc::clone(&self.config);
         let space_sender = match self.spaces.entry(space.clone()) {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => entry.insert(AsyncLazy::new(async move {
-                let (send, evt_recv) = spawn_space(space2, transport, config)
-                    .await
-                    .expect("cannot fail to create space");
+                let (send, evt_recv) = spawn_space(
+                    space2,
+                    transport,
+                    wire_protocols,
+                    lan_discovery,
+                    reputation,
+                    config,
+                )
+                .await
+                .expect("cannot fail to create space");
                 internal_sender
                     .register_space_event_handler(evt_recv)
                     .await
@@ -570,6 +835,28 @@ impl KitsuneP2pHandler for KitsuneP2pActor {
         .into())
     }
 
+    fn handle_rpc_stream(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+        to_agent: Arc<KitsuneAgent>,
+        from_agent: Arc<KitsuneAgent>,
+        payload: Vec<u8>,
+        timeout_ms: Option<u64>,
+    ) -> KitsuneP2pHandlerResult<actor::RpcStream> {
+        let space_sender = match self.spaces.get_mut(&space) {
+            None => return Err(KitsuneP2pError::RoutingSpaceError(space)),
+            Some(space) => space.get(),
+        };
+        Ok(async move {
+            space_sender
+                .await
+                .rpc_stream(space, to_agent, from_agent, payload, timeout_ms)
+                .await
+        }
+        .boxed()
+        .into())
+    }
+
     #[tracing::instrument(skip(self, input))]
     fn handle_rpc_multi(
         &mut self,
@@ -593,4 +880,89 @@ impl KitsuneP2pHandler for KitsuneP2pActor {
             .boxed()
             .into())
     }
+
+    fn handle_group_bases_by_authority(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+        from_agent: Arc<KitsuneAgent>,
+        bases: Vec<Arc<KitsuneBasis>>,
+    ) -> KitsuneP2pHandlerResult<actor::AuthorityGroups> {
+        let space_sender = match self.spaces.get_mut(&space) {
+            None => return Err(KitsuneP2pError::RoutingSpaceError(space)),
+            Some(space) => space.get(),
+        };
+        Ok(async move {
+            space_sender
+                .await
+                .group_bases_by_authority(space, from_agent, bases)
+                .await
+        }
+        .boxed()
+        .into())
+    }
+
+    fn handle_space_stats(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+    ) -> KitsuneP2pHandlerResult<actor::SpaceStats> {
+        let space_sender = match self.spaces.get_mut(&space) {
+            None => return Err(KitsuneP2pError::RoutingSpaceError(space)),
+            Some(space) => space.get(),
+        };
+        Ok(async move { space_sender.await.space_stats(space).await }
+            .boxed()
+            .into())
+    }
+
+    fn handle_report_invalid_data(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+        agent: Arc<KitsuneAgent>,
+    ) -> KitsuneP2pHandlerResult<()> {
+        let space_sender = match self.spaces.get_mut(&space) {
+            None => return Err(KitsuneP2pError::RoutingSpaceError(space)),
+            Some(space) => space.get(),
+        };
+        Ok(
+            async move { space_sender.await.report_invalid_data(space, agent).await }
+                .boxed()
+                .into(),
+        )
+    }
+
+    fn handle_block_peer(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+        agent: Arc<KitsuneAgent>,
+        duration_ms: Option<u64>,
+    ) -> KitsuneP2pHandlerResult<()> {
+        let space_sender = match self.spaces.get_mut(&space) {
+            None => return Err(KitsuneP2pError::RoutingSpaceError(space)),
+            Some(space) => space.get(),
+        };
+        Ok(async move {
+            space_sender
+                .await
+                .block_peer(space, agent, duration_ms)
+                .await
+        }
+        .boxed()
+        .into())
+    }
+
+    fn handle_unblock_peer(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+        agent: Arc<KitsuneAgent>,
+    ) -> KitsuneP2pHandlerResult<()> {
+        let space_sender = match self.spaces.get_mut(&space) {
+            None => return Err(KitsuneP2pError::RoutingSpaceError(space)),
+            Some(space) => space.get(),
+        };
+        Ok(
+            async move { space_sender.await.unblock_peer(space, agent).await }
+                .boxed()
+                .into(),
+        )
+    }
 }
@@ -1,15 +1,25 @@
 #![allow(dead_code)]
+use super::network_stats::RequestOutcome;
+use super::reputation::PeerEvent;
+use super::reputation::PeerReputation;
+use super::space::failure_event;
 use super::*;
 use crate::agent_store::AgentInfo;
+use crate::agent_store::AgentInfoSigned;
+use crate::types::KitsuneBinType;
 use ghost_actor::dependencies::must_future::MustBoxFuture;
 use kitsune_p2p_types::codec::Codec;
+use kitsune_p2p_types::dht_arc::shortest_arc_distance;
+use kitsune_p2p_types::dht_arc::DhtArc;
+use kitsune_p2p_types::dht_arc::MAX_HALF_LENGTH;
 use std::collections::HashSet;
 use std::convert::TryFrom;
 
 /// This enum represents the outcomes from peer discovery
 /// - OkShortcut - the agent is locally joined, just mirror the request back out
 /// - OkRemote - we were able to successfully establish a remote connection
-/// - Err - we were not able to establish a connection within the timeout
+/// - Unreachable - we found where the agent is, but couldn't connect to it
+/// - TimedOut - we were not able to find the agent within the timeout
 pub(crate) enum PeerDiscoverResult {
     OkShortcut,
     OkRemote {
@@ -17,7 +27,8 @@ pub(crate) enum PeerDiscoverResult {
         write: TransportChannelWrite,
         read: TransportChannelRead,
     },
-    Err(KitsuneP2pError),
+    Unreachable(KitsuneP2pError),
+    TimedOut(KitsuneP2pError),
 }
 
 /// attempt to establish a connection to another peer within given timeout
@@ -31,6 +42,7 @@ pub(crate) fn peer_discover(
     let evt_sender = space.evt_sender.clone();
     let tx = space.transport.clone();
     let bootstrap_service = space.config.bootstrap_service.clone();
+    let reputation = space.reputation.clone();
     let space = space.space.clone();
     async move {
         // run tx.create_channel an conver success result into our return type
@@ -49,7 +61,6 @@ pub(crate) fn peer_discover(
         };
 
         // check if we have a reference to this agent in our peer store
-        // if so, see if that url is valid via try_connect
         let check_peer_store = || async {
             if let Some(info) = evt_sender
                 .get_agent_info_signed(GetAgentInfoSignedEvt {
@@ -64,7 +75,7 @@ pub(crate) fn peer_discover(
                     .get(0)
                     .ok_or_else(|| KitsuneP2pError::from("no url"))?
                     .clone();
-                return try_connect(url).await;
+                return Ok(url);
             }
 
             KitsuneP2pResult::Err("failed to connect".into())
@@ -77,7 +88,9 @@ pub(crate) fn peer_discover(
                 Arc::new(KitsuneBasis(to_agent.to_vec())),
                 i_s.clone(),
                 evt_sender.clone(),
+                tx.clone(),
                 bootstrap_service.clone(),
+                reputation.clone(),
             )
             .await?;
 
@@ -138,14 +151,13 @@ pub(crate) fn peer_discover(
                 })
                 .await;
 
-            // we got a result, try to connect to it
             let info = types::agent_store::AgentInfo::try_from(&req_info)?;
             let url = info
                 .as_urls_ref()
                 .get(0)
                 .ok_or_else(|| KitsuneP2pError::from("no url"))?
                 .clone();
-            try_connect(url).await
+            KitsuneP2pResult::Ok(url)
         };
 
         let start_time = std::time::Instant::now();
@@ -156,17 +168,29 @@ pub(crate) fn peer_discover(
                 return res;
             }
 
-            if let Ok(res) = check_peer_store().await {
-                return res;
+            // the last error connecting to a url we found for the agent
+            let mut unreachable = None;
+
+            if let Ok(url) = check_peer_store().await {
+                match try_connect(url).await {
+                    Ok(res) => return res,
+                    Err(e) => unreachable = Some(e),
+                }
             }
 
-            if let Ok(res) = check_network().await {
-                return res;
+            if let Ok(url) = check_network().await {
+                match try_connect(url).await {
+                    Ok(res) => return res,
+                    Err(e) => unreachable = Some(e),
+                }
             }
 
             let elapsed_ms = start_time.elapsed().as_millis() as u64;
             if elapsed_ms >= timeout_ms {
-                return PeerDiscoverResult::Err("timeout".into());
+                return match unreachable {
+                    Some(e) => PeerDiscoverResult::Unreachable(e),
+                    None => PeerDiscoverResult::TimedOut("timeout".into()),
+                };
             }
 
             interval_ms *= 2;
@@ -181,32 +205,41 @@ pub(crate) fn peer_discover(
     .into()
 }
 
-/// attempt to send messages to remote nodes in a staged timeout format
+/// attempt to send messages to remote nodes in a staged timeout format,
+/// until `enough_results_cb` is satisfied with the results so far.
+/// `accept_result_cb` is given each responding agent
+/// with the url of the node it responded from.
 #[allow(clippy::too_many_arguments)]
-pub(crate) fn message_neighborhood<T, F>(
+pub(crate) fn message_neighborhood<T, F, E>(
     space: &mut Space,
     from_agent: Arc<KitsuneAgent>,
-    target_node_count: u8,
+    enough_results_cb: E,
     stage_1_timeout_if_any_ms: u64,
     stage_2_timeout_even_if_none_ms: u64,
-    // ignored while full-sync
-    _basis: Arc<KitsuneBasis>,
+    basis: Arc<KitsuneBasis>,
     payload: wire::Wire,
     accept_result_cb: F,
 ) -> MustBoxFuture<'static, Vec<T>>
 where
     T: 'static + Send,
-    F: Fn(Arc<KitsuneAgent>, wire::Wire) -> Result<T, ()> + 'static + Send + Sync,
+    F: Fn(Arc<KitsuneAgent>, url2::Url2, wire::Wire) -> Result<T, ()> + 'static + Send + Sync,
+    E: Fn(&[T]) -> bool + 'static + Send,
 {
     let i_s = space.i_s.clone();
     let evt_sender = space.evt_sender.clone();
     let tx = space.transport.clone();
     let bootstrap_service = space.config.bootstrap_service.clone();
+    let network_stats = space.network_stats.clone();
+    let reputation = space.reputation.clone();
     let space = space.space.clone();
     let accept_result_cb = Arc::new(accept_result_cb);
     async move {
         let out = Arc::new(tokio::sync::Mutex::new(Vec::new()));
 
+        // agents we are waiting on a response from,
+        // None once we have stopped waiting
+        let pending = Arc::new(std::sync::Mutex::new(Some(HashSet::new())));
+
         let mut sent_to = HashSet::new();
         let start_time = std::time::Instant::now();
         let mut interval_ms = 50;
@@ -220,8 +253,11 @@ where
             // we'll check the fetch count / timing after every full
             // iteration before deciding to send more requests.
 
-            let fetched_count = out.lock().await.len();
-            if fetched_count >= target_node_count as usize {
+            let (enough, fetched_count) = {
+                let out = out.lock().await;
+                (enough_results_cb(&out), out.len())
+            };
+            if enough {
                 break;
             }
 
@@ -238,10 +274,12 @@ where
             if let Ok(nodes) = get_5_or_less_non_local_agents_near_basis(
                 space.clone(),
                 from_agent.clone(),
-                _basis.clone(),
+                basis.clone(),
                 i_s.clone(),
                 evt_sender.clone(),
+                tx.clone(),
                 bootstrap_service.clone(),
+                reputation.clone(),
             )
             .await
             {
@@ -253,29 +291,76 @@ where
                             None => continue,
                             Some(url) => url.clone(),
                         };
+                        if let Some(pending) = pending.lock().unwrap().as_mut() {
+                            pending.insert(to_agent.clone());
+                        }
                         let fut = tx.create_channel(url);
                         let mut payload = payload.clone();
                         let accept_result_cb = accept_result_cb.clone();
                         let out = out.clone();
+                        let pending = pending.clone();
+                        let network_stats = network_stats.clone();
+                        let reputation = reputation.clone();
                         tokio::task::spawn(async move {
-                            let (_, mut write, read) = fut.await?;
-                            let metric_type = match &mut payload {
-                                wire::Wire::Notify(n) => {
-                                    n.to_agent = to_agent.clone();
-                                    KitsuneMetrics::Notify
-                                }
-                                wire::Wire::Call(c) => {
-                                    c.to_agent = to_agent.clone();
-                                    KitsuneMetrics::Call
-                                }
-                                _ => panic!("cannot message {:?}", payload),
+                            let start = std::time::Instant::now();
+                            let res = async {
+                                let (con_url, mut write, read) = fut.await?;
+                                let metric_type = match &mut payload {
+                                    wire::Wire::Notify(n) => {
+                                        n.to_agent = to_agent.clone();
+                                        KitsuneMetrics::Notify
+                                    }
+                                    wire::Wire::Call(c) => {
+                                        c.to_agent = to_agent.clone();
+                                        KitsuneMetrics::Call
+                                    }
+                                    _ => panic!("cannot message {:?}", payload),
+                                };
+                                let payload = payload.encode_vec()?;
+                                KitsuneMetrics::count(metric_type, payload.len());
+                                write.write_and_close(payload).await?;
+                                KitsuneP2pResult::Ok((con_url, read.read_to_end().await))
+                            }
+                            .await;
+                            let (event, res) = match res {
+                                Err(e) => (PeerEvent::Failed, Err(e)),
+                                Ok((con_url, res)) => match wire::Wire::decode_ref(&res) {
+                                    Err(e) => (PeerEvent::BadResponse, Err(e.into())),
+                                    Ok((_, wire::Wire::Failure(failure))) => {
+                                        let (event, e) = failure_event(failure.into_error());
+                                        (event, Err(e))
+                                    }
+                                    Ok((_, wire::Wire::Busy(busy))) => {
+                                        let (event, e) = failure_event(busy.into_error());
+                                        (event, Err(e))
+                                    }
+                                    Ok((_, wire::Wire::Rejected(rejected))) => {
+                                        let (event, e) = failure_event(rejected.into_error());
+                                        (event, Err(e))
+                                    }
+                                    Ok((_, res)) => {
+                                        match accept_result_cb(to_agent.clone(), con_url, res) {
+                                            Ok(res) => {
+                                                (PeerEvent::Responded(start.elapsed()), Ok(res))
+                                            }
+                                            Err(_) => (
+                                                PeerEvent::Failed,
+                                                Err(KitsuneP2pError::from("unexpected response")),
+                                            ),
+                                        }
+                                    }
+                                },
                             };
-                            let payload = payload.encode_vec()?;
-                            KitsuneMetrics::count(metric_type, payload.len());
-                            write.write_and_close(payload).await?;
-                            let res = read.read_to_end().await;
-                            let (_, res) = wire::Wire::decode_ref(&res)?;
-                            if let Ok(res) = accept_result_cb(to_agent, res) {
+
+                            // responses after we stopped waiting were timed out
+                            if let Some(pending) = pending.lock().unwrap().as_mut() {
+                                if pending.remove(&to_agent) {
+                                    network_stats.record_result(to_agent.clone(), &res);
+                                    reputation.record(to_agent, event);
+                                }
+                            }
+
+                            if let Ok(res) = res {
                                 out.lock().await.push(res);
                             }
 
@@ -299,6 +384,13 @@ where
             tokio::time::delay_for(std::time::Duration::from_millis(interval_ms)).await;
         }
 
+        if let Some(pending) = pending.lock().unwrap().take() {
+            for agent in pending {
+                network_stats.record_request(agent.clone(), RequestOutcome::TimedOut);
+                reputation.record(agent, PeerEvent::TimedOut);
+            }
+        }
+
         let mut lock = out.lock().await;
         lock.drain(..).collect()
     }
@@ -306,42 +398,120 @@ where
     .into()
 }
 
-/// search for agents to contact
+/// How many rounds of asking nearer peers for even nearer peers
+/// we make before giving up on finding an agent holding the basis.
+const MAX_LOOKUP_ROUNDS: usize = 3;
+
+/// How many stored agents we ask for peers when joining a space.
+const SEED_PEER_COUNT: usize = 5;
+
+/// How many agents a remote returns for a basis query.
+pub(crate) const BASIS_QUERY_LIMIT: usize = 8;
+
+/// Sort agents so that those holding the basis come first,
+/// then by the distance of their arc's center from the basis.
+pub(crate) fn sort_near_basis<T>(
+    basis_loc: u32,
+    list: &mut [T],
+    get_info: impl Fn(&T) -> &AgentInfo,
+) {
+    list.sort_by_key(|item| {
+        let arc = get_info(item).dht_arc();
+        (
+            !arc.contains(basis_loc),
+            shortest_arc_distance(arc.center_loc, basis_loc),
+        )
+    });
+}
+
+/// Among agents sorted by [sort_near_basis], put those holding the basis
+/// with the best reputation first. Any of them can answer for the basis
+/// so we'd rather ask the ones that have answered well before.
+pub(crate) fn prefer_reputable_holders(
+    basis_loc: u32,
+    list: &mut [AgentInfo],
+    reputation: &PeerReputation,
+) {
+    let holders = list
+        .iter()
+        .take_while(|info| info.dht_arc().contains(basis_loc))
+        .count();
+    list[..holders].sort_by(|a, b| {
+        reputation
+            .score(b.as_agent_ref())
+            .partial_cmp(&reputation.score(a.as_agent_ref()))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// search for agents to contact, nearest to the basis first, skipping
+/// agents we've blocked and preferring those with a good reputation
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn get_5_or_less_non_local_agents_near_basis(
     space: Arc<KitsuneSpace>,
     from_agent: Arc<KitsuneAgent>,
-    // ignored while full-sync
-    _basis: Arc<KitsuneBasis>,
+    basis: Arc<KitsuneBasis>,
     i_s: ghost_actor::GhostSender<SpaceInternal>,
     evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+    tx: ghost_actor::GhostSender<TransportListener>,
     bootstrap_service: Option<url2::Url2>,
+    reputation: PeerReputation,
 ) -> MustBoxFuture<'static, KitsuneP2pResult<HashSet<AgentInfo>>> {
     async move {
-        let mut out = HashSet::new();
+        let basis_loc = basis.get_loc();
 
-        if let Ok(mut list) = evt_sender
+        let mut nearest = Vec::new();
+        if let Ok(list) = evt_sender
             .query_agent_info_signed(QueryAgentInfoSignedEvt {
                 space: space.clone(),
                 agent: from_agent.clone(),
             })
             .await
         {
-            // randomize the results
-            rand::seq::SliceRandom::shuffle(&mut list[..], &mut rand::thread_rng());
             for item in list {
                 if let Ok(info) = AgentInfo::try_from(&item) {
-                    if let Ok(is_local) = i_s
+                    if reputation.is_blocked(info.as_agent_ref()) {
+                        continue;
+                    }
+                    if let Ok(false) = i_s
                         .is_agent_local(Arc::new(info.as_agent_ref().clone()))
                         .await
                     {
-                        if !is_local {
-                            out.insert(info);
-                        }
+                        nearest.push(info);
                     }
                 }
-                if out.len() >= 5 {
-                    return Ok(out);
-                }
+            }
+        }
+        // shuffle first so agents at the same distance are picked evenly
+        rand::seq::SliceRandom::shuffle(&mut nearest[..], &mut rand::thread_rng());
+        sort_near_basis(basis_loc, &mut nearest, |i| i);
+        prefer_reputable_holders(basis_loc, &mut nearest, &reputation);
+
+        // if nobody we know of holds the basis,
+        // ask the nearest agents we do know about for nearer ones
+        if !nearest.is_empty() && !nearest[0].dht_arc().contains(basis_loc) {
+            nearest = lookup_near_basis(
+                space.clone(),
+                from_agent.clone(),
+                basis.clone(),
+                i_s.clone(),
+                evt_sender.clone(),
+                tx,
+                nearest,
+            )
+            .await;
+            prefer_reputable_holders(basis_loc, &mut nearest, &reputation);
+        }
+
+        let mut out = HashSet::new();
+        for info in nearest {
+            // lookup may have found agents we've blocked
+            if reputation.is_blocked(info.as_agent_ref()) {
+                continue;
+            }
+            out.insert(info);
+            if out.len() >= actor::MAX_QUORUM as usize {
+                return Ok(out);
             }
         }
 
@@ -358,6 +528,9 @@ pub(crate) fn get_5_or_less_non_local_agents_near_basis(
             for item in list {
                 // TODO - someday some validation here
                 if let Ok(info) = AgentInfo::try_from(&item) {
+                    if reputation.is_blocked(info.as_agent_ref()) {
+                        continue;
+                    }
                     if let Ok(is_local) = i_s
                         .is_agent_local(Arc::new(info.as_agent_ref().clone()))
                         .await
@@ -375,7 +548,7 @@ pub(crate) fn get_5_or_less_non_local_agents_near_basis(
                         }
                     }
                 }
-                if out.len() >= 5 {
+                if out.len() >= actor::MAX_QUORUM as usize {
                     return Ok(out);
                 }
             }
@@ -391,6 +564,214 @@ pub(crate) fn get_5_or_less_non_local_agents_near_basis(
     .into()
 }
 
+/// Iterative lookup over the network, storing every agent it finds.
+/// Returns the candidates sorted nearest first.
+async fn lookup_near_basis(
+    space: Arc<KitsuneSpace>,
+    from_agent: Arc<KitsuneAgent>,
+    basis: Arc<KitsuneBasis>,
+    i_s: ghost_actor::GhostSender<SpaceInternal>,
+    evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+    tx: ghost_actor::GhostSender<TransportListener>,
+    nearest: Vec<AgentInfo>,
+) -> Vec<AgentInfo> {
+    let basis_loc = basis.get_loc();
+    iterative_lookup(basis_loc, nearest, move |info, arc| {
+        let space = space.clone();
+        let from_agent = from_agent.clone();
+        let basis = basis.clone();
+        let i_s = i_s.clone();
+        let evt_sender = evt_sender.clone();
+        let tx = tx.clone();
+        async move {
+            let mut found = Vec::new();
+            let list = query_near_basis(space.clone(), basis, arc, tx, info)
+                .await
+                .unwrap_or_default();
+            for item in list {
+                let info = match AgentInfo::try_from(&item) {
+                    Ok(info) => info,
+                    Err(_) => continue,
+                };
+                if let Ok(false) = i_s
+                    .is_agent_local(Arc::new(info.as_agent_ref().clone()))
+                    .await
+                {
+                    // we got a result - let's add it to our store for the future
+                    let _ = evt_sender
+                        .put_agent_info_signed(PutAgentInfoSignedEvt {
+                            space: space.clone(),
+                            agent: from_agent.clone(),
+                            agent_info_signed: item,
+                        })
+                        .await;
+                    found.push(info);
+                }
+            }
+            found
+        }
+    })
+    .await
+}
+
+/// Iterative lookup: ask the nearest known agents for agents
+/// nearer to the basis than they are, repeating with the nearer agents
+/// until we find one holding the basis or stop getting any closer.
+/// `query` asks one agent for the non-local agents it knows within an arc.
+/// Returns the candidates sorted nearest first.
+async fn iterative_lookup<Q, F>(
+    basis_loc: u32,
+    mut nearest: Vec<AgentInfo>,
+    query: Q,
+) -> Vec<AgentInfo>
+where
+    Q: Fn(AgentInfo, DhtArc) -> F,
+    F: std::future::Future<Output = Vec<AgentInfo>>,
+{
+    let mut asked = HashSet::new();
+
+    for _ in 0..MAX_LOOKUP_ROUNDS {
+        let to_ask = nearest
+            .iter()
+            .filter(|info| !asked.contains(info.as_agent_ref()))
+            .take(3)
+            .cloned()
+            .collect::<Vec<_>>();
+        if to_ask.is_empty() {
+            break;
+        }
+
+        // only agents nearer than our current nearest are any use
+        let radius = shortest_arc_distance(nearest[0].dht_arc().center_loc, basis_loc);
+        let arc = DhtArc::new(basis_loc, radius.saturating_add(1));
+        let results = futures::future::join_all(to_ask.into_iter().map(|info| {
+            asked.insert(info.as_agent_ref().clone());
+            query(info, arc)
+        }))
+        .await;
+
+        let mut found_nearer = false;
+        for info in results.into_iter().flatten() {
+            if nearest
+                .iter()
+                .any(|n| n.as_agent_ref() == info.as_agent_ref())
+            {
+                continue;
+            }
+            found_nearer = true;
+            nearest.push(info);
+        }
+
+        sort_near_basis(basis_loc, &mut nearest, |i| i);
+        if !found_nearer || nearest[0].dht_arc().contains(basis_loc) {
+            break;
+        }
+    }
+
+    nearest
+}
+
+/// Ask a remote agent for the agents it knows of near a basis.
+async fn query_near_basis(
+    space: Arc<KitsuneSpace>,
+    basis: Arc<KitsuneBasis>,
+    arc: DhtArc,
+    tx: ghost_actor::GhostSender<TransportListener>,
+    info: AgentInfo,
+) -> KitsuneP2pResult<Vec<AgentInfoSigned>> {
+    let url = info
+        .as_urls_ref()
+        .get(0)
+        .ok_or_else(|| KitsuneP2pError::from("no url"))?
+        .clone();
+    let (_, mut write, read) = tx.create_channel(url).await?;
+
+    let msg = wire::Wire::agent_info_query(
+        space,
+        Arc::new(info.as_agent_ref().clone()),
+        None,
+        Some((basis, arc)),
+    )
+    .encode_vec()?;
+    KitsuneMetrics::count(KitsuneMetrics::AgentInfoQuery, msg.len());
+    write.write_and_close(msg).await?;
+
+    let res = read.read_to_end().await;
+    let (_, res) = wire::Wire::decode_ref(&res)?;
+    match res {
+        wire::Wire::AgentInfoQueryResp(wire::AgentInfoQueryResp { agent_infos }) => Ok(agent_infos),
+        _ => Err("failed to query near basis".into()),
+    }
+}
+
+/// Seed discovery from the unexpired agent infos we already have stored,
+/// so peers we knew about before a restart can be found without a
+/// bootstrap service. Asks the stored agents nearest to us for the
+/// agents they know near us, and stores any new ones.
+pub(crate) fn seed_from_peer_store(
+    space: Arc<KitsuneSpace>,
+    from_agent: Arc<KitsuneAgent>,
+    i_s: ghost_actor::GhostSender<SpaceInternal>,
+    evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+    tx: ghost_actor::GhostSender<TransportListener>,
+) -> MustBoxFuture<'static, KitsuneP2pResult<()>> {
+    async move {
+        let mut known = Vec::new();
+        for item in evt_sender
+            .query_agent_info_signed(QueryAgentInfoSignedEvt {
+                space: space.clone(),
+                agent: from_agent.clone(),
+            })
+            .await?
+        {
+            if let Ok(info) = AgentInfo::try_from(&item) {
+                if let Ok(false) = i_s
+                    .is_agent_local(Arc::new(info.as_agent_ref().clone()))
+                    .await
+                {
+                    known.push(info);
+                }
+            }
+        }
+
+        let basis = Arc::new(KitsuneBasis(from_agent.to_vec()));
+        let basis_loc = basis.get_loc();
+        sort_near_basis(basis_loc, &mut known, |i| i);
+        let results =
+            futures::future::join_all(known.into_iter().take(SEED_PEER_COUNT).map(|info| {
+                query_near_basis(
+                    space.clone(),
+                    basis.clone(),
+                    DhtArc::new(basis_loc, MAX_HALF_LENGTH),
+                    tx.clone(),
+                    info,
+                )
+            }))
+            .await;
+
+        for item in results.into_iter().filter_map(Result::ok).flatten() {
+            if let Ok(info) = AgentInfo::try_from(&item) {
+                if let Ok(false) = i_s
+                    .is_agent_local(Arc::new(info.as_agent_ref().clone()))
+                    .await
+                {
+                    let _ = evt_sender
+                        .put_agent_info_signed(PutAgentInfoSignedEvt {
+                            space: space.clone(),
+                            agent: from_agent.clone(),
+                            agent_info_signed: item,
+                        })
+                        .await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+    .boxed()
+    .into()
+}
+
 pub(crate) fn add_5_or_less_non_local_agents(
     space: Arc<KitsuneSpace>,
     from_agent: Arc<KitsuneAgent>,
@@ -434,3 +815,62 @@ pub(crate) fn add_5_or_less_non_local_agents(
     .boxed()
     .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn info_at(loc: u32, half_length: u32) -> AgentInfo {
+        let mut agent = vec![0; 32];
+        agent.extend_from_slice(&loc.to_le_bytes());
+        AgentInfo::new(KitsuneSpace(vec![0; 36]), KitsuneAgent(agent), vec![], 0, 0)
+            .with_dht_arc(DhtArc::new(loc, half_length))
+    }
+
+    #[test]
+    fn test_sort_near_basis() {
+        let basis_loc = 1000;
+        let far = info_at(u32::MAX / 2, 10);
+        let near = info_at(900, 10);
+        let holder = info_at(2000, 1500);
+        let wrapped = info_at(u32::MAX - 10, 10);
+
+        let mut list = vec![far.clone(), near.clone(), wrapped.clone(), holder.clone()];
+        sort_near_basis(basis_loc, &mut list, |i| i);
+        assert_eq!(vec![holder, near, wrapped, far], list);
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_iterative_lookup_multiple_hops() {
+        let basis_loc = 1000;
+        let far = info_at(u32::MAX / 2, 10);
+        let mid = info_at(100_000, 10);
+        let holder = info_at(2000, 1500);
+
+        // each agent only knows part of the network,
+        // so the holder can only be found by asking far then mid
+        let mut network: HashMap<KitsuneAgent, Vec<AgentInfo>> = HashMap::new();
+        network.insert(far.as_agent_ref().clone(), vec![mid.clone()]);
+        network.insert(mid.as_agent_ref().clone(), vec![holder.clone()]);
+        let asked = std::sync::Mutex::new(Vec::new());
+
+        let nearest = iterative_lookup(basis_loc, vec![far.clone()], |info, arc| {
+            asked.lock().unwrap().push(info.clone());
+            let known = network
+                .get(info.as_agent_ref())
+                .cloned()
+                .unwrap_or_default();
+            async move {
+                known
+                    .into_iter()
+                    .filter(|i| arc.contains(i.dht_arc().center_loc))
+                    .collect()
+            }
+        })
+        .await;
+
+        assert_eq!(nearest[0], holder);
+        assert_eq!(*asked.lock().unwrap(), vec![far, mid]);
+    }
+}
@@ -1,6 +1,7 @@
 //! This is a temporary quick-hack gossip module for use with the
 //! in-memory / full-sync / non-sharded networking module
 
+use super::network_stats::NetworkStats;
 use crate::types::actor::KitsuneP2pResult;
 use crate::types::gossip::*;
 use crate::*;
@@ -24,6 +25,18 @@ ghost_actor::ghost_chan! {
             input: ReqOpHashesEvt,
         ) -> OpHashesAgentHashes;
 
+        /// fetch op hashes, with the time each op was authored,
+        /// from a local agent
+        fn req_local_op_hash_times(
+            input: ReqLocalOpHashTimesEvt,
+        ) -> LocalOpHashTimesAgentHashes;
+
+        /// fetch summaries of op hashes bucketed by authoring time,
+        /// None if the agent's node can't summarize them
+        fn req_op_hash_summary(
+            input: ReqOpHashSummaryEvt,
+        ) -> Option<OpHashSummaryAgentHashes>;
+
         /// fetch op data for op hash list
         fn req_op_data(
             input: ReqOpDataEvt
@@ -39,22 +52,30 @@ ghost_actor::ghost_chan! {
 pub type GossipEventReceiver = futures::channel::mpsc::Receiver<GossipEvent>;
 
 /// spawn a gossip module to control gossip for a space
-pub fn spawn_gossip_module(config: Arc<KitsuneP2pConfig>) -> GossipEventReceiver {
+pub fn spawn_gossip_module(
+    config: Arc<KitsuneP2pConfig>,
+    network_stats: NetworkStats,
+) -> GossipEventReceiver {
     let (evt_send, evt_recv) = futures::channel::mpsc::channel(10);
 
-    tokio::task::spawn(gossip_loop(config, evt_send));
+    tokio::task::spawn(gossip_loop(config, evt_send, network_stats));
 
     evt_recv
 }
 
-#[tracing::instrument(skip(evt_send))]
+#[tracing::instrument(skip(evt_send, network_stats))]
 /// the gossip module is not an actor because we want to pause while
 /// awaiting requests - not process requests in parallel.
 async fn gossip_loop(
     config: Arc<KitsuneP2pConfig>,
     evt_send: futures::channel::mpsc::Sender<GossipEvent>,
+    network_stats: NetworkStats,
 ) -> KitsuneP2pResult<()> {
-    let mut gossip_data = GossipData::new(evt_send);
+    let mut gossip_data = GossipData::new(
+        evt_send,
+        network_stats,
+        config.tuning_params.gossip_bucket_width_s as i64,
+    );
     loop {
         match gossip_data.take_action().await {
             Err(KitsuneP2pError::GhostError(GhostError::Disconnected)) => {
@@ -78,14 +99,23 @@ struct GossipData {
     evt_send: futures::channel::mpsc::Sender<GossipEvent>,
     pending_gossip_list: Vec<(Arc<KitsuneAgent>, Arc<KitsuneAgent>)>,
     last_counts: HashMap<Arc<KitsuneAgent>, (u64, u64)>,
+    network_stats: NetworkStats,
+    /// 0 = always exchange full hash lists
+    bucket_width_s: i64,
 }
 
 impl GossipData {
-    pub fn new(evt_send: futures::channel::mpsc::Sender<GossipEvent>) -> Self {
+    pub fn new(
+        evt_send: futures::channel::mpsc::Sender<GossipEvent>,
+        network_stats: NetworkStats,
+        bucket_width_s: i64,
+    ) -> Self {
         Self {
             evt_send,
             pending_gossip_list: Vec::new(),
             last_counts: HashMap::new(),
+            network_stats,
+            bucket_width_s,
         }
     }
 
@@ -122,6 +152,14 @@ impl GossipData {
         let (from_agent, to_agent) = self.pending_gossip_list.remove(0);
         let span = tracing::debug_span!("next_gossip", ?from_agent, ?to_agent);
 
+        if self.bucket_width_s > 0
+            && self
+                .reconcile_buckets(from_agent.clone(), to_agent.clone(), &span)
+                .await?
+        {
+            return Ok(());
+        }
+
         // Get the last count for this interaction
         let last_count = self.last_counts.entry(to_agent.clone()).or_insert((0, 0));
 
@@ -182,11 +220,15 @@ impl GossipData {
         let op_hashes_to = match op_hashes_to {
             OpConsistency::Variance(h) => {
                 last_count.1 = h.len() as u64;
+                self.network_stats
+                    .record_gossip(to_agent.clone(), last_count.0, last_count.1);
                 h
             }
             // There's no new gossip from us or them
             // so our job is done.
             OpConsistency::Consistent => {
+                self.network_stats
+                    .record_gossip(to_agent.clone(), last_count.0, last_count.1);
                 return Ok(());
             }
         };
@@ -196,6 +238,154 @@ impl GossipData {
             tracing::debug!(to_has_len = ?op_hashes_to.len());
         });
 
+        self.exchange_missing(
+            from_agent,
+            to_agent,
+            op_hashes_from,
+            agent_info_from,
+            op_hashes_to,
+            agent_info_to,
+            &span,
+        )
+        .await
+    }
+
+    /// Compare summaries of the ops each agent holds, bucketed by when
+    /// they were authored, narrowing mismatched buckets down to the bucket
+    /// width before comparing the hashes in them. Returns false, having
+    /// done nothing, if `to_agent`'s node can't summarize its ops.
+    async fn reconcile_buckets(
+        &mut self,
+        from_agent: Arc<KitsuneAgent>,
+        to_agent: Arc<KitsuneAgent>,
+        span: &tracing::Span,
+    ) -> KitsuneP2pResult<bool> {
+        // we'll just fetch all with no constraints for now
+        let dht_arc = DhtArc::new(0, u32::MAX);
+        let (op_hash_times_from, agent_info_from) = self
+            .evt_send
+            .req_local_op_hash_times(ReqLocalOpHashTimesEvt::new(from_agent.clone(), dht_arc))
+            .await?;
+        let now_s = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        // sorted once, so each level only looks at the ops in its windows
+        let timeline_from = OpHashTimeline::new(op_hash_times_from);
+        let oldest_s = timeline_from.oldest().unwrap_or(now_s);
+
+        let mut windows = TimeWindow::roots(oldest_s, now_s, self.bucket_width_s);
+        let mut mismatched = Vec::new();
+        let mut agent_info_to = None;
+        let mut remote_op_count = None;
+        while !windows.is_empty() {
+            let mut next = Vec::new();
+            // each level's windows are in order, so any chunk of them
+            // is a valid request on its own
+            for chunk in windows.chunks(MAX_SUMMARY_WINDOWS) {
+                let (buckets_to, peer_hashes) = match self
+                    .evt_send
+                    .req_op_hash_summary(ReqOpHashSummaryEvt::new(
+                        from_agent.clone(),
+                        to_agent.clone(),
+                        dht_arc,
+                        chunk.to_vec(),
+                        false,
+                    ))
+                    .await?
+                {
+                    Some(r) => r,
+                    None => return Ok(false),
+                };
+                if remote_op_count.is_none() {
+                    remote_op_count = Some(buckets_to.iter().map(|b| b.count).sum::<u64>());
+                }
+                agent_info_to.get_or_insert(peer_hashes);
+
+                let buckets_from = timeline_from.summarize(chunk, false);
+                // a node that answers for different windows can't be compared
+                if buckets_to.len() != buckets_from.len() {
+                    return Err("op hash summary has the wrong number of buckets".into());
+                }
+                for (from, to) in buckets_from.iter().zip(buckets_to.iter()) {
+                    if from.matches(to) {
+                        continue;
+                    }
+                    match from.window.split(self.bucket_width_s) {
+                        Some(narrower) => next.extend(narrower),
+                        None => mismatched.push(from.window),
+                    }
+                }
+            }
+            windows = next;
+        }
+        // open ended windows are found at the first level,
+        // so put them back in order with the narrower ones
+        mismatched.sort_by_key(|w: &TimeWindow| w.since_utc_epoch_s);
+        self.network_stats.record_gossip(
+            to_agent.clone(),
+            timeline_from.len() as u64,
+            remote_op_count.unwrap_or(0),
+        );
+        span.in_scope(|| {
+            tracing::debug!(mismatched_buckets = ?mismatched.len());
+        });
+
+        // required so from_iters below know the build_hasher type
+        type S = HashSet<Arc<KitsuneOpHash>>;
+        type A = HashSet<(Arc<KitsuneAgent>, u64)>;
+
+        let mut op_hashes_to: S = HashSet::new();
+        for chunk in mismatched.chunks(MAX_SUMMARY_WINDOWS) {
+            let (buckets_to, _) = match self
+                .evt_send
+                .req_op_hash_summary(ReqOpHashSummaryEvt::new(
+                    from_agent.clone(),
+                    to_agent.clone(),
+                    dht_arc,
+                    chunk.to_vec(),
+                    true,
+                ))
+                .await?
+            {
+                Some(r) => r,
+                None => return Ok(false),
+            };
+            op_hashes_to.extend(buckets_to.into_iter().flat_map(|b| b.op_hashes));
+        }
+        let op_hashes_from: S = mismatched
+            .iter()
+            .flat_map(|w| timeline_from.in_window(w))
+            .map(|(h, _)| h.clone())
+            .collect();
+        let agent_info_from: A = HashSet::from_iter(agent_info_from);
+        let agent_info_to: A = HashSet::from_iter(agent_info_to.unwrap_or_default());
+
+        self.exchange_missing(
+            from_agent,
+            to_agent,
+            op_hashes_from,
+            agent_info_from,
+            op_hashes_to,
+            agent_info_to,
+            span,
+        )
+        .await?;
+        Ok(true)
+    }
+
+    /// Send each agent the ops and agent info the other has that it doesn't.
+    #[allow(clippy::too_many_arguments)]
+    async fn exchange_missing(
+        &mut self,
+        from_agent: Arc<KitsuneAgent>,
+        to_agent: Arc<KitsuneAgent>,
+        op_hashes_from: HashSet<Arc<KitsuneOpHash>>,
+        agent_info_from: HashSet<(Arc<KitsuneAgent>, u64)>,
+        op_hashes_to: HashSet<Arc<KitsuneOpHash>>,
+        agent_info_to: HashSet<(Arc<KitsuneAgent>, u64)>,
+        span: &tracing::Span,
+    ) -> KitsuneP2pResult<()> {
         // values that to_agent has, and from_agent needs
         let from_needs = op_hashes_to
             .difference(&op_hashes_from)
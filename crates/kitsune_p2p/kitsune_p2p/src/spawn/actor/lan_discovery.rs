@@ -0,0 +1,299 @@
+//! Finds peers on the local network, without a bootstrap service or
+//! internet access, by multicasting the signed agent infos of our joined
+//! agents over udp. Agent infos announced by other nodes, in spaces we
+//! have joined, are handed to the host to store like any other peer,
+//! if they are signed by their agent and newer than the one stored.
+
+use crate::actor::KitsuneP2pResult;
+use crate::event::*;
+use crate::types::agent_store::AgentInfo;
+use crate::types::agent_store::AgentInfoSigned;
+use crate::*;
+use ghost_actor::dependencies::tracing;
+use lair_keystore_api::internal::sign_ed25519::SignEd25519PubKey;
+use lair_keystore_api::internal::sign_ed25519::SignEd25519Signature;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::net::SocketAddrV4;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+
+/// Prefixes every announcement, so we ignore anything
+/// else sent to the multicast group.
+const ANNOUNCE_MAGIC: &[u8] = b"kitsune-lan-1\0";
+
+/// Agent infos are far smaller than this,
+/// anything bigger isn't an announcement.
+const MAX_ANNOUNCE_BYTES: usize = 8 * 1024;
+
+type LocalAgentInfos = HashMap<(Arc<KitsuneSpace>, Arc<KitsuneAgent>), AgentInfoSigned>;
+
+/// Handle to the local network discovery of a kitsune node.
+/// Spaces announce the agent infos of their joined agents through it.
+#[derive(Clone)]
+pub(crate) struct LanDiscovery {
+    local: Arc<Mutex<LocalAgentInfos>>,
+    send: Arc<tokio::sync::Mutex<tokio::net::udp::SendHalf>>,
+    group: SocketAddr,
+}
+
+impl LanDiscovery {
+    /// Announce a freshly signed agent info of a local agent now,
+    /// and again every announce interval until the agent leaves.
+    pub async fn announce(&self, agent_info_signed: AgentInfoSigned) -> KitsuneP2pResult<()> {
+        let info = AgentInfo::try_from(&agent_info_signed)?;
+        let key = (
+            Arc::new(info.as_space_ref().clone()),
+            Arc::new(info.as_agent_ref().clone()),
+        );
+        self.local
+            .lock()
+            .unwrap()
+            .insert(key, agent_info_signed.clone());
+        send_announce(&self.send, &self.group, &agent_info_signed).await
+    }
+
+    /// Stop announcing an agent that has left a space.
+    pub fn withdraw(&self, space: &Arc<KitsuneSpace>, agent: &Arc<KitsuneAgent>) {
+        self.local
+            .lock()
+            .unwrap()
+            .remove(&(space.clone(), agent.clone()));
+    }
+}
+
+/// Join the configured multicast group and start announcing and listening.
+pub(crate) async fn spawn_lan_discovery(
+    config: LanDiscoveryConfig,
+    evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+) -> KitsuneP2pResult<LanDiscovery> {
+    let socket = bind_multicast(&config)?;
+    let (recv, send) = tokio::net::UdpSocket::from_std(socket)?.split();
+    let lan_discovery = LanDiscovery {
+        local: Arc::new(Mutex::new(HashMap::new())),
+        send: Arc::new(tokio::sync::Mutex::new(send)),
+        group: SocketAddrV4::new(config.multicast_group, config.port).into(),
+    };
+
+    tokio::task::spawn(recv_loop(
+        recv,
+        Arc::downgrade(&lan_discovery.local),
+        evt_sender,
+    ));
+
+    tokio::task::spawn({
+        let local = Arc::downgrade(&lan_discovery.local);
+        let send = lan_discovery.send.clone();
+        let group = lan_discovery.group;
+        let interval = std::time::Duration::from_millis(config.announce_interval_ms.max(100));
+        async move {
+            loop {
+                tokio::time::delay_for(interval).await;
+                // stop once the node has shut down
+                let local = match local.upgrade() {
+                    None => break,
+                    Some(local) => local,
+                };
+                let infos = local.lock().unwrap().values().cloned().collect::<Vec<_>>();
+                for info in infos {
+                    if let Err(e) = send_announce(&send, &group, &info).await {
+                        tracing::warn!(
+                            msg = "Failed to announce agent info on the local network",
+                            ?e
+                        );
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(lan_discovery)
+}
+
+fn bind_multicast(config: &LanDiscoveryConfig) -> KitsuneP2pResult<std::net::UdpSocket> {
+    use socket2::*;
+    let socket = Socket::new(Domain::ipv4(), Type::dgram(), Some(Protocol::udp()))?;
+    // every node on this host listens on the same port
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SockAddr::from(SocketAddrV4::new(
+        Ipv4Addr::UNSPECIFIED,
+        config.port,
+    )))?;
+    socket.join_multicast_v4(&config.multicast_group, &config.interface)?;
+    socket.set_multicast_if_v4(&config.interface)?;
+    // other nodes on this host should hear us too
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket.into_udp_socket())
+}
+
+async fn send_announce(
+    send: &tokio::sync::Mutex<tokio::net::udp::SendHalf>,
+    group: &SocketAddr,
+    agent_info_signed: &AgentInfoSigned,
+) -> KitsuneP2pResult<()> {
+    let mut data = ANNOUNCE_MAGIC.to_vec();
+    kitsune_p2p_types::codec::rmp_encode(&mut data, agent_info_signed)?;
+    send.lock().await.send_to(&data, group).await?;
+    Ok(())
+}
+
+fn decode_announce(data: &[u8]) -> Option<(AgentInfoSigned, AgentInfo)> {
+    if !data.starts_with(ANNOUNCE_MAGIC) {
+        return None;
+    }
+    let signed: AgentInfoSigned =
+        kitsune_p2p_types::codec::rmp_decode(&mut &data[ANNOUNCE_MAGIC.len()..]).ok()?;
+    let info = AgentInfo::try_from(&signed).ok()?;
+    Some((signed, info))
+}
+
+/// True if the agent info was signed by the agent it is for.
+async fn verify_announce(signed: &AgentInfoSigned, info: &AgentInfo) -> bool {
+    if info.as_agent_ref() != signed.as_agent_ref() {
+        return false;
+    }
+    let pub_key: SignEd25519PubKey = signed.as_agent_ref().get_bytes().to_vec().into();
+    let signature: SignEd25519Signature = signed.as_signature_ref().0.clone().into();
+    pub_key
+        .verify(Arc::new(signed.as_agent_info_ref().to_vec()), signature)
+        .await
+        .unwrap_or(false)
+}
+
+async fn recv_loop(
+    mut recv: tokio::net::udp::RecvHalf,
+    local: Weak<Mutex<LocalAgentInfos>>,
+    evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+) {
+    let mut buf = vec![0; MAX_ANNOUNCE_BYTES];
+    loop {
+        let len = match recv.recv_from(&mut buf).await {
+            Err(e) => {
+                tracing::warn!(msg = "Local network discovery stopped", ?e);
+                return;
+            }
+            Ok((len, _)) => len,
+        };
+        let (signed, info) = match decode_announce(&buf[..len]) {
+            None => continue,
+            Some(r) => r,
+        };
+        let space = Arc::new(info.as_space_ref().clone());
+        let agent = Arc::new(info.as_agent_ref().clone());
+        let local_agent = {
+            let local = match local.upgrade() {
+                None => return,
+                Some(local) => local,
+            };
+            let local = local.lock().unwrap();
+            // our own announcements come back to us
+            if local.contains_key(&(space.clone(), agent)) {
+                continue;
+            }
+            // only store peers of spaces we have joined
+            match local.keys().find(|(s, _)| *s == space) {
+                None => continue,
+                Some((_, a)) => a.clone(),
+            }
+        };
+        match super::bootstrap::now_once(None).await {
+            Ok(now) if info.signed_at_ms() + info.expires_after_ms() > now => (),
+            _ => continue,
+        }
+        if !verify_announce(&signed, &info).await {
+            tracing::debug!(msg = "Ignoring badly signed agent info from the local network");
+            continue;
+        }
+        // peers announce the same info every interval,
+        // only an info signed since the stored one replaces it
+        match evt_sender
+            .get_agent_info_signed(GetAgentInfoSignedEvt {
+                space: space.clone(),
+                agent: Arc::new(info.as_agent_ref().clone()),
+            })
+            .await
+        {
+            Ok(Some(stored)) => match AgentInfo::try_from(&stored) {
+                Ok(stored) if stored.signed_at_ms() >= info.signed_at_ms() => continue,
+                _ => (),
+            },
+            Ok(None) => (),
+            Err(e) => {
+                tracing::warn!(
+                    msg = "Failed to get stored agent info for the local network",
+                    ?e
+                );
+                continue;
+            }
+        }
+        if let Err(e) = evt_sender
+            .put_agent_info_signed(PutAgentInfoSignedEvt {
+                space,
+                agent: local_agent,
+                agent_info_signed: signed,
+            })
+            .await
+        {
+            tracing::warn!(
+                msg = "Failed to store agent info from the local network",
+                ?e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_other_multicast_traffic() {
+        assert!(decode_announce(b"").is_none());
+        assert!(decode_announce(b"M-SEARCH * HTTP/1.1\r\n").is_none());
+        let mut data = ANNOUNCE_MAGIC.to_vec();
+        data.extend_from_slice(&[0xc1, 0x00, 0x01]);
+        assert!(decode_announce(&data).is_none());
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn ignores_badly_signed_announcements() {
+        use crate::types::KitsuneSignature;
+        use lair_keystore_api::internal::sign_ed25519::sign_ed25519_keypair_new_from_entropy;
+
+        let keypair = sign_ed25519_keypair_new_from_entropy().await.unwrap();
+        let agent = KitsuneAgent::new((*keypair.pub_key.0).clone());
+        let info = AgentInfo::new(
+            KitsuneSpace::new(vec![1; 36]),
+            agent.clone(),
+            vec![url2::url2!("kitsune-proxy://test")],
+            1,
+            1000,
+        );
+        let mut data = Vec::new();
+        kitsune_p2p_types::codec::rmp_encode(&mut data, &info).unwrap();
+        let signature = keypair.sign(Arc::new(data.clone())).await.unwrap();
+        let signature = KitsuneSignature((*signature.0).clone());
+
+        let signed =
+            AgentInfoSigned::try_new(agent.clone(), signature.clone(), data.clone()).unwrap();
+        assert!(verify_announce(&signed, &info).await);
+
+        // signed by the agent, but not this info
+        let mut tampered = data.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        let signed = AgentInfoSigned::try_new(agent, signature.clone(), tampered).unwrap();
+        assert!(!verify_announce(&signed, &info).await);
+
+        // claimed by an agent that didn't sign it
+        let other = KitsuneAgent::new(vec![2; 32]);
+        let signed = AgentInfoSigned::try_new(other, signature, data).unwrap();
+        assert!(!verify_announce(&signed, &info).await);
+    }
+}
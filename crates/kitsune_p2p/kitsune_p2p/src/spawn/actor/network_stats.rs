@@ -0,0 +1,193 @@
+//! Records the gossip rounds and requests of a space, so they can be
+//! reported by `KitsuneP2p::space_stats`. Shared between the space actor,
+//! the tasks it spawns and its gossip loop. Agents we haven't heard
+//! from in an hour are forgotten.
+
+use crate::actor::GossipPartnerStats;
+use crate::actor::RequestStats;
+use crate::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Agents we haven't gossiped with or made requests of for this long
+/// are dropped from the stats.
+const STATS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How a request of a remote agent turned out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RequestOutcome {
+    Succeeded,
+    TimedOut,
+    Failed,
+}
+
+struct Inner {
+    gossip: HashMap<Arc<KitsuneAgent>, (GossipPartnerStats, Instant)>,
+    requests: HashMap<Arc<KitsuneAgent>, (RequestStats, Instant)>,
+    pruned_at: Instant,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self {
+            gossip: HashMap::new(),
+            requests: HashMap::new(),
+            pruned_at: Instant::now(),
+        }
+    }
+}
+
+impl Inner {
+    /// Drop the agents we haven't heard from within the ttl,
+    /// checking at most once a minute.
+    fn prune(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.pruned_at) < Duration::from_secs(60) {
+            return;
+        }
+        self.pruned_at = now;
+        let live = |at: &Instant| now.saturating_duration_since(*at) < STATS_TTL;
+        self.gossip.retain(|_, (_, at)| live(at));
+        self.requests.retain(|_, (_, at)| live(at));
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct NetworkStats(Arc<Mutex<Inner>>);
+
+impl NetworkStats {
+    /// Note a comparison of op hashes with `agent`.
+    pub(crate) fn record_gossip(
+        &self,
+        agent: Arc<KitsuneAgent>,
+        local_op_count: u64,
+        remote_op_count: u64,
+    ) {
+        let last_gossip_at_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let now = Instant::now();
+        let mut inner = self.0.lock().unwrap();
+        inner.prune(now);
+        inner.gossip.insert(
+            agent.clone(),
+            (
+                GossipPartnerStats {
+                    agent,
+                    last_gossip_at_ms,
+                    local_op_count,
+                    remote_op_count,
+                },
+                now,
+            ),
+        );
+    }
+
+    /// Note how a request of `agent` turned out.
+    pub(crate) fn record_request(&self, agent: Arc<KitsuneAgent>, outcome: RequestOutcome) {
+        let now = Instant::now();
+        let mut inner = self.0.lock().unwrap();
+        inner.prune(now);
+        let (stats, at) = inner
+            .requests
+            .entry(agent)
+            .or_insert_with(|| (RequestStats::default(), now));
+        *at = now;
+        match outcome {
+            RequestOutcome::Succeeded => stats.succeeded += 1,
+            RequestOutcome::TimedOut => stats.timed_out += 1,
+            RequestOutcome::Failed => stats.failed += 1,
+        }
+    }
+
+    /// Note how a request of `agent` turned out from its result.
+    pub(crate) fn record_result<T>(&self, agent: Arc<KitsuneAgent>, res: &KitsuneP2pResult<T>) {
+        self.record_request(
+            agent,
+            match res {
+                Ok(_) => RequestOutcome::Succeeded,
+                Err(_) => RequestOutcome::Failed,
+            },
+        );
+    }
+
+    /// The agents we have gossiped with, most recent first.
+    pub(crate) fn gossip_partners(&self) -> Vec<GossipPartnerStats> {
+        let mut out = self
+            .0
+            .lock()
+            .unwrap()
+            .gossip
+            .values()
+            .map(|(stats, _)| stats.clone())
+            .collect::<Vec<_>>();
+        out.sort_by(|a, b| b.last_gossip_at_ms.cmp(&a.last_gossip_at_ms));
+        out
+    }
+
+    /// Request outcomes for each agent we have made requests of.
+    pub(crate) fn requests(&self) -> HashMap<Arc<KitsuneAgent>, RequestStats> {
+        self.0
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .map(|(agent, (stats, _))| (agent.clone(), *stats))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::KitsuneBinType;
+
+    #[test]
+    fn records_requests_per_agent() {
+        let stats = NetworkStats::default();
+        let a = Arc::new(KitsuneAgent::new(vec![1; 36]));
+        let b = Arc::new(KitsuneAgent::new(vec![2; 36]));
+        stats.record_request(a.clone(), RequestOutcome::Succeeded);
+        stats.record_request(a.clone(), RequestOutcome::TimedOut);
+        stats.record_result::<()>(b.clone(), &Err("nope".into()));
+        stats.record_gossip(a.clone(), 3, 4);
+
+        let requests = stats.requests();
+        assert_eq!(
+            RequestStats {
+                succeeded: 1,
+                timed_out: 1,
+                failed: 0,
+            },
+            requests[&a]
+        );
+        assert_eq!(1, requests[&b].failed);
+
+        let gossip = stats.gossip_partners();
+        assert_eq!(1, gossip.len());
+        assert_eq!(
+            (3, 4),
+            (gossip[0].local_op_count, gossip[0].remote_op_count)
+        );
+    }
+
+    #[test]
+    fn prunes_agents_past_the_ttl() {
+        let stats = NetworkStats::default();
+        let a = Arc::new(KitsuneAgent::new(vec![1; 36]));
+        stats.record_request(a.clone(), RequestOutcome::Succeeded);
+        stats.record_gossip(a, 3, 4);
+
+        let now = Instant::now();
+        stats.0.lock().unwrap().prune(now + STATS_TTL / 2);
+        assert_eq!(1, stats.requests().len());
+        assert_eq!(1, stats.gossip_partners().len());
+
+        stats.0.lock().unwrap().prune(now + STATS_TTL * 2);
+        assert!(stats.requests().is_empty());
+        assert!(stats.gossip_partners().is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+//! Scores remote agents by how they have answered our requests, so
+//! discovery can prefer good peers and temporarily block bad ones.
+//! One set of scores is shared by all the spaces of a node. Scores decay
+//! back toward zero so old behaviour is forgiven, and agents whose score
+//! has decayed away are forgotten.
+
+use crate::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// A score halves toward zero over this long.
+const SCORE_HALF_LIFE: Duration = Duration::from_secs(60 * 10);
+
+/// Good behaviour can't bank more credit than this.
+const MAX_SCORE: f64 = 10.0;
+
+/// Responses slower than this earn nothing.
+const SLOW_RESPONSE: Duration = Duration::from_secs(2);
+
+/// Scores closer to zero than this are forgotten.
+const FORGET_SCORE: f64 = 0.01;
+
+/// How often to look for scores to forget.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Something a remote agent did that affects its score.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PeerEvent {
+    /// Answered a request after this long.
+    Responded(Duration),
+    /// Didn't answer a request within its timeout.
+    TimedOut,
+    /// Couldn't be reached, or answered with a failure.
+    Failed,
+    /// Answered with something we couldn't decode.
+    BadResponse,
+    /// Sent data that failed validation.
+    InvalidData,
+    /// Refused a request as it is busy serving that type of request.
+    /// The host backs off from the agent for that type, see
+    /// [crate::request_quota::RequestBackoff].
+    Busy,
+    /// Got a request, but refused it or its handler failed, e.g. a zome
+    /// call it isn't allowed to make. The agent is there and answering.
+    Rejected,
+}
+
+impl PeerEvent {
+    fn score(&self) -> f64 {
+        match self {
+            // faster responses earn more
+            PeerEvent::Responded(latency) => {
+                1.0 - (latency.as_secs_f64() / SLOW_RESPONSE.as_secs_f64()).min(1.0)
+            }
+            PeerEvent::TimedOut => -2.0,
+            PeerEvent::Failed => -1.0,
+            PeerEvent::BadResponse => -4.0,
+            PeerEvent::InvalidData => -10.0,
+            // it told us to back off, which is better than timing out
+            PeerEvent::Busy => 0.0,
+            // the request was the problem, not the agent
+            PeerEvent::Rejected => 0.0,
+        }
+    }
+}
+
+struct Score {
+    score: f64,
+    updated_at: Instant,
+    blocked_until: Option<Instant>,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            score: 0.0,
+            updated_at: Instant::now(),
+            blocked_until: None,
+        }
+    }
+}
+
+impl Score {
+    fn decayed(&self, now: Instant) -> f64 {
+        let half_lives =
+            now.duration_since(self.updated_at).as_secs_f64() / SCORE_HALF_LIFE.as_secs_f64();
+        self.score * 0.5f64.powf(half_lives)
+    }
+
+    fn is_blocked(&self, now: Instant) -> bool {
+        matches!(self.blocked_until, Some(until) if until > now)
+    }
+}
+
+struct Scores {
+    scores: HashMap<Arc<KitsuneAgent>, Score>,
+    pruned_at: Instant,
+}
+
+impl Scores {
+    /// Forget the agents that aren't blocked and whose score
+    /// has decayed to about zero, checking at most once a minute.
+    fn prune(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.pruned_at) < PRUNE_INTERVAL {
+            return;
+        }
+        self.pruned_at = now;
+        self.scores
+            .retain(|_, s| s.is_blocked(now) || s.decayed(now).abs() >= FORGET_SCORE);
+    }
+}
+
+/// The scores of remote agents, shared by all the spaces of a node
+/// so that requests from a blocked agent are refused as they come in.
+#[derive(Clone)]
+pub(crate) struct PeerReputation {
+    scores: Arc<Mutex<Scores>>,
+    block_score: f64,
+    block_duration: Duration,
+}
+
+impl PeerReputation {
+    /// Agents are blocked for `block_duration_ms` when their score
+    /// falls to `-block_score_threshold`, or never if that is 0.
+    pub(crate) fn new(block_score_threshold: u32, block_duration_ms: u64) -> Self {
+        Self {
+            scores: Arc::new(Mutex::new(Scores {
+                scores: HashMap::new(),
+                pruned_at: Instant::now(),
+            })),
+            block_score: -(block_score_threshold as f64),
+            block_duration: Duration::from_millis(block_duration_ms),
+        }
+    }
+
+    /// Note something an agent did, blocking it if its score gets too low.
+    pub(crate) fn record(&self, agent: Arc<KitsuneAgent>, event: PeerEvent) {
+        let now = Instant::now();
+        let mut scores = self.scores.lock().unwrap();
+        scores.prune(now);
+        let score = scores.scores.entry(agent).or_default();
+        score.score = (score.decayed(now) + event.score()).min(MAX_SCORE);
+        score.updated_at = now;
+        if self.block_score < 0.0 && score.score <= self.block_score && !score.is_blocked(now) {
+            // start over once the block ends
+            score.score = 0.0;
+            score.blocked_until = Some(now + self.block_duration);
+        }
+    }
+
+    /// Block an agent for this long, or the configured duration if None.
+    pub(crate) fn block(&self, agent: Arc<KitsuneAgent>, duration: Option<Duration>) {
+        let now = Instant::now();
+        let until = now + duration.unwrap_or(self.block_duration);
+        let mut scores = self.scores.lock().unwrap();
+        scores.prune(now);
+        scores.scores.entry(agent).or_default().blocked_until = Some(until);
+    }
+
+    /// Lift any block on an agent.
+    pub(crate) fn unblock(&self, agent: &Arc<KitsuneAgent>) {
+        if let Some(score) = self.scores.lock().unwrap().scores.get_mut(agent) {
+            score.blocked_until = None;
+        }
+    }
+
+    /// Whether an agent is currently blocked.
+    pub(crate) fn is_blocked(&self, agent: &KitsuneAgent) -> bool {
+        let now = Instant::now();
+        self.scores
+            .lock()
+            .unwrap()
+            .scores
+            .get(agent)
+            .map(|s| s.is_blocked(now))
+            .unwrap_or(false)
+    }
+
+    /// An agent's current score, 0 if we know nothing about it.
+    pub(crate) fn score(&self, agent: &KitsuneAgent) -> f64 {
+        let now = Instant::now();
+        self.scores
+            .lock()
+            .unwrap()
+            .scores
+            .get(agent)
+            .map(|s| s.decayed(now))
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::KitsuneBinType;
+
+    #[test]
+    fn scores_and_blocks_peers() {
+        let reputation = PeerReputation::new(5, 60_000);
+        let good = Arc::new(KitsuneAgent::new(vec![1; 36]));
+        let bad = Arc::new(KitsuneAgent::new(vec![2; 36]));
+
+        reputation.record(
+            good.clone(),
+            PeerEvent::Responded(Duration::from_millis(10)),
+        );
+        reputation.record(bad.clone(), PeerEvent::TimedOut);
+        assert!(reputation.score(&good) > 0.0);
+        assert!(reputation.score(&bad) < 0.0);
+        assert!(!reputation.is_blocked(&bad));
+
+        reputation.record(bad.clone(), PeerEvent::BadResponse);
+        assert!(reputation.is_blocked(&bad));
+        reputation.unblock(&bad);
+        assert!(!reputation.is_blocked(&bad));
+
+        reputation.block(good.clone(), None);
+        assert!(reputation.is_blocked(&good));
+        reputation.block(good.clone(), Some(Duration::from_millis(0)));
+        assert!(!reputation.is_blocked(&good));
+    }
+
+    #[test]
+    fn busy_and_rejecting_peers_are_not_penalized() {
+        let reputation = PeerReputation::new(5, 60_000);
+        let busy = Arc::new(KitsuneAgent::new(vec![3; 36]));
+        for event in &[PeerEvent::Busy, PeerEvent::Rejected] {
+            for _ in 0..10 {
+                reputation.record(busy.clone(), *event);
+            }
+        }
+        assert!(!reputation.is_blocked(&busy));
+        assert_eq!(0.0, reputation.score(&busy));
+    }
+
+    #[test]
+    fn threshold_zero_never_blocks() {
+        let reputation = PeerReputation::new(0, 60_000);
+        let bad = Arc::new(KitsuneAgent::new(vec![2; 36]));
+        for _ in 0..10 {
+            reputation.record(bad.clone(), PeerEvent::InvalidData);
+        }
+        assert!(!reputation.is_blocked(&bad));
+    }
+
+    #[test]
+    fn forgets_decayed_scores() {
+        let reputation = PeerReputation::new(5, 60_000);
+        let good = Arc::new(KitsuneAgent::new(vec![1; 36]));
+        let blocked = Arc::new(KitsuneAgent::new(vec![2; 36]));
+        reputation.record(good, PeerEvent::Responded(Duration::from_millis(10)));
+        reputation.block(blocked, Some(SCORE_HALF_LIFE * 100));
+
+        let now = Instant::now();
+        let mut scores = reputation.scores.lock().unwrap();
+        scores.prune(now + SCORE_HALF_LIFE);
+        assert_eq!(2, scores.scores.len());
+
+        // the good score has decayed away, the block hasn't ended
+        scores.prune(now + SCORE_HALF_LIFE * 20);
+        assert_eq!(1, scores.scores.len());
+    }
+}
@@ -0,0 +1,515 @@
+//! Stream large call responses in chunks, see [wire::Wire::CallStream].
+//! The remote holds on to the response while the requester asks for
+//! each chunk with a [wire::Wire::StreamNext], keeping a few requests in
+//! flight. No message carries more than a chunk, and the remote only
+//! sends as fast as the requester asks.
+//! Only the node that made the request can ask for its chunks, each chunk
+//! is sent once, and the asks count against that node's request quota.
+//! The requester in turn won't fetch more than a remote could have held
+//! for it, nor chunks that don't match the first.
+
+use super::wire_handshake::WireProtocols;
+use crate::actor::KitsuneP2pResult;
+use crate::metrics::KitsuneMetrics;
+use crate::request_quota::RequestQuotas;
+use crate::wire;
+use crate::*;
+use futures::stream::Stream;
+use futures::stream::StreamExt;
+use kitsune_p2p_types::codec::Codec;
+use kitsune_p2p_types::transport::*;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Responses nobody has asked for a chunk of in this long are dropped.
+const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// We hold at most this many responses for requesters at once.
+const MAX_OUTGOING_STREAMS: usize = 256;
+
+/// We hold at most this many bytes of responses for requesters at once.
+const MAX_HELD_BYTES: usize = 256 * 1024 * 1024;
+
+/// We hold at most this many bytes of responses for any one node,
+/// bigger responses can't be streamed.
+const MAX_HELD_BYTES_PER_NODE: usize = 64 * 1024 * 1024;
+
+/// How long to ask a requester to wait when we hold too many responses.
+const STREAMS_FULL_RETRY_AFTER_MS: u64 = 1000;
+
+/// The request type [wire::Wire::StreamNext] counts against in the quotas.
+pub(crate) const STREAM_NEXT_REQUEST_TYPE: &str = "stream_next";
+
+/// Requesters can't make us cut responses up smaller than this.
+const MIN_CHUNK_SIZE: usize = 1024;
+
+/// We accept at most this many bytes of a response streamed to us,
+/// as no node holds more than this for us.
+const MAX_STREAMED_BYTES: usize = MAX_HELD_BYTES_PER_NODE;
+
+struct OutgoingStream {
+    node: url2::Url2,
+    data: Vec<u8>,
+    chunk_size: usize,
+    unsent: HashSet<u32>,
+    last_request_at: Instant,
+}
+
+impl OutgoingStream {
+    fn chunk_count(&self) -> u32 {
+        chunk_count(self.data.len(), self.chunk_size)
+    }
+
+    fn chunk(&self, seq: u32) -> &[u8] {
+        let start = seq as usize * self.chunk_size;
+        let end = (start + self.chunk_size).min(self.data.len());
+        &self.data[start..end]
+    }
+}
+
+fn chunk_count(len: usize, chunk_size: usize) -> u32 {
+    // an empty response is still one (empty) chunk
+    ((len + chunk_size - 1) / chunk_size).max(1) as u32
+}
+
+/// The responses we are streaming to requesters, by stream id.
+#[derive(Clone)]
+pub(crate) struct OutgoingStreams {
+    streams: Arc<Mutex<HashMap<u64, OutgoingStream>>>,
+    quotas: RequestQuotas,
+    wire_protocols: WireProtocols,
+}
+
+impl OutgoingStreams {
+    /// Asks for chunks are limited by the `peer_request_*` tuning params,
+    /// as the `stream_next` request type.
+    pub(crate) fn new(quotas: RequestQuotas, wire_protocols: WireProtocols) -> Self {
+        Self {
+            streams: Arc::new(Mutex::new(HashMap::new())),
+            quotas,
+            wire_protocols,
+        }
+    }
+
+    /// The response to send a node when we're too busy to serve it.
+    fn busy(&self, node: &url2::Url2, err: &KitsuneP2pError) -> wire::Wire {
+        let failure_kinds = self
+            .wire_protocols
+            .has_feature(node, wire::WIRE_FEATURE_FAILURE_KINDS);
+        wire::Wire::handler_failure(err, failure_kinds)
+    }
+
+    /// Respond to a [wire::Wire::CallStream] from the node at `node` with
+    /// the first chunk of `data`, holding on to the rest until it asks.
+    pub(crate) fn start(&self, node: url2::Url2, data: Vec<u8>, chunk_size: u32) -> wire::Wire {
+        let chunk_size = (chunk_size as usize).max(MIN_CHUNK_SIZE);
+        let count = chunk_count(data.len(), chunk_size);
+        if count == 1 {
+            return wire::Wire::stream_chunk(0, 0, 1, data.into());
+        }
+        if data.len() > MAX_HELD_BYTES_PER_NODE {
+            return wire::Wire::failure(format!(
+                "response of {} bytes is too large to stream",
+                data.len()
+            ));
+        }
+
+        let now = Instant::now();
+        let mut streams = self.streams.lock().unwrap();
+        streams.retain(|_, s| now.duration_since(s.last_request_at) < STREAM_IDLE_TIMEOUT);
+        let held_bytes: usize = streams.values().map(|s| s.data.len()).sum();
+        let node_held_bytes: usize = streams
+            .values()
+            .filter(|s| s.node == node)
+            .map(|s| s.data.len())
+            .sum();
+        if streams.len() >= MAX_OUTGOING_STREAMS
+            || held_bytes + data.len() > MAX_HELD_BYTES
+            || node_held_bytes + data.len() > MAX_HELD_BYTES_PER_NODE
+        {
+            return self.busy(
+                &node,
+                &KitsuneP2pError::Busy {
+                    retry_after_ms: STREAMS_FULL_RETRY_AFTER_MS,
+                },
+            );
+        }
+
+        let mut stream_id = rand::random();
+        while stream_id == 0 || streams.contains_key(&stream_id) {
+            stream_id = rand::random();
+        }
+        let stream = OutgoingStream {
+            node,
+            data,
+            chunk_size,
+            unsent: (1..count).collect(),
+            last_request_at: now,
+        };
+        let first = wire::Wire::stream_chunk(stream_id, 0, count, stream.chunk(0).to_vec().into());
+        streams.insert(stream_id, stream);
+        first
+    }
+
+    /// Respond to a [wire::Wire::StreamNext] from the node at `node`.
+    /// The response is dropped once every chunk has been sent.
+    pub(crate) fn next(&self, node: url2::Url2, stream_id: u64, seq: u32) -> wire::Wire {
+        // served at once, so the permit is only held for the rate limit
+        if let Err(e) = self
+            .quotas
+            .try_start(node.clone(), STREAM_NEXT_REQUEST_TYPE)
+        {
+            return self.busy(&node, &e);
+        }
+        let mut streams = self.streams.lock().unwrap();
+        let stream = match streams.get_mut(&stream_id) {
+            // the stream id of another node's response is as good as unknown
+            Some(stream) if stream.node == node => stream,
+            _ => return wire::Wire::failure("unknown or expired stream".to_string()),
+        };
+        let count = stream.chunk_count();
+        if seq >= count {
+            return wire::Wire::failure(format!("no chunk {} of {}", seq, count));
+        }
+        if !stream.unsent.contains(&seq) {
+            return wire::Wire::failure(format!("chunk {} was already sent", seq));
+        }
+        stream.last_request_at = Instant::now();
+        stream.unsent.remove(&seq);
+        let chunk =
+            wire::Wire::stream_chunk(stream_id, seq, count, stream.chunk(seq).to_vec().into());
+        if stream.unsent.is_empty() {
+            streams.remove(&stream_id);
+        }
+        chunk
+    }
+}
+
+/// Checks the chunks a remote streams to us against what it could
+/// have sent for our request, so it can't make us fetch more.
+struct ChunkCheck {
+    chunk_count: u32,
+    chunk_size: usize,
+    remaining_bytes: usize,
+}
+
+impl ChunkCheck {
+    /// Check the first chunk of a response we asked to have cut up
+    /// into `chunk_size` chunks.
+    fn new(first: &wire::StreamChunk, chunk_size: u32) -> KitsuneP2pResult<Self> {
+        // the remote raises the chunk size the same way
+        let chunk_size = (chunk_size as usize).max(MIN_CHUNK_SIZE);
+        let max_chunk_count = chunk_count(MAX_STREAMED_BYTES, chunk_size);
+        if first.chunk_count == 0 || first.chunk_count > max_chunk_count {
+            return Err(format!(
+                "streamed response of {} chunks, at most {} allowed",
+                first.chunk_count, max_chunk_count
+            )
+            .into());
+        }
+        let mut check = Self {
+            chunk_count: first.chunk_count,
+            chunk_size,
+            remaining_bytes: MAX_STREAMED_BYTES,
+        };
+        check.check(first)?;
+        Ok(check)
+    }
+
+    fn check(&mut self, chunk: &wire::StreamChunk) -> KitsuneP2pResult<()> {
+        if chunk.chunk_count != self.chunk_count {
+            return Err(format!(
+                "chunk {} is one of {} chunks, the first chunk said {}",
+                chunk.seq, chunk.chunk_count, self.chunk_count
+            )
+            .into());
+        }
+        let len = chunk.data.0.len();
+        if len > self.chunk_size || len > self.remaining_bytes {
+            return Err(format!("chunk {} of {} bytes is too large", chunk.seq, len).into());
+        }
+        self.remaining_bytes -= len;
+        Ok(())
+    }
+}
+
+/// The chunks of a streamed response from the node at `url`, starting
+/// with `first`, which must be chunk 0 of a response we asked to have cut
+/// up into `chunk_size` chunks. Fails if the response is too large, and
+/// the stream ends at the first error, e.g. a chunk that doesn't match.
+/// See [fetch_chunks] for `window` and `timeout`.
+pub(crate) fn receive_chunks(
+    transport: ghost_actor::GhostSender<TransportListener>,
+    url: url2::Url2,
+    first: wire::StreamChunk,
+    chunk_size: u32,
+    window: u32,
+    timeout: Duration,
+) -> KitsuneP2pResult<impl Stream<Item = KitsuneP2pResult<Vec<u8>>> + Send + 'static> {
+    let check = Arc::new(Mutex::new(ChunkCheck::new(&first, chunk_size)?));
+    let rest = fetch_chunks(
+        transport,
+        url,
+        first.stream_id,
+        first.chunk_count,
+        window,
+        timeout,
+        check,
+    );
+    Ok(futures::stream::once(async move { Ok(first.data.into()) })
+        .chain(rest)
+        // end the stream at the first error
+        .scan(false, |failed, res: KitsuneP2pResult<Vec<u8>>| {
+            let out = if *failed { None } else { Some(res) };
+            *failed = out.as_ref().map(|r| r.is_err()).unwrap_or(true);
+            async move { out }
+        }))
+}
+
+/// Fetch chunks `1..chunk_count` of a streamed response from the node at
+/// `url`, in order, with up to `window` requests in flight.
+/// Each request times out after `timeout`, a chunk the node is too busy
+/// to send is asked for again once it says to, within the same timeout.
+fn fetch_chunks(
+    transport: ghost_actor::GhostSender<TransportListener>,
+    url: url2::Url2,
+    stream_id: u64,
+    chunk_count: u32,
+    window: u32,
+    timeout: Duration,
+    check: Arc<Mutex<ChunkCheck>>,
+) -> impl Stream<Item = KitsuneP2pResult<Vec<u8>>> + Send + 'static {
+    futures::stream::iter(1..chunk_count)
+        .map(move |seq| {
+            let transport = transport.clone();
+            let url = url.clone();
+            let check = check.clone();
+            async move {
+                let msg = wire::Wire::stream_next(stream_id, seq).encode_vec()?;
+                let deadline = Instant::now() + timeout;
+                loop {
+                    KitsuneMetrics::count(KitsuneMetrics::StreamNext, msg.len());
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    let res = tokio::time::timeout(
+                        remaining,
+                        transport.request(url.clone(), msg.clone()),
+                    )
+                    .await
+                    .map_err(|_| KitsuneP2pError::from("timed out fetching a stream chunk"))??;
+                    let err = match wire::Wire::decode_ref(&res)?.1 {
+                        wire::Wire::StreamChunk(chunk)
+                            if chunk.stream_id == stream_id && chunk.seq == seq =>
+                        {
+                            check.lock().unwrap().check(&chunk)?;
+                            return Ok(chunk.data.into());
+                        }
+                        wire::Wire::Failure(failure) => failure.into_error(),
+                        wire::Wire::Busy(busy) => busy.into_error(),
+                        wire::Wire::Rejected(rejected) => rejected.into_error(),
+                        r => return Err(format!("invalid response: {:?}", r).into()),
+                    };
+                    match err {
+                        KitsuneP2pError::Busy { retry_after_ms }
+                            if Duration::from_millis(retry_after_ms) < remaining =>
+                        {
+                            tokio::time::delay_for(Duration::from_millis(retry_after_ms)).await;
+                        }
+                        err => return Err(err),
+                    }
+                }
+            }
+        })
+        .buffered(window.max(1) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::KitsuneP2pTuningParams;
+    use kitsune_p2p_types::transport_mem::spawn_bind_transport_mem;
+
+    fn streams() -> OutgoingStreams {
+        let wire_protocols = WireProtocols::new(60_000);
+        for name in &["a", "b"] {
+            wire_protocols.remote_hello(
+                node(name),
+                wire::WireProtocol::negotiate(
+                    wire::WIRE_PROTOCOL_VERSION,
+                    wire::WIRE_MIN_PROTOCOL_VERSION,
+                    &[wire::WIRE_FEATURE_FAILURE_KINDS.to_string()],
+                )
+                .unwrap(),
+            );
+        }
+        OutgoingStreams::new(
+            RequestQuotas::new(KitsuneP2pTuningParams::default()),
+            wire_protocols,
+        )
+    }
+
+    fn node(name: &str) -> url2::Url2 {
+        url2::url2!("kitsune-proxy://{}", name)
+    }
+
+    fn chunk(msg: wire::Wire) -> wire::StreamChunk {
+        match msg {
+            wire::Wire::StreamChunk(chunk) => chunk,
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn small_responses_are_not_held() {
+        let streams = streams();
+        let first = chunk(streams.start(node("a"), vec![1; 10], 4096));
+        assert_eq!((0, 0, 1), (first.stream_id, first.seq, first.chunk_count));
+        assert_eq!(vec![1; 10], first.data.0);
+        assert!(streams.streams.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn chunks_are_served_until_all_sent() {
+        let streams = streams();
+        let data: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+        let first = chunk(streams.start(node("a"), data.clone(), 2048));
+        assert_eq!(3, first.chunk_count);
+        let id = first.stream_id;
+
+        // chunks can be asked for out of order, but only once
+        let third = chunk(streams.next(node("a"), id, 2));
+        assert!(matches!(
+            streams.next(node("a"), id, 2),
+            wire::Wire::Failure(_)
+        ));
+        let second = chunk(streams.next(node("a"), id, 1));
+        assert_eq!(data, [first.data.0, second.data.0, third.data.0].concat());
+
+        // dropped once everything has been sent
+        assert!(matches!(
+            streams.next(node("a"), id, 1),
+            wire::Wire::Failure(_)
+        ));
+        assert!(streams.streams.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn bad_chunk_requests_fail() {
+        let streams = streams();
+        let first = chunk(streams.start(node("a"), vec![0; 3000], 1));
+        // the chunk size was raised to the minimum
+        assert_eq!(3, first.chunk_count);
+        assert!(matches!(
+            streams.next(node("a"), first.stream_id, 3),
+            wire::Wire::Failure(_)
+        ));
+        assert!(matches!(
+            streams.next(node("a"), first.stream_id.wrapping_add(1), 1),
+            wire::Wire::Failure(_)
+        ));
+        // only the node that asked can fetch the chunks
+        assert!(matches!(
+            streams.next(node("b"), first.stream_id, 1),
+            wire::Wire::Failure(_)
+        ));
+    }
+
+    #[test]
+    fn held_bytes_are_limited() {
+        let streams = streams();
+        let too_big = vec![0; MAX_HELD_BYTES_PER_NODE + 1];
+        assert!(matches!(
+            streams.start(node("a"), too_big, 64 * 1024),
+            wire::Wire::Failure(_)
+        ));
+
+        // a node can't hold more than its share
+        let half = MAX_HELD_BYTES_PER_NODE / 2;
+        chunk(streams.start(node("a"), vec![0; half], 64 * 1024));
+        chunk(streams.start(node("a"), vec![0; half], 64 * 1024));
+        let busy = streams.start(node("a"), vec![0; half], 64 * 1024);
+        assert!(matches!(busy, wire::Wire::Busy(_)));
+
+        // other nodes still can
+        chunk(streams.start(node("b"), vec![0; half], 64 * 1024));
+    }
+
+    /// A remote answering every ask for a chunk with `lie(seq)`,
+    /// and the transport to ask it with.
+    async fn lying_remote(
+        lie: fn(u32) -> wire::Wire,
+    ) -> (ghost_actor::GhostSender<TransportListener>, url2::Url2) {
+        let (local, local_events) = spawn_bind_transport_mem().await.unwrap();
+        tokio::task::spawn(local_events.for_each(|_| async {}));
+        let (remote, mut remote_events) = spawn_bind_transport_mem().await.unwrap();
+        tokio::task::spawn(async move {
+            while let Some(TransportEvent::IncomingChannel(_, mut write, read)) =
+                remote_events.next().await
+            {
+                let data = read.read_to_end().await;
+                let resp = match wire::Wire::decode_ref(&data) {
+                    Ok((_, wire::Wire::StreamNext(next))) => lie(next.seq),
+                    r => panic!("unexpected {:?}", r),
+                };
+                let _ = write.write_and_close(resp.encode_vec().unwrap()).await;
+            }
+        });
+        let url = remote.bound_url().await.unwrap();
+        (local, url)
+    }
+
+    async fn receive_all(
+        transport: ghost_actor::GhostSender<TransportListener>,
+        url: url2::Url2,
+        first: wire::Wire,
+    ) -> KitsuneP2pResult<Vec<KitsuneP2pResult<Vec<u8>>>> {
+        let chunks = receive_chunks(
+            transport,
+            url,
+            chunk(first),
+            MIN_CHUNK_SIZE as u32,
+            2,
+            Duration::from_secs(5),
+        )?;
+        Ok(chunks.collect().await)
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn lying_remotes_are_cut_off() {
+        let first = || wire::Wire::stream_chunk(1, 0, 3, vec![0; MIN_CHUNK_SIZE].into());
+
+        // an honest remote for comparison
+        let (local, url) =
+            lying_remote(|seq| wire::Wire::stream_chunk(1, seq, 3, vec![0; MIN_CHUNK_SIZE].into()))
+                .await;
+        let chunks = receive_all(local, url, first()).await.unwrap();
+        assert_eq!(3, chunks.len());
+        assert!(chunks.iter().all(|c| c.is_ok()));
+
+        // more chunks than it could be holding for us
+        let (local, url) = lying_remote(|_| panic!("fetched a chunk")).await;
+        let too_many = wire::Wire::stream_chunk(1, 0, u32::MAX, vec![0; MIN_CHUNK_SIZE].into());
+        assert!(receive_all(local, url, too_many).await.is_err());
+
+        // chunks that claim a different chunk count
+        let (local, url) = lying_remote(|seq| {
+            wire::Wire::stream_chunk(1, seq, 1000, vec![0; MIN_CHUNK_SIZE].into())
+        })
+        .await;
+        let chunks = receive_all(local, url, first()).await.unwrap();
+        assert_eq!(2, chunks.len());
+        assert!(chunks[1].is_err());
+
+        // chunks bigger than we asked for
+        let (local, url) = lying_remote(|seq| {
+            wire::Wire::stream_chunk(1, seq, 3, vec![0; MIN_CHUNK_SIZE * 2].into())
+        })
+        .await;
+        let chunks = receive_all(local, url, first()).await.unwrap();
+        assert_eq!(2, chunks.len());
+        assert!(chunks[1].is_err());
+    }
+}
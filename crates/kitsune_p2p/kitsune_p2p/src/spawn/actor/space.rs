@@ -1,9 +1,20 @@
 use crate::types::metrics::KitsuneMetrics;
+use crate::types::KitsuneBinType;
 
+use super::lan_discovery::LanDiscovery;
+use super::network_stats::NetworkStats;
+use super::network_stats::RequestOutcome;
+use super::reputation::PeerEvent;
+use super::reputation::PeerReputation;
+use super::wire_handshake::WireProtocols;
 use super::*;
+use futures::stream::TryStreamExt;
 use ghost_actor::dependencies::tracing;
 use ghost_actor::dependencies::tracing_futures::Instrument;
 use kitsune_p2p_types::codec::Codec;
+use kitsune_p2p_types::dht_arc::DhtArc;
+use kitsune_p2p_types::dht_arc::MAX_HALF_LENGTH;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::convert::TryFrom;
 
@@ -27,6 +38,9 @@ ghost_actor::ghost_chan! {
 pub(crate) async fn spawn_space(
     space: Arc<KitsuneSpace>,
     transport: ghost_actor::GhostSender<TransportListener>,
+    wire_protocols: WireProtocols,
+    lan_discovery: Option<LanDiscovery>,
+    reputation: PeerReputation,
     config: Arc<KitsuneP2pConfig>,
 ) -> KitsuneP2pResult<(
     ghost_actor::GhostSender<KitsuneP2p>,
@@ -37,7 +51,8 @@ pub(crate) async fn spawn_space(
     let builder = ghost_actor::actor_builder::GhostActorBuilder::new();
 
     // initialize gossip module
-    let gossip_recv = gossip::spawn_gossip_module(config.clone());
+    let network_stats = NetworkStats::default();
+    let gossip_recv = gossip::spawn_gossip_module(config.clone(), network_stats.clone());
     builder
         .channel_factory()
         .attach_receiver(gossip_recv)
@@ -53,7 +68,17 @@ pub(crate) async fn spawn_space(
         .create_channel::<KitsuneP2p>()
         .await?;
 
-    tokio::task::spawn(builder.spawn(Space::new(space, i_s, evt_send, transport, config)));
+    tokio::task::spawn(builder.spawn(Space::new(
+        space,
+        i_s,
+        evt_send,
+        transport,
+        wire_protocols,
+        lan_discovery,
+        network_stats,
+        reputation,
+        config,
+    )));
 
     Ok((sender, evt_recv))
 }
@@ -71,6 +96,7 @@ impl gossip::GossipEventHandler for Space {
             .cloned()
             .collect::<HashSet<_>>();
         let agent = self.local_joined_agents.iter().next().cloned();
+        let reputation = self.reputation.clone();
         let fut = match agent {
             Some(agent) => self
                 .evt_sender
@@ -85,7 +111,7 @@ impl gossip::GossipEventHandler for Space {
                 .await?
                 .into_iter()
                 .map(|ai| Arc::new(ai.into_agent()))
-                .filter(|a| !local_agents.contains(a))
+                .filter(|a| !local_agents.contains(a) && !reputation.is_blocked(a))
                 .collect::<Vec<_>>();
             let local_agents = local_agents.into_iter().collect::<Vec<_>>();
             Ok((local_agents, remote_agents))
@@ -160,6 +186,89 @@ impl gossip::GossipEventHandler for Space {
         }
     }
 
+    fn handle_req_local_op_hash_times(
+        &mut self,
+        input: ReqLocalOpHashTimesEvt,
+    ) -> gossip::GossipEventHandlerResult<LocalOpHashTimesAgentHashes> {
+        if !self.local_joined_agents.contains(&input.agent) {
+            return Err(KitsuneP2pError::RoutingAgentError(input.agent));
+        }
+        let fut = local_req_op_hash_times(
+            &self.evt_sender,
+            self.space.clone(),
+            input.agent,
+            input.dht_arc,
+            TimeWindow::new(i64::MIN, i64::MAX),
+        );
+        Ok(async move { fut.await }.boxed().into())
+    }
+
+    fn handle_req_op_hash_summary(
+        &mut self,
+        input: ReqOpHashSummaryEvt,
+    ) -> gossip::GossipEventHandlerResult<Option<OpHashSummaryAgentHashes>> {
+        if self.local_joined_agents.contains(&input.to_agent) {
+            let fut = local_req_op_hash_summary(&self.evt_sender, self.space.clone(), input);
+            Ok(async move { fut.await.map(Some) }.boxed().into())
+        } else {
+            let ReqOpHashSummaryEvt {
+                from_agent,
+                to_agent,
+                dht_arc,
+                windows,
+                include_hashes,
+            } = input;
+            let transport_tx = self.transport.clone();
+            let evt_sender = self.evt_sender.clone();
+            let wire_protocols = self.wire_protocols.clone();
+            let space = self.space.clone();
+            Ok(async move {
+                // see if we have an entry for this agent in our agent_store
+                let info = match evt_sender
+                    .get_agent_info_signed(GetAgentInfoSignedEvt {
+                        space: space.clone(),
+                        agent: to_agent.clone(),
+                    })
+                    .await?
+                {
+                    None => return Err(KitsuneP2pError::RoutingAgentError(to_agent)),
+                    Some(i) => i,
+                };
+                let info = types::agent_store::AgentInfo::try_from(&info)?;
+                let url = info.as_urls_ref().get(0).unwrap().clone();
+                // older nodes, and nodes we haven't talked to yet,
+                // get the full hash list
+                if !wire_protocols.has_feature(&url, wire::WIRE_FEATURE_OP_HASH_SUMMARY) {
+                    return Ok(None);
+                }
+                let data = wire::Wire::fetch_op_hash_summary(
+                    space,
+                    from_agent,
+                    to_agent,
+                    dht_arc,
+                    windows,
+                    include_hashes,
+                )
+                .encode_vec()?;
+                let (_, mut write, read) = transport_tx.create_channel(url).await?;
+                KitsuneMetrics::count(KitsuneMetrics::FetchOpHashSummary, data.len());
+                write.write_and_close(data.to_vec()).await?;
+                let read = read.read_to_end().await;
+                let (_, read) = wire::Wire::decode_ref(&read)?;
+                match read {
+                    wire::Wire::Failure(wire::Failure { reason }) => Err(reason.into()),
+                    wire::Wire::FetchOpHashSummaryResponse(wire::FetchOpHashSummaryResponse {
+                        buckets,
+                        peer_hashes,
+                    }) => Ok(Some((buckets, peer_hashes))),
+                    r => Err(format!("invalid response: {:?}", r).into()),
+                }
+            }
+            .boxed()
+            .into())
+        }
+    }
+
     fn handle_req_op_data(
         &mut self,
         input: ReqOpDataEvt,
@@ -289,21 +398,82 @@ pub fn local_req_op_hashes(
         since_utc_epoch_s,
         until_utc_epoch_s,
     });
-    let peer_fut = evt_sender.query_agent_info_signed(QueryAgentInfoSignedEvt {
-        space,
-        agent: to_agent,
-    });
+    let peer_fut = local_peer_hashes(evt_sender, space, to_agent);
+    async move { Ok((fut.await?, peer_fut.await?)) }
+}
+
+/// The agents a local agent has information on, with when each signed it.
+fn local_peer_hashes(
+    evt_sender: &futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+    space: Arc<KitsuneSpace>,
+    agent: Arc<KitsuneAgent>,
+) -> impl std::future::Future<Output = Result<Vec<(Arc<KitsuneAgent>, u64)>, KitsuneP2pError>> {
+    let peer_fut = evt_sender.query_agent_info_signed(QueryAgentInfoSignedEvt { space, agent });
     async move {
         let agent_infos = peer_fut.await?;
-        let agent_infos = agent_infos
+        agent_infos
             .into_iter()
             .map(|ai| {
                 let ai = types::agent_store::AgentInfo::try_from(&ai)?;
                 let time = ai.signed_at_ms();
                 Ok((Arc::new(ai.into()), time))
             })
-            .collect::<Result<Vec<_>, KitsuneP2pError>>()?;
-        Ok((fut.await?, agent_infos))
+            .collect::<Result<Vec<_>, KitsuneP2pError>>()
+    }
+}
+
+/// The op hashes a local agent holds that were authored in the window,
+/// with their authoring times.
+pub fn local_req_op_hash_times(
+    evt_sender: &futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+    space: Arc<KitsuneSpace>,
+    agent: Arc<KitsuneAgent>,
+    dht_arc: DhtArc,
+    window: TimeWindow,
+) -> impl std::future::Future<Output = Result<LocalOpHashTimesAgentHashes, KitsuneP2pError>> {
+    let fut = evt_sender.fetch_op_hash_times_for_constraints(FetchOpHashTimesForConstraintsEvt {
+        space: space.clone(),
+        agent: agent.clone(),
+        dht_arc,
+        since_utc_epoch_s: window.since_utc_epoch_s,
+        until_utc_epoch_s: window.until_utc_epoch_s,
+    });
+    let peer_fut = local_peer_hashes(evt_sender, space, agent);
+    async move { Ok((fut.await?, peer_fut.await?)) }
+}
+
+pub fn local_req_op_hash_summary(
+    evt_sender: &futures::channel::mpsc::Sender<KitsuneP2pEvent>,
+    space: Arc<KitsuneSpace>,
+    input: ReqOpHashSummaryEvt,
+) -> impl std::future::Future<Output = Result<OpHashSummaryAgentHashes, KitsuneP2pError>> {
+    let ReqOpHashSummaryEvt {
+        to_agent,
+        dht_arc,
+        windows,
+        include_hashes,
+        ..
+    } = input;
+    let checked = TimeWindow::check_summary_windows(&windows);
+    // the windows are in order, so only fetch the ops from the first to the last
+    let span = TimeWindow::new(
+        windows
+            .first()
+            .map(|w| w.since_utc_epoch_s)
+            .unwrap_or(i64::MIN),
+        windows
+            .last()
+            .map(|w| w.until_utc_epoch_s)
+            .unwrap_or(i64::MAX),
+    );
+    let fut = local_req_op_hash_times(evt_sender, space, to_agent, dht_arc, span);
+    async move {
+        checked?;
+        let (op_hash_times, peer_hashes) = fut.await?;
+        Ok((
+            OpHashTimeline::new(op_hash_times).summarize(&windows, include_hashes),
+            peer_hashes,
+        ))
     }
 }
 
@@ -432,6 +602,7 @@ impl SpaceInternalHandler for Space {
         let bound_url = self.transport.bound_url();
         let evt_sender = self.evt_sender.clone();
         let bootstrap_service = self.config.bootstrap_service.clone();
+        let lan_discovery = self.lan_discovery.clone();
         let expires_after = self.config.tuning_params.agent_info_expires_after_ms as u64;
         Ok(async move {
             let bound_url = bound_url.await?;
@@ -446,7 +617,9 @@ impl SpaceInternalHandler for Space {
                     urls.clone(),
                     crate::spawn::actor::bootstrap::now_once(None).await?,
                     expires_after,
-                );
+                )
+                // while full-sync every agent holds the whole dht
+                .with_dht_arc(DhtArc::new(agent.get_loc(), MAX_HALF_LENGTH));
                 let mut data = Vec::new();
                 kitsune_p2p_types::codec::rmp_encode(&mut data, &agent_info)?;
                 let sign_req = SignNetworkDataEvt {
@@ -469,9 +642,27 @@ impl SpaceInternalHandler for Space {
                     })
                     .await?;
 
+                // Tell any peers on the local network.
+                if let Some(lan_discovery) = &lan_discovery {
+                    if let Err(e) = lan_discovery.announce(agent_info_signed.clone()).await {
+                        tracing::warn!(
+                            msg = "Failed to announce agent info on the local network",
+                            ?e
+                        );
+                    }
+                }
+
                 // Push to the bootstrap as well.
-                crate::spawn::actor::bootstrap::put(bootstrap_service.clone(), agent_info_signed)
-                    .await?;
+                // We may well be offline, which shouldn't stop us
+                // from keeping our info fresh for the peers we can reach.
+                if let Err(e) = crate::spawn::actor::bootstrap::put(
+                    bootstrap_service.clone(),
+                    agent_info_signed,
+                )
+                .await
+                {
+                    tracing::warn!(msg = "Failed to publish agent info to bootstrap", ?e);
+                }
             }
             Ok(())
         }
@@ -508,7 +699,23 @@ impl KitsuneP2pHandler for Space {
         let fut = self.i_s.update_agent_info();
         let i_s = self.i_s.clone();
         let evt_sender = self.evt_sender.clone();
+        let tx = self.transport.clone();
         let bootstrap_service = self.config.bootstrap_service.clone();
+        {
+            let space = space.clone();
+            let agent = agent.clone();
+            let i_s = i_s.clone();
+            let evt_sender = evt_sender.clone();
+            tokio::task::spawn(async move {
+                // peers we knew before a restart may be reachable
+                // even when the bootstrap service isn't
+                if let Err(e) =
+                    super::discover::seed_from_peer_store(space, agent, i_s, evt_sender, tx).await
+                {
+                    tracing::warn!(msg = "Failed to seed peers from the peer store", ?e);
+                }
+            });
+        }
         if let Some(bootstrap_service) = bootstrap_service {
             tokio::task::spawn(async move {
                 const START_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
@@ -540,10 +747,13 @@ impl KitsuneP2pHandler for Space {
 
     fn handle_leave(
         &mut self,
-        _space: Arc<KitsuneSpace>,
+        space: Arc<KitsuneSpace>,
         agent: Arc<KitsuneAgent>,
     ) -> KitsuneP2pHandlerResult<()> {
         self.local_joined_agents.remove(&agent);
+        if let Some(lan_discovery) = &self.lan_discovery {
+            lan_discovery.withdraw(&space, &agent);
+        }
         Ok(async move { Ok(()) }.boxed().into())
     }
 
@@ -555,49 +765,27 @@ impl KitsuneP2pHandler for Space {
         payload: Vec<u8>,
         timeout_ms: Option<u64>,
     ) -> KitsuneP2pHandlerResult<Vec<u8>> {
-        let evt_sender = self.evt_sender.clone();
-
-        let timeout_ms = match timeout_ms {
-            None | Some(0) => self.config.tuning_params.default_rpc_single_timeout_ms as u64,
-            _ => timeout_ms.unwrap(),
-        };
-
-        let discover_fut =
-            discover::peer_discover(self, to_agent.clone(), from_agent.clone(), timeout_ms);
-
+        let chunks = self.rpc_to_agent(space, to_agent, from_agent, payload, timeout_ms, false)?;
         Ok(async move {
-            match discover_fut.await {
-                discover::PeerDiscoverResult::OkShortcut => {
-                    // reflect this request locally
-                    evt_sender.call(space, to_agent, from_agent, payload).await
-                }
-                discover::PeerDiscoverResult::OkRemote {
-                    mut write, read, ..
-                } => {
-                    let payload = wire::Wire::call(
-                        space.clone(),
-                        from_agent.clone(),
-                        to_agent.clone(),
-                        payload.into(),
-                    )
-                    .encode_vec()?;
-                    KitsuneMetrics::count(KitsuneMetrics::Call, payload.len());
-                    write.write_and_close(payload).await?;
-                    let res = read.read_to_end().await;
-                    let (_, res) = wire::Wire::decode_ref(&res)?;
-                    match res {
-                        wire::Wire::Failure(wire::Failure { reason }) => Err(reason.into()),
-                        wire::Wire::CallResp(wire::CallResp { data }) => Ok(data.into()),
-                        r => Err(format!("invalid response: {:?}", r).into()),
-                    }
-                }
-                discover::PeerDiscoverResult::Err(e) => Err(e),
-            }
+            // never streamed, so this is the whole response
+            let chunks: Vec<Vec<u8>> = chunks.await?.try_collect().await?;
+            Ok(chunks.concat())
         }
         .boxed()
         .into())
     }
 
+    fn handle_rpc_stream(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+        to_agent: Arc<KitsuneAgent>,
+        from_agent: Arc<KitsuneAgent>,
+        payload: Vec<u8>,
+        timeout_ms: Option<u64>,
+    ) -> KitsuneP2pHandlerResult<actor::RpcStream> {
+        self.rpc_to_agent(space, to_agent, from_agent, payload, timeout_ms, true)
+    }
+
     fn handle_rpc_multi(
         &mut self,
         mut input: actor::RpcMulti,
@@ -636,6 +824,11 @@ impl KitsuneP2pHandler for Space {
             input.race_timeout_ms = Some(input.timeout_ms.unwrap());
         }
 
+        // we never ask enough agents to reach a larger quorum
+        if let actor::RpcMultiStrategy::Quorum(quorum) = &mut input.strategy {
+            *quorum = (*quorum).max(1).min(actor::MAX_QUORUM);
+        }
+
         self.handle_rpc_multi_inner(input)
     }
 
@@ -677,8 +870,139 @@ impl KitsuneP2pHandler for Space {
             Ok(inner_fut)
         }
     }
+
+    fn handle_group_bases_by_authority(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+        from_agent: Arc<KitsuneAgent>,
+        bases: Vec<Arc<KitsuneBasis>>,
+    ) -> KitsuneP2pHandlerResult<actor::AuthorityGroups> {
+        let peers_fut = self
+            .evt_sender
+            .query_agent_info_signed(QueryAgentInfoSignedEvt {
+                space,
+                agent: from_agent.clone(),
+            });
+        // our own agents can't answer for us remotely
+        let local_agents = self.local_joined_agents.clone();
+        let reputation = self.reputation.clone();
+        Ok(async move {
+            let mut authorities = Vec::new();
+            for info in peers_fut.await? {
+                let info = match types::agent_store::AgentInfo::try_from(&info) {
+                    Ok(info) => info,
+                    Err(_) => continue,
+                };
+                let agent = Arc::new(info.as_agent_ref().clone());
+                if agent == from_agent
+                    || local_agents.contains(&agent)
+                    || reputation.is_blocked(&agent)
+                {
+                    continue;
+                }
+                authorities.push((agent, info.dht_arc()));
+            }
+            Ok(group_bases_by_arc(authorities, bases))
+        }
+        .boxed()
+        .into())
+    }
+
+    fn handle_space_stats(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+    ) -> KitsuneP2pHandlerResult<actor::SpaceStats> {
+        let local_agents = self.local_joined_agents.iter().cloned().collect::<Vec<_>>();
+        let peers_fut = match local_agents.first() {
+            Some(agent) => self
+                .evt_sender
+                .query_agent_info_signed(QueryAgentInfoSignedEvt {
+                    space,
+                    agent: agent.clone(),
+                }),
+            None => async { Ok(Vec::new()) }.boxed().into(),
+        };
+        let network_stats = self.network_stats.clone();
+        let reputation = self.reputation.clone();
+        let connected_fut = self.transport.connected_urls();
+        Ok(async move {
+            // peers behind a proxy are reached over a connection to its base url
+            let connected_urls = connected_fut.await?;
+            let is_connected = |url: &url2::Url2| {
+                let base = match kitsune_p2p_proxy::ProxyUrl::from_full(url.as_str()) {
+                    Ok(proxy_url) => proxy_url.into_base(),
+                    Err(_) => url.clone(),
+                };
+                connected_urls.contains(&base)
+            };
+            let mut request_stats = network_stats.requests();
+            let mut requests = actor::RequestStats::default();
+            for stats in request_stats.values() {
+                requests.add(stats);
+            }
+            let mut peers = Vec::new();
+            for info in peers_fut.await? {
+                let info = types::agent_store::AgentInfo::try_from(&info)?;
+                let agent = Arc::new(info.as_agent_ref().clone());
+                if local_agents.contains(&agent) {
+                    continue;
+                }
+                let connected = info.as_urls_ref().iter().any(is_connected);
+                peers.push(actor::PeerStats {
+                    requests: request_stats.remove(&agent).unwrap_or_default(),
+                    agent,
+                    urls: info.as_urls_ref().to_vec(),
+                    signed_at_ms: info.signed_at_ms(),
+                    expires_at_ms: info.signed_at_ms() + info.expires_after_ms(),
+                    connected,
+                    score: reputation.score(&agent),
+                    blocked: reputation.is_blocked(&agent),
+                });
+            }
+            Ok(actor::SpaceStats {
+                local_agents,
+                peers,
+                gossip_partners: network_stats.gossip_partners(),
+                requests,
+            })
+        }
+        .boxed()
+        .into())
+    }
+
+    fn handle_report_invalid_data(
+        &mut self,
+        _space: Arc<KitsuneSpace>,
+        agent: Arc<KitsuneAgent>,
+    ) -> KitsuneP2pHandlerResult<()> {
+        self.reputation.record(agent, PeerEvent::InvalidData);
+        Ok(async move { Ok(()) }.boxed().into())
+    }
+
+    fn handle_block_peer(
+        &mut self,
+        _space: Arc<KitsuneSpace>,
+        agent: Arc<KitsuneAgent>,
+        duration_ms: Option<u64>,
+    ) -> KitsuneP2pHandlerResult<()> {
+        self.reputation
+            .block(agent, duration_ms.map(std::time::Duration::from_millis));
+        Ok(async move { Ok(()) }.boxed().into())
+    }
+
+    fn handle_unblock_peer(
+        &mut self,
+        _space: Arc<KitsuneSpace>,
+        agent: Arc<KitsuneAgent>,
+    ) -> KitsuneP2pHandlerResult<()> {
+        self.reputation.unblock(&agent);
+        Ok(async move { Ok(()) }.boxed().into())
+    }
 }
 
+/// The longest we wait between re-signing and publishing our agent infos.
+const AGENT_INFO_MAX_UPDATE_INTERVAL_MS: u64 = 1000 * 60 * 5;
+
 /// A Kitsune P2p Node can track multiple "spaces" -- Non-interacting namespaced
 /// areas that share common transport infrastructure for communication.
 pub(crate) struct Space {
@@ -687,22 +1011,178 @@ pub(crate) struct Space {
     pub(crate) evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
     pub(crate) transport: ghost_actor::GhostSender<TransportListener>,
     pub(crate) local_joined_agents: HashSet<Arc<KitsuneAgent>>,
+    pub(crate) wire_protocols: WireProtocols,
+    pub(crate) lan_discovery: Option<LanDiscovery>,
+    pub(crate) network_stats: NetworkStats,
+    pub(crate) reputation: PeerReputation,
     pub(crate) config: Arc<KitsuneP2pConfig>,
 }
 
 impl Space {
+    /// Make a request of a specific agent, reflected locally if it is
+    /// joined here. If `stream` is set and the remote supports it, the
+    /// response is streamed in chunks, otherwise it arrives in one chunk.
+    fn rpc_to_agent(
+        &mut self,
+        space: Arc<KitsuneSpace>,
+        to_agent: Arc<KitsuneAgent>,
+        from_agent: Arc<KitsuneAgent>,
+        payload: Vec<u8>,
+        timeout_ms: Option<u64>,
+        stream: bool,
+    ) -> KitsuneP2pHandlerResult<actor::RpcStream> {
+        if self.reputation.is_blocked(&to_agent) {
+            return Err(format!("peer is blocked: {:?}", to_agent).into());
+        }
+
+        let evt_sender = self.evt_sender.clone();
+        let transport = self.transport.clone();
+        let wire_protocols = self.wire_protocols.clone();
+        let network_stats = self.network_stats.clone();
+        let reputation = self.reputation.clone();
+        let chunk_size = self.config.tuning_params.rpc_stream_chunk_size_bytes;
+        let window = self.config.tuning_params.rpc_stream_window;
+
+        let timeout_ms = match timeout_ms {
+            None | Some(0) => self.config.tuning_params.default_rpc_single_timeout_ms as u64,
+            _ => timeout_ms.unwrap(),
+        };
+
+        let discover_fut =
+            discover::peer_discover(self, to_agent.clone(), from_agent.clone(), timeout_ms);
+
+        Ok(async move {
+            match discover_fut.await {
+                discover::PeerDiscoverResult::OkShortcut => {
+                    // reflect this request locally, in one chunk
+                    let res = evt_sender
+                        .call(space, to_agent, from_agent, None, payload)
+                        .await?;
+                    Ok(futures::stream::once(async move { Ok(res) }).boxed())
+                }
+                discover::PeerDiscoverResult::OkRemote {
+                    url,
+                    mut write,
+                    read,
+                } => {
+                    // older nodes send the whole response in one go
+                    let stream =
+                        stream && wire_protocols.has_feature(&url, wire::WIRE_FEATURE_STREAM);
+                    let (metric, payload) = if stream {
+                        (
+                            KitsuneMetrics::CallStream,
+                            wire::Wire::call_stream(
+                                space.clone(),
+                                from_agent.clone(),
+                                to_agent.clone(),
+                                payload.into(),
+                                chunk_size,
+                            ),
+                        )
+                    } else {
+                        (
+                            KitsuneMetrics::Call,
+                            wire::Wire::call(
+                                space.clone(),
+                                from_agent.clone(),
+                                to_agent.clone(),
+                                payload.into(),
+                            ),
+                        )
+                    };
+                    let payload = payload.encode_vec()?;
+                    let start = std::time::Instant::now();
+                    let res = async move {
+                        KitsuneMetrics::count(metric, payload.len());
+                        write.write_and_close(payload).await?;
+                        KitsuneP2pResult::Ok(read.read_to_end().await)
+                    }
+                    .await;
+                    let (event, res): (PeerEvent, KitsuneP2pResult<actor::RpcStream>) = match res {
+                        Err(e) => (PeerEvent::Failed, Err(e)),
+                        Ok(res) => match wire::Wire::decode_ref(&res) {
+                            Err(e) => (PeerEvent::BadResponse, Err(e.into())),
+                            Ok((_, wire::Wire::CallResp(wire::CallResp { data }))) => (
+                                PeerEvent::Responded(start.elapsed()),
+                                Ok(futures::stream::once(async move { Ok(data.into()) }).boxed()),
+                            ),
+                            Ok((_, wire::Wire::StreamChunk(first))) if first.seq == 0 => {
+                                match rpc_stream::receive_chunks(
+                                    transport,
+                                    url,
+                                    first,
+                                    chunk_size,
+                                    window,
+                                    std::time::Duration::from_millis(timeout_ms),
+                                ) {
+                                    Ok(chunks) => {
+                                        (PeerEvent::Responded(start.elapsed()), Ok(chunks.boxed()))
+                                    }
+                                    Err(e) => (PeerEvent::BadResponse, Err(e)),
+                                }
+                            }
+                            Ok((_, wire::Wire::Failure(failure))) => {
+                                let (event, e) = failure_event(failure.into_error());
+                                (event, Err(e))
+                            }
+                            Ok((_, wire::Wire::Busy(busy))) => {
+                                let (event, e) = failure_event(busy.into_error());
+                                (event, Err(e))
+                            }
+                            Ok((_, wire::Wire::Rejected(rejected))) => {
+                                let (event, e) = failure_event(rejected.into_error());
+                                (event, Err(e))
+                            }
+                            Ok((_, r)) => (
+                                PeerEvent::BadResponse,
+                                Err(format!("invalid response: {:?}", r).into()),
+                            ),
+                        },
+                    };
+                    network_stats.record_result(to_agent.clone(), &res);
+                    reputation.record(to_agent, event);
+                    res
+                }
+                discover::PeerDiscoverResult::Unreachable(e) => {
+                    network_stats.record_request(to_agent.clone(), RequestOutcome::Failed);
+                    reputation.record(to_agent, PeerEvent::Failed);
+                    Err(e)
+                }
+                discover::PeerDiscoverResult::TimedOut(e) => {
+                    network_stats.record_request(to_agent.clone(), RequestOutcome::TimedOut);
+                    reputation.record(to_agent, PeerEvent::TimedOut);
+                    Err(e)
+                }
+            }
+        }
+        .boxed()
+        .into())
+    }
+
     /// space constructor
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         space: Arc<KitsuneSpace>,
         i_s: ghost_actor::GhostSender<SpaceInternal>,
         evt_sender: futures::channel::mpsc::Sender<KitsuneP2pEvent>,
         transport: ghost_actor::GhostSender<TransportListener>,
+        wire_protocols: WireProtocols,
+        lan_discovery: Option<LanDiscovery>,
+        network_stats: NetworkStats,
+        reputation: PeerReputation,
         config: Arc<KitsuneP2pConfig>,
     ) -> Self {
+        // re-sign our agent infos well before they expire
+        let expires_after_ms = config.tuning_params.agent_info_expires_after_ms as u64;
+        let update_interval = std::time::Duration::from_millis(
+            (expires_after_ms / 2)
+                .max(1000)
+                .min(AGENT_INFO_MAX_UPDATE_INTERVAL_MS),
+        );
         let i_s_c = i_s.clone();
         tokio::task::spawn(async move {
             loop {
-                tokio::time::delay_for(std::time::Duration::from_secs(5 * 60)).await;
+                tokio::time::delay_for(update_interval).await;
                 if i_s_c.update_agent_info().await.is_err() {
                     break;
                 }
@@ -715,6 +1195,10 @@ impl Space {
             evt_sender,
             transport,
             local_joined_agents: HashSet::new(),
+            wire_protocols,
+            lan_discovery,
+            network_stats,
+            reputation,
             config,
         }
     }
@@ -734,12 +1218,18 @@ impl Space {
             timeout_ms,
             //as_race,
             //race_timeout_ms,
+            strategy,
+            quorum_key,
             payload,
             ..
         } = input;
         let remote_agent_count = remote_agent_count.unwrap();
         let timeout_ms = timeout_ms.unwrap();
-        let stage_1_timeout_ms = timeout_ms / 2;
+        // only settle for whatever we have early when racing
+        let stage_1_timeout_ms = match strategy {
+            actor::RpcMultiStrategy::FirstN => timeout_ms / 2,
+            _ => timeout_ms,
+        };
 
         // as an optimization - request to all local joins
         // but don't count that toward our request total
@@ -753,16 +1243,34 @@ impl Space {
                         space.clone(),
                         agent.clone(),
                         from_agent.clone(),
+                        None,
                         payload.clone(),
                     )
                     .then(|r| async move { (r, agent) })
             })
             .collect::<Vec<_>>();
 
+        // only remote nodes count toward a quorum,
+        // local agents can't vouch for data we hold ourselves
+        let quorum_key = Arc::new(move |response: &[u8]| match &quorum_key {
+            Some(key) => (key.0)(response),
+            None => response.to_vec(),
+        });
+        let enough_results_cb = {
+            let quorum_key = quorum_key.clone();
+            move |out: &[(url2::Url2, actor::RpcMultiResponse)]| match strategy {
+                actor::RpcMultiStrategy::Quorum(quorum) => {
+                    let (_, agreeing) = most_agreed_response(out, &*quorum_key);
+                    agreeing >= quorum as usize
+                }
+                _ => out.len() >= remote_agent_count as usize,
+            }
+        };
+
         let remote_fut = discover::message_neighborhood(
             self,
             from_agent.clone(),
-            remote_agent_count,
+            enough_results_cb,
             stage_1_timeout_ms,
             timeout_ms,
             basis,
@@ -772,11 +1280,14 @@ impl Space {
                 from_agent,
                 payload.clone().into(),
             ),
-            |a, w| match w {
-                wire::Wire::CallResp(c) => Ok(actor::RpcMultiResponse {
-                    agent: a,
-                    response: c.data.into(),
-                }),
+            |a, url, w| match w {
+                wire::Wire::CallResp(c) => Ok((
+                    url,
+                    actor::RpcMultiResponse {
+                        agent: a,
+                        response: c.data.into(),
+                    },
+                )),
                 _ => Err(()),
             },
         )
@@ -797,8 +1308,23 @@ impl Space {
                     }
                 })
                 .collect();
+            let remote = remote_fut.await;
 
-            out.append(&mut remote_fut.await);
+            if let actor::RpcMultiStrategy::Quorum(quorum) = strategy {
+                let (key, agreeing) = most_agreed_response(&remote, &*quorum_key);
+                if agreeing < quorum as usize {
+                    return Err(KitsuneP2pError::QuorumNotReached { agreeing, quorum });
+                }
+                out.retain(|r| Some(quorum_key(&r.response)) == key);
+                out.extend(
+                    remote
+                        .into_iter()
+                        .map(|(_, r)| r)
+                        .filter(|r| Some(quorum_key(&r.response)) == key),
+                );
+            } else {
+                out.extend(remote.into_iter().map(|(_, r)| r));
+            }
 
             Ok(out)
         }
@@ -836,6 +1362,7 @@ impl Space {
                     space.clone(),
                     agent.clone(),
                     from_agent.clone(),
+                    None,
                     payload.clone(),
                 )
             })
@@ -844,7 +1371,7 @@ impl Space {
         let remote_fut = discover::message_neighborhood(
             self,
             from_agent.clone(),
-            remote_agent_count,
+            move |out: &[()]| out.len() >= remote_agent_count as usize,
             stage_1_timeout_ms,
             timeout_ms,
             basis,
@@ -854,7 +1381,7 @@ impl Space {
                 from_agent,
                 payload.into(),
             ),
-            |_, w| match w {
+            |_, _, w| match w {
                 wire::Wire::NotifyResp(_) => Ok(()),
                 _ => Err(()),
             },
@@ -869,3 +1396,64 @@ impl Space {
         .into())
     }
 }
+
+/// How the error of a failure response counts against the agent that
+/// sent it, passing the error on for the requester.
+pub(crate) fn failure_event(e: KitsuneP2pError) -> (PeerEvent, KitsuneP2pError) {
+    match e {
+        e @ KitsuneP2pError::Busy { .. } => (PeerEvent::Busy, e),
+        e @ KitsuneP2pError::Rejected(_) => (PeerEvent::Rejected, e),
+        e => (PeerEvent::Failed, e),
+    }
+}
+
+/// Greedily pick the agent whose arc covers the most ungrouped bases
+/// until no agent covers any more of them.
+fn group_bases_by_arc(
+    authorities: Vec<(Arc<KitsuneAgent>, DhtArc)>,
+    mut bases: Vec<Arc<KitsuneBasis>>,
+) -> actor::AuthorityGroups {
+    let mut groups = Vec::new();
+    loop {
+        let best = authorities
+            .iter()
+            .map(|(agent, arc)| {
+                let covered = bases.iter().filter(|b| arc.contains(b.get_loc())).count();
+                (agent, arc, covered)
+            })
+            .filter(|(_, _, covered)| *covered > 0)
+            .max_by_key(|(_, _, covered)| *covered);
+        let (agent, arc) = match best {
+            None => break,
+            Some((agent, arc, _)) => (agent.clone(), *arc),
+        };
+        let (covered, rest) = bases.into_iter().partition(|b| arc.contains(b.get_loc()));
+        groups.push((agent, covered));
+        bases = rest;
+    }
+    actor::AuthorityGroups {
+        groups,
+        unassigned: bases,
+    }
+}
+
+/// The quorum key the most remote nodes agree on, and how many nodes
+/// agree on it. Each node counts once, however many of its agents responded.
+fn most_agreed_response(
+    responses: &[(url2::Url2, actor::RpcMultiResponse)],
+    quorum_key: &(dyn Fn(&[u8]) -> Vec<u8> + Send + Sync),
+) -> (Option<Vec<u8>>, usize) {
+    let mut nodes: HashMap<Vec<u8>, HashSet<&url2::Url2>> = HashMap::new();
+    for (url, r) in responses {
+        nodes
+            .entry(quorum_key(&r.response))
+            .or_default()
+            .insert(url);
+    }
+    nodes
+        .into_iter()
+        .map(|(key, nodes)| (key, nodes.len()))
+        .max_by_key(|(_, count)| *count)
+        .map(|(key, count)| (Some(key), count))
+        .unwrap_or((None, 0))
+}
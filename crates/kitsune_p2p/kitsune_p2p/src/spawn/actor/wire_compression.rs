@@ -0,0 +1,96 @@
+//! Compress messages to and from remotes with the "deflate" wire feature,
+//! see [wire::Wire::Compressed].
+
+use crate::metrics::KitsuneMetrics;
+use crate::wire;
+use futures::sink::SinkExt;
+use futures::stream::StreamExt;
+use kitsune_p2p_types::codec::Codec;
+use kitsune_p2p_types::metrics::metric_task;
+use kitsune_p2p_types::transport::*;
+
+/// The most a compressed message may inflate to.
+pub(crate) const MAX_INFLATED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Get the compression threshold from the tuning param,
+/// where 0 means don't compress.
+pub(crate) fn threshold(wire_compression_threshold_bytes: u32) -> Option<usize> {
+    match wire_compression_threshold_bytes {
+        0 => None,
+        t => Some(t as usize),
+    }
+}
+
+/// Compress the message written to this channel if it is over the threshold
+/// and compressing makes it smaller, otherwise send it as it is.
+pub(crate) fn compress_write(
+    mut write: TransportChannelWrite,
+    threshold: usize,
+) -> TransportChannelWrite {
+    let (send, recv) = futures::channel::mpsc::channel::<Vec<u8>>(10);
+    metric_task(async move {
+        let mut data: Vec<u8> = recv.concat().await;
+        if data.len() >= threshold {
+            let compressed = wire::Wire::compress(&data).map_err(TransportError::other)?;
+            if compressed.len() < data.len() {
+                KitsuneMetrics::count(
+                    KitsuneMetrics::CompressionSaved,
+                    data.len() - compressed.len(),
+                );
+                data = compressed;
+            }
+        }
+        write.send(data).await?;
+        write.close().await?;
+        TransportResult::Ok(())
+    });
+    Box::new(send.sink_map_err(TransportError::other))
+}
+
+/// Inflate the message read from this channel if it is compressed.
+pub(crate) fn decompress_read(read: TransportChannelRead) -> TransportChannelRead {
+    let (mut send, recv) = futures::channel::mpsc::channel::<Vec<u8>>(10);
+    metric_task(async move {
+        let data: Vec<u8> = read.concat().await;
+        let data = match wire::Wire::decode_ref(&data) {
+            Ok((_, wire::Wire::Compressed(wire::Compressed { data }))) => {
+                match wire::Wire::decompress(&data, MAX_INFLATED_BYTES) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        let reason = format!("could not inflate compressed message: {}", e);
+                        wire::Wire::failure(reason)
+                            .encode_vec()
+                            .map_err(TransportError::other)?
+                    }
+                }
+            }
+            // e.g. a failure to decode our request
+            _ => data,
+        };
+        // it's ok if the reader is gone
+        let _ = send.send(data).await;
+        TransportResult::Ok(())
+    });
+    Box::new(recv)
+}
+
+/// Decode an incoming message, inflating it if it is compressed.
+/// Returns true along with the message if it was compressed,
+/// so our response should be too. Returns the [wire::Wire::Failure] to
+/// respond with if the message can't be decoded.
+pub(crate) fn decode_incoming(data: &[u8]) -> Result<(wire::Wire, bool), wire::Wire> {
+    match wire::Wire::decode_incoming(data)? {
+        wire::Wire::Compressed(wire::Compressed { data }) => {
+            let data = wire::Wire::decompress(&data, MAX_INFLATED_BYTES).map_err(|e| {
+                wire::Wire::failure(format!("could not inflate compressed message: {}", e))
+            })?;
+            match wire::Wire::decode_incoming(&data)? {
+                wire::Wire::Compressed(_) => Err(wire::Wire::failure(
+                    "compressed messages cannot be nested".to_string(),
+                )),
+                msg => Ok((msg, true)),
+            }
+        }
+        msg => Ok((msg, false)),
+    }
+}
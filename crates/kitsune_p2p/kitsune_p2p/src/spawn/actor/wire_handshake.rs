@@ -0,0 +1,384 @@
+//! Agree on a wire protocol version with each remote before sending it
+//! anything else. This wraps the transport so every outgoing channel,
+//! whichever space or task opens it, is covered. Channels to remotes
+//! with the "deflate" feature are also compressed here.
+//!
+//! Channels opened while a hello is in flight wait for it rather than
+//! sending their own, and a failed hello is remembered for a while so
+//! an unreachable remote isn't sent a hello for every channel.
+
+use super::wire_compression;
+use crate::metrics::KitsuneMetrics;
+use crate::wire;
+use futures::future::BoxFuture;
+use futures::future::FutureExt;
+use ghost_actor::dependencies::must_future::MustBoxFuture;
+use ghost_actor::GhostControlSender;
+use kitsune_p2p_types::codec::Codec;
+use kitsune_p2p_types::dependencies::serde_json;
+use kitsune_p2p_types::transport::*;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How long we fail channels to a remote without trying another hello
+/// after a hello to it fails.
+const HELLO_FAILURE_TTL: Duration = Duration::from_secs(10);
+
+/// A hello in flight, which every channel opened to the remote meanwhile waits on.
+type PendingHello = futures::future::Shared<BoxFuture<'static, Result<wire::WireProtocol, String>>>;
+
+static NEXT_HELLO_ID: AtomicU64 = AtomicU64::new(1);
+
+enum Remote {
+    /// We have agreed a protocol with the remote.
+    Agreed {
+        protocol: wire::WireProtocol,
+        last_channel_at: Instant,
+    },
+    /// We are exchanging hellos with the remote.
+    Pending { id: u64, hello: PendingHello },
+    /// The last hello to the remote failed.
+    Failed { error: String, failed_at: Instant },
+}
+
+/// The wire protocols agreed with remotes, keyed by their url.
+#[derive(Clone, Default)]
+pub(crate) struct WireProtocols {
+    remotes: Arc<Mutex<HashMap<url2::Url2, Remote>>>,
+    /// The transport closes connections idle for this long, so a protocol
+    /// agreed over one is agreed again on the next connection. 0 = never.
+    idle_timeout_ms: u64,
+}
+
+impl WireProtocols {
+    /// Track the protocols agreed over connections closed after
+    /// `idle_timeout_ms` without use.
+    pub(crate) fn new(idle_timeout_ms: u64) -> Self {
+        Self {
+            remotes: Default::default(),
+            idle_timeout_ms,
+        }
+    }
+
+    /// True if we have agreed this feature with the remote.
+    /// False until we have talked to it.
+    pub(crate) fn has_feature(&self, url: &url2::Url2, feature: &str) -> bool {
+        match self.remotes.lock().unwrap().get(url) {
+            Some(Remote::Agreed { protocol, .. }) => protocol.has_feature(feature),
+            _ => false,
+        }
+    }
+
+    /// Note the protocol this remote agreed by saying hello to us,
+    /// unless we are already agreeing one with it.
+    pub(crate) fn remote_hello(&self, url: url2::Url2, protocol: wire::WireProtocol) {
+        self.remotes
+            .lock()
+            .unwrap()
+            .entry(url)
+            .or_insert_with(|| Remote::Agreed {
+                protocol,
+                last_channel_at: Instant::now(),
+            });
+    }
+
+    /// Forget the protocol agreed with this remote, e.g. because
+    /// the connection to it dropped, so the next channel says hello again.
+    fn invalidate(&self, url: &url2::Url2) {
+        let mut remotes = self.remotes.lock().unwrap();
+        if let Some(Remote::Agreed { .. }) = remotes.get(url) {
+            remotes.remove(url);
+        }
+    }
+
+    fn is_idle(&self, last_channel_at: Instant) -> bool {
+        self.idle_timeout_ms > 0
+            && last_channel_at.elapsed() >= Duration::from_millis(self.idle_timeout_ms)
+    }
+
+    /// The protocol agreed with this remote, saying hello first if we
+    /// haven't agreed one yet. Notes that we are opening a channel to it.
+    async fn agree(
+        &self,
+        sub: &ghost_actor::GhostSender<TransportListener>,
+        url: &url2::Url2,
+    ) -> TransportResult<wire::WireProtocol> {
+        let (id, pending) = {
+            let mut remotes = self.remotes.lock().unwrap();
+            let existing = match remotes.get_mut(url) {
+                Some(Remote::Agreed {
+                    protocol,
+                    last_channel_at,
+                }) if !self.is_idle(*last_channel_at) => {
+                    *last_channel_at = Instant::now();
+                    return Ok(protocol.clone());
+                }
+                Some(Remote::Failed { error, failed_at })
+                    if failed_at.elapsed() < HELLO_FAILURE_TTL =>
+                {
+                    return Err(error.clone().into());
+                }
+                Some(Remote::Pending { id, hello }) => Some((*id, hello.clone())),
+                _ => None,
+            };
+            match existing {
+                Some(existing) => existing,
+                None => {
+                    let id = NEXT_HELLO_ID.fetch_add(1, Ordering::Relaxed);
+                    let hello = hello(sub.clone(), url.clone())
+                        .map(|r| r.map_err(|e| e.to_string()))
+                        .boxed()
+                        .shared();
+                    remotes.insert(
+                        url.clone(),
+                        Remote::Pending {
+                            id,
+                            hello: hello.clone(),
+                        },
+                    );
+                    (id, hello)
+                }
+            }
+        };
+
+        let result = pending.await;
+
+        let mut remotes = self.remotes.lock().unwrap();
+        // the first channel to finish waiting records the outcome
+        if let Some(Remote::Pending { id: pending_id, .. }) = remotes.get(url) {
+            if *pending_id == id {
+                let remote = match &result {
+                    Ok(protocol) => Remote::Agreed {
+                        protocol: protocol.clone(),
+                        last_channel_at: Instant::now(),
+                    },
+                    Err(error) => Remote::Failed {
+                        error: error.clone(),
+                        failed_at: Instant::now(),
+                    },
+                };
+                remotes.insert(url.clone(), remote);
+            }
+        }
+        result.map_err(TransportError::from)
+    }
+}
+
+/// Wrap a transport so the first channel to each remote is preceded
+/// by a hello exchange. Messages are compressed over the
+/// `compression_threshold` when the remote supports it.
+pub(crate) async fn spawn_wire_handshake(
+    sub: ghost_actor::GhostSender<TransportListener>,
+    protocols: WireProtocols,
+    compression_threshold: Option<usize>,
+) -> TransportResult<ghost_actor::GhostSender<TransportListener>> {
+    let builder = ghost_actor::actor_builder::GhostActorBuilder::new();
+    let sender = builder
+        .channel_factory()
+        .create_channel::<TransportListener>()
+        .await?;
+    kitsune_p2p_types::metrics::metric_task(builder.spawn(WireHandshake {
+        sub,
+        protocols,
+        compression_threshold,
+    }));
+    Ok(sender)
+}
+
+/// Exchange hellos with this remote and agree on a protocol.
+async fn hello(
+    sub: ghost_actor::GhostSender<TransportListener>,
+    url: url2::Url2,
+) -> TransportResult<wire::WireProtocol> {
+    let (_, mut write, read) = sub.create_channel(url).await?;
+    let msg = wire::Wire::local_hello()
+        .encode_vec()
+        .map_err(TransportError::other)?;
+    KitsuneMetrics::count(KitsuneMetrics::Hello, msg.len());
+    write.write_and_close(msg).await?;
+    let res = read.read_to_end().await;
+    match wire::Wire::decode_ref(&res)
+        .map_err(TransportError::other)?
+        .1
+    {
+        wire::Wire::HelloResp(wire::HelloResp {
+            protocol_version,
+            min_protocol_version,
+            features,
+        }) => Ok(wire::WireProtocol::negotiate(
+            protocol_version,
+            min_protocol_version,
+            &features,
+        )?),
+        // nodes from before the handshake fail to decode our hello
+        wire::Wire::Failure(_) => Ok(wire::WireProtocol::legacy()),
+        r => Err(format!("unexpected hello response: {:?}", r).into()),
+    }
+}
+
+struct WireHandshake {
+    sub: ghost_actor::GhostSender<TransportListener>,
+    protocols: WireProtocols,
+    compression_threshold: Option<usize>,
+}
+
+impl ghost_actor::GhostControlHandler for WireHandshake {
+    fn handle_ghost_actor_shutdown(self) -> MustBoxFuture<'static, ()> {
+        async move {
+            let _ = self.sub.ghost_actor_shutdown().await;
+        }
+        .boxed()
+        .into()
+    }
+}
+
+impl ghost_actor::GhostHandler<TransportListener> for WireHandshake {}
+
+impl TransportListenerHandler for WireHandshake {
+    fn handle_debug(&mut self) -> TransportListenerHandlerResult<serde_json::Value> {
+        let fut = self.sub.debug();
+        let protocols = self
+            .protocols
+            .remotes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(k, v)| match v {
+                Remote::Agreed { protocol, .. } => {
+                    Some((k.to_string(), serde_json::json!(protocol)))
+                }
+                _ => None,
+            })
+            .collect::<serde_json::map::Map<String, serde_json::Value>>();
+        Ok(async move {
+            let mut out = fut.await?;
+            if let serde_json::Value::Object(map) = &mut out {
+                map.insert("wire_protocols".to_string(), protocols.into());
+            }
+            Ok(out)
+        }
+        .boxed()
+        .into())
+    }
+
+    fn handle_bound_url(&mut self) -> TransportListenerHandlerResult<url2::Url2> {
+        Ok(self.sub.bound_url())
+    }
+
+    fn handle_connected_urls(&mut self) -> TransportListenerHandlerResult<Vec<url2::Url2>> {
+        Ok(self.sub.connected_urls())
+    }
+
+    fn handle_create_channel(
+        &mut self,
+        url: url2::Url2,
+    ) -> TransportListenerHandlerResult<(url2::Url2, TransportChannelWrite, TransportChannelRead)>
+    {
+        let sub = self.sub.clone();
+        let protocols = self.protocols.clone();
+        let compression_threshold = self.compression_threshold;
+        Ok(async move {
+            let protocol = protocols.agree(&sub, &url).await?;
+            let (url, write, read) = match sub.create_channel(url.clone()).await {
+                Ok(r) => r,
+                Err(e) => {
+                    // the connection is gone, the remote may be
+                    // different when we next reach it
+                    protocols.invalidate(&url);
+                    return Err(e);
+                }
+            };
+            match compression_threshold {
+                Some(threshold) if protocol.has_feature(wire::WIRE_FEATURE_DEFLATE) => Ok((
+                    url,
+                    wire_compression::compress_write(write, threshold),
+                    wire_compression::decompress_read(read),
+                )),
+                _ => Ok((url, write, read)),
+            }
+        }
+        .boxed()
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream::StreamExt;
+    use kitsune_p2p_types::transport_mem::spawn_bind_transport_mem;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Answer hellos with `hello_resp`, counting them, and echo anything else.
+    fn respond(
+        mut events: TransportEventReceiver,
+        hellos: Arc<AtomicUsize>,
+        hello_resp: fn() -> wire::Wire,
+    ) {
+        tokio::task::spawn(async move {
+            while let Some(TransportEvent::IncomingChannel(_, mut write, read)) =
+                events.next().await
+            {
+                let hellos = hellos.clone();
+                tokio::task::spawn(async move {
+                    let data = read.read_to_end().await;
+                    let resp = match wire::Wire::decode_ref(&data) {
+                        Ok((_, wire::Wire::Hello(_))) => {
+                            hellos.fetch_add(1, Ordering::SeqCst);
+                            hello_resp().encode_vec().unwrap()
+                        }
+                        _ => data,
+                    };
+                    let _ = write.write_and_close(resp).await;
+                });
+            }
+        });
+    }
+
+    async fn setup(
+        hello_resp: fn() -> wire::Wire,
+    ) -> (
+        ghost_actor::GhostSender<TransportListener>,
+        url2::Url2,
+        Arc<AtomicUsize>,
+    ) {
+        let (local, local_events) = spawn_bind_transport_mem().await.unwrap();
+        tokio::task::spawn(local_events.for_each(|_| async {}));
+        let (remote, remote_events) = spawn_bind_transport_mem().await.unwrap();
+        let hellos = Arc::new(AtomicUsize::new(0));
+        respond(remote_events, hellos.clone(), hello_resp);
+        let url = remote.bound_url().await.unwrap();
+        let local = spawn_wire_handshake(local, WireProtocols::default(), None)
+            .await
+            .unwrap();
+        (local, url, hellos)
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn concurrent_channels_share_one_hello() {
+        let (local, url, hellos) = setup(wire::Wire::local_hello_resp).await;
+
+        let responses =
+            futures::future::join_all((0..5).map(|_| local.request(url.clone(), b"data".to_vec())))
+                .await;
+        for response in responses {
+            assert_eq!(b"data".to_vec(), response.unwrap());
+        }
+        assert_eq!(1, hellos.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn failed_hello_is_remembered() {
+        // answering a hello with a hello fails the handshake
+        let (local, url, hellos) = setup(wire::Wire::local_hello).await;
+
+        assert!(local.request(url.clone(), b"data".to_vec()).await.is_err());
+        assert!(local.request(url.clone(), b"data".to_vec()).await.is_err());
+        assert_eq!(1, hellos.load(Ordering::SeqCst));
+    }
+}
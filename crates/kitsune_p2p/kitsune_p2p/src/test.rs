@@ -32,6 +32,76 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn test_space_stats() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        let space = harness.add_space().await?;
+        let (a1, p2p1) = harness.add_direct_agent("one".into()).await?;
+        let (a2, _p2p2) = harness.add_direct_agent("two".into()).await?;
+
+        // needed until we have some way of bootstrapping
+        harness.magic_peer_info_exchange().await?;
+
+        p2p1.rpc_single(space.clone(), a2.clone(), a1.clone(), b"m1".to_vec(), None)
+            .await?;
+
+        let stats = p2p1.space_stats(space).await?;
+        assert_eq!(vec![a1], stats.local_agents);
+        assert_eq!(1, stats.requests.succeeded);
+        let peer = stats.peers.iter().find(|p| p.agent == a2).unwrap();
+        assert!(peer.connected);
+        assert!(peer.expires_at_ms > peer.signed_at_ms);
+        assert_eq!(1, peer.requests.succeeded);
+
+        harness.ghost_actor_shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_block_peer() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        let space = harness.add_space().await?;
+        let (a1, p2p1) = harness.add_direct_agent("one".into()).await?;
+        let (a2, p2p2) = harness.add_direct_agent("two".into()).await?;
+
+        // needed until we have some way of bootstrapping
+        harness.magic_peer_info_exchange().await?;
+
+        p2p1.block_peer(space.clone(), a2.clone(), None).await?;
+        assert!(p2p1
+            .rpc_single(space.clone(), a2.clone(), a1.clone(), b"m1".to_vec(), None)
+            .await
+            .is_err());
+        // nor will we answer it
+        assert!(p2p2
+            .rpc_single(space.clone(), a1.clone(), a2.clone(), b"m1".to_vec(), None)
+            .await
+            .is_err());
+        let stats = p2p1.space_stats(space.clone()).await?;
+        assert!(stats.peers.iter().find(|p| p.agent == a2).unwrap().blocked);
+
+        p2p1.unblock_peer(space.clone(), a2.clone()).await?;
+        p2p1.rpc_single(space.clone(), a2.clone(), a1.clone(), b"m2".to_vec(), None)
+            .await?;
+
+        // bad data counts against a peer, until it gets blocked
+        p2p1.report_invalid_data(space.clone(), a2.clone()).await?;
+        let stats = p2p1.space_stats(space.clone()).await?;
+        let peer = stats.peers.iter().find(|p| p.agent == a2).unwrap();
+        assert!(peer.score < 0.0);
+        assert!(!peer.blocked);
+        p2p1.report_invalid_data(space.clone(), a2.clone()).await?;
+        let stats = p2p1.space_stats(space).await?;
+        assert!(stats.peers.iter().find(|p| p.agent == a2).unwrap().blocked);
+
+        harness.ghost_actor_shutdown().await?;
+        Ok(())
+    }
+
     #[tokio::test(threaded_scheduler)]
     async fn test_transport_multi_coms() -> Result<(), KitsuneP2pError> {
         observability::test_run().ok();
@@ -55,6 +125,8 @@ mod tests {
                 timeout_ms: Some(200),
                 as_race: true,
                 race_timeout_ms: Some(100),
+                strategy: actor::RpcMultiStrategy::FirstN,
+                quorum_key: None,
                 payload: b"test-multi-request".to_vec(),
             })
             .await
@@ -71,6 +143,114 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn test_transport_multi_strategies() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        let space = harness.add_space().await?;
+        let (a1, p2p1) = harness.add_direct_agent("one".into()).await?;
+        let (_a2, _p2p2) = harness.add_direct_agent("two".into()).await?;
+        let (_a3, _p2p3) = harness.add_direct_agent("tre".into()).await?;
+
+        // needed until we have some way of bootstrapping
+        harness.magic_peer_info_exchange().await?;
+
+        let rpc_multi = |strategy| actor::RpcMulti {
+            space: space.clone(),
+            from_agent: a1.clone(),
+            // this is just a dummy value right now
+            basis: TestVal::test_val(),
+            remote_agent_count: Some(2),
+            timeout_ms: Some(200),
+            as_race: false,
+            race_timeout_ms: None,
+            strategy,
+            quorum_key: None,
+            payload: b"test-multi-request".to_vec(),
+        };
+
+        // every agent echoes the same response,
+        // the local agent's is returned but only the remote nodes agree
+        let res = p2p1
+            .rpc_multi(rpc_multi(actor::RpcMultiStrategy::Quorum(2)))
+            .await?;
+        assert_eq!(3, res.len());
+        for r in res {
+            assert_eq!(b"echo: test-multi-request".to_vec(), r.response);
+        }
+
+        // the local agent can't make up a third
+        let res = p2p1
+            .rpc_multi(rpc_multi(actor::RpcMultiStrategy::Quorum(3)))
+            .await;
+        assert_matches::assert_matches!(
+            res,
+            Err(KitsuneP2pError::QuorumNotReached {
+                agreeing: 2,
+                quorum: 3
+            })
+        );
+
+        // no more agents than MAX_QUORUM are asked, so that is all we wait for
+        let res = p2p1
+            .rpc_multi(rpc_multi(actor::RpcMultiStrategy::Quorum(u8::MAX)))
+            .await;
+        assert_matches::assert_matches!(
+            res,
+            Err(KitsuneP2pError::QuorumNotReached {
+                agreeing: 2,
+                quorum: actor::MAX_QUORUM
+            })
+        );
+
+        // the local agent plus both remotes
+        let res = p2p1
+            .rpc_multi(rpc_multi(actor::RpcMultiStrategy::AllWithDeadline))
+            .await?;
+        assert_eq!(3, res.len());
+
+        harness.ghost_actor_shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_group_bases_by_authority() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        let space = harness.add_space().await?;
+        let (a1, p2p1) = harness.add_direct_agent("one".into()).await?;
+        let (a2, _p2p2) = harness.add_direct_agent("two".into()).await?;
+        let (a3, _p2p3) = harness.add_direct_agent("tre".into()).await?;
+
+        // needed until we have some way of bootstrapping
+        harness.magic_peer_info_exchange().await?;
+
+        let bases: Vec<Arc<KitsuneBasis>> = (0..5).map(|_| TestVal::test_val()).collect();
+
+        // every agent holds the full arc, so one of them can answer for all
+        let res = p2p1
+            .group_bases_by_authority(space.clone(), a1.clone(), bases.clone())
+            .await?;
+        assert_eq!(1, res.groups.len());
+        assert_ne!(a1, res.groups[0].0);
+        assert_eq!(5, res.groups[0].1.len());
+        assert!(res.unassigned.is_empty());
+
+        // with the other agents blocked nobody is left to ask
+        p2p1.block_peer(space.clone(), a2, None).await?;
+        p2p1.block_peer(space.clone(), a3, None).await?;
+        let res = p2p1
+            .group_bases_by_authority(space.clone(), a1.clone(), bases)
+            .await?;
+        assert!(res.groups.is_empty());
+        assert_eq!(5, res.unassigned.len());
+
+        harness.ghost_actor_shutdown().await?;
+        Ok(())
+    }
+
     #[tokio::test(threaded_scheduler)]
     async fn test_transport_notify_coms() -> Result<(), KitsuneP2pError> {
         observability::test_run().ok();
@@ -168,6 +348,100 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn test_large_call_is_compressed() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        let space = harness.add_space().await?;
+        let (a1, p2p1) = harness.add_direct_agent("one".into()).await?;
+        let (a2, _p2p2) = harness.add_direct_agent("two".into()).await?;
+
+        harness.magic_peer_info_exchange().await?;
+
+        use crate::metrics::KitsuneMetrics;
+        crate::metrics::enable();
+        let saved_before = KitsuneMetrics::get(KitsuneMetrics::CompressionSaved);
+
+        // both the call and the echo are well over the compression threshold
+        let data = vec![b'a'; 100_000];
+        let res = p2p1.rpc_single(space, a2, a1, data.clone(), None).await?;
+        assert_eq!([&b"echo: "[..], &data[..]].concat(), res);
+
+        // and both compress to a fraction of their size
+        let saved = KitsuneMetrics::get(KitsuneMetrics::CompressionSaved) - saved_before;
+        assert!(saved > 2 * 90_000, "only saved {} bytes", saved);
+
+        harness.ghost_actor_shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_rpc_stream() -> Result<(), KitsuneP2pError> {
+        use futures::stream::StreamExt;
+        observability::test_run().ok();
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        let space = harness.add_space().await?;
+        let (a1, p2p1) = harness.add_direct_agent("one".into()).await?;
+        let (a2, _p2p2) = harness.add_direct_agent("two".into()).await?;
+
+        harness.magic_peer_info_exchange().await?;
+
+        // the echo is several chunks long
+        let data = vec![b'a'; 300_000];
+        let chunks: Vec<Vec<u8>> = p2p1
+            .rpc_stream(space.clone(), a2.clone(), a1.clone(), data.clone(), None)
+            .await?
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        let chunk_size = KitsuneP2pTuningParams::default().rpc_stream_chunk_size_bytes as usize;
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.len() <= chunk_size));
+        assert_eq!([&b"echo: "[..], &data[..]].concat(), chunks.concat());
+
+        // small responses are a single chunk
+        let chunks: Vec<_> = p2p1
+            .rpc_stream(space, a2, a1, b"hello".to_vec(), None)
+            .await?
+            .collect()
+            .await;
+        assert_eq!(1, chunks.len());
+        assert_eq!(b"echo: hello".to_vec(), *chunks[0].as_ref().unwrap());
+
+        harness.ghost_actor_shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_tcp_transport_request() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+
+        let (harness, _evt) = spawn_test_harness_tcp().await?;
+        let space = harness.add_space().await?;
+        let (a1, p2p1) = harness.add_direct_agent("one".into()).await?;
+        let (a2, _p2p2) = harness.add_direct_agent("two".into()).await?;
+
+        // the proxy binding runs over tcp
+        let bindings = p2p1.list_transport_bindings().await?;
+        assert_eq!(1, bindings.len());
+        assert_eq!(
+            "kitsune-tcp",
+            bindings[0].path_segments().unwrap().next().unwrap()
+        );
+
+        harness.magic_peer_info_exchange().await?;
+
+        let res = p2p1
+            .rpc_single(space, a2, a1, b"hello".to_vec(), None)
+            .await?;
+        assert_eq!(b"echo: hello".to_vec(), res);
+
+        harness.ghost_actor_shutdown().await?;
+        Ok(())
+    }
+
     #[tokio::test(threaded_scheduler)]
     async fn test_request_workflow() -> Result<(), KitsuneP2pError> {
         observability::test_run().ok();
@@ -263,6 +537,8 @@ mod tests {
                 timeout_ms: Some(20),
                 as_race: true,
                 race_timeout_ms: Some(20),
+                strategy: actor::RpcMultiStrategy::FirstN,
+                quorum_key: None,
                 payload: b"test-multi-request".to_vec(),
             })
             .await
@@ -299,6 +575,8 @@ mod tests {
                 timeout_ms: Some(20),
                 as_race: true,
                 race_timeout_ms: Some(20),
+                strategy: actor::RpcMultiStrategy::FirstN,
+                quorum_key: None,
                 payload: b"test-multi-request".to_vec(),
             })
             .await
@@ -354,6 +632,79 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn test_lan_discovery() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        harness.add_space().await?;
+        let (a1, _) = harness.add_lan_agent("one".into()).await?;
+        let (a2, _) = harness.add_lan_agent("two".into()).await?;
+
+        // no bootstrap service or magic exchange,
+        // the agents only hear of each other over multicast
+        let mut found = false;
+        for _ in 0..100 {
+            let res1 = harness.dump_local_peer_data(a1.clone()).await?;
+            let res2 = harness.dump_local_peer_data(a2.clone()).await?;
+            if res1.contains_key(&a2) && res2.contains_key(&a1) {
+                found = true;
+                break;
+            }
+            tokio::time::delay_for(std::time::Duration::from_millis(50)).await;
+        }
+        assert!(found, "lan agents never discovered each other");
+
+        harness.ghost_actor_shutdown().await.unwrap();
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_seed_from_peer_store() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        let space = harness.add_space().await?;
+        let (a1, p2p1) = harness.add_direct_agent("one".into()).await?;
+        let (a2, _) = harness.add_direct_agent("two".into()).await?;
+        let (a3, _) = harness.add_direct_agent("three".into()).await?;
+
+        // one only has two in its peer store, and only two knows three
+        let two_info = harness.dump_local_peer_data(a2.clone()).await?;
+        let three_info = harness.dump_local_peer_data(a3.clone()).await?;
+        harness.inject_local_peer_data(a1.clone(), two_info).await?;
+        harness
+            .inject_local_peer_data(a2.clone(), three_info)
+            .await?;
+        assert!(!harness
+            .dump_local_peer_data(a1.clone())
+            .await?
+            .contains_key(&a3));
+
+        // joining another agent on one's node asks the stored peers
+        // for the agents near it, no bootstrap service needed
+        let a4: Arc<KitsuneAgent> = TestVal::test_val();
+        p2p1.join(space, a4).await?;
+
+        let mut seeded = None;
+        for _ in 0..100 {
+            let res = harness.dump_local_peer_data(a1.clone()).await?;
+            if res.contains_key(&a3) {
+                seeded = Some(res);
+                break;
+            }
+            tokio::time::delay_for(std::time::Duration::from_millis(50)).await;
+        }
+        let seeded = seeded.expect("three was never seeded from two");
+        assert!(seeded.contains_key(&a1));
+        assert!(seeded.contains_key(&a2));
+
+        harness.ghost_actor_shutdown().await.unwrap();
+        Ok(())
+    }
+
     #[tokio::test(threaded_scheduler)]
     async fn test_peer_data_workflow() -> Result<(), KitsuneP2pError> {
         observability::test_run().ok();
@@ -385,6 +736,40 @@ mod tests {
         Ok(())
     }
 
+    /// Test that gossip with a node we've already talked to
+    /// only exchanges hashes for the buckets that differ.
+    #[tokio::test(threaded_scheduler)]
+    async fn test_gossip_buckets() -> Result<(), KitsuneP2pError> {
+        observability::test_run().ok();
+        let (harness, _evt) = spawn_test_harness_mem().await?;
+
+        harness.add_space().await?;
+        let (a1, _) = harness.add_direct_agent("one".into()).await?;
+        let (a2, _) = harness.add_direct_agent("two".into()).await?;
+        let op1 = harness
+            .inject_gossip_data(a1.clone(), "agent-1-data".to_string())
+            .await?;
+
+        // needed until we have some way of bootstrapping
+        harness.magic_peer_info_exchange().await?;
+        tokio::time::delay_for(std::time::Duration::from_millis(200)).await;
+        let res = harness.dump_local_gossip_data(a2.clone()).await?;
+        assert!(res.contains_key(&op1));
+
+        // the nodes have agreed a protocol by now, so this
+        // gets reconciled from bucket summaries
+        let op2 = harness
+            .inject_gossip_data(a2.clone(), "agent-2-data".to_string())
+            .await?;
+        tokio::time::delay_for(std::time::Duration::from_millis(200)).await;
+        let res = harness.dump_local_gossip_data(a1.clone()).await?;
+        assert_eq!(Some(&"agent-2-data".to_string()), res.get(&op2));
+        assert_eq!(2, res.len());
+
+        harness.ghost_actor_shutdown().await?;
+        Ok(())
+    }
+
     /// Test that we can gossip across a in memory transport layer.
     #[tokio::test(threaded_scheduler)]
     async fn test_gossip_transport() -> Result<(), KitsuneP2pError> {
@@ -21,6 +21,13 @@ ghost_actor::ghost_chan! {
             ghost_actor::GhostSender<KitsuneP2p>,
         );
 
+        /// Create a new directly addressable agent that discovers
+        /// peers multicasting on the loopback interface.
+        fn add_lan_agent(nick: String) -> (
+            Arc<KitsuneAgent>,
+            ghost_actor::GhostSender<KitsuneP2p>,
+        );
+
         /// Create a new agent that will connect via proxy.
         fn add_nat_agent(nick: String, proxy_url: url2::Url2) -> (
             Arc<KitsuneAgent>,
@@ -38,6 +45,9 @@ ghost_actor::ghost_chan! {
 
         /// Dump all local peer data from a specific agent
         fn dump_local_peer_data(agent: Arc<KitsuneAgent>) -> HashMap<Arc<KitsuneAgent>, Arc<AgentInfoSigned>>;
+
+        /// Add peer data to the peer store of a specific agent
+        fn inject_local_peer_data(agent: Arc<KitsuneAgent>, info: HashMap<Arc<KitsuneAgent>, Arc<AgentInfoSigned>>) -> ();
     }
 }
 
@@ -49,7 +59,10 @@ pub async fn spawn_test_harness_mem() -> Result<
     ),
     KitsuneP2pError,
 > {
-    spawn_test_harness(TransportConfig::Mem {}).await
+    spawn_test_harness(TransportConfig::Mem {
+        simulated_network: None,
+    })
+    .await
 }
 
 /// construct a test suite around a quic transport
@@ -68,6 +81,22 @@ pub async fn spawn_test_harness_quic() -> Result<
     .await
 }
 
+/// construct a test suite around a tcp transport
+pub async fn spawn_test_harness_tcp() -> Result<
+    (
+        ghost_actor::GhostSender<HarnessControlApi>,
+        HarnessEventChannel,
+    ),
+    KitsuneP2pError,
+> {
+    spawn_test_harness(TransportConfig::Tcp {
+        bind_to: Some(url2::url2!("kitsune-tcp://0.0.0.0:0")),
+        override_host: None,
+        override_port: None,
+    })
+    .await
+}
+
 /// construct a test suite around a sub transport config concept
 pub async fn spawn_test_harness(
     sub_config: TransportConfig,
@@ -120,6 +149,9 @@ struct HarnessActor {
             ghost_actor::GhostSender<HarnessAgentControl>,
         ),
     >,
+    /// The port lan agents of this harness multicast on,
+    /// so parallel tests don't hear each other.
+    lan_port: Option<u16>,
 }
 
 impl HarnessActor {
@@ -134,6 +166,7 @@ impl HarnessActor {
             sub_config,
             space_list: Vec::new(),
             agents: HashMap::new(),
+            lan_port: None,
         }
     }
 }
@@ -258,6 +291,51 @@ impl HarnessControlApiHandler for HarnessActor {
         .into())
     }
 
+    fn handle_add_lan_agent(
+        &mut self,
+        nick: String,
+    ) -> HarnessControlApiHandlerResult<(Arc<KitsuneAgent>, ghost_actor::GhostSender<KitsuneP2p>)>
+    {
+        let mut lan_agent_config = KitsuneP2pConfig::default();
+        lan_agent_config
+            .transport_pool
+            .push(TransportConfig::Proxy {
+                sub_transport: Box::new(self.sub_config.clone()),
+                proxy_config: ProxyConfig::LocalProxyServer {
+                    proxy_accept_config: Some(ProxyAcceptConfig::RejectAll),
+                },
+            });
+        let port = match self.lan_port {
+            Some(port) => port,
+            None => {
+                // let the os pick a port that's free right now
+                let port = std::net::UdpSocket::bind((std::net::Ipv4Addr::LOCALHOST, 0))?
+                    .local_addr()?
+                    .port();
+                self.lan_port = Some(port);
+                port
+            }
+        };
+        lan_agent_config.lan_discovery = Some(LanDiscoveryConfig {
+            interface: std::net::Ipv4Addr::LOCALHOST,
+            port,
+            announce_interval_ms: 100,
+            ..Default::default()
+        });
+
+        let sub_harness = self.harness_chan.sub_clone(nick);
+        let i_s = self.i_s.clone();
+        Ok(async move {
+            let (agent, p2p, ctrl) = spawn_test_agent(sub_harness, lan_agent_config).await?;
+
+            i_s.finish_agent(agent.clone(), p2p.clone(), ctrl).await?;
+
+            Ok((agent, p2p))
+        }
+        .boxed()
+        .into())
+    }
+
     fn handle_add_nat_agent(
         &mut self,
         nick: String,
@@ -345,4 +423,17 @@ impl HarnessControlApiHandler for HarnessActor {
         let fut = ctrl.dump_local_peer_data();
         Ok(async move { fut.await }.boxed().into())
     }
+
+    fn handle_inject_local_peer_data(
+        &mut self,
+        agent: Arc<KitsuneAgent>,
+        info: HashMap<Arc<KitsuneAgent>, Arc<AgentInfoSigned>>,
+    ) -> HarnessControlApiHandlerResult<()> {
+        let (_, ctrl) = self
+            .agents
+            .get(&agent)
+            .ok_or_else(|| KitsuneP2pError::from("invalid agent"))?;
+        let fut = ctrl.inject_agent_info(info);
+        Ok(async move { fut.await }.boxed().into())
+    }
 }
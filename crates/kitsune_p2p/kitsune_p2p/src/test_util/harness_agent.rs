@@ -54,6 +54,7 @@ pub(crate) async fn spawn_test_agent(
     Ok((agent, p2p, control))
 }
 
+use crate::types::gossip::TimeWindow;
 use lair_keystore_api::entry::EntrySignEd25519;
 use lair_keystore_api::internal::sign_ed25519::*;
 
@@ -134,7 +135,8 @@ impl KitsuneP2pEventHandler for AgentHarness {
         input: PutAgentInfoSignedEvt,
     ) -> KitsuneP2pEventHandlerResult<()> {
         let info = Arc::new(input.agent_info_signed);
-        self.agent_store.insert(input.agent.clone(), info.clone());
+        self.agent_store
+            .insert(Arc::new(info.as_agent_ref().clone()), info.clone());
         self.harness_chan.publish(HarnessEventType::StoreAgentInfo {
             agent: (&input.agent).into(),
             agent_info: info,
@@ -163,6 +165,7 @@ impl KitsuneP2pEventHandler for AgentHarness {
         space: Arc<super::KitsuneSpace>,
         to_agent: Arc<super::KitsuneAgent>,
         from_agent: Arc<super::KitsuneAgent>,
+        _from_node: Option<url2::Url2>,
         payload: Vec<u8>,
     ) -> KitsuneP2pEventHandlerResult<Vec<u8>> {
         let data = String::from_utf8_lossy(&payload);
@@ -182,6 +185,7 @@ impl KitsuneP2pEventHandler for AgentHarness {
         space: Arc<super::KitsuneSpace>,
         to_agent: Arc<super::KitsuneAgent>,
         from_agent: Arc<super::KitsuneAgent>,
+        _from_node: Option<url2::Url2>,
         payload: Vec<u8>,
     ) -> KitsuneP2pEventHandlerResult<()> {
         let data = String::from_utf8_lossy(&payload);
@@ -221,6 +225,20 @@ impl KitsuneP2pEventHandler for AgentHarness {
         Ok(async move { Ok(hashes) }.boxed().into())
     }
 
+    fn handle_fetch_op_hash_times_for_constraints(
+        &mut self,
+        input: FetchOpHashTimesForConstraintsEvt,
+    ) -> KitsuneP2pEventHandlerResult<Vec<(Arc<super::KitsuneOpHash>, i64)>> {
+        let window = TimeWindow::new(input.since_utc_epoch_s, input.until_utc_epoch_s);
+        let hashes: Vec<(Arc<super::KitsuneOpHash>, i64)> = self
+            .gossip_store
+            .keys()
+            .map(|h| (h.clone(), harness_authored_at(h)))
+            .filter(|(_, t)| window.contains(*t))
+            .collect();
+        Ok(async move { Ok(hashes) }.boxed().into())
+    }
+
     fn handle_fetch_op_hash_data(
         &mut self,
         input: FetchOpHashDataEvt,
@@ -248,3 +266,10 @@ impl KitsuneP2pEventHandler for AgentHarness {
         .into())
     }
 }
+
+/// The harness has no authoring times for its gossip data,
+/// so spread it over a year by a time every agent can derive from the hash.
+fn harness_authored_at(op_hash: &KitsuneOpHash) -> i64 {
+    const YEAR_S: i64 = 60 * 60 * 24 * 365;
+    1_600_000_000 + op_hash.get_loc() as i64 % YEAR_S
+}
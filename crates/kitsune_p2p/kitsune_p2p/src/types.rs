@@ -44,6 +44,32 @@ pub enum KitsuneP2pError {
     #[error(transparent)]
     TryFromInt(#[from] std::num::TryFromIntError),
 
+    /// rpc_multi didn't get enough agreeing responses.
+    #[error("Quorum not reached: {agreeing} nodes agreed, {quorum} needed")]
+    QuorumNotReached {
+        /// The most remote nodes that gave agreeing responses.
+        agreeing: usize,
+        /// How many needed to agree.
+        quorum: u8,
+    },
+
+    /// The remote agent is serving too many of our requests,
+    /// and won't take more of this type for a while.
+    #[error("Busy, retry after {retry_after_ms}ms")]
+    Busy {
+        /// How long to wait before asking again.
+        retry_after_ms: u64,
+    },
+
+    /// The remote node couldn't get our request to the agent, e.g. because
+    /// the agent isn't there. Older nodes report every failure this way.
+    #[error("Remote Failure: {0}")]
+    RemoteFailure(Box<str>),
+
+    /// The remote agent got our request, but refused it or failed to handle it.
+    #[error("Rejected: {0}")]
+    Rejected(Box<str>),
+
     /// Other
     #[error("Other: {0}")]
     Other(Box<dyn std::error::Error + Send + Sync>),
@@ -219,6 +245,7 @@ pub mod actor;
 pub mod agent_store;
 pub mod event;
 pub mod gossip;
+pub mod request_quota;
 pub(crate) mod wire;
 
 pub use kitsune_p2p_types::dht_arc;
@@ -25,10 +25,56 @@ pub struct RpcMulti {
     /// See `as_race` for details.
     /// Set to `None` for a default "best-effort" race.
     pub race_timeout_ms: Option<u64>,
+    /// How the responses are aggregated.
+    pub strategy: RpcMultiStrategy,
+    /// What responses must agree on to count toward a
+    /// [RpcMultiStrategy::Quorum], the whole response if `None`.
+    pub quorum_key: Option<QuorumKey>,
     /// Request data.
     pub payload: Vec<u8>,
 }
 
+/// Picks out the part of a response that agents must agree on
+/// for a quorum, e.g. the hash of the data in it, so responses
+/// that only differ in incidental details still agree.
+#[derive(Clone)]
+pub struct QuorumKey(pub Arc<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>);
+
+impl std::fmt::Debug for QuorumKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("QuorumKey").finish()
+    }
+}
+
+/// The most remote agents `rpc_multi` asks near a basis,
+/// and so the largest [RpcMultiStrategy::Quorum] it can reach.
+pub const MAX_QUORUM: u8 = 5;
+
+/// How `rpc_multi` decides it has heard enough, and what it returns.
+/// Responses from agents joined locally are returned like any others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpcMultiStrategy {
+    /// Return as soon as `remote_agent_count` agents have responded,
+    /// or with whatever responses we have once half the timeout has passed.
+    FirstN,
+    /// Return once this many remote nodes have given agreeing responses,
+    /// with only the agreeing responses. Agents joined locally, and
+    /// further agents on a node that already responded, don't count.
+    /// Fails with [crate::KitsuneP2pError::QuorumNotReached]
+    /// if they haven't by the timeout. At most [MAX_QUORUM] agents
+    /// are asked, so a larger quorum is lowered to that.
+    Quorum(u8),
+    /// Wait for `remote_agent_count` agents to respond,
+    /// returning whatever responses we have at the timeout.
+    AllWithDeadline,
+}
+
+impl Default for RpcMultiStrategy {
+    fn default() -> Self {
+        RpcMultiStrategy::FirstN
+    }
+}
+
 /// A response type helps indicate what agent gave what response.
 #[derive(Clone, Debug)]
 pub struct RpcMultiResponse {
@@ -38,6 +84,11 @@ pub struct RpcMultiResponse {
     pub response: Vec<u8>,
 }
 
+/// The response to a call, as the chunks it arrives in.
+/// Concatenated, the chunks are the response the remote gave.
+/// The stream ends after the last chunk, or the first error.
+pub type RpcStream = futures::stream::BoxStream<'static, KitsuneP2pResult<Vec<u8>>>;
+
 /// Publish data to a "neighborhood" of remote nodes surrounding the "basis" hash.
 /// Returns an approximate number of nodes reached.
 #[derive(Clone, Debug)]
@@ -63,6 +114,86 @@ pub struct NotifyMulti {
     pub payload: Vec<u8>,
 }
 
+/// Bases grouped by a remote agent that claims to hold all of them,
+/// so each group can be requested from that agent in a single call.
+#[derive(Clone, Debug, Default)]
+pub struct AuthorityGroups {
+    /// Each agent with the bases it should be asked for.
+    /// Every basis appears in at most one group.
+    pub groups: Vec<(Arc<super::KitsuneAgent>, Vec<Arc<super::KitsuneBasis>>)>,
+    /// Bases no known agent claims to hold.
+    pub unassigned: Vec<Arc<super::KitsuneBasis>>,
+}
+
+/// What we know about the network of one space, for debugging.
+#[derive(Clone, Debug)]
+pub struct SpaceStats {
+    /// The agents joined to this space on this node.
+    pub local_agents: Vec<Arc<super::KitsuneAgent>>,
+    /// The remote agents in our peer store for this space.
+    pub peers: Vec<PeerStats>,
+    /// The agents we have gossiped with.
+    pub gossip_partners: Vec<GossipPartnerStats>,
+    /// Outcomes of all the requests we made of remote agents in this space.
+    pub requests: RequestStats,
+}
+
+/// What we know about one remote agent.
+#[derive(Clone, Debug)]
+pub struct PeerStats {
+    /// The remote agent.
+    pub agent: Arc<super::KitsuneAgent>,
+    /// The urls the agent can be reached at.
+    pub urls: Vec<Url2>,
+    /// When the agent signed its agent info, in ms since the unix epoch.
+    pub signed_at_ms: u64,
+    /// When the agent info expires, in ms since the unix epoch.
+    pub expires_at_ms: u64,
+    /// Whether we have a transport connection to the agent,
+    /// i.e. we opened a channel to its url within the
+    /// `transport_connection_idle_timeout_ms` tuning param.
+    pub connected: bool,
+    /// Outcomes of the requests we made of this agent.
+    pub requests: RequestStats,
+    /// How well the agent has answered us lately, positive is good.
+    pub score: f64,
+    /// Whether we are currently refusing to contact the agent.
+    pub blocked: bool,
+}
+
+/// The last gossip round with one agent.
+#[derive(Clone, Debug)]
+pub struct GossipPartnerStats {
+    /// The agent we gossiped with.
+    pub agent: Arc<super::KitsuneAgent>,
+    /// When we last compared op hashes, in ms since the unix epoch.
+    pub last_gossip_at_ms: u64,
+    /// How many op hashes we had when we last compared.
+    pub local_op_count: u64,
+    /// How many op hashes they had when we last compared.
+    pub remote_op_count: u64,
+}
+
+/// Counts of how requests of remote agents turned out.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RequestStats {
+    /// Requests that got a response.
+    pub succeeded: u64,
+    /// Requests that got no response within their timeout.
+    pub timed_out: u64,
+    /// Requests that failed, or got a failure response.
+    pub failed: u64,
+}
+
+impl RequestStats {
+    /// Add another set of counts to these.
+    pub fn add(&mut self, other: &RequestStats) {
+        self.succeeded += other.succeeded;
+        self.timed_out += other.timed_out;
+        self.failed += other.failed;
+    }
+}
+
 ghost_actor::ghost_chan! {
     /// The KitsuneP2pSender allows async remote-control of the KitsuneP2p actor.
     pub chan KitsuneP2p<super::KitsuneP2pError> {
@@ -79,6 +210,13 @@ ghost_actor::ghost_chan! {
         /// The remote side will receive a "Call" event.
         fn rpc_single(space: Arc<super::KitsuneSpace>, to_agent: Arc<super::KitsuneAgent>, from_agent: Arc<super::KitsuneAgent>, payload: Vec<u8>, timeout_ms: Option<u64>) -> Vec<u8>;
 
+        /// Make a request of a single remote agent, streaming the response
+        /// in chunks of the `rpc_stream_chunk_size_bytes` tuning param.
+        /// Each chunk is requested separately, with `timeout_ms`, so large
+        /// responses don't need to arrive within one timeout.
+        /// The remote side will receive a "Call" event.
+        fn rpc_stream(space: Arc<super::KitsuneSpace>, to_agent: Arc<super::KitsuneAgent>, from_agent: Arc<super::KitsuneAgent>, payload: Vec<u8>, timeout_ms: Option<u64>) -> RpcStream;
+
         /// Make a request to multiple destination agents - awaiting/aggregating the responses.
         /// The remote sides will see these messages as "Call" events.
         fn rpc_multi(input: RpcMulti) -> Vec<RpcMultiResponse>;
@@ -87,5 +225,24 @@ ghost_actor::ghost_chan! {
         /// Returns an approximate number of nodes reached.
         /// The remote sides will see these messages as "Notify" events.
         fn notify_multi(input: NotifyMulti) -> u8;
+
+        /// Group bases by the agents whose dht arcs cover them, using as few
+        /// agents as we can, so many bases can be requested in few calls.
+        /// Blocked agents, `from_agent` and our other local agents are never chosen.
+        fn group_bases_by_authority(space: Arc<super::KitsuneSpace>, from_agent: Arc<super::KitsuneAgent>, bases: Vec<Arc<super::KitsuneBasis>>) -> AuthorityGroups;
+
+        /// Get what we know about the network of a space this node has joined.
+        fn space_stats(space: Arc<super::KitsuneSpace>) -> SpaceStats;
+
+        /// Report that a remote agent sent us data that failed validation,
+        /// counting against its reputation in this space.
+        fn report_invalid_data(space: Arc<super::KitsuneSpace>, agent: Arc<super::KitsuneAgent>) -> ();
+
+        /// Stop contacting a remote agent in this space for `duration_ms`,
+        /// or the `peer_block_duration_ms` tuning param if None.
+        fn block_peer(space: Arc<super::KitsuneSpace>, agent: Arc<super::KitsuneAgent>, duration_ms: Option<u64>) -> ();
+
+        /// Lift any block on a remote agent in this space.
+        fn unblock_peer(space: Arc<super::KitsuneSpace>, agent: Arc<super::KitsuneAgent>) -> ();
     }
 }
@@ -1,9 +1,12 @@
 //! Data structures to be stored in the agent/peer database.
 
 use crate::types::KitsuneAgent;
+use crate::types::KitsuneBinType;
 use crate::types::KitsuneP2pError;
 use crate::types::KitsuneSignature;
 use crate::types::KitsuneSpace;
+use kitsune_p2p_types::dht_arc::DhtArc;
+use kitsune_p2p_types::dht_arc::MAX_HALF_LENGTH;
 use url2::Url2;
 
 /// A list of Urls.
@@ -85,6 +88,11 @@ pub struct AgentInfo {
     // The expiry ttl for the agent info relative to the signing time.
     #[as_ref(ignore)]
     expires_after_ms: u64,
+    // The arc of the dht this agent claims to hold.
+    // Infos signed before arcs were advertised don't include one.
+    #[as_ref(ignore)]
+    #[serde(default)]
+    dht_arc: Option<DhtArc>,
 }
 
 impl std::convert::TryFrom<&AgentInfoSigned> for AgentInfo {
@@ -111,8 +119,15 @@ impl AgentInfo {
             urls,
             signed_at_ms,
             expires_after_ms,
+            dht_arc: None,
         }
     }
+
+    /// Advertise the arc of the dht this agent holds.
+    pub fn with_dht_arc(mut self, dht_arc: DhtArc) -> Self {
+        self.dht_arc = Some(dht_arc);
+        self
+    }
 }
 
 impl AsRef<[Url2]> for AgentInfo {
@@ -146,6 +161,14 @@ impl AgentInfo {
     pub fn expires_after_ms(&self) -> u64 {
         self.expires_after_ms
     }
+
+    /// The arc of the dht this agent holds.
+    /// Agents that don't advertise an arc are assumed to hold
+    /// the full arc, centered on their own location.
+    pub fn dht_arc(&self) -> DhtArc {
+        self.dht_arc
+            .unwrap_or_else(|| DhtArc::new(self.agent.get_loc(), MAX_HALF_LENGTH))
+    }
 }
 
 impl From<AgentInfoSigned> for KitsuneAgent {
@@ -18,6 +18,22 @@ pub struct FetchOpHashesForConstraintsEvt {
     pub until_utc_epoch_s: i64,
 }
 
+/// Gather a list of op-hashes from our implementor, with the time each
+/// op was authored, for ops authored within the constraints.
+#[derive(Debug)]
+pub struct FetchOpHashTimesForConstraintsEvt {
+    /// The "space" context.
+    pub space: Arc<super::KitsuneSpace>,
+    /// The "agent" context.
+    pub agent: Arc<super::KitsuneAgent>,
+    /// The dht arc to query.
+    pub dht_arc: kitsune_p2p_types::dht_arc::DhtArc,
+    /// Only retreive items authored since this time (INCLUSIVE).
+    pub since_utc_epoch_s: i64,
+    /// Only retreive items authored until this time (EXCLUSIVE).
+    pub until_utc_epoch_s: i64,
+}
+
 /// Gather all op-hash data for a list of op-hashes from our implementor.
 #[derive(Debug)]
 pub struct FetchOpHashDataEvt {
@@ -84,10 +100,14 @@ ghost_actor::ghost_chan! {
         fn query_agent_info_signed(input: QueryAgentInfoSignedEvt) -> Vec<crate::types::agent_store::AgentInfoSigned>;
 
         /// We are receiving a request from a remote node.
-        fn call(space: Arc<super::KitsuneSpace>, to_agent: Arc<super::KitsuneAgent>, from_agent: Arc<super::KitsuneAgent>, payload: Vec<u8>) -> Vec<u8>;
+        /// `from_node` is the url of the connection it came in on, which
+        /// unlike `from_agent` the sender can't make up, or None if it is
+        /// from an agent on this node.
+        fn call(space: Arc<super::KitsuneSpace>, to_agent: Arc<super::KitsuneAgent>, from_agent: Arc<super::KitsuneAgent>, from_node: Option<url2::Url2>, payload: Vec<u8>) -> Vec<u8>;
 
         /// We are receiving a notification from a remote node.
-        fn notify(space: Arc<super::KitsuneSpace>, to_agent: Arc<super::KitsuneAgent>, from_agent: Arc<super::KitsuneAgent>, payload: Vec<u8>) -> ();
+        /// `from_node` is as for `call`.
+        fn notify(space: Arc<super::KitsuneSpace>, to_agent: Arc<super::KitsuneAgent>, from_agent: Arc<super::KitsuneAgent>, from_node: Option<url2::Url2>, payload: Vec<u8>) -> ();
 
         /// We are receiving a dht op we may need to hold distributed via gossip.
         fn gossip(
@@ -101,6 +121,10 @@ ghost_actor::ghost_chan! {
         /// Gather a list of op-hashes from our implementor that meet criteria.
         fn fetch_op_hashes_for_constraints(input: FetchOpHashesForConstraintsEvt) -> Vec<Arc<super::KitsuneOpHash>>;
 
+        /// Gather a list of op-hashes from our implementor, with the time each op
+        /// was authored in seconds since the unix epoch, that meet criteria.
+        fn fetch_op_hash_times_for_constraints(input: FetchOpHashTimesForConstraintsEvt) -> Vec<(Arc<super::KitsuneOpHash>, i64)>;
+
         /// Gather all op-hash data for a list of op-hashes from our implementor.
         fn fetch_op_hash_data(input: FetchOpHashDataEvt) -> Vec<(Arc<super::KitsuneOpHash>, Vec<u8>)>;
 
@@ -76,6 +76,246 @@ pub enum OpCount {
     Consistent(u64),
 }
 
+#[derive(Debug, derive_more::Constructor)]
+/// Request the hashes of the dht ops a local agent holds,
+/// with the time each op was authored, and agent store information.
+pub struct ReqLocalOpHashTimesEvt {
+    /// The local agent you are requesting ops from.
+    pub agent: Arc<KitsuneAgent>,
+    /// The arc on the dht that you want ops from.
+    pub dht_arc: DhtArc,
+}
+
+#[derive(Debug, derive_more::Constructor)]
+/// Request summaries of the dht op hashes an agent holds,
+/// bucketed by the time each op was authored, and
+/// agent store information.
+pub struct ReqOpHashSummaryEvt {
+    /// Agent Requesting the summaries.
+    pub from_agent: Arc<KitsuneAgent>,
+    /// The agent you are requesting summaries from.
+    pub to_agent: Arc<KitsuneAgent>,
+    /// The arc on the dht that you want ops from.
+    pub dht_arc: DhtArc,
+    /// The windows to summarize, one bucket each.
+    pub windows: Vec<TimeWindow>,
+    /// Also list the hashes in each bucket.
+    pub include_hashes: bool,
+}
+
+/// How many narrower windows gossip splits a window into
+/// when the buckets for it don't match.
+pub const GOSSIP_BUCKET_FANOUT: i64 = 16;
+
+/// Gossip starts comparing windows this many times
+/// the bucket width, split three times to get down to the bucket width.
+const ROOT_WINDOW_BUCKETS: i64 = GOSSIP_BUCKET_FANOUT * GOSSIP_BUCKET_FANOUT * GOSSIP_BUCKET_FANOUT;
+
+/// Gossip never starts with more root windows than this.
+/// Anything older falls in the open ended first window.
+const MAX_ROOT_WINDOWS: i64 = 64;
+
+/// A node summarizes at most this many windows per request.
+/// Gossip sends wider comparisons in several requests.
+pub const MAX_SUMMARY_WINDOWS: usize = 1024;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    derive_more::Constructor,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+/// A span of op authoring times in seconds since the unix epoch.
+pub struct TimeWindow {
+    /// Start of the window (INCLUSIVE), i64::MIN for no start.
+    pub since_utc_epoch_s: i64,
+    /// End of the window (EXCLUSIVE), i64::MAX for no end.
+    pub until_utc_epoch_s: i64,
+}
+
+impl TimeWindow {
+    /// The windows gossip compares first: aligned windows from the oldest op
+    /// we hold until now, plus open ended windows for anything older or newer.
+    pub fn roots(oldest_utc_epoch_s: i64, now_utc_epoch_s: i64, bucket_width_s: i64) -> Vec<Self> {
+        let width = bucket_width_s.max(1) * ROOT_WINDOW_BUCKETS;
+        let until = (now_utc_epoch_s.div_euclid(width) + 1) * width;
+        let oldest = oldest_utc_epoch_s.max(until - width * MAX_ROOT_WINDOWS);
+        let mut since = oldest.min(now_utc_epoch_s).div_euclid(width) * width;
+        let mut out = vec![Self::new(i64::MIN, since)];
+        while since < until {
+            out.push(Self::new(since, since + width));
+            since += width;
+        }
+        out.push(Self::new(until, i64::MAX));
+        out
+    }
+
+    /// True if an op authored at this time is in the window.
+    pub fn contains(&self, utc_epoch_s: i64) -> bool {
+        utc_epoch_s >= self.since_utc_epoch_s && utc_epoch_s < self.until_utc_epoch_s
+    }
+
+    /// Split into [GOSSIP_BUCKET_FANOUT] narrower windows, or None if the window
+    /// is open ended or already no wider than `bucket_width_s`.
+    pub fn split(&self, bucket_width_s: i64) -> Option<Vec<Self>> {
+        if self.since_utc_epoch_s == i64::MIN || self.until_utc_epoch_s == i64::MAX {
+            return None;
+        }
+        let width = self.until_utc_epoch_s - self.since_utc_epoch_s;
+        if width <= bucket_width_s {
+            return None;
+        }
+        let step = ((width + GOSSIP_BUCKET_FANOUT - 1) / GOSSIP_BUCKET_FANOUT).max(bucket_width_s);
+        let mut out = Vec::new();
+        let mut since = self.since_utc_epoch_s;
+        while since < self.until_utc_epoch_s {
+            let until = (since + step).min(self.until_utc_epoch_s);
+            out.push(Self::new(since, until));
+            since = until;
+        }
+        Some(out)
+    }
+
+    /// Check windows asked to be summarized are few enough, and
+    /// in order without overlapping, so they can be bucketed in one pass.
+    pub fn check_summary_windows(windows: &[Self]) -> Result<(), String> {
+        if windows.len() > MAX_SUMMARY_WINDOWS {
+            return Err(format!(
+                "asked to summarize {} windows, the limit is {}",
+                windows.len(),
+                MAX_SUMMARY_WINDOWS
+            ));
+        }
+        if windows
+            .iter()
+            .any(|w| w.since_utc_epoch_s >= w.until_utc_epoch_s)
+        {
+            return Err("asked to summarize an empty window".into());
+        }
+        if windows
+            .windows(2)
+            .any(|pair| pair[0].until_utc_epoch_s > pair[1].since_utc_epoch_s)
+        {
+            return Err("asked to summarize windows out of order or overlapping".into());
+        }
+        Ok(())
+    }
+}
+
+/// Dht op hashes sorted by the time each op was authored,
+/// so the ops in any window can be found without scanning them all.
+#[derive(Debug, Clone, Default)]
+pub struct OpHashTimeline(Vec<(Arc<KitsuneOpHash>, i64)>);
+
+impl OpHashTimeline {
+    /// Sort the ops by when they were authored.
+    pub fn new(mut op_hash_times: Vec<(Arc<KitsuneOpHash>, i64)>) -> Self {
+        op_hash_times.sort_by_key(|(_, t)| *t);
+        Self(op_hash_times)
+    }
+
+    /// How many ops there are.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// True if there are no ops.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// When the oldest op was authored.
+    pub fn oldest(&self) -> Option<i64> {
+        self.0.first().map(|(_, t)| *t)
+    }
+
+    /// The ops authored in the window, oldest first.
+    pub fn in_window(&self, window: &TimeWindow) -> &[(Arc<KitsuneOpHash>, i64)] {
+        let start = self.first_at_or_after(window.since_utc_epoch_s);
+        let end = self.first_at_or_after(window.until_utc_epoch_s);
+        &self.0[start..end.max(start)]
+    }
+
+    /// Summarize the ops authored in each window,
+    /// listing their hashes if `include_hashes`.
+    pub fn summarize(&self, windows: &[TimeWindow], include_hashes: bool) -> Vec<OpHashBucket> {
+        windows
+            .iter()
+            .map(|window| OpHashBucket::new(*window, self.in_window(window), include_hashes))
+            .collect()
+    }
+
+    fn first_at_or_after(&self, utc_epoch_s: i64) -> usize {
+        self.0
+            .binary_search_by(|(_, t)| {
+                if *t < utc_epoch_s {
+                    std::cmp::Ordering::Less
+                } else {
+                    std::cmp::Ordering::Greater
+                }
+            })
+            .unwrap_or_else(|i| i)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+/// A compact summary of the dht op hashes an agent holds
+/// that were authored in one time window. Two agents hold
+/// the same ops in a window if their buckets match.
+pub struct OpHashBucket {
+    /// The window summarized.
+    pub window: TimeWindow,
+    /// How many ops were authored in the window.
+    pub count: u64,
+    /// The XOR of the hashes of those ops.
+    #[serde(with = "serde_bytes")]
+    pub fingerprint: Vec<u8>,
+    /// The hashes themselves, if they were asked for.
+    pub op_hashes: OpHashes,
+}
+
+impl OpHashBucket {
+    /// Summarize the ops authored in a window,
+    /// listing their hashes if `include_hashes`.
+    fn new(
+        window: TimeWindow,
+        op_hash_times: &[(Arc<KitsuneOpHash>, i64)],
+        include_hashes: bool,
+    ) -> Self {
+        let mut bucket = Self {
+            window,
+            count: 0,
+            fingerprint: Vec::new(),
+            op_hashes: Vec::new(),
+        };
+        for (hash, _) in op_hash_times {
+            bucket.count += 1;
+            if bucket.fingerprint.len() < hash.len() {
+                bucket.fingerprint.resize(hash.len(), 0);
+            }
+            for (f, b) in bucket.fingerprint.iter_mut().zip(hash.iter()) {
+                *f ^= b;
+            }
+            if include_hashes {
+                bucket.op_hashes.push(hash.clone());
+            }
+        }
+        bucket
+    }
+
+    /// True if both buckets summarize the same ops.
+    pub fn matches(&self, other: &Self) -> bool {
+        self.window == other.window
+            && self.count == other.count
+            && self.fingerprint == other.fingerprint
+    }
+}
+
 /// Dht Op hashes that an agent holds
 pub type OpHashes = Vec<Arc<KitsuneOpHash>>;
 
@@ -85,6 +325,14 @@ pub type OpHashesAgentHashes = (OpConsistency, Vec<(Arc<KitsuneAgent>, u64)>);
 /// Dht op and agent hashes that the agent has information on.
 /// Same as [OpHashesAgentHashes] but without consistency information.
 pub type LocalOpHashesAgentHashes = (OpHashes, Vec<(Arc<KitsuneAgent>, u64)>);
+/// Dht op hashes that a local agent holds, with the time each op was
+/// authored, and the agent hashes it has information on.
+pub type LocalOpHashTimesAgentHashes = (
+    Vec<(Arc<KitsuneOpHash>, i64)>,
+    Vec<(Arc<KitsuneAgent>, u64)>,
+);
+/// Dht op hash summaries and the agent hashes the agent has information on.
+pub type OpHashSummaryAgentHashes = (Vec<OpHashBucket>, Vec<(Arc<KitsuneAgent>, u64)>);
 /// The Dht op data and agent store information
 pub type OpDataAgentInfo = (Vec<(Arc<KitsuneOpHash>, Vec<u8>)>, Vec<AgentInfoSigned>);
 /// Local and remote neighbors.
@@ -95,3 +343,91 @@ impl Default for OpCount {
         OpCount::Variance
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_windows_cover_all_time() {
+        let hour = 60 * 60;
+        let root_width = hour * ROOT_WINDOW_BUCKETS;
+        let roots = TimeWindow::roots(root_width * 3 + 5, root_width * 5 + 7, hour);
+        assert_eq!(TimeWindow::new(i64::MIN, root_width * 3), roots[0]);
+        assert_eq!(
+            TimeWindow::new(root_width * 6, i64::MAX),
+            roots[roots.len() - 1]
+        );
+        assert_eq!(5, roots.len());
+        for pair in roots.windows(2) {
+            assert_eq!(pair[0].until_utc_epoch_s, pair[1].since_utc_epoch_s);
+        }
+
+        // open ended windows aren't split
+        assert_eq!(None, roots[0].split(hour));
+        // splitting a root window three times gets down to the bucket width
+        let mut windows = vec![roots[1]];
+        for _ in 0..3 {
+            windows = windows
+                .iter()
+                .flat_map(|w| w.split(hour).unwrap())
+                .collect();
+        }
+        assert_eq!(ROOT_WINDOW_BUCKETS as usize, windows.len());
+        assert!(windows
+            .iter()
+            .all(|w| w.until_utc_epoch_s - w.since_utc_epoch_s == hour));
+        assert_eq!(None, windows[0].split(hour));
+    }
+
+    #[test]
+    fn buckets_match_for_the_same_ops() {
+        let a = Arc::new(KitsuneOpHash(vec![1; 36]));
+        let b = Arc::new(KitsuneOpHash(vec![2; 36]));
+        let c = Arc::new(KitsuneOpHash(vec![3; 36]));
+        let windows = vec![TimeWindow::new(0, 10), TimeWindow::new(10, 20)];
+
+        let ours = OpHashTimeline::new(vec![(a.clone(), 1), (b.clone(), 11)]);
+        let theirs = OpHashTimeline::new(vec![(b.clone(), 11), (c.clone(), 12), (a.clone(), 1)]);
+        let ours = ours.summarize(&windows, false);
+        let theirs = theirs.summarize(&windows, true);
+        assert!(ours[0].matches(&theirs[0]));
+        assert!(!ours[1].matches(&theirs[1]));
+        assert!(ours[1].op_hashes.is_empty());
+        assert_eq!(vec![b, c], theirs[1].op_hashes);
+    }
+
+    #[test]
+    fn timeline_finds_ops_at_window_edges() {
+        let ops: Vec<_> = (0..10)
+            .map(|i| (Arc::new(KitsuneOpHash(vec![i as u8; 36])), i / 2))
+            .collect();
+        let timeline = OpHashTimeline::new(ops.into_iter().rev().collect());
+        assert_eq!(Some(0), timeline.oldest());
+        // since is inclusive and until exclusive
+        let window = timeline.in_window(&TimeWindow::new(1, 3));
+        assert_eq!(4, window.len());
+        assert!(window.iter().all(|(_, t)| *t == 1 || *t == 2));
+        assert!(timeline.in_window(&TimeWindow::new(5, 9)).is_empty());
+        assert_eq!(
+            10,
+            timeline
+                .in_window(&TimeWindow::new(i64::MIN, i64::MAX))
+                .len()
+        );
+    }
+
+    #[test]
+    fn summary_windows_are_checked() {
+        let ok = vec![TimeWindow::new(i64::MIN, 0), TimeWindow::new(0, 10)];
+        assert!(TimeWindow::check_summary_windows(&ok).is_ok());
+        let overlapping = vec![TimeWindow::new(0, 10), TimeWindow::new(5, 15)];
+        assert!(TimeWindow::check_summary_windows(&overlapping).is_err());
+        let out_of_order = vec![TimeWindow::new(10, 20), TimeWindow::new(0, 10)];
+        assert!(TimeWindow::check_summary_windows(&out_of_order).is_err());
+        let too_many: Vec<_> = (0..=MAX_SUMMARY_WINDOWS as i64)
+            .map(|i| TimeWindow::new(i, i + 1))
+            .collect();
+        assert!(TimeWindow::check_summary_windows(&too_many).is_err());
+    }
+}
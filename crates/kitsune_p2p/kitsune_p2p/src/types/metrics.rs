@@ -2,19 +2,27 @@ use ghost_actor::dependencies::tracing;
 
 observability::metrics!(
     KitsuneMetrics,
+    Hello,
+    HelloResp,
     Call,
     CallResp,
+    CallStream,
+    StreamNext,
+    StreamChunk,
     Notify,
     NotifyResp,
     FetchOpHashes,
     FetchOpHashesResp,
+    FetchOpHashSummary,
+    FetchOpHashSummaryResp,
     FetchOpData,
     FetchOpDataResp,
     AgentInfoQuery,
     AgentInfoQueryResp,
     Gossip,
     GossipResp,
-    Fail
+    Fail,
+    CompressionSaved
 );
 
 /// Print all metrics as tracing events
@@ -36,7 +44,8 @@ pub fn print_all_metrics() {
         .expect("Failed to print metrics");
         for (metric, count) in KitsuneMetrics::iter() {
             match metric {
-                Call | Notify | FetchOpHashes | FetchOpData | AgentInfoQuery | Gossip => {
+                Hello | Call | CallStream | StreamNext | Notify | FetchOpHashes
+                | FetchOpHashSummary | FetchOpData | AgentInfoQuery | Gossip => {
                     let percent = if total_writes > 0.0 {
                         count as f64 / total_writes * 100.0
                     } else {
@@ -52,8 +61,16 @@ pub fn print_all_metrics() {
                     )
                     .expect("Failed to print metrics");
                 }
-                CallResp | NotifyResp | FetchOpHashesResp | FetchOpDataResp
-                | AgentInfoQueryResp | GossipResp | Fail => {
+                HelloResp
+                | CallResp
+                | StreamChunk
+                | NotifyResp
+                | FetchOpHashesResp
+                | FetchOpHashSummaryResp
+                | FetchOpDataResp
+                | AgentInfoQueryResp
+                | GossipResp
+                | Fail => {
                     let percent = if total_reads > 0.0 {
                         count as f64 / total_reads * 100.0
                     } else {
@@ -69,6 +86,16 @@ pub fn print_all_metrics() {
                     )
                     .expect("Failed to print metrics");
                 }
+                CompressionSaved => {
+                    writeln!(
+                        out,
+                        "metric: {:?} {}Bytes {:.4}MB",
+                        metric,
+                        count,
+                        count as f64 / 1_000_000.0,
+                    )
+                    .expect("Failed to print metrics");
+                }
             }
         }
         writeln!(
@@ -80,10 +107,26 @@ pub fn print_all_metrics() {
             reads as f64 / 1_000_000.0,
         )
         .expect("Failed to print metrics");
+        writeln!(
+            out,
+            "connections opened: {} closed: {} reused: {}",
+            KitsuneTransportMetrics::get(KitsuneTransportMetrics::ConnectionOpen),
+            KitsuneTransportMetrics::get(KitsuneTransportMetrics::ConnectionClose),
+            KitsuneTransportMetrics::get(KitsuneTransportMetrics::ConnectionReuse),
+        )
+        .expect("Failed to print metrics");
         tracing::trace!(metric = %out);
     }
 }
 
+/// Turn on metrics regardless of `KITSUNE_METRICS`,
+/// e.g. because they are being exported.
+pub fn enable() {
+    if !observability::metrics::is_enabled() {
+        observability::metrics::init();
+    }
+}
+
 /// Turn on metrics if `KITSUNE_METRICS=ON`
 pub fn init() {
     if let Some(km) = std::env::var_os("KITSUNE_METRICS") {
@@ -0,0 +1,256 @@
+//! Limits on the requests a node serves each remote node, by request type,
+//! so a single peer can't saturate the node. Kitsune doesn't know what the
+//! requests of its host are, so the host names the request types and checks
+//! each incoming request here, refusing it with [KitsuneP2pError::Busy] when
+//! over quota. Quotas are kept per connection url rather than per agent, as
+//! a peer can claim to be any agent it likes. Requesters back off from a busy
+//! agent, for that type of request, for the time it asks, see [RequestBackoff].
+
+use crate::actor::KitsuneP2pResult;
+use crate::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How long to ask a requester to wait when it has
+/// too many requests in flight, rather than too many per second.
+const CONCURRENCY_RETRY_AFTER_MS: u64 = 100;
+
+/// Past this many tracked quotas we forget the idle ones.
+const MAX_IDLE_QUOTAS: usize = 1024;
+
+/// A quota is idle once it hasn't been used for this long,
+/// as any rate limit has fully refilled by then.
+const IDLE_AFTER: Duration = Duration::from_secs(1);
+
+/// Past this many backoffs we forget the ones that are over.
+const MAX_EXPIRED_BACKOFFS: usize = 1024;
+
+type QuotaKey = (url2::Url2, String);
+
+struct Quota {
+    /// Requests allowed before we are over the rate.
+    tokens: f64,
+    refilled_at: Instant,
+    in_flight: u32,
+}
+
+/// The quotas of every remote node we serve.
+#[derive(Clone)]
+pub struct RequestQuotas {
+    tuning_params: Arc<KitsuneP2pTuningParams>,
+    quotas: Arc<Mutex<HashMap<QuotaKey, Quota>>>,
+}
+
+impl RequestQuotas {
+    /// Limit requests by the `peer_request_*` tuning params.
+    pub fn new(tuning_params: KitsuneP2pTuningParams) -> Self {
+        Self {
+            tuning_params: Arc::new(tuning_params),
+            quotas: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Count a request of this type, that came in on a connection
+    /// from this url, against the quota of the node that sent it.
+    /// Hold on to the permit until the request has been served.
+    pub fn try_start(
+        &self,
+        from_node: url2::Url2,
+        request_type: &str,
+    ) -> KitsuneP2pResult<RequestPermit> {
+        let limits = self.tuning_params.peer_request_limits(request_type);
+        let now = Instant::now();
+        let mut quotas = self.quotas.lock().unwrap();
+        if quotas.len() > MAX_IDLE_QUOTAS {
+            quotas.retain(|_, q| q.in_flight > 0 || q.refilled_at + IDLE_AFTER > now);
+        }
+        let key = (from_node, request_type.to_string());
+        let quota = quotas.entry(key.clone()).or_insert_with(|| Quota {
+            tokens: limits.rate_per_s as f64,
+            refilled_at: now,
+            in_flight: 0,
+        });
+
+        if limits.max_concurrent > 0 && quota.in_flight >= limits.max_concurrent {
+            return Err(KitsuneP2pError::Busy {
+                retry_after_ms: CONCURRENCY_RETRY_AFTER_MS,
+            });
+        }
+
+        if limits.rate_per_s > 0 {
+            let rate = limits.rate_per_s as f64;
+            let elapsed = now.duration_since(quota.refilled_at).as_secs_f64();
+            quota.tokens = (quota.tokens + elapsed * rate).min(rate);
+            quota.refilled_at = now;
+            if quota.tokens < 1.0 {
+                let retry_after_ms = ((1.0 - quota.tokens) / rate * 1000.0).ceil() as u64;
+                return Err(KitsuneP2pError::Busy {
+                    retry_after_ms: retry_after_ms.max(1),
+                });
+            }
+            quota.tokens -= 1.0;
+        }
+
+        quota.in_flight += 1;
+        Ok(RequestPermit {
+            quotas: self.quotas.clone(),
+            key,
+        })
+    }
+}
+
+/// A request being served within its quota.
+/// Dropping it frees its place in the concurrency limit.
+pub struct RequestPermit {
+    quotas: Arc<Mutex<HashMap<QuotaKey, Quota>>>,
+    key: QuotaKey,
+}
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        if let Some(quota) = self.quotas.lock().unwrap().get_mut(&self.key) {
+            quota.in_flight = quota.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+/// How long each remote agent asked us to wait before sending it
+/// more of a type of request, after refusing one as busy.
+/// Other types of request to the agent go ahead as normal.
+#[derive(Clone, Default)]
+pub struct RequestBackoff {
+    busy_until: Arc<Mutex<HashMap<(Arc<KitsuneAgent>, String), Instant>>>,
+}
+
+impl RequestBackoff {
+    /// Note the outcome of a request of this type to an agent,
+    /// backing off from it if it refused the request as busy.
+    pub fn record<T>(
+        &self,
+        agent: &Arc<KitsuneAgent>,
+        request_type: &str,
+        res: &KitsuneP2pResult<T>,
+    ) {
+        if let Err(KitsuneP2pError::Busy { retry_after_ms }) = res {
+            let now = Instant::now();
+            let mut busy_until = self.busy_until.lock().unwrap();
+            if busy_until.len() > MAX_EXPIRED_BACKOFFS {
+                busy_until.retain(|_, until| *until > now);
+            }
+            busy_until.insert(
+                (agent.clone(), request_type.to_string()),
+                now + Duration::from_millis(*retry_after_ms),
+            );
+        }
+    }
+
+    /// Fails with [KitsuneP2pError::Busy] if the agent
+    /// asked us to wait before sending it this type of request.
+    pub fn check(&self, agent: &Arc<KitsuneAgent>, request_type: &str) -> KitsuneP2pResult<()> {
+        let now = Instant::now();
+        match self
+            .busy_until
+            .lock()
+            .unwrap()
+            .get(&(agent.clone(), request_type.to_string()))
+        {
+            Some(until) if *until > now => Err(KitsuneP2pError::Busy {
+                retry_after_ms: (*until - now).as_millis() as u64,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quotas(rate_per_s: u32, max_concurrent: u32) -> RequestQuotas {
+        let mut tuning_params = KitsuneP2pTuningParams::default();
+        tuning_params.peer_request_rate_limit = rate_per_s;
+        tuning_params.peer_request_concurrency_limit = max_concurrent;
+        tuning_params.peer_request_limit_overrides.clear();
+        tuning_params
+            .peer_request_limit_overrides
+            .insert("peer_request_rate_limit.expensive".to_string(), 1);
+        RequestQuotas::new(tuning_params)
+    }
+
+    #[test]
+    fn limits_rate_per_node_and_type() {
+        let quotas = quotas(3, 0);
+        let a = url2::url2!("kitsune-proxy://a");
+        let b = url2::url2!("kitsune-proxy://b");
+
+        for _ in 0..3 {
+            quotas.try_start(a.clone(), "get").unwrap();
+        }
+        match quotas.try_start(a.clone(), "get") {
+            Err(KitsuneP2pError::Busy { retry_after_ms }) => {
+                assert!(
+                    retry_after_ms > 0 && retry_after_ms <= 334,
+                    "{}",
+                    retry_after_ms
+                )
+            }
+            _ => panic!("expected busy"),
+        }
+
+        // other nodes and request types have their own quotas
+        quotas.try_start(b, "get").unwrap();
+        quotas.try_start(a.clone(), "get_links").unwrap();
+
+        // overridden per request type
+        quotas.try_start(a.clone(), "expensive").unwrap();
+        assert!(quotas.try_start(a, "expensive").is_err());
+    }
+
+    #[test]
+    fn limits_concurrency() {
+        let quotas = quotas(0, 2);
+        let a = url2::url2!("kitsune-proxy://a");
+
+        let p1 = quotas.try_start(a.clone(), "get").unwrap();
+        let _p2 = quotas.try_start(a.clone(), "get").unwrap();
+        assert!(matches!(
+            quotas.try_start(a.clone(), "get"),
+            Err(KitsuneP2pError::Busy { .. })
+        ));
+
+        drop(p1);
+        quotas.try_start(a, "get").unwrap();
+    }
+
+    #[test]
+    fn backs_off_per_agent_and_type() {
+        let backoff = RequestBackoff::default();
+        let a = Arc::new(KitsuneAgent::new(vec![1; 36]));
+        let b = Arc::new(KitsuneAgent::new(vec![2; 36]));
+
+        backoff.record(&a, "get", &KitsuneP2pResult::Ok(()));
+        backoff.check(&a, "get").unwrap();
+
+        let busy: KitsuneP2pResult<()> = Err(KitsuneP2pError::Busy {
+            retry_after_ms: 60_000,
+        });
+        backoff.record(&a, "get", &busy);
+        match backoff.check(&a, "get") {
+            Err(KitsuneP2pError::Busy { retry_after_ms }) => {
+                assert!(retry_after_ms > 50_000, "{}", retry_after_ms)
+            }
+            _ => panic!("expected busy"),
+        }
+
+        // only that agent, and only that type of request, waits
+        backoff.check(&a, "get_links").unwrap();
+        backoff.check(&b, "get").unwrap();
+
+        let done: KitsuneP2pResult<()> = Err(KitsuneP2pError::Busy { retry_after_ms: 0 });
+        backoff.record(&a, "get", &done);
+        backoff.check(&a, "get").unwrap();
+    }
+}
@@ -1,12 +1,92 @@
 //! KitsuneP2p Wire Protocol Encoding Decoding
 
 use crate::agent_store::AgentInfoSigned;
-use crate::types::gossip::{OpConsistency, OpCount};
+use crate::types::gossip::{OpConsistency, OpCount, OpHashBucket, TimeWindow};
 use crate::types::*;
 use derive_more::*;
 use kitsune_p2p_types::dht_arc::DhtArc;
 use std::sync::Arc;
 
+/// The kitsune wire protocol version spoken by this node.
+/// New messages are added behind a feature in [WIRE_FEATURES], which
+/// both nodes must support before the message is sent, so they don't
+/// need a new version. Only bump this for a change that can't be
+/// negotiated as a feature, such as a change to an existing message.
+pub const WIRE_PROTOCOL_VERSION: u32 = 1;
+
+/// The oldest wire protocol version this node can still talk to.
+/// Nodes that predate the hello handshake speak version 1.
+pub const WIRE_MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Nodes with this feature understand [Wire::Compressed] messages.
+pub const WIRE_FEATURE_DEFLATE: &str = "deflate";
+
+/// Nodes with this feature understand [Wire::FetchOpHashSummary] messages.
+pub const WIRE_FEATURE_OP_HASH_SUMMARY: &str = "op_hash_summary";
+
+/// Nodes with this feature understand [Wire::CallStream] and
+/// [Wire::StreamNext] messages.
+pub const WIRE_FEATURE_STREAM: &str = "stream";
+
+/// Nodes with this feature understand [Wire::Busy] and [Wire::Rejected]
+/// responses, other nodes only get a [Wire::Failure].
+pub const WIRE_FEATURE_FAILURE_KINDS: &str = "failure_kinds";
+
+/// Optional protocol features this node supports,
+/// advertised in the hello handshake.
+pub const WIRE_FEATURES: &[&str] = &[
+    WIRE_FEATURE_DEFLATE,
+    WIRE_FEATURE_OP_HASH_SUMMARY,
+    WIRE_FEATURE_STREAM,
+    WIRE_FEATURE_FAILURE_KINDS,
+];
+
+/// The wire protocol agreed with a remote node.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct WireProtocol {
+    /// The highest version both nodes speak.
+    pub version: u32,
+    /// The features both nodes support.
+    pub features: Vec<String>,
+}
+
+impl WireProtocol {
+    /// The protocol assumed for nodes that predate the hello handshake.
+    pub fn legacy() -> Self {
+        Self {
+            version: 1,
+            features: Vec::new(),
+        }
+    }
+
+    /// Agree on a protocol with a remote that speaks versions
+    /// `remote_min_version..=remote_version`.
+    pub fn negotiate(
+        remote_version: u32,
+        remote_min_version: u32,
+        remote_features: &[String],
+    ) -> Result<Self, String> {
+        let version = WIRE_PROTOCOL_VERSION.min(remote_version);
+        if version < WIRE_MIN_PROTOCOL_VERSION.max(remote_min_version) {
+            return Err(format!(
+                "kitsune wire protocol mismatch: we speak versions {}..={}, the remote speaks {}..={}",
+                WIRE_MIN_PROTOCOL_VERSION, WIRE_PROTOCOL_VERSION, remote_min_version, remote_version,
+            ));
+        }
+        let features = remote_features
+            .iter()
+            .filter(|f| WIRE_FEATURES.contains(&f.as_str()))
+            .cloned()
+            .collect();
+        Ok(Self { version, features })
+    }
+
+    /// True if both nodes support this feature.
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
 /// Type used for content data of wire messages.
 #[derive(
     Debug, Clone, PartialEq, Deref, AsRef, From, Into, serde::Serialize, serde::Deserialize,
@@ -21,6 +101,43 @@ kitsune_p2p_types::write_codec_enum! {
             reason.0: String,
         },
 
+        /// Sent before the first request to a remote, to agree on a
+        /// protocol version. New protocol capabilities should be added as
+        /// features rather than by changing these fields.
+        Hello(0x01) {
+            protocol_version.0: u32,
+            min_protocol_version.1: u32,
+            features.2: Vec<String>,
+        },
+
+        /// The remote's side of the hello handshake.
+        HelloResp(0x02) {
+            protocol_version.0: u32,
+            min_protocol_version.1: u32,
+            features.2: Vec<String>,
+        },
+
+        /// Another message, deflated. Only sent to nodes with the
+        /// "deflate" feature, and only for messages over the compression
+        /// threshold. Responses are compressed for remotes that said hello
+        /// with the feature or compressed their request.
+        Compressed(0x03) {
+            data.0: WireData,
+        },
+
+        /// The remote's host is serving too many of our requests of this
+        /// type, and asks us to wait before sending more.
+        /// Only sent to nodes with the "failure_kinds" feature.
+        Busy(0x04) {
+            retry_after_ms.0: u64,
+        },
+
+        /// The remote's host got our request and refused it, or failed to
+        /// handle it. Only sent to nodes with the "failure_kinds" feature.
+        Rejected(0x05) {
+            reason.0: String,
+        },
+
         /// "Call" to the remote.
         Call(0x010) {
             space.0: Arc<KitsuneSpace>,
@@ -34,6 +151,31 @@ kitsune_p2p_types::write_codec_enum! {
             data.0: WireData,
         },
 
+        /// "Call" to the remote, asking for the response in chunks of at
+        /// most `chunk_size` bytes. Answered with the first [Wire::StreamChunk].
+        /// Only sent to nodes with the "stream" feature.
+        CallStream(0x12) {
+            space.0: Arc<KitsuneSpace>,
+            from_agent.1: Arc<KitsuneAgent>,
+            to_agent.2: Arc<KitsuneAgent>,
+            data.3: WireData,
+            chunk_size.4: u32,
+        },
+
+        /// Chunk `seq` of the `chunk_count` chunks of a streamed response.
+        StreamChunk(0x13) {
+            stream_id.0: u64,
+            seq.1: u32,
+            chunk_count.2: u32,
+            data.3: WireData,
+        },
+
+        /// Ask for chunk `seq` of a streamed response.
+        StreamNext(0x14) {
+            stream_id.0: u64,
+            seq.1: u32,
+        },
+
         /// "Notify" the remote.
         Notify(0x20) {
             space.0: Arc<KitsuneSpace>,
@@ -78,6 +220,23 @@ kitsune_p2p_types::write_codec_enum! {
             agent_infos.1: Vec<AgentInfoSigned>,
         },
 
+        /// Fetch summaries of the DhtOp hashes authored in each time window,
+        /// and Agent Hashes. Only sent to nodes with the "op_hash_summary" feature.
+        FetchOpHashSummary(0x35) {
+            space.0: Arc<KitsuneSpace>,
+            from_agent.1: Arc<KitsuneAgent>,
+            to_agent.2: Arc<KitsuneAgent>,
+            dht_arc.3: DhtArc,
+            windows.4: Vec<TimeWindow>,
+            include_hashes.5: bool,
+        },
+
+        /// Summaries in response to FetchOpHashSummary
+        FetchOpHashSummaryResponse(0x36) {
+            buckets.0: Vec<OpHashBucket>,
+            peer_hashes.1: Vec<(Arc<KitsuneAgent>, u64)>,
+        },
+
         /// Query Agent data from a remote node
         AgentInfoQuery(0x40) {
             space.0: Arc<KitsuneSpace>,
@@ -105,3 +264,260 @@ kitsune_p2p_types::write_codec_enum! {
         },
     }
 }
+
+impl Wire {
+    /// Our side of the hello handshake.
+    pub fn local_hello() -> Self {
+        Self::hello(
+            WIRE_PROTOCOL_VERSION,
+            WIRE_MIN_PROTOCOL_VERSION,
+            WIRE_FEATURES.iter().map(|f| f.to_string()).collect(),
+        )
+    }
+
+    /// Our response to a remote hello.
+    pub fn local_hello_resp() -> Self {
+        Self::hello_resp(
+            WIRE_PROTOCOL_VERSION,
+            WIRE_MIN_PROTOCOL_VERSION,
+            WIRE_FEATURES.iter().map(|f| f.to_string()).collect(),
+        )
+    }
+
+    /// Decode an incoming message, or the [Wire::Failure] to respond with
+    /// if it isn't one we understand (e.g. it is from a newer protocol version).
+    pub fn decode_incoming(data: &[u8]) -> Result<Self, Self> {
+        use kitsune_p2p_types::codec::Codec;
+        Self::decode_ref(data).map(|(_, w)| w).map_err(|e| {
+            Self::failure(format!(
+                "could not decode kitsune wire message with protocol version {}: {}",
+                WIRE_PROTOCOL_VERSION, e
+            ))
+        })
+    }
+
+    /// The agent that sent this request, if it is one that names its sender.
+    pub fn from_agent(&self) -> Option<&Arc<KitsuneAgent>> {
+        match self {
+            Wire::Call(Call { from_agent, .. })
+            | Wire::CallStream(CallStream { from_agent, .. })
+            | Wire::Notify(Notify { from_agent, .. })
+            | Wire::FetchOpHashes(FetchOpHashes { from_agent, .. })
+            | Wire::FetchOpData(FetchOpData { from_agent, .. })
+            | Wire::FetchOpHashSummary(FetchOpHashSummary { from_agent, .. })
+            | Wire::Gossip(Gossip { from_agent, .. }) => Some(from_agent),
+            _ => None,
+        }
+    }
+
+    /// The response to send when the host fails to handle a request.
+    /// If the remote has the "failure_kinds" feature, a busy or refusing
+    /// host is told apart from an agent or host that isn't there,
+    /// which is a [Wire::Failure]. Older nodes only get failures.
+    pub fn handler_failure(err: &KitsuneP2pError, failure_kinds: bool) -> Self {
+        match err {
+            KitsuneP2pError::GhostError(_)
+            | KitsuneP2pError::RoutingSpaceError(_)
+            | KitsuneP2pError::RoutingAgentError(_) => Self::failure(format!("{:?}", err)),
+            KitsuneP2pError::Busy { retry_after_ms } if failure_kinds => {
+                Self::busy(*retry_after_ms)
+            }
+            _ if failure_kinds => Self::rejected(format!("{:?}", err)),
+            _ => Self::failure(format!("{:?}", err)),
+        }
+    }
+
+    /// True for the responses that say a request failed.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Wire::Failure(_) | Wire::Busy(_) | Wire::Rejected(_))
+    }
+}
+
+impl Failure {
+    /// The error the requester should see for this failure.
+    pub fn into_error(self) -> KitsuneP2pError {
+        KitsuneP2pError::RemoteFailure(self.reason.into_boxed_str())
+    }
+}
+
+impl Busy {
+    /// The error the requester should see for this failure.
+    pub fn into_error(self) -> KitsuneP2pError {
+        KitsuneP2pError::Busy {
+            retry_after_ms: self.retry_after_ms,
+        }
+    }
+}
+
+impl Rejected {
+    /// The error the requester should see for this failure.
+    pub fn into_error(self) -> KitsuneP2pError {
+        KitsuneP2pError::Rejected(self.reason.into_boxed_str())
+    }
+}
+
+impl Wire {
+    /// Deflate an encoded message and wrap it in a [Wire::Compressed].
+    pub fn compress(encoded: &[u8]) -> std::io::Result<Vec<u8>> {
+        use flate2::write::DeflateEncoder;
+        use kitsune_p2p_types::codec::Codec;
+        use std::io::Write;
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(encoded)?;
+        Self::compressed(encoder.finish()?.into()).encode_vec()
+    }
+
+    /// Inflate the encoded message wrapped in a [Wire::Compressed],
+    /// failing rather than inflating more than `max_bytes`.
+    pub fn decompress(data: &[u8], max_bytes: usize) -> std::io::Result<Vec<u8>> {
+        use std::io::Read;
+        let mut out = Vec::new();
+        flate2::read::DeflateDecoder::new(data)
+            .take(max_bytes as u64 + 1)
+            .read_to_end(&mut out)?;
+        if out.len() > max_bytes {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("compressed message inflates to over {} bytes", max_bytes),
+            ));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kitsune_p2p_types::codec::Codec;
+
+    // A later version of the protocol, with a message we don't know about.
+    kitsune_p2p_types::write_codec_enum! {
+        /// Next version of the wire protocol.
+        codec WireNext {
+            /// Failure
+            Failure(0x00) {
+                reason.0: String,
+            },
+
+            /// Hello
+            Hello(0x01) {
+                protocol_version.0: u32,
+                min_protocol_version.1: u32,
+                features.2: Vec<String>,
+            },
+
+            /// Something only the new version knows
+            Shiny(0x60) {
+                data.0: WireData,
+            },
+        }
+    }
+
+    #[test]
+    fn unknown_wire_message_fails_with_reason() {
+        let hello = WireNext::hello(2, 1, vec!["shiny".to_string()])
+            .encode_vec()
+            .unwrap();
+        assert_eq!(
+            Wire::hello(2, 1, vec!["shiny".to_string()]),
+            Wire::decode_incoming(&hello).unwrap()
+        );
+
+        let shiny = WireNext::shiny(b"new".to_vec().into())
+            .encode_vec()
+            .unwrap();
+        match Wire::decode_incoming(&shiny) {
+            Err(Wire::Failure(Failure { reason })) => {
+                assert!(reason.contains("protocol version 1"), "{}", reason);
+                assert!(reason.contains("0x60"), "{}", reason);
+            }
+            r => panic!("unexpected {:?}", r),
+        }
+
+        // the failure is understood by the new version
+        let fail = Wire::decode_incoming(&shiny)
+            .unwrap_err()
+            .encode_vec()
+            .unwrap();
+        assert!(matches!(
+            WireNext::decode_ref(&fail).unwrap().1,
+            WireNext::Failure(_)
+        ));
+    }
+
+    #[test]
+    fn handler_failures_keep_their_kind() {
+        let busy = KitsuneP2pError::Busy {
+            retry_after_ms: 250,
+        };
+        let fail = Wire::handler_failure(&busy, true).encode_vec().unwrap();
+        match Wire::decode_ref(&fail).unwrap().1 {
+            Wire::Busy(b) => assert!(matches!(
+                b.into_error(),
+                KitsuneP2pError::Busy {
+                    retry_after_ms: 250
+                }
+            )),
+            r => panic!("unexpected {:?}", r),
+        }
+
+        match Wire::handler_failure(&KitsuneP2pError::from("nope"), true) {
+            Wire::Rejected(r) => {
+                assert!(matches!(r.into_error(), KitsuneP2pError::Rejected(_)))
+            }
+            r => panic!("unexpected {:?}", r),
+        }
+
+        // an agent that isn't there is a plain failure, not a refusal
+        let missing = KitsuneP2pError::RoutingAgentError(Arc::new(KitsuneAgent::new(vec![0; 36])));
+        match Wire::handler_failure(&missing, true) {
+            Wire::Failure(f) => {
+                assert!(matches!(f.into_error(), KitsuneP2pError::RemoteFailure(_)))
+            }
+            r => panic!("unexpected {:?}", r),
+        }
+
+        // older nodes only understand failures
+        for err in &[busy, KitsuneP2pError::from("nope")] {
+            let fail = Wire::handler_failure(err, false).encode_vec().unwrap();
+            assert!(matches!(
+                WireNext::decode_ref(&fail).unwrap().1,
+                WireNext::Failure(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn compressed_wire_round_trip() {
+        let call = Wire::call_resp(vec![42; 10_000].into());
+        let encoded = call.encode_vec().unwrap();
+
+        let compressed = Wire::compress(&encoded).unwrap();
+        assert!(compressed.len() < encoded.len() / 10);
+
+        match Wire::decode_incoming(&compressed).unwrap() {
+            Wire::Compressed(Compressed { data }) => {
+                let inner = Wire::decompress(&data, encoded.len()).unwrap();
+                assert_eq!(call, Wire::decode_incoming(&inner).unwrap());
+                // don't inflate past the limit
+                assert!(Wire::decompress(&data, encoded.len() - 1).is_err());
+            }
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn wire_protocol_negotiation() {
+        // a newer remote that can still talk to us
+        let protocol = WireProtocol::negotiate(2, 1, &["shiny".to_string()]).unwrap();
+        assert_eq!(WireProtocol::legacy(), protocol);
+        assert!(!protocol.has_feature("shiny"));
+
+        let protocol = WireProtocol::negotiate(1, 1, &[WIRE_FEATURE_DEFLATE.to_string()]).unwrap();
+        assert!(protocol.has_feature(WIRE_FEATURE_DEFLATE));
+
+        // a remote that has dropped support for our version
+        let err = WireProtocol::negotiate(3, 2, &[]).unwrap_err();
+        assert!(err.contains("mismatch"), "{}", err);
+    }
+}
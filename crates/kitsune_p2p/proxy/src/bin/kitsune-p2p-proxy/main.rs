@@ -2,6 +2,7 @@ use futures::stream::StreamExt;
 use ghost_actor::dependencies::tracing;
 use kitsune_p2p_proxy::*;
 use kitsune_p2p_transport_quic::*;
+use kitsune_p2p_transport_tcp::*;
 use kitsune_p2p_types::dependencies::ghost_actor;
 use kitsune_p2p_types::dependencies::serde_json;
 use kitsune_p2p_types::metrics::metric_task;
@@ -51,6 +52,7 @@ impl From<TlsFileCert> for TlsConfig {
             cert: f.cert.into(),
             cert_priv_key: f.priv_key.into(),
             cert_digest: f.digest.into(),
+            allowed_client_digests: None,
         }
     }
 }
@@ -89,9 +91,25 @@ async fn inner() -> TransportResult<()> {
         TlsConfig::new_ephemeral().await?
     };
 
-    let (listener, events) = spawn_transport_listener_quic(opt.into()).await?;
+    let tls_conf = match opt.allowed_client_digests()? {
+        Some(digests) => tls_conf.with_allowed_client_digests(digests),
+        None => tls_conf,
+    };
+
+    let policy = opt.policy()?;
+
+    let (listener, events) = match &opt.bind_to {
+        Some(bind_to) if bind_to.starts_with("kitsune-tcp:") => {
+            spawn_transport_listener_tcp((&opt).into()).await?
+        }
+        _ => spawn_transport_listener_quic(opt.into()).await?,
+    };
 
-    let proxy_config = ProxyConfig::local_proxy_server(tls_conf, AcceptProxyCallback::accept_all());
+    let proxy_config = ProxyConfig::local_proxy_server_with_policy(
+        tls_conf,
+        AcceptProxyCallback::accept_all(),
+        policy,
+    );
 
     let (listener, mut events) =
         spawn_kitsune_proxy_listener(proxy_config, listener, events).await?;
@@ -1,3 +1,7 @@
+use kitsune_p2p_proxy::*;
+use kitsune_p2p_types::transport::*;
+use lair_keystore_api::actor::CertDigest;
+
 /// Option Parsing
 #[derive(structopt::StructOpt, Debug)]
 #[structopt(name = "kitsune-p2p-proxy")]
@@ -12,6 +16,7 @@ pub struct Opt {
     pub danger_use_unenc_cert: Option<std::path::PathBuf>,
 
     /// To which network interface / port should we bind?
+    /// Use a "kitsune-tcp://" url to serve clients over tcp.
     /// Default: "kitsune-quic://0.0.0.0:0".
     #[structopt(short = "b", long)]
     pub bind_to: Option<String>,
@@ -22,6 +27,73 @@ pub struct Opt {
     /// Default: None = use NIC ip.
     #[structopt(short = "h", long)]
     pub override_host: Option<String>,
+
+    /// Only host clients with this tls cert digest (base64 url encoded,
+    /// as in their proxy url). May be given more than once.
+    /// Default: host any client.
+    #[structopt(long)]
+    pub allow_digest: Vec<String>,
+
+    /// Never host clients with this tls cert digest (base64 url encoded,
+    /// as in their proxy url). May be given more than once.
+    #[structopt(long)]
+    pub deny_digest: Vec<String>,
+
+    /// The most clients to host at once.
+    /// Default: 0 = unlimited.
+    #[structopt(long, default_value = "0")]
+    pub max_clients: usize,
+
+    /// The most bytes per second to relay to and from each client.
+    /// Default: 0 = unlimited.
+    #[structopt(long, default_value = "0")]
+    pub client_bytes_per_second: u64,
+
+    /// The most new channels per second to open to each client.
+    /// Default: 0 = unlimited.
+    #[structopt(long, default_value = "0")]
+    pub client_channels_per_second: u64,
+
+    /// Only accept tls connections from clients presenting a cert
+    /// with this digest (base64 url encoded, as in their proxy url),
+    /// whether they want to be hosted or to talk to this proxy.
+    /// May be given more than once.
+    /// Default: accept any client cert.
+    #[structopt(long)]
+    pub allow_client_digest: Vec<String>,
+}
+
+fn decode_digests(digests: &[String]) -> TransportResult<Vec<CertDigest>> {
+    digests
+        .iter()
+        .map(|d| decode_cert_digest(d.as_str()))
+        .collect()
+}
+
+impl Opt {
+    /// The client cert digests we accept tls connections from.
+    pub fn allowed_client_digests(&self) -> TransportResult<Option<Vec<CertDigest>>> {
+        if self.allow_client_digest.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(decode_digests(&self.allow_client_digest)?))
+        }
+    }
+
+    /// The policy for the clients we host.
+    pub fn policy(&self) -> TransportResult<ProxyPolicy> {
+        let allow_digests = if self.allow_digest.is_empty() {
+            None
+        } else {
+            Some(decode_digests(&self.allow_digest)?)
+        };
+        Ok(ProxyPolicy::default()
+            .set_allow_digests(allow_digests)
+            .set_deny_digests(decode_digests(&self.deny_digest)?)
+            .set_max_clients(self.max_clients)
+            .set_client_bytes_per_second(self.client_bytes_per_second)
+            .set_client_channels_per_second(self.client_channels_per_second))
+    }
 }
 
 impl From<&Opt> for kitsune_p2p_transport_quic::ConfigListenerQuic {
@@ -42,3 +114,16 @@ impl From<Opt> for kitsune_p2p_transport_quic::ConfigListenerQuic {
         (&o).into()
     }
 }
+
+impl From<&Opt> for kitsune_p2p_transport_tcp::ConfigListenerTcp {
+    fn from(o: &Opt) -> Self {
+        let mut out = Self::default();
+        if let Some(b) = &o.bind_to {
+            out = out.set_bind_to(Some(kitsune_p2p_types::dependencies::url2::url2!("{}", b)));
+        }
+        if let Some(h) = &o.override_host {
+            out = out.set_override_host(Some(h));
+        }
+        out
+    }
+}
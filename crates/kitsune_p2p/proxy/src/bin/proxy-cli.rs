@@ -2,6 +2,7 @@ use futures::stream::StreamExt;
 use kitsune_p2p_proxy::*;
 use kitsune_p2p_transport_quic::*;
 use kitsune_p2p_types::dependencies::ghost_actor;
+use kitsune_p2p_types::dependencies::serde_json;
 use kitsune_p2p_types::metrics::metric_task;
 use kitsune_p2p_types::transport::*;
 use structopt::StructOpt;
@@ -16,6 +17,11 @@ pub struct Opt {
     /// If you would like to keep pinging, set an interval here.
     #[structopt(short = "t", long)]
     pub time_interval_ms: Option<u64>,
+
+    /// Only print the proxy stats: the number of hosted clients,
+    /// and the total traffic relayed for them.
+    #[structopt(short = "s", long)]
+    pub stats: bool,
 }
 
 #[tokio::main]
@@ -65,10 +71,20 @@ async fn inner() -> TransportResult<()> {
         let (_url, mut write, read) = listener.create_channel((&proxy_url).into()).await?;
         write.write_and_close(Vec::with_capacity(0)).await?;
         let res = read.read_to_end().await;
-        println!(
-            "#DEBUG:START#\n{}\n#DEBUG:END#",
-            String::from_utf8_lossy(&res)
-        );
+        if opt.stats {
+            let dump: serde_json::Value =
+                serde_json::from_slice(&res).map_err(TransportError::other)?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&dump["proxy_stats"])
+                    .map_err(TransportError::other)?
+            );
+        } else {
+            println!(
+                "#DEBUG:START#\n{}\n#DEBUG:END#",
+                String::from_utf8_lossy(&res)
+            );
+        }
 
         match &opt.time_interval_ms {
             None => break,
@@ -35,6 +35,10 @@ pub struct TlsConfig {
 
     /// Cert Digest
     pub cert_digest: CertDigest,
+
+    /// If set, incoming tls connections are only accepted from clients
+    /// presenting a certificate with one of these digests.
+    pub allowed_client_digests: Option<Vec<CertDigest>>,
 }
 
 impl TlsConfig {
@@ -49,8 +53,29 @@ impl TlsConfig {
             cert: cert.cert_der,
             cert_priv_key: cert.priv_key_der,
             cert_digest: cert.cert_digest,
+            allowed_client_digests: None,
         })
     }
+
+    /// Only accept incoming tls connections from clients
+    /// presenting a certificate with one of these digests.
+    pub fn with_allowed_client_digests(mut self, digests: Vec<CertDigest>) -> Self {
+        self.allowed_client_digests = Some(digests);
+        self
+    }
+
+    /// Check a client certificate digest against `allowed_client_digests`.
+    pub(crate) fn check_client_digest(&self, cert_digest: &[u8]) -> TransportResult<()> {
+        match &self.allowed_client_digests {
+            Some(allowed) if !allowed.iter().any(|d| d[..] == cert_digest[..]) => {
+                Err(TransportError::CertDigestRejected(base64::encode_config(
+                    cert_digest,
+                    base64::URL_SAFE_NO_PAD,
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Configuration for proxy binding.
@@ -73,6 +98,10 @@ pub enum ProxyConfig {
         /// Return true if we should take on proxying for the
         /// requesting client.
         accept_proxy_cb: AcceptProxyCallback,
+
+        /// Which clients to host, and the limits on relaying for them.
+        /// Checked before `accept_proxy_cb`.
+        policy: ProxyPolicy,
     },
 }
 
@@ -85,9 +114,20 @@ impl ProxyConfig {
     /// We want to be a proxy server for others.
     /// (We can also deny all proxy requests for something in-between).
     pub fn local_proxy_server(tls: TlsConfig, accept_proxy_cb: AcceptProxyCallback) -> Arc<Self> {
+        Self::local_proxy_server_with_policy(tls, accept_proxy_cb, ProxyPolicy::default())
+    }
+
+    /// We want to be a proxy server for others,
+    /// within the limits of this policy.
+    pub fn local_proxy_server_with_policy(
+        tls: TlsConfig,
+        accept_proxy_cb: AcceptProxyCallback,
+        policy: ProxyPolicy,
+    ) -> Arc<Self> {
         Arc::new(Self::LocalProxyServer {
             tls,
             accept_proxy_cb,
+            policy,
         })
     }
 }
@@ -142,11 +182,61 @@ pub(crate) fn gen_tls_configs(
     Ok((tls_server_config, tls_client_config))
 }
 
-struct TlsServerVerifier;
+/// Returns a copy of the client config that will only complete a
+/// handshake with a server presenting a certificate with this digest,
+/// along with the verifier so a mismatch can be reported.
+pub(crate) fn pin_tls_client_config(
+    tls_client_config: &rustls::ClientConfig,
+    expected: CertDigest,
+) -> (Arc<rustls::ClientConfig>, Arc<TlsServerVerifier>) {
+    let verifier = TlsServerVerifier::pinned(expected);
+    let mut tls_client_config = tls_client_config.clone();
+    tls_client_config
+        .dangerous()
+        .set_certificate_verifier(verifier.clone());
+    (Arc::new(tls_client_config), verifier)
+}
+
+/// Server certificate verifier. Kitsune proxy certificates are self-signed,
+/// so instead of a chain of trust we check the certificate digest against
+/// the one embedded in the proxy url we are connecting to.
+pub(crate) struct TlsServerVerifier {
+    expected: Option<CertDigest>,
+    mismatch: std::sync::Mutex<Option<Vec<u8>>>,
+}
 
 impl TlsServerVerifier {
+    /// Accept any server certificate, for configs that will be pinned
+    /// per connection.
     fn new() -> Arc<Self> {
-        Arc::new(Self)
+        Arc::new(Self {
+            expected: None,
+            mismatch: std::sync::Mutex::new(None),
+        })
+    }
+
+    /// Only accept a server certificate with this digest.
+    fn pinned(expected: CertDigest) -> Arc<Self> {
+        Arc::new(Self {
+            expected: Some(expected),
+            mismatch: std::sync::Mutex::new(None),
+        })
+    }
+
+    /// If a handshake was aborted because the server presented the wrong
+    /// certificate, get the error describing the mismatch.
+    pub(crate) fn mismatch_error(&self) -> Option<TransportError> {
+        let expected = self.expected.as_ref()?;
+        let received = self.mismatch.lock().unwrap().clone()?;
+        Some(cert_digest_mismatch(&expected[..], &received))
+    }
+}
+
+/// Build a [TransportError::CertDigestMismatch] from the raw digests.
+pub(crate) fn cert_digest_mismatch(expected: &[u8], received: &[u8]) -> TransportError {
+    TransportError::CertDigestMismatch {
+        expected: base64::encode_config(expected, base64::URL_SAFE_NO_PAD),
+        received: base64::encode_config(received, base64::URL_SAFE_NO_PAD),
     }
 }
 
@@ -154,12 +244,23 @@ impl rustls::ServerCertVerifier for TlsServerVerifier {
     fn verify_server_cert(
         &self,
         _roots: &rustls::RootCertStore,
-        _presented_certs: &[rustls::Certificate],
+        presented_certs: &[rustls::Certificate],
         _dns_name: webpki::DNSNameRef,
         _ocsp_response: &[u8],
     ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
-        // TODO - check acceptable cert digest
-
+        let expected = match &self.expected {
+            None => return Ok(rustls::ServerCertVerified::assertion()),
+            Some(expected) => expected,
+        };
+        let cert = presented_certs
+            .get(0)
+            .ok_or(rustls::TLSError::NoCertificatesPresented)?;
+        let cert_digest = blake2b_32(cert.as_ref());
+        if cert_digest[..] != expected[..] {
+            let err = cert_digest_mismatch(&expected[..], &cert_digest);
+            *self.mismatch.lock().unwrap() = Some(cert_digest);
+            return Err(rustls::TLSError::General(err.to_string()));
+        }
         Ok(rustls::ServerCertVerified::assertion())
     }
 }
@@ -22,18 +22,24 @@ pub async fn spawn_kitsune_proxy_listener(
     TransportEventReceiver,
 )> {
     // sort out our proxy config
-    let (tls, accept_proxy_cb, proxy_url): (TlsConfig, AcceptProxyCallback, Option<ProxyUrl>) =
-        match proxy_config.as_ref() {
-            ProxyConfig::RemoteProxyClient { tls, proxy_url } => (
-                tls.clone(),
-                AcceptProxyCallback::reject_all(),
-                Some(proxy_url.clone()),
-            ),
-            ProxyConfig::LocalProxyServer {
-                tls,
-                accept_proxy_cb,
-            } => (tls.clone(), accept_proxy_cb.clone(), None),
-        };
+    let (tls, accept_proxy_cb, policy, proxy_url): (
+        TlsConfig,
+        AcceptProxyCallback,
+        ProxyPolicy,
+        Option<ProxyUrl>,
+    ) = match proxy_config.as_ref() {
+        ProxyConfig::RemoteProxyClient { tls, proxy_url } => (
+            tls.clone(),
+            AcceptProxyCallback::reject_all(),
+            ProxyPolicy::default(),
+            Some(proxy_url.clone()),
+        ),
+        ProxyConfig::LocalProxyServer {
+            tls,
+            accept_proxy_cb,
+            policy,
+        } => (tls.clone(), accept_proxy_cb.clone(), policy.clone(), None),
+    };
 
     // Configure our own proxy url based of connection details / tls cert.
     let this_url = sub_sender.bound_url().await?;
@@ -60,6 +66,7 @@ pub async fn spawn_kitsune_proxy_listener(
                 this_url,
                 tls,
                 accept_proxy_cb,
+                policy,
                 sub_sender,
                 evt_send,
             )
@@ -136,18 +143,34 @@ struct ProxyTo {
 
     /// when this proxy contract expires
     expires_at: std::time::Instant,
+
+    /// the traffic we relay for this client
+    quota: Arc<ClientQuota>,
+}
+
+#[derive(Debug)]
+/// The traffic we relay for a remote host that isn't one of our clients.
+struct SourceQuota {
+    /// when a channel was last opened from this host
+    last_used: std::time::Instant,
+
+    /// the traffic we relay for this host
+    quota: Arc<ClientQuota>,
 }
 
 struct InnerListen {
     i_s: ghost_actor::GhostSender<Internal>,
     this_url: ProxyUrl,
     accept_proxy_cb: AcceptProxyCallback,
+    policy: ProxyPolicy,
+    relay_stats: Arc<RelayStats>,
     sub_sender: ghost_actor::GhostSender<TransportListener>,
     evt_send: TransportEventSender,
     tls: TlsConfig,
     tls_server_config: Arc<rustls::ServerConfig>,
     tls_client_config: Arc<rustls::ClientConfig>,
     proxy_list: HashMap<ProxyUrl, ProxyTo>,
+    source_quotas: HashMap<String, SourceQuota>,
 }
 
 impl InnerListen {
@@ -156,6 +179,7 @@ impl InnerListen {
         this_url: ProxyUrl,
         tls: TlsConfig,
         accept_proxy_cb: AcceptProxyCallback,
+        policy: ProxyPolicy,
         sub_sender: ghost_actor::GhostSender<TransportListener>,
         evt_send: TransportEventSender,
     ) -> TransportResult<Self> {
@@ -171,14 +195,49 @@ impl InnerListen {
             i_s,
             this_url,
             accept_proxy_cb,
+            policy,
+            relay_stats: Arc::new(RelayStats::default()),
             sub_sender,
             evt_send,
             tls,
             tls_server_config,
             tls_client_config,
             proxy_list: HashMap::new(),
+            source_quotas: HashMap::new(),
         })
     }
+
+    /// The quota for channels opened over the connection `base_url`.
+    /// A client we host is limited by its own quota, anyone else
+    /// by a quota for their host - so a third party flooding
+    /// one of our clients can't use up that client's quota, and
+    /// can't get a fresh quota by reconnecting from another port.
+    fn source_quota(&mut self, base_url: &url2::Url2) -> Arc<ClientQuota> {
+        if let Some(proxy_to) = self
+            .proxy_list
+            .values()
+            .find(|p| &p.base_connection_url == base_url)
+        {
+            return proxy_to.quota.clone();
+        }
+
+        let now = std::time::Instant::now();
+        let idle = std::time::Duration::from_millis(PROXY_KEEPALIVE_MS);
+        self.source_quotas
+            .retain(|_, s| now.duration_since(s.last_used) < idle);
+
+        let policy = &self.policy;
+        let relay_stats = &self.relay_stats;
+        let source = self
+            .source_quotas
+            .entry(base_url.host_str().unwrap_or("").to_string())
+            .or_insert_with(|| SourceQuota {
+                last_used: now,
+                quota: ClientQuota::new(policy, relay_stats.clone()),
+            });
+        source.last_used = now;
+        source.quota.clone()
+    }
 }
 
 impl ghost_actor::GhostControlHandler for InnerListen {
@@ -203,7 +262,7 @@ ghost_actor::ghost_chan! {
 
         fn incoming_req_proxy(
             base_url: url2::Url2,
-            _cert_digest: ChannelData,
+            claimed_digest: ChannelData,
             write: futures::channel::mpsc::Sender<ProxyWire>,
             read: futures::channel::mpsc::Receiver<ProxyWire>,
         ) -> ();
@@ -234,15 +293,21 @@ ghost_actor::ghost_chan! {
 impl ghost_actor::GhostHandler<Internal> for InnerListen {}
 
 // If we're forwarding data to another channel,
-// we need to forward all data read from a reader to a writer.
+// we need to forward all data read from a reader to a writer,
+// slowing down if whoever opened the channel is over their quota.
 fn cross_join_channel_forward(
     mut write: futures::channel::mpsc::Sender<ProxyWire>,
     mut read: futures::channel::mpsc::Receiver<ProxyWire>,
+    quota: Arc<ClientQuota>,
 ) {
     metric_task(async move {
         while let Some(msg) = read.next().await {
-            // do we need to inspect these??
-            // for now just forwarding everything
+            if let ProxyWire::ChanSend(ChanSend { channel_data }) = &msg {
+                let wait = quota.relay_bytes(channel_data.len());
+                if wait > std::time::Duration::from_secs(0) {
+                    tokio::time::delay_for(wait).await;
+                }
+            }
             write.send(msg).await.map_err(TransportError::other)?;
         }
         TransportResult::Ok(())
@@ -290,20 +355,58 @@ impl InternalHandler for InnerListen {
     fn handle_incoming_req_proxy(
         &mut self,
         base_url: url2::Url2,
-        cert_digest: ChannelData,
-        mut write: futures::channel::mpsc::Sender<ProxyWire>,
-        _read: futures::channel::mpsc::Receiver<ProxyWire>,
+        claimed_digest: ChannelData,
+        write: futures::channel::mpsc::Sender<ProxyWire>,
+        read: futures::channel::mpsc::Receiver<ProxyWire>,
     ) -> InternalHandlerResult<()> {
-        tracing::info!(
-            "{}: {} would like us to proxy them",
-            self.this_url.short(),
-            base_url
+        let short = self.this_url.short().to_string();
+        tracing::info!("{}: {} would like us to proxy them", short, base_url);
+
+        // The digest in the request is only a claim,
+        // the client has to prove it holds the cert in a tls handshake.
+        // The handshake also refuses clients not in `allowed_client_digests`,
+        // the policy then decides which of the remaining clients we host.
+        let (tls_evt_send, mut tls_evt_recv) = futures::channel::mpsc::channel(1);
+        tls_srv::spawn_tls_server(
+            short.clone(),
+            base_url.clone(),
+            self.tls_server_config.clone(),
+            self.tls.clone(),
+            tls_evt_send,
+            write,
+            read,
         );
+
+        let policy = self.policy.clone();
         let accept_proxy_cb = self.accept_proxy_cb.clone();
-        let proxy_url = ProxyUrl::new(self.this_url.as_base().as_str(), cert_digest.0.into())?;
+        let relay_stats = self.relay_stats.clone();
+        let this_base = self.this_url.as_base().clone();
         let i_s = self.i_s.clone();
         Ok(async move {
-            if !accept_proxy_cb(vec![32].into()).await {
+            let (remote_url, write, _read) = match tls_evt_recv.next().await {
+                Some(TransportEvent::IncomingChannel(url, write, read)) => (url, write, read),
+                None => return Err("proxy request tls handshake failed".into()),
+            };
+            let mut write = wire_write::wrap_wire_write(write);
+            let cert_digest = ProxyUrl::from(remote_url).digest()?;
+
+            let check = if cert_digest[..] != claimed_digest.0[..] {
+                Err("Proxy Request Rejected: cert digest does not match the tls cert".to_string())
+            } else {
+                policy.check_digest(&cert_digest)
+            };
+            if let Err(reason) = check {
+                tracing::warn!("{}: {}", short, reason);
+                relay_stats.reject_client();
+                write
+                    .send(ProxyWire::failure(reason))
+                    .await
+                    .map_err(TransportError::other)?;
+                return Ok(());
+            }
+
+            if !accept_proxy_cb(cert_digest.clone()).await {
+                relay_stats.reject_client();
                 write
                     .send(ProxyWire::failure("Proxy Request Rejected".into()))
                     .await
@@ -311,7 +414,16 @@ impl InternalHandler for InnerListen {
                 return Ok(());
             }
 
-            i_s.register_proxy_to(proxy_url.clone(), base_url).await?;
+            // the proxy url comes from the authenticated digest,
+            // so only the holder of a cert can register or renew its entry
+            let proxy_url = ProxyUrl::new(this_base.as_str(), cert_digest)?;
+            if let Err(e) = i_s.register_proxy_to(proxy_url.clone(), base_url).await {
+                write
+                    .send(ProxyWire::failure(e.to_string()))
+                    .await
+                    .map_err(TransportError::other)?;
+                return Ok(());
+            }
 
             write
                 .send(ProxyWire::req_proxy_ok(proxy_url.into()))
@@ -338,11 +450,10 @@ impl InternalHandler for InnerListen {
 
         // first check to see if we should proxy this
         // to a client we are servicing.
-        let proxy_to = if let Some(proxy_to) = self.proxy_list.get(&dest_proxy_url) {
-            Some(proxy_to.base_connection_url.clone())
-        } else {
-            None
-        };
+        let proxy_to = self
+            .proxy_list
+            .get(&dest_proxy_url)
+            .map(|proxy_to| proxy_to.base_connection_url.clone());
 
         // if we're not proxying for a client,
         // check to see if our owner is the destination.
@@ -356,6 +467,7 @@ impl InternalHandler for InnerListen {
                     short,
                     base_url,
                     self.tls_server_config.clone(),
+                    self.tls.clone(),
                     self.evt_send.clone(),
                     write,
                     read,
@@ -384,7 +496,7 @@ impl InternalHandler for InnerListen {
         // we're assuming our sub-transport is holding open a connection
         // and the channel create will re-use that.
         // If it is not, it will try to create a new connection that may fail.
-        let fut = match proxy_to {
+        let proxy_to = match proxy_to {
             None => {
                 tracing::warn!("Dropping message for {}", dest_proxy_url.as_full_str());
                 return Ok(async move {
@@ -400,8 +512,30 @@ impl InternalHandler for InnerListen {
                 .boxed()
                 .into());
             }
-            Some(proxy_to) => self.i_s.create_low_level_channel(proxy_to),
+            Some(proxy_to) => proxy_to,
         };
+
+        // charge the channel to whoever opened it, not to its destination
+        let quota = self.source_quota(&base_url);
+        if !quota.open_channel() {
+            tracing::warn!(
+                "Refusing channel over rate limit to {}",
+                dest_proxy_url.as_full_str()
+            );
+            return Ok(async move {
+                write
+                    .send(ProxyWire::failure(format!(
+                        "Channel rate limit exceeded for {}",
+                        dest_proxy_url.as_full_str()
+                    )))
+                    .await
+                    .map_err(TransportError::other)?;
+                Ok(())
+            }
+            .boxed()
+            .into());
+        }
+        let fut = self.i_s.create_low_level_channel(proxy_to);
         let i_s = self.i_s.clone();
         Ok(async move {
             let url = dest_proxy_url.clone();
@@ -425,8 +559,8 @@ impl InternalHandler for InnerListen {
                 }
                 Ok(t) => t,
             };
-            cross_join_channel_forward(fwd_write, read);
-            cross_join_channel_forward(write, fwd_read);
+            cross_join_channel_forward(fwd_write, read, quota.clone());
+            cross_join_channel_forward(write, fwd_read, quota);
             Ok(())
         }
         .boxed()
@@ -462,8 +596,24 @@ impl InternalHandler for InnerListen {
         proxy_url: ProxyUrl,
         base_url: url2::Url2,
     ) -> InternalHandlerResult<()> {
+        let now = std::time::Instant::now();
+        self.proxy_list.retain(|_, p| p.expires_at >= now);
+
+        // renewing a contract keeps the quota we already have for the client
+        let quota = match self.proxy_list.get(&proxy_url) {
+            Some(proxy_to) => proxy_to.quota.clone(),
+            None => {
+                if let Err(reason) = self.policy.check_capacity(self.proxy_list.len()) {
+                    tracing::warn!("{}: {}", self.this_url.short(), reason);
+                    self.relay_stats.reject_client();
+                    return Err(reason.into());
+                }
+                ClientQuota::new(&self.policy, self.relay_stats.clone())
+            }
+        };
+
         // expire ProxyTo entries at double the proxy keepalive timeframe.
-        let expires_at = std::time::Instant::now()
+        let expires_at = now
             .checked_add(std::time::Duration::from_millis(
                 PROXY_KEEPALIVE_MS * KEEPALIVE_MULTIPLIER,
             ))
@@ -473,6 +623,7 @@ impl InternalHandler for InnerListen {
             ProxyTo {
                 base_connection_url: base_url,
                 expires_at,
+                quota,
             },
         );
         Ok(async move { Ok(()) }.boxed().into())
@@ -485,16 +636,38 @@ impl InternalHandler for InnerListen {
             proxy_url.short(),
             proxy_url
         );
+        let short = self.this_url.short().to_string();
         let cert_digest = self.tls.cert_digest.clone();
-        let fut = self.i_s.create_low_level_channel(proxy_url.into_base());
+        let tls_client_config = self.tls_client_config.clone();
+        let fut = self
+            .i_s
+            .create_low_level_channel(proxy_url.as_base().clone());
         let i_s = self.i_s.clone();
         Ok(async move {
-            let (mut write, mut read) = fut.await?;
+            let (mut write, read) = fut.await?;
 
             write
                 .send(ProxyWire::req_proxy(cert_digest.to_vec().into()))
                 .await
                 .map_err(TransportError::other)?;
+
+            // the proxy only takes our word for our cert digest
+            // once we've shown it our cert in a tls handshake
+            let ((send1, recv1), (send2, recv2)) = create_transport_channel_pair();
+            tls_cli::spawn_tls_client(
+                short,
+                proxy_url,
+                tls_client_config,
+                send1,
+                recv1,
+                write,
+                read,
+            )
+            .await
+            .map_err(TransportError::other)??;
+            let _write = wire_write::wrap_wire_write(send2);
+            let mut read = wire_read::wrap_wire_read(recv2);
+
             let res = match read.next().await {
                 None => return Err("no response to proxy request".into()),
                 Some(r) => r,
@@ -526,12 +699,19 @@ impl TransportListenerHandler for InnerListen {
         let url = self.this_url.to_string();
         let sub = self.sub_sender.debug();
         let proxy_count = self.proxy_list.iter().count();
+        // the dump is served to anyone connecting to the proxy,
+        // so it only holds totals - never which clients we host
+        let mut proxy_stats = self.relay_stats.to_json();
+        if let serde_json::Value::Object(map) = &mut proxy_stats {
+            map.insert("max_clients".to_string(), self.policy.max_clients.into());
+        }
         Ok(async move {
             let sub = sub.await?;
             Ok(serde_json::json! {{
                 "sub_transport": sub,
                 "url": url,
                 "proxy_count": proxy_count,
+                "proxy_stats": proxy_stats,
                 "tokio_task_count": kitsune_p2p_types::metrics::metric_task_count(),
                 "sys_info": kitsune_p2p_types::metrics::get_sys_info(),
             }})
@@ -545,6 +725,12 @@ impl TransportListenerHandler for InnerListen {
         Ok(async move { Ok(this_url) }.boxed().into())
     }
 
+    fn handle_connected_urls(&mut self) -> TransportListenerHandlerResult<Vec<url2::Url2>> {
+        // channels are tunnelled over low-level connections,
+        // so these are the base urls of the remotes we're connected to
+        Ok(self.sub_sender.connected_urls())
+    }
+
     fn handle_create_channel(
         &mut self,
         url: url2::Url2,
@@ -42,3 +42,6 @@ pub use inner_listen::*;
 
 mod config;
 pub use config::*;
+
+mod policy;
+pub use policy::*;
@@ -0,0 +1,254 @@
+//! Limits a proxy server applies to the clients it hosts.
+
+use crate::*;
+use kitsune_p2p_types::dependencies::serde_json;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Which clients a proxy server will host, and how much
+/// traffic it will relay for each of them.
+/// The default hosts any number of clients without limits.
+#[non_exhaustive]
+#[derive(Clone, Debug, Default)]
+pub struct ProxyPolicy {
+    /// If set, only clients with one of these cert digests are hosted.
+    pub allow_digests: Option<Vec<CertDigest>>,
+
+    /// Clients with one of these cert digests are never hosted.
+    pub deny_digests: Vec<CertDigest>,
+
+    /// The most clients to host at once. 0 = unlimited.
+    pub max_clients: usize,
+
+    /// The most bytes per second to relay to and from each client.
+    /// Relaying is slowed down to stay within this. 0 = unlimited.
+    pub client_bytes_per_second: u64,
+
+    /// The most new channels per second to open to each client.
+    /// Channels over this are refused. 0 = unlimited.
+    pub client_channels_per_second: u64,
+}
+
+impl ProxyPolicy {
+    /// Set 'allow_digests' builder pattern.
+    pub fn set_allow_digests(mut self, allow_digests: Option<Vec<CertDigest>>) -> Self {
+        self.allow_digests = allow_digests;
+        self
+    }
+
+    /// Set 'deny_digests' builder pattern.
+    pub fn set_deny_digests(mut self, deny_digests: Vec<CertDigest>) -> Self {
+        self.deny_digests = deny_digests;
+        self
+    }
+
+    /// Set 'max_clients' builder pattern.
+    pub fn set_max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = max_clients;
+        self
+    }
+
+    /// Set 'client_bytes_per_second' builder pattern.
+    pub fn set_client_bytes_per_second(mut self, client_bytes_per_second: u64) -> Self {
+        self.client_bytes_per_second = client_bytes_per_second;
+        self
+    }
+
+    /// Set 'client_channels_per_second' builder pattern.
+    pub fn set_client_channels_per_second(mut self, client_channels_per_second: u64) -> Self {
+        self.client_channels_per_second = client_channels_per_second;
+        self
+    }
+
+    /// Check a client cert digest against the allow and deny lists.
+    pub fn check_digest(&self, cert_digest: &[u8]) -> Result<(), String> {
+        let listed = |list: &[CertDigest]| list.iter().any(|d| d[..] == cert_digest[..]);
+        let denied = listed(&self.deny_digests)
+            || match &self.allow_digests {
+                Some(allow) => !listed(allow),
+                None => false,
+            };
+        if denied {
+            return Err(format!(
+                "Proxy Request Rejected: cert digest {} is not allowed",
+                base64::encode_config(cert_digest, base64::URL_SAFE_NO_PAD),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check whether we can host one more client.
+    pub(crate) fn check_capacity(&self, client_count: usize) -> Result<(), String> {
+        if self.max_clients > 0 && client_count >= self.max_clients {
+            return Err(format!(
+                "Proxy Request Rejected: already hosting {} clients",
+                client_count
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Token bucket allowing a steady rate with up to one second of burst.
+pub(crate) struct RateLimiter {
+    per_second: u64,
+    available: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `per_second`, where 0 = unlimited.
+    pub(crate) fn new(per_second: u64) -> Self {
+        Self {
+            per_second,
+            available: per_second as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+        self.available =
+            (self.available + elapsed * self.per_second as f64).min(self.per_second as f64);
+    }
+
+    /// Take `amount` if it is available now.
+    pub(crate) fn try_take(&mut self, amount: u64) -> bool {
+        if self.per_second == 0 {
+            return true;
+        }
+        self.refill();
+        if self.available < amount as f64 {
+            return false;
+        }
+        self.available -= amount as f64;
+        true
+    }
+
+    /// Take `amount` even if it isn't available yet, returning how long
+    /// the caller should wait to stay within the rate.
+    pub(crate) fn take(&mut self, amount: u64) -> Duration {
+        if self.per_second == 0 {
+            return Duration::from_secs(0);
+        }
+        self.refill();
+        self.available -= amount as f64;
+        if self.available >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-self.available / self.per_second as f64)
+        }
+    }
+}
+
+/// Traffic counters, for one client or totalled over all of them.
+#[derive(Default)]
+pub(crate) struct RelayStats {
+    bytes_relayed: AtomicU64,
+    channels_relayed: AtomicU64,
+    channels_refused: AtomicU64,
+    clients_rejected: AtomicU64,
+}
+
+impl RelayStats {
+    /// Count a proxy request we turned down.
+    pub(crate) fn reject_client(&self) {
+        self.clients_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        serde_json::json! {{
+            "bytes_relayed": self.bytes_relayed.load(Ordering::Relaxed),
+            "channels_relayed": self.channels_relayed.load(Ordering::Relaxed),
+            "channels_refused": self.channels_refused.load(Ordering::Relaxed),
+            "clients_rejected": self.clients_rejected.load(Ordering::Relaxed),
+        }}
+    }
+}
+
+/// The limits on traffic relayed to and from one hosted client.
+/// Kept across proxy contract renewals.
+pub(crate) struct ClientQuota {
+    bytes: std::sync::Mutex<RateLimiter>,
+    channels: std::sync::Mutex<RateLimiter>,
+    totals: Arc<RelayStats>,
+}
+
+impl ClientQuota {
+    pub(crate) fn new(policy: &ProxyPolicy, totals: Arc<RelayStats>) -> Arc<Self> {
+        Arc::new(Self {
+            bytes: std::sync::Mutex::new(RateLimiter::new(policy.client_bytes_per_second)),
+            channels: std::sync::Mutex::new(RateLimiter::new(policy.client_channels_per_second)),
+            totals,
+        })
+    }
+
+    /// Count a new channel to this client, returning false
+    /// if it is over the rate limit and should be refused.
+    pub(crate) fn open_channel(&self) -> bool {
+        let ok = self.channels.lock().unwrap().try_take(1);
+        if ok {
+            self.totals.channels_relayed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.totals.channels_refused.fetch_add(1, Ordering::Relaxed);
+        }
+        ok
+    }
+
+    /// Count bytes relayed for this client, returning
+    /// how long to wait before relaying them.
+    pub(crate) fn relay_bytes(&self, len: usize) -> Duration {
+        self.totals
+            .bytes_relayed
+            .fetch_add(len as u64, Ordering::Relaxed);
+        self.bytes.lock().unwrap().take(len as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_checks_digests_and_capacity() {
+        let a: CertDigest = vec![1; 32].into();
+        let b: CertDigest = vec![2; 32].into();
+        let c: CertDigest = vec![3; 32].into();
+
+        let policy = ProxyPolicy::default();
+        assert!(policy.check_digest(&a).is_ok());
+        assert!(policy.check_capacity(1000).is_ok());
+
+        let policy = ProxyPolicy::default()
+            .set_allow_digests(Some(vec![a.clone(), b.clone()]))
+            .set_deny_digests(vec![b.clone()])
+            .set_max_clients(2);
+        assert!(policy.check_digest(&a).is_ok());
+        assert!(policy.check_digest(&b).is_err());
+        assert!(policy.check_digest(&c).is_err());
+        assert!(policy.check_capacity(1).is_ok());
+        assert!(policy.check_capacity(2).is_err());
+    }
+
+    #[test]
+    fn rate_limiter_limits() {
+        let mut unlimited = RateLimiter::new(0);
+        assert!(unlimited.try_take(u64::MAX));
+        assert_eq!(Duration::from_secs(0), unlimited.take(u64::MAX));
+
+        let mut channels = RateLimiter::new(2);
+        assert!(channels.try_take(1));
+        assert!(channels.try_take(1));
+        assert!(!channels.try_take(1));
+
+        // going over the limit means waiting for the difference
+        let mut bytes = RateLimiter::new(1000);
+        assert_eq!(Duration::from_secs(0), bytes.take(1000));
+        let wait = bytes.take(500);
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+    }
+}
@@ -28,6 +28,14 @@ pub struct ProxyUrl {
     base: url2::Url2,
 }
 
+/// Decode a tls certificate digest as it appears in a proxy url
+/// (base64 url encoded, without padding).
+pub fn decode_cert_digest(digest: &str) -> TransportResult<CertDigest> {
+    let d = base64::decode_config(digest, base64::URL_SAFE_NO_PAD)
+        .map_err(|e| TransportError::from(format!("invalid cert digest '{}': {}", digest, e)))?;
+    Ok(d.into())
+}
+
 impl ProxyUrl {
     /// Create a new proxy url from a full url str.
     pub fn from_full(full: &str) -> TransportResult<Self> {
@@ -136,6 +144,15 @@ impl ProxyUrl {
         &h[..std::cmp::min(h.chars().count(), 6)]
     }
 
+    /// Get the tls certificate digest this proxy url expects the remote to present.
+    pub fn digest(&self) -> TransportResult<CertDigest> {
+        let h = self
+            .full
+            .host_str()
+            .ok_or_else(|| TransportError::from(format!("Invalid Proxy Url: {}", self.full)))?;
+        decode_cert_digest(h)
+    }
+
     /// Get the base url this proxy is addressable at.
     pub fn as_base(&self) -> &url2::Url2 {
         &self.base
@@ -42,6 +42,9 @@ async fn tls_client(
 ) -> TransportResult<()> {
     let mut setup_send = Some(setup_send);
     let res: TransportResult<()> = async {
+        // pin the handshake to the cert digest in the proxy url
+        let (tls_client_config, verifier) =
+            pin_tls_client_config(&tls_client_config, expected_proxy_url.digest()?);
         let nr = webpki::DNSNameRef::try_from_ascii_str("stub.stub").unwrap();
         let mut cli = rustls::ClientSession::new(&tls_client_config, nr);
         let mut buf = [0_u8; 4096];
@@ -64,20 +67,15 @@ async fn tls_client(
                         .as_ref(),
                 );
 
-                let remote_proxy_url =
-                    ProxyUrl::new(expected_proxy_url.as_base().as_str(), cert_digest.into())?;
+                // resumed sessions skip the verifier, so check again here
+                let expected_digest = expected_proxy_url.digest()?;
+                if cert_digest[..] != expected_digest[..] {
+                    return Err(cert_digest_mismatch(&expected_digest[..], &cert_digest));
+                }
+
                 if let Some(setup_send) = setup_send.take() {
-                    if expected_proxy_url == remote_proxy_url {
-                        tracing::info!("{}: CLI: CONNECTED TLS: {}", short, remote_proxy_url);
-                        let _ = setup_send.send(Ok(()));
-                    } else {
-                        let msg = format!(
-                            "expected remote {} != received {}",
-                            expected_proxy_url, remote_proxy_url,
-                        );
-                        let _ = setup_send.send(Err(msg.clone().into()));
-                        return Err(msg.into());
-                    }
+                    tracing::info!("{}: CLI: CONNECTED TLS: {}", short, expected_proxy_url);
+                    let _ = setup_send.send(Ok(()));
                 }
             }
 
@@ -121,7 +119,11 @@ async fn tls_client(
                                 break;
                             }
                             cli.read_tls(&mut in_pre).map_err(TransportError::other)?;
-                            cli.process_new_packets().map_err(TransportError::other)?;
+                            cli.process_new_packets().map_err(|e| {
+                                verifier
+                                    .mismatch_error()
+                                    .unwrap_or_else(|| TransportError::other(e))
+                            })?;
                             while let Ok(size) = cli.read(&mut buf) {
                                 tracing::trace!("{}: CLI incoming decrypted {} bytes", short, size);
                                 if size == 0 {
@@ -10,6 +10,7 @@ pub(crate) fn spawn_tls_server(
     short: String,
     incoming_base_url: url2::Url2,
     tls_server_config: Arc<rustls::ServerConfig>,
+    tls: TlsConfig,
     evt_send: TransportEventSender,
     write: futures::channel::mpsc::Sender<ProxyWire>,
     read: futures::channel::mpsc::Receiver<ProxyWire>,
@@ -18,6 +19,7 @@ pub(crate) fn spawn_tls_server(
         short,
         incoming_base_url,
         tls_server_config,
+        tls,
         evt_send,
         write,
         read,
@@ -28,6 +30,7 @@ async fn tls_server(
     short: String,
     incoming_base_url: url2::Url2,
     tls_server_config: Arc<rustls::ServerConfig>,
+    tls: TlsConfig,
     mut evt_send: TransportEventSender,
     mut write: futures::channel::mpsc::Sender<ProxyWire>,
     read: futures::channel::mpsc::Receiver<ProxyWire>,
@@ -58,6 +61,8 @@ async fn tls_server(
                         .as_ref(),
                 );
 
+                tls.check_client_digest(&cert_digest)?;
+
                 let remote_proxy_url =
                     ProxyUrl::new(incoming_base_url.as_str(), cert_digest.into())?;
                 tracing::info!("{}: SRV: INCOMING TLS: {}", short, remote_proxy_url);
@@ -53,6 +53,7 @@ async fn tls_server_and_client_inner() -> TransportResult<()> {
         "srv".to_string(),
         url2::url2!("srv://srv.srv"),
         tls_srv_conf,
+        tls_config_1.clone(),
         in_con_send,
         srv_proxy_send,
         srv_proxy_recv,
@@ -61,7 +62,7 @@ async fn tls_server_and_client_inner() -> TransportResult<()> {
     let ((cli_data_send1, cli_data_recv1), (mut cli_data_send2, cli_data_recv2)) =
         kitsune_p2p_types::transport::create_transport_channel_pair();
 
-    let expected_proxy_url = ProxyUrl::new("srv://srv.srv", tls_config_1.cert_digest)?;
+    let expected_proxy_url = ProxyUrl::new("srv://srv.srv", tls_config_1.cert_digest.clone())?;
     tls_cli::spawn_tls_client(
         "cli".to_string(),
         expected_proxy_url,
@@ -90,3 +91,119 @@ async fn tls_server_and_client_inner() -> TransportResult<()> {
 
     Ok(())
 }
+
+#[tokio::test(threaded_scheduler)]
+async fn tls_client_rejects_wrong_cert_digest() {
+    init_tracing();
+    if let Err(e) = tls_client_rejects_wrong_cert_digest_inner().await {
+        panic!("{:?}", e);
+    }
+}
+
+async fn tls_client_rejects_wrong_cert_digest_inner() -> TransportResult<()> {
+    let tls_config_1 = TlsConfig::new_ephemeral().await?;
+    let tls_config_2 = TlsConfig::new_ephemeral().await?;
+    let tls_config_3 = TlsConfig::new_ephemeral().await?;
+
+    let (tls_srv_conf, _tls_cli_conf) = gen_tls_configs(&tls_config_1)?;
+    let (_tls_srv_conf, tls_cli_conf) = gen_tls_configs(&tls_config_2)?;
+
+    let (in_con_send, _in_con_recv) = futures::channel::mpsc::channel::<TransportEvent>(10);
+
+    let (srv_proxy_send, cli_proxy_recv) = futures::channel::mpsc::channel(10);
+    let (cli_proxy_send, srv_proxy_recv) = futures::channel::mpsc::channel(10);
+
+    tls_srv::spawn_tls_server(
+        "srv".to_string(),
+        url2::url2!("srv://srv.srv"),
+        tls_srv_conf,
+        tls_config_1,
+        in_con_send,
+        srv_proxy_send,
+        srv_proxy_recv,
+    );
+
+    let ((cli_data_send1, cli_data_recv1), (_cli_data_send2, _cli_data_recv2)) =
+        kitsune_p2p_types::transport::create_transport_channel_pair();
+
+    // expect the digest of a cert the server doesn't have
+    let expected_proxy_url = ProxyUrl::new("srv://srv.srv", tls_config_3.cert_digest)?;
+    let res = tls_cli::spawn_tls_client(
+        "cli".to_string(),
+        expected_proxy_url,
+        tls_cli_conf,
+        cli_data_send1,
+        cli_data_recv1,
+        cli_proxy_send,
+        cli_proxy_recv,
+    )
+    .await
+    .map_err(TransportError::other)?;
+
+    match res {
+        Err(TransportError::CertDigestMismatch { .. }) => Ok(()),
+        oth => panic!("expected CertDigestMismatch, got: {:?}", oth),
+    }
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn tls_server_rejects_unknown_client_digest() {
+    init_tracing();
+    if let Err(e) = tls_server_rejects_unknown_client_digest_inner().await {
+        panic!("{:?}", e);
+    }
+}
+
+async fn tls_server_rejects_unknown_client_digest_inner() -> TransportResult<()> {
+    let tls_config_1 = TlsConfig::new_ephemeral().await?;
+    let tls_config_2 = TlsConfig::new_ephemeral().await?;
+    let tls_config_3 = TlsConfig::new_ephemeral().await?;
+
+    // the server only accepts the cert of config 3, the client presents config 2
+    let tls_config_1 =
+        tls_config_1.with_allowed_client_digests(vec![tls_config_3.cert_digest.clone()]);
+
+    let (tls_srv_conf, _tls_cli_conf) = gen_tls_configs(&tls_config_1)?;
+    let (_tls_srv_conf, tls_cli_conf) = gen_tls_configs(&tls_config_2)?;
+
+    let (in_con_send, mut in_con_recv) = futures::channel::mpsc::channel::<TransportEvent>(10);
+
+    let (srv_proxy_send, cli_proxy_recv) = futures::channel::mpsc::channel(10);
+    let (cli_proxy_send, srv_proxy_recv) = futures::channel::mpsc::channel(10);
+
+    tls_srv::spawn_tls_server(
+        "srv".to_string(),
+        url2::url2!("srv://srv.srv"),
+        tls_srv_conf,
+        tls_config_1.clone(),
+        in_con_send,
+        srv_proxy_send,
+        srv_proxy_recv,
+    );
+
+    let ((cli_data_send1, cli_data_recv1), (mut cli_data_send2, _cli_data_recv2)) =
+        kitsune_p2p_types::transport::create_transport_channel_pair();
+
+    let expected_proxy_url = ProxyUrl::new("srv://srv.srv", tls_config_1.cert_digest.clone())?;
+    tls_cli::spawn_tls_client(
+        "cli".to_string(),
+        expected_proxy_url,
+        tls_cli_conf,
+        cli_data_send1,
+        cli_data_recv1,
+        cli_proxy_send,
+        cli_proxy_recv,
+    );
+
+    // the server may already be gone by the time we write
+    let _ = cli_data_send2.write_and_close(b"hello".to_vec()).await;
+
+    // the server refuses the handshake and exits
+    // without ever emitting an incoming channel
+    let evt = tokio::time::timeout(std::time::Duration::from_secs(10), in_con_recv.next())
+        .await
+        .map_err(TransportError::other)?;
+    assert!(evt.is_none(), "unlisted client was accepted");
+
+    Ok(())
+}
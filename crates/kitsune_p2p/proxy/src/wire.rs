@@ -54,6 +54,8 @@ kitsune_p2p_types::write_codec_enum! {
         },
 
         /// Request that the remote end proxy for us.
+        /// Followed by a TLS handshake proving we hold the cert,
+        /// the response is sent over that TLS session.
         ReqProxy(0x10) {
             /// The cert digest others should expect when tunnelling TLS
             cert_digest.0: ChannelData,
@@ -88,3 +88,113 @@ async fn test_inner() -> TransportResult<()> {
 
     Ok(())
 }
+
+#[tokio::test(threaded_scheduler)]
+async fn test_proxy_policy() {
+    if let Err(e) = test_policy_inner().await {
+        panic!("{:?}", e);
+    }
+}
+
+async fn test_policy_inner() -> TransportResult<()> {
+    init_tracing();
+
+    let denied_tls = TlsConfig::new_ephemeral().await?;
+
+    let proxy_config = ProxyConfig::local_proxy_server_with_policy(
+        TlsConfig::new_ephemeral().await?,
+        AcceptProxyCallback::accept_all(),
+        ProxyPolicy::default()
+            .set_deny_digests(vec![denied_tls.cert_digest.clone()])
+            .set_max_clients(1),
+    );
+    let proxy = connect(proxy_config).await?;
+    let proxy_addr = proxy.bound_url().await?;
+
+    // denied by digest
+    let _denied = connect(ProxyConfig::remote_proxy_client(
+        denied_tls,
+        proxy_addr.clone().into(),
+    ))
+    .await?;
+
+    // hosted
+    let _hosted = connect(ProxyConfig::remote_proxy_client(
+        TlsConfig::new_ephemeral().await?,
+        proxy_addr.clone().into(),
+    ))
+    .await?;
+
+    // over max_clients
+    let _full = connect(ProxyConfig::remote_proxy_client(
+        TlsConfig::new_ephemeral().await?,
+        proxy_addr.into(),
+    ))
+    .await?;
+
+    let debug = proxy.debug().await?;
+    assert_eq!(1, debug["proxy_count"]);
+    assert_eq!(2, debug["proxy_stats"]["clients_rejected"]);
+    assert_eq!(1, debug["proxy_stats"]["max_clients"]);
+    // anyone can fetch the dump, so it doesn't say who we host
+    assert!(debug["proxy_stats"].get("clients").is_none());
+
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn test_proxy_third_party_flood() {
+    if let Err(e) = test_third_party_flood_inner().await {
+        panic!("{:?}", e);
+    }
+}
+
+async fn test_third_party_flood_inner() -> TransportResult<()> {
+    init_tracing();
+
+    let proxy_config = ProxyConfig::local_proxy_server_with_policy(
+        TlsConfig::new_ephemeral().await?,
+        AcceptProxyCallback::accept_all(),
+        ProxyPolicy::default().set_client_channels_per_second(1),
+    );
+    let proxy = connect(proxy_config).await?;
+    let proxy_addr = proxy.bound_url().await?;
+
+    let hosted = connect(ProxyConfig::remote_proxy_client(
+        TlsConfig::new_ephemeral().await?,
+        proxy_addr.into(),
+    ))
+    .await?;
+    let hosted_addr = hosted.bound_url().await?;
+
+    let flooder = connect(ProxyConfig::local_proxy_server(
+        TlsConfig::new_ephemeral().await?,
+        AcceptProxyCallback::accept_all(),
+    ))
+    .await?;
+
+    // the flooder runs into its own limit
+    let mut refused = 0;
+    for _ in 0..5 {
+        if flooder.create_channel(hosted_addr.clone()).await.is_err() {
+            refused += 1;
+        }
+    }
+    assert!(refused >= 2, "only {} of 5 channels refused", refused);
+
+    // but that hasn't used up the limit for anyone else
+    let other = connect(ProxyConfig::local_proxy_server(
+        TlsConfig::new_ephemeral().await?,
+        AcceptProxyCallback::accept_all(),
+    ))
+    .await?;
+    let (_url, mut write, read) = other.create_channel(hosted_addr).await?;
+    write.write_and_close(b"test".to_vec()).await?;
+    let data = read.read_to_end().await;
+    assert_eq!("echo: test", String::from_utf8_lossy(&data));
+
+    let debug = proxy.debug().await?;
+    assert!(debug["proxy_stats"]["channels_refused"].as_u64().unwrap() >= 2);
+
+    Ok(())
+}
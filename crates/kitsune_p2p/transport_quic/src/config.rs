@@ -1,4 +1,5 @@
 use crate::*;
+use kitsune_p2p_types::connection_lru::ConnectionLimits;
 
 /// Configuration struct for spawn_transport_listener_quic()
 #[non_exhaustive]
@@ -18,6 +19,10 @@ pub struct ConfigListenerQuic {
     /// Default: None = use NIC port.
     pub override_port: Option<u16>,
 
+    /// Bound the number of open connections and close idle ones.
+    /// Default: 256 connections, closed after 2 minutes idle.
+    pub connection_limits: ConnectionLimits,
+
     /// Tls config
     /// Default: None = ephemeral.
     pub tls: Option<(
@@ -32,6 +37,7 @@ impl Default for ConfigListenerQuic {
             bind_to: None,
             override_host: None,
             override_port: None,
+            connection_limits: ConnectionLimits::default(),
             tls: None,
         }
     }
@@ -56,6 +62,12 @@ impl ConfigListenerQuic {
         self
     }
 
+    /// Set 'connection_limits' builder pattern.
+    pub fn set_connection_limits(mut self, connection_limits: ConnectionLimits) -> Self {
+        self.connection_limits = connection_limits;
+        self
+    }
+
     /// Set 'tls' builder pattern.
     pub fn set_tls(
         mut self,
@@ -3,12 +3,14 @@ use futures::future::FutureExt;
 use futures::sink::SinkExt;
 use futures::stream::StreamExt;
 use ghost_actor::dependencies::tracing;
+use kitsune_p2p_types::connection_lru::ChannelGuard;
+use kitsune_p2p_types::connection_lru::ChannelTracker;
+use kitsune_p2p_types::connection_lru::ConnectionLru;
 use kitsune_p2p_types::dependencies::ghost_actor;
 use kitsune_p2p_types::dependencies::ghost_actor::GhostControlSender;
 use kitsune_p2p_types::dependencies::serde_json;
 use kitsune_p2p_types::dependencies::url2;
 use kitsune_p2p_types::transport::*;
-use std::collections::HashMap;
 use std::net::SocketAddr;
 
 /// Convert quinn async read/write streams into Vec<u8> senders / receivers.
@@ -17,10 +19,14 @@ use std::net::SocketAddr;
 fn tx_bi_chan(
     mut bi_send: quinn::SendStream,
     mut bi_recv: quinn::RecvStream,
+    guard: ChannelGuard,
 ) -> (TransportChannelWrite, TransportChannelRead) {
     let (write_send, mut write_recv) = futures::channel::mpsc::channel::<Vec<u8>>(10);
     let write_send = write_send.sink_map_err(TransportError::other);
+    let write_guard = guard.clone();
     metric_task(async move {
+        // the channel is open until both of these tasks end
+        let _guard = write_guard;
         while let Some(data) = write_recv.next().await {
             bi_send
                 .write_all(&data)
@@ -32,6 +38,7 @@ fn tx_bi_chan(
     });
     let (mut read_send, read_recv) = futures::channel::mpsc::channel::<Vec<u8>>(10);
     metric_task(async move {
+        let _guard = guard;
         let mut buf = [0_u8; 4096];
         while let Some(read) = bi_recv
             .read(&mut buf)
@@ -65,7 +72,7 @@ struct TransportListenerQuic {
     /// the quinn binding (akin to a socket listener)
     quinn_endpoint: quinn::Endpoint,
     /// pool of active connections
-    connections: HashMap<Url2, quinn::Connection>,
+    connections: ConnectionLru<quinn::Connection>,
 }
 
 impl ghost_actor::GhostControlHandler for TransportListenerQuic {
@@ -78,7 +85,7 @@ impl ghost_actor::GhostControlHandler for TransportListenerQuic {
             // in some signals to start rejecting incoming connections,
             // then we could use `quinn_endpoint.wait_idle().await`.
             let _ = self.incoming_channel_sender.close_channel();
-            for (_, con) in self.connections.into_iter() {
+            for con in self.connections.drain() {
                 con.close(0_u8.into(), b"");
                 drop(con);
             }
@@ -112,12 +119,19 @@ ghost_actor::ghost_chan! {
         fn set_connection(
             url: Url2,
             con: quinn::Connection,
+            channels: ChannelTracker,
         ) -> ();
 
         /// If we get an error making outgoing channels,
         /// or if the incoming channel receiver stops,
         /// we want to remove this connection from our pool. It is done.
         fn drop_connection(url: Url2) -> ();
+
+        /// The remote opened a channel on this connection, so it isn't idle.
+        fn touch_connection(url: Url2) -> ();
+
+        /// Close connections that have been idle too long.
+        fn evict_idle() -> ();
     }
 }
 
@@ -152,12 +166,13 @@ impl ListenerInnerHandler for TransportListenerQuic {
                 mut bi_streams,
                 ..
             } = maybe_con.await.map_err(TransportError::other)?;
+            let channels = ChannelTracker::default();
 
             // if we are making an outgoing connection
             // we also need to make an initial channel
             let out = if with_channel {
                 let (bi_send, bi_recv) = con.open_bi().await.map_err(TransportError::other)?;
-                Some(tx_bi_chan(bi_send, bi_recv))
+                Some(tx_bi_chan(bi_send, bi_recv, channels.open()))
             } else {
                 None
             };
@@ -167,13 +182,16 @@ impl ListenerInnerHandler for TransportListenerQuic {
             tracing::debug!("QUIC handle connection: {}", url);
 
             // pass the connection off to our actor
-            i_s.set_connection(url.clone(), con).await?;
+            i_s.set_connection(url.clone(), con, channels.clone())
+                .await?;
 
             // pass any incoming channels off to our actor
             let url_clone = url.clone();
             metric_task(async move {
                 while let Some(Ok((bi_send, bi_recv))) = bi_streams.next().await {
-                    let (write, read) = tx_bi_chan(bi_send, bi_recv);
+                    // it's ok if this errors... we are shutting down.
+                    let _ = i_s.touch_connection(url_clone.clone()).await;
+                    let (write, read) = tx_bi_chan(bi_send, bi_recv, channels.open());
                     if incoming_channel_sender
                         .send(TransportEvent::IncomingChannel(
                             url_clone.clone(),
@@ -199,8 +217,15 @@ impl ListenerInnerHandler for TransportListenerQuic {
         &mut self,
         url: Url2,
         con: quinn::Connection,
+        channels: ChannelTracker,
     ) -> ListenerInnerHandlerResult<()> {
-        self.connections.insert(url, con);
+        for evicted in self.connections.insert(url, con, channels) {
+            tracing::debug!(
+                "QUIC closing evicted connection: {}",
+                evicted.remote_address()
+            );
+            evicted.close(0_u8.into(), b"");
+        }
         Ok(async move { Ok(()) }.boxed().into())
     }
 
@@ -208,6 +233,19 @@ impl ListenerInnerHandler for TransportListenerQuic {
         self.connections.remove(&url);
         Ok(async move { Ok(()) }.boxed().into())
     }
+
+    fn handle_touch_connection(&mut self, url: Url2) -> ListenerInnerHandlerResult<()> {
+        self.connections.touch(&url);
+        Ok(async move { Ok(()) }.boxed().into())
+    }
+
+    fn handle_evict_idle(&mut self) -> ListenerInnerHandlerResult<()> {
+        for con in self.connections.take_idle() {
+            tracing::debug!("QUIC closing idle connection: {}", con.remote_address());
+            con.close(0_u8.into(), b"");
+        }
+        Ok(async move { Ok(()) }.boxed().into())
+    }
 }
 
 impl ghost_actor::GhostHandler<TransportListener> for TransportListenerQuic {}
@@ -215,11 +253,13 @@ impl ghost_actor::GhostHandler<TransportListener> for TransportListenerQuic {}
 impl TransportListenerHandler for TransportListenerQuic {
     fn handle_debug(&mut self) -> TransportListenerHandlerResult<serde_json::Value> {
         let url = self.bound_url.clone();
-        let connections = self.connections.keys().cloned().collect::<Vec<_>>();
+        let connection_count = self.connections.len();
+        let max_connections = self.connections.limits().max_connections;
         Ok(async move {
             Ok(serde_json::json! {{
                 "url": url,
-                "connection_count": connections.len(),
+                "connection_count": connection_count,
+                "max_connections": max_connections,
             }})
         }
         .boxed()
@@ -231,22 +271,31 @@ impl TransportListenerHandler for TransportListenerQuic {
         Ok(async move { Ok(out) }.boxed().into())
     }
 
+    fn handle_connected_urls(&mut self) -> TransportListenerHandlerResult<Vec<Url2>> {
+        let out = self.connections.urls().cloned().collect();
+        Ok(async move { Ok(out) }.boxed().into())
+    }
+
     fn handle_create_channel(
         &mut self,
         url: Url2,
     ) -> TransportListenerHandlerResult<(Url2, TransportChannelWrite, TransportChannelRead)> {
         // if we already have an open connection to the remote end,
         // just directly try to open the bi-stream channel.
-        let maybe_bi = self.connections.get(&url).map(|con| con.open_bi());
+        let maybe_bi = self
+            .connections
+            .get(&url)
+            .map(|con| con.open_bi())
+            .and_then(|bi| Some((bi, self.connections.channels(&url)?.open())));
 
         let i_s = self.internal_sender.clone();
         Ok(async move {
             // if we already had a connection and the bi-stream
             // channel is successfully opened, return early using that
-            if let Some(maybe_bi) = maybe_bi {
+            if let Some((maybe_bi, guard)) = maybe_bi {
                 match maybe_bi.await {
                     Ok((bi_send, bi_recv)) => {
-                        let (write, read) = tx_bi_chan(bi_send, bi_recv);
+                        let (write, read) = tx_bi_chan(bi_send, bi_recv, guard);
                         return Ok((url, write, read));
                     }
                     Err(_) => {
@@ -345,12 +394,25 @@ pub async fn spawn_transport_listener_quic(
         }
     }
 
+    if let Some(interval) = config.connection_limits.idle_check_interval() {
+        let i_s = internal_sender.clone();
+        metric_task(async move {
+            loop {
+                tokio::time::delay_for(interval).await;
+                if i_s.evict_idle().await.is_err() {
+                    break;
+                }
+            }
+            <Result<(), ()>>::Ok(())
+        });
+    }
+
     let actor = TransportListenerQuic {
         internal_sender,
         incoming_channel_sender,
         bound_url,
         quinn_endpoint,
-        connections: HashMap::new(),
+        connections: ConnectionLru::new(config.connection_limits),
     };
 
     metric_task(builder.spawn(actor));
@@ -0,0 +1,388 @@
+use crate::*;
+use futures::sink::SinkExt;
+use futures::stream::StreamExt;
+use kitsune_p2p_types::connection_lru::ChannelGuard;
+use kitsune_p2p_types::connection_lru::ChannelTracker;
+use kitsune_p2p_types::dependencies::ghost_actor::dependencies::tracing;
+use kitsune_p2p_types::transport::*;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+
+/// Frame carrying channel data.
+const FRAME_DATA: u8 = 0;
+
+/// Frame marking the end of the sender's half of a channel.
+const FRAME_CLOSE: u8 = 1;
+
+/// Frame asking the sender to stop writing to a channel,
+/// its data is being dropped. The sender answers with `FRAME_CLOSE`.
+const FRAME_RESET: u8 = 2;
+
+/// kind (1 byte) + channel id (8 bytes) + data length (4 bytes)
+const FRAME_HEADER_LEN: usize = 13;
+
+/// Larger writes are split across multiple frames.
+const MAX_FRAME_DATA: usize = 64 * 1024;
+
+/// How many frames are buffered for each channel waiting for its reader.
+/// A channel whose reader falls further behind is reset, so one slow
+/// reader never holds up the other channels on the connection.
+const CHANNEL_BUFFER_FRAMES: usize = 64;
+
+/// The most channels the remote can have open to us on one connection.
+/// Further channels are reset until some of these are read to the end.
+const MAX_INCOMING_CHANNELS: usize = 64;
+
+static CON_ID: AtomicU64 = AtomicU64::new(1);
+
+type ChannelMap = Arc<Mutex<HashMap<u64, tokio::sync::mpsc::Sender<Vec<u8>>>>>;
+
+/// Counts a channel the remote opened until its read half is dropped.
+struct IncomingSlot(Arc<AtomicUsize>);
+
+impl Drop for IncomingSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The read half of a channel, keeping it counted as open until dropped.
+struct ChannelRead {
+    recv: tokio::sync::mpsc::Receiver<Vec<u8>>,
+    _guard: ChannelGuard,
+    _slot: Option<IncomingSlot>,
+}
+
+impl futures::stream::Stream for ChannelRead {
+    type Item = Vec<u8>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        futures::stream::Stream::poll_next(std::pin::Pin::new(&mut self.recv), cx)
+    }
+}
+
+fn encode_frame(kind: u8, id: u64, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(FRAME_HEADER_LEN + data.len());
+    out.push(kind);
+    out.extend_from_slice(&id.to_be_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+/// A tcp connection carrying any number of kitsune channels in
+/// either direction. This lets a remote we connected to open channels
+/// back to us, even if we are not otherwise reachable.
+///
+/// Each side numbers the channels it opens with its own parity
+/// (odd for the side that connected), so either end can open channels
+/// without coordinating.
+#[derive(Clone)]
+pub(crate) struct TcpCon {
+    con_id: u64,
+    url: Url2,
+    parity: u64,
+    next_id: Arc<AtomicU64>,
+    frame_send: futures::channel::mpsc::Sender<Vec<u8>>,
+    channels: ChannelMap,
+    /// Channels whose incoming data we drop until the remote closes them.
+    reset_reads: Arc<Mutex<HashSet<u64>>>,
+    /// Our live channel writers, flagged once the remote resets them.
+    writers: Arc<Mutex<HashMap<u64, bool>>>,
+    incoming_count: Arc<AtomicUsize>,
+    channel_tracker: ChannelTracker,
+    incoming_channel_sender: TransportEventSender,
+    on_incoming_channel: Arc<dyn Fn() + Send + Sync + 'static>,
+}
+
+impl TcpCon {
+    /// Start driving this stream. Channels opened by the remote end are
+    /// sent to `incoming_channel_sender`, calling `on_incoming_channel`
+    /// for each. `on_close` is called with our con_id once the remote
+    /// closes the connection, or it errors.
+    pub(crate) fn spawn(
+        stream: tokio::net::TcpStream,
+        url: Url2,
+        is_outgoing: bool,
+        incoming_channel_sender: TransportEventSender,
+        on_incoming_channel: impl Fn() + 'static + Send + Sync,
+        on_close: impl FnOnce(u64) + 'static + Send,
+    ) -> Self {
+        let _ = stream.set_nodelay(true);
+        let (mut read_half, mut write_half) = tokio::io::split(stream);
+
+        let (frame_send, mut frame_recv) = futures::channel::mpsc::channel::<Vec<u8>>(10);
+        metric_task(async move {
+            while let Some(frame) = frame_recv.next().await {
+                write_half
+                    .write_all(&frame)
+                    .await
+                    .map_err(TransportError::other)?;
+            }
+            write_half.shutdown().await.map_err(TransportError::other)?;
+            TransportResult::Ok(())
+        });
+
+        let parity = if is_outgoing { 1 } else { 0 };
+        let con = Self {
+            con_id: CON_ID.fetch_add(1, Ordering::Relaxed),
+            url,
+            parity,
+            next_id: Arc::new(AtomicU64::new(parity)),
+            frame_send,
+            channels: Arc::new(Mutex::new(HashMap::new())),
+            reset_reads: Arc::new(Mutex::new(HashSet::new())),
+            writers: Arc::new(Mutex::new(HashMap::new())),
+            incoming_count: Arc::new(AtomicUsize::new(0)),
+            channel_tracker: ChannelTracker::default(),
+            incoming_channel_sender,
+            on_incoming_channel: Arc::new(on_incoming_channel),
+        };
+
+        let con_clone = con.clone();
+        metric_task(async move {
+            let con = con_clone;
+            let res: TransportResult<()> = async {
+                let mut header = [0_u8; FRAME_HEADER_LEN];
+                loop {
+                    match read_half.read_exact(&mut header).await {
+                        Ok(_) => (),
+                        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                            return Ok(());
+                        }
+                        Err(e) => return Err(TransportError::other(e)),
+                    }
+                    let mut id = [0_u8; 8];
+                    id.copy_from_slice(&header[1..9]);
+                    let id = u64::from_be_bytes(id);
+                    let mut len = [0_u8; 4];
+                    len.copy_from_slice(&header[9..13]);
+                    let len = u32::from_be_bytes(len) as usize;
+                    if len > MAX_FRAME_DATA {
+                        return Err(format!("tcp frame too large: {} bytes", len).into());
+                    }
+                    let mut data = vec![0; len];
+                    read_half
+                        .read_exact(&mut data)
+                        .await
+                        .map_err(TransportError::other)?;
+
+                    match header[0] {
+                        FRAME_DATA => {
+                            if con.reset_reads.lock().unwrap().contains(&id) {
+                                continue;
+                            }
+                            if let Some(mut send) = con.channel_sender(id).await? {
+                                // never wait on a slow reader, that would stall
+                                // every channel. it's ok if the reader is gone.
+                                if let Err(tokio::sync::mpsc::error::TrySendError::Full(_)) =
+                                    send.try_send(data)
+                                {
+                                    tracing::debug!("tcp channel {} on {} overflowed", id, con.url);
+                                    con.reset_read(id).await?;
+                                }
+                            }
+                        }
+                        FRAME_CLOSE => {
+                            if con.reset_reads.lock().unwrap().remove(&id) {
+                                continue;
+                            }
+                            // make sure an empty channel still reaches our owner
+                            con.channel_sender(id).await?;
+                            con.channels.lock().unwrap().remove(&id);
+                        }
+                        FRAME_RESET => {
+                            if let Some(reset) = con.writers.lock().unwrap().get_mut(&id) {
+                                *reset = true;
+                            }
+                        }
+                        kind => return Err(format!("invalid tcp frame kind: {}", kind).into()),
+                    }
+                }
+            }
+            .await;
+            if let Err(err) = res {
+                tracing::debug!(?err, "tcp connection to {} failed", con.url);
+            }
+
+            // end all the channel reads on this connection
+            con.channels.lock().unwrap().clear();
+            con.close();
+            on_close(con.con_id);
+
+            <Result<(), ()>>::Ok(())
+        });
+
+        con
+    }
+
+    /// Identifies this connection among any others to the same url.
+    pub(crate) fn con_id(&self) -> u64 {
+        self.con_id
+    }
+
+    /// Counts the channels open on this connection, in either direction.
+    pub(crate) fn channels(&self) -> ChannelTracker {
+        self.channel_tracker.clone()
+    }
+
+    /// True once this connection can no longer send.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.frame_send.is_closed()
+    }
+
+    /// Stop sending on this connection.
+    pub(crate) fn close(&self) {
+        self.frame_send.clone().close_channel();
+    }
+
+    /// Open a new channel to the remote end.
+    pub(crate) fn open_channel(
+        &self,
+    ) -> TransportResult<(TransportChannelWrite, TransportChannelRead)> {
+        if self.is_closed() {
+            return Err(format!("tcp connection to {} is closed", self.url).into());
+        }
+        let id = self.next_id.fetch_add(2, Ordering::Relaxed);
+        let (send, recv) = tokio::sync::mpsc::channel(CHANNEL_BUFFER_FRAMES);
+        self.channels.lock().unwrap().insert(id, send);
+        let guard = self.channel_tracker.open();
+        let write = self.channel_write(id, guard.clone());
+        let read: TransportChannelRead = Box::new(ChannelRead {
+            recv,
+            _guard: guard,
+            _slot: None,
+        });
+        Ok((write, read))
+    }
+
+    /// Drop the data arriving on this channel,
+    /// and ask the remote to stop sending it.
+    async fn reset_read(&self, id: u64) -> TransportResult<()> {
+        self.channels.lock().unwrap().remove(&id);
+        {
+            let mut reset_reads = self.reset_reads.lock().unwrap();
+            // a remote that won't close what we reset is misbehaving
+            if reset_reads.len() >= MAX_INCOMING_CHANNELS {
+                return Err(format!("too many reset tcp channels on {}", self.url).into());
+            }
+            reset_reads.insert(id);
+        }
+        self.frame_send
+            .clone()
+            .send(encode_frame(FRAME_RESET, id, &[]))
+            .await
+            .map_err(TransportError::other)
+    }
+
+    /// Get the sender for data arriving on this channel.
+    /// If the remote is opening a new channel this passes it to our owner,
+    /// or resets it if the remote already has too many open.
+    async fn channel_sender(
+        &self,
+        id: u64,
+    ) -> TransportResult<Option<tokio::sync::mpsc::Sender<Vec<u8>>>> {
+        let new_channel = {
+            let mut channels = self.channels.lock().unwrap();
+            if let Some(send) = channels.get(&id) {
+                return Ok(Some(send.clone()));
+            }
+            if id % 2 == self.parity {
+                // one of our channels that we have already closed
+                return Ok(None);
+            }
+            if self.incoming_count.load(Ordering::SeqCst) >= MAX_INCOMING_CHANNELS {
+                None
+            } else {
+                let (send, recv) = tokio::sync::mpsc::channel(CHANNEL_BUFFER_FRAMES);
+                channels.insert(id, send.clone());
+                Some((send, recv))
+            }
+        };
+        let (send, recv) = match new_channel {
+            Some(new_channel) => new_channel,
+            None => {
+                tracing::debug!("too many incoming tcp channels on {}", self.url);
+                self.reset_read(id).await?;
+                // there will be no response either
+                self.frame_send
+                    .clone()
+                    .send(encode_frame(FRAME_CLOSE, id, &[]))
+                    .await
+                    .map_err(TransportError::other)?;
+                return Ok(None);
+            }
+        };
+
+        self.incoming_count.fetch_add(1, Ordering::SeqCst);
+        let slot = IncomingSlot(self.incoming_count.clone());
+        (self.on_incoming_channel)();
+        let guard = self.channel_tracker.open();
+        let write = self.channel_write(id, guard.clone());
+        let read: TransportChannelRead = Box::new(ChannelRead {
+            recv,
+            _guard: guard,
+            _slot: Some(slot),
+        });
+        let url = self.url.clone();
+        let mut incoming_channel_sender = self.incoming_channel_sender.clone();
+        // if we don't spawn here we could deadlock the connection
+        // while our owner is processing a previous channel.
+        metric_task(async move {
+            // it's ok if this errors... the channel will close.
+            let _ = incoming_channel_sender
+                .send(TransportEvent::IncomingChannel(url, write, read))
+                .await;
+            <Result<(), ()>>::Ok(())
+        });
+
+        Ok(Some(send))
+    }
+
+    /// Frame everything written to this channel onto the connection.
+    /// The channel stays open until the close frame is sent.
+    fn channel_write(&self, id: u64, guard: ChannelGuard) -> TransportChannelWrite {
+        let (write_send, mut write_recv) = futures::channel::mpsc::channel::<Vec<u8>>(10);
+        let mut frame_send = self.frame_send.clone();
+        let writers = self.writers.clone();
+        writers.lock().unwrap().insert(id, false);
+        metric_task(async move {
+            let mut reset = false;
+            'write: while let Some(data) = write_recv.next().await {
+                for chunk in data.chunks(MAX_FRAME_DATA) {
+                    if writers.lock().unwrap().get(&id).copied().unwrap_or(true) {
+                        reset = true;
+                        break 'write;
+                    }
+                    frame_send
+                        .send(encode_frame(FRAME_DATA, id, chunk))
+                        .await
+                        .map_err(TransportError::other)?;
+                }
+            }
+            // the remote drops this channel's data until it sees the close
+            let res = frame_send
+                .send(encode_frame(FRAME_CLOSE, id, &[]))
+                .await
+                .map_err(TransportError::other);
+            writers.lock().unwrap().remove(&id);
+            drop(guard);
+            res?;
+            if reset {
+                return Err("tcp channel reset by the remote".into());
+            }
+            TransportResult::Ok(())
+        });
+        Box::new(write_send.sink_map_err(TransportError::other))
+    }
+}
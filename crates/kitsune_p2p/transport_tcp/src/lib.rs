@@ -0,0 +1,59 @@
+#![deny(missing_docs)]
+//! TCP transport module for kitsune-p2p
+//!
+//! A fallback for networks that block UDP, and so QUIC.
+//! This transport does not encrypt anything itself, so kitsune only
+//! allows it as the `sub_transport` of a proxy transport, which tls
+//! tunnels all traffic.
+
+/// Re-exported dependencies.
+pub mod dependencies {
+    pub use ::kitsune_p2p_types;
+}
+
+use kitsune_p2p_types::dependencies::url2::*;
+use kitsune_p2p_types::metrics::metric_task;
+use kitsune_p2p_types::transport::TransportResult;
+use std::net::SocketAddr;
+
+const SCHEME: &str = "kitsune-tcp";
+
+/// internal helper convert urls to socket addrs for binding / connection
+pub(crate) async fn url_to_addr(url: &Url2, scheme: &str) -> TransportResult<SocketAddr> {
+    if url.scheme() != scheme || url.host_str().is_none() || url.port().is_none() {
+        return Err(format!(
+            "invalid input. got: '{}', expected: '{}://host:port'",
+            url, scheme
+        )
+        .into());
+    }
+
+    let rendered = format!("{}:{}", url.host_str().unwrap(), url.port().unwrap());
+
+    if let Ok(iter) = tokio::net::lookup_host(rendered.clone()).await {
+        let mut fallback = None;
+        for addr in iter {
+            if addr.is_ipv4() {
+                return Ok(addr);
+            }
+            if fallback.is_none() {
+                fallback = Some(addr);
+            }
+        }
+        if let Some(addr) = fallback {
+            return Ok(addr);
+        }
+    }
+
+    Err(format!("could not parse '{}', as 'host:port'", rendered).into())
+}
+
+mod config;
+pub use config::*;
+
+mod connection;
+
+mod listener;
+pub use listener::*;
+
+mod test;
@@ -0,0 +1,291 @@
+use crate::connection::TcpCon;
+use crate::*;
+use futures::future::FutureExt;
+use ghost_actor::dependencies::tracing;
+use kitsune_p2p_types::connection_lru::ConnectionLru;
+use kitsune_p2p_types::dependencies::ghost_actor;
+use kitsune_p2p_types::dependencies::ghost_actor::GhostControlSender;
+use kitsune_p2p_types::dependencies::serde_json;
+use kitsune_p2p_types::dependencies::url2;
+use kitsune_p2p_types::transport::*;
+
+/// TCP implementation of kitsune TransportListener actor.
+struct TransportListenerTcp {
+    /// internal api logic
+    internal_sender: ghost_actor::GhostSender<ListenerInner>,
+    /// incoming channel send to our owner
+    incoming_channel_sender: TransportEventSender,
+    /// the url to return on 'bound_url' calls - what we bound to
+    bound_url: Url2,
+    /// stops accepting incoming connections
+    accept_abort: futures::future::AbortHandle,
+    /// pool of active connections
+    connections: ConnectionLru<TcpCon>,
+}
+
+impl ghost_actor::GhostControlHandler for TransportListenerTcp {
+    fn handle_ghost_actor_shutdown(
+        mut self,
+    ) -> ghost_actor::dependencies::must_future::MustBoxFuture<'static, ()> {
+        async move {
+            self.accept_abort.abort();
+            self.incoming_channel_sender.close_channel();
+            for con in self.connections.drain() {
+                con.close();
+            }
+        }
+        .boxed()
+        .into()
+    }
+}
+
+ghost_actor::ghost_chan! {
+    /// Internal Sender
+    chan ListenerInner<TransportError> {
+        /// Take a tcp stream pulling it into our logic.
+        /// Shared code for both incoming and outgoing connections.
+        fn take_stream(
+            url: Url2,
+            stream: tokio::net::TcpStream,
+            is_outgoing: bool,
+        ) -> TcpCon;
+
+        /// The connection has closed, remove it from our pool
+        /// (unless it has already been replaced by a newer one).
+        fn drop_connection(url: Url2, con_id: u64) -> ();
+
+        /// The remote opened a channel on this connection, so it isn't idle.
+        fn touch_connection(url: Url2) -> ();
+
+        /// Close connections that have been idle too long.
+        fn evict_idle() -> ();
+    }
+}
+
+impl ghost_actor::GhostHandler<ListenerInner> for TransportListenerTcp {}
+
+impl ListenerInnerHandler for TransportListenerTcp {
+    fn handle_take_stream(
+        &mut self,
+        url: Url2,
+        stream: tokio::net::TcpStream,
+        is_outgoing: bool,
+    ) -> ListenerInnerHandlerResult<TcpCon> {
+        tracing::debug!("TCP handle connection: {}", url);
+        let i_s = self.internal_sender.clone();
+        let url_clone = url.clone();
+        let touch_i_s = self.internal_sender.clone();
+        let touch_url = url.clone();
+        let con = TcpCon::spawn(
+            stream,
+            url.clone(),
+            is_outgoing,
+            self.incoming_channel_sender.clone(),
+            move || {
+                let i_s = touch_i_s.clone();
+                let url = touch_url.clone();
+                metric_task(async move {
+                    // it's ok if this errors... we are shutting down.
+                    let _ = i_s.touch_connection(url).await;
+                    <Result<(), ()>>::Ok(())
+                });
+            },
+            move |con_id| {
+                metric_task(async move {
+                    // it's ok if this errors... we are shutting down.
+                    let _ = i_s.drop_connection(url_clone, con_id).await;
+                    <Result<(), ()>>::Ok(())
+                });
+            },
+        );
+        for evicted in self.connections.insert(url, con.clone(), con.channels()) {
+            evicted.close();
+        }
+        Ok(async move { Ok(con) }.boxed().into())
+    }
+
+    fn handle_drop_connection(&mut self, url: Url2, con_id: u64) -> ListenerInnerHandlerResult<()> {
+        if let Some(con) = self.connections.peek(&url) {
+            if con.con_id() == con_id {
+                self.connections.remove(&url);
+            }
+        }
+        Ok(async move { Ok(()) }.boxed().into())
+    }
+
+    fn handle_touch_connection(&mut self, url: Url2) -> ListenerInnerHandlerResult<()> {
+        self.connections.touch(&url);
+        Ok(async move { Ok(()) }.boxed().into())
+    }
+
+    fn handle_evict_idle(&mut self) -> ListenerInnerHandlerResult<()> {
+        for con in self.connections.take_idle() {
+            tracing::debug!("TCP closing idle connection: {}", con.con_id());
+            con.close();
+        }
+        Ok(async move { Ok(()) }.boxed().into())
+    }
+}
+
+impl ghost_actor::GhostHandler<TransportListener> for TransportListenerTcp {}
+
+impl TransportListenerHandler for TransportListenerTcp {
+    fn handle_debug(&mut self) -> TransportListenerHandlerResult<serde_json::Value> {
+        let url = self.bound_url.clone();
+        let connection_count = self.connections.len();
+        let max_connections = self.connections.limits().max_connections;
+        Ok(async move {
+            Ok(serde_json::json! {{
+                "url": url,
+                "connection_count": connection_count,
+                "max_connections": max_connections,
+            }})
+        }
+        .boxed()
+        .into())
+    }
+
+    fn handle_bound_url(&mut self) -> TransportListenerHandlerResult<Url2> {
+        let out = self.bound_url.clone();
+        Ok(async move { Ok(out) }.boxed().into())
+    }
+
+    fn handle_connected_urls(&mut self) -> TransportListenerHandlerResult<Vec<Url2>> {
+        let out = self.connections.urls().cloned().collect();
+        Ok(async move { Ok(out) }.boxed().into())
+    }
+
+    fn handle_create_channel(
+        &mut self,
+        url: Url2,
+    ) -> TransportListenerHandlerResult<(Url2, TransportChannelWrite, TransportChannelRead)> {
+        // if we already have an open connection to the remote end,
+        // open the channel on that.
+        if let Some(con) = self.connections.get(&url) {
+            match con.open_channel() {
+                Ok((write, read)) => {
+                    return Ok(async move { Ok((url, write, read)) }.boxed().into());
+                }
+                Err(_) => {
+                    // it no longer works for us
+                    self.connections.remove(&url);
+                }
+            }
+        }
+
+        // otherwise establish a new connection
+        let i_s = self.internal_sender.clone();
+        Ok(async move {
+            let addr = crate::url_to_addr(&url, crate::SCHEME).await?;
+            tracing::debug!("attempt raw connect: {:?}", addr);
+            let stream = tokio::net::TcpStream::connect(addr)
+                .await
+                .map_err(TransportError::other)?;
+            let con = i_s.take_stream(url.clone(), stream, true).await?;
+            let (write, read) = con.open_channel()?;
+            Ok((url, write, read))
+        }
+        .boxed()
+        .into())
+    }
+}
+
+/// Spawn a new TCP TransportListenerSender.
+pub async fn spawn_transport_listener_tcp(
+    config: ConfigListenerTcp,
+) -> TransportListenerResult<(
+    ghost_actor::GhostSender<TransportListener>,
+    TransportEventReceiver,
+)> {
+    let bind_to = config
+        .bind_to
+        .unwrap_or_else(|| url2::url2!("kitsune-tcp://0.0.0.0:0"));
+    let mut tcp_listener =
+        tokio::net::TcpListener::bind(crate::url_to_addr(&bind_to, crate::SCHEME).await?)
+            .await
+            .map_err(TransportError::other)?;
+    let local_addr = tcp_listener.local_addr().map_err(TransportError::other)?;
+
+    let (incoming_channel_sender, receiver) = futures::channel::mpsc::channel(10);
+
+    let builder = ghost_actor::actor_builder::GhostActorBuilder::new();
+
+    let internal_sender = builder.channel_factory().create_channel().await?;
+
+    let sender = builder.channel_factory().create_channel().await?;
+
+    let i_s = internal_sender.clone();
+    let (accept_abort, accept_reg) = futures::future::AbortHandle::new_pair();
+    metric_task(async move {
+        let accept = async {
+            loop {
+                let (stream, addr) = match tcp_listener.accept().await {
+                    Ok(r) => r,
+                    Err(err) => {
+                        ghost_actor::dependencies::tracing::error!(?err);
+                        break;
+                    }
+                };
+                let url = url2!("{}://{}", crate::SCHEME, addr);
+                if i_s.take_stream(url, stream, false).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let _ = futures::future::Abortable::new(accept, accept_reg).await;
+
+        // We can no longer accept incoming connections.
+        // I.e., we need to shut down (if we aren't already).
+        let _ = i_s.ghost_actor_shutdown().await;
+
+        <Result<(), ()>>::Ok(())
+    });
+
+    let mut bound_url = url2!("{}://{}", crate::SCHEME, local_addr);
+    if let Some(override_host) = &config.override_host {
+        bound_url.set_host(Some(override_host)).unwrap();
+    } else if let Some(host) = bound_url.host_str() {
+        if host == "0.0.0.0" {
+            for iface in if_addrs::get_if_addrs().map_err(TransportError::other)? {
+                // super naive - just picking the first v4 that is not 127.0.0.1
+                let addr = iface.addr.ip();
+                if let std::net::IpAddr::V4(addr) = addr {
+                    if addr != std::net::Ipv4Addr::from([127, 0, 0, 1]) {
+                        bound_url
+                            .set_host(Some(&iface.addr.ip().to_string()))
+                            .unwrap();
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    if let Some(override_port) = config.override_port {
+        bound_url.set_port(Some(override_port)).unwrap();
+    }
+
+    if let Some(interval) = config.connection_limits.idle_check_interval() {
+        let i_s = internal_sender.clone();
+        metric_task(async move {
+            loop {
+                tokio::time::delay_for(interval).await;
+                if i_s.evict_idle().await.is_err() {
+                    break;
+                }
+            }
+            <Result<(), ()>>::Ok(())
+        });
+    }
+
+    let actor = TransportListenerTcp {
+        internal_sender,
+        incoming_channel_sender,
+        bound_url,
+        accept_abort,
+        connections: ConnectionLru::new(config.connection_limits),
+    };
+
+    metric_task(builder.spawn(actor));
+
+    Ok((sender, receiver))
+}
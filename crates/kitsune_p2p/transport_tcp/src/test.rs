@@ -0,0 +1,224 @@
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use futures::stream::StreamExt;
+    use kitsune_p2p_types::transport::*;
+
+    fn echo_events(mut events: TransportEventReceiver) {
+        metric_task(async move {
+            while let Some(evt) = events.next().await {
+                match evt {
+                    TransportEvent::IncomingChannel(_url, mut write, read) => {
+                        let data = read.read_to_end().await;
+                        let data = format!("echo: {}", String::from_utf8_lossy(&data)).into_bytes();
+                        write.write_and_close(data).await?;
+                    }
+                }
+            }
+            TransportResult::Ok(())
+        });
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_message() {
+        let (listener1, _events1) = spawn_transport_listener_tcp(
+            ConfigListenerTcp::default().set_override_host(Some("127.0.0.1")),
+        )
+        .await
+        .unwrap();
+
+        let bound1 = listener1.bound_url().await.unwrap();
+        assert_eq!("127.0.0.1", bound1.host_str().unwrap());
+        assert_eq!("kitsune-tcp", bound1.scheme());
+
+        let (listener2, events2) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+        echo_events(events2);
+
+        let bound2 = listener2.bound_url().await.unwrap();
+
+        // a second request reuses the same connection
+        for _ in 0..2 {
+            let resp = listener1
+                .request(bound2.clone(), b"hello".to_vec())
+                .await
+                .unwrap();
+            assert_eq!("echo: hello", &String::from_utf8_lossy(&resp));
+        }
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_large_message() {
+        let (listener1, _events1) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+
+        let (listener2, events2) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+        echo_events(events2);
+
+        let bound2 = listener2.bound_url().await.unwrap();
+
+        let large_msg = std::iter::repeat(b"a"[0]).take(200_000).collect::<Vec<_>>();
+        let resp = listener1.request(bound2, large_msg.clone()).await.unwrap();
+
+        assert_eq!(
+            format!("echo: {}", String::from_utf8_lossy(&large_msg)),
+            String::from_utf8_lossy(&resp)
+        );
+        assert_eq!(resp.len(), 200_006);
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_channel_back_over_incoming_connection() {
+        let (listener1, events1) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+        echo_events(events1);
+
+        let (listener2, mut events2) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+
+        let bound2 = listener2.bound_url().await.unwrap();
+
+        let (_url, mut write, _read) = listener1.create_channel(bound2).await.unwrap();
+        write.write_and_close(b"hello".to_vec()).await.unwrap();
+
+        // the url we see listener1 at is its side of the connection,
+        // not its bound url - opening a channel to it must reuse that
+        let incoming_url = match events2.next().await.unwrap() {
+            TransportEvent::IncomingChannel(url, _write, read) => {
+                assert_eq!(b"hello".to_vec(), read.read_to_end().await);
+                url
+            }
+        };
+        assert_ne!(incoming_url, listener1.bound_url().await.unwrap());
+
+        let resp = listener2
+            .request(incoming_url, b"back".to_vec())
+            .await
+            .unwrap();
+        assert_eq!("echo: back", &String::from_utf8_lossy(&resp));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_slow_reader_does_not_block_other_channels() {
+        let (listener1, _events1) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+
+        let (listener2, mut events2) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+
+        let bound2 = listener2.bound_url().await.unwrap();
+
+        // far more than a channel buffers, to a reader that never reads
+        let (_url, mut write, _read) = listener1.create_channel(bound2.clone()).await.unwrap();
+        let large_msg = std::iter::repeat(b"a"[0])
+            .take(8 * 1024 * 1024)
+            .collect::<Vec<_>>();
+        write.write_and_close(large_msg).await.unwrap();
+        let _stalled = match events2.next().await.unwrap() {
+            TransportEvent::IncomingChannel(_url, write, read) => (write, read),
+        };
+        echo_events(events2);
+
+        let resp = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            listener1.request(bound2, b"hello".to_vec()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!("echo: hello", &String::from_utf8_lossy(&resp));
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_incoming_channels_are_capped() {
+        let (listener1, _events1) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+
+        let (listener2, mut events2) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+
+        let bound2 = listener2.bound_url().await.unwrap();
+
+        // hold on to every channel without answering
+        let held = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let held_clone = held.clone();
+        metric_task(async move {
+            while let Some(TransportEvent::IncomingChannel(_url, write, read)) =
+                events2.next().await
+            {
+                held_clone.lock().unwrap().push((write, read));
+            }
+            TransportResult::Ok(())
+        });
+
+        let mut reads = Vec::new();
+        for _ in 0..65 {
+            let (_url, mut write, read) = listener1.create_channel(bound2.clone()).await.unwrap();
+            write.write_and_close(b"hi".to_vec()).await.unwrap();
+            reads.push(read);
+        }
+
+        // the channel over the cap is closed with no response
+        let over = reads.pop().unwrap();
+        let resp = tokio::time::timeout(std::time::Duration::from_secs(10), over.read_to_end())
+            .await
+            .unwrap();
+        assert!(resp.is_empty());
+        for _ in 0..100 {
+            if held.lock().unwrap().len() == 64 {
+                break;
+            }
+            tokio::time::delay_for(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(64, held.lock().unwrap().len());
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn test_idle_eviction_waits_for_open_channels() {
+        let (listener1, _events1) =
+            spawn_transport_listener_tcp(ConfigListenerTcp::default().set_connection_limits(
+                kitsune_p2p_types::connection_lru::ConnectionLimits {
+                    max_connections: 0,
+                    idle_timeout_ms: 100,
+                },
+            ))
+            .await
+            .unwrap();
+
+        let (listener2, mut events2) = spawn_transport_listener_tcp(ConfigListenerTcp::default())
+            .await
+            .unwrap();
+
+        // answer well after the idle timeout
+        metric_task(async move {
+            while let Some(evt) = events2.next().await {
+                match evt {
+                    TransportEvent::IncomingChannel(_url, mut write, read) => {
+                        let data = read.read_to_end().await;
+                        tokio::time::delay_for(std::time::Duration::from_millis(500)).await;
+                        write.write_and_close(data).await?;
+                    }
+                }
+            }
+            TransportResult::Ok(())
+        });
+
+        let bound2 = listener2.bound_url().await.unwrap();
+        let resp = listener1
+            .request(bound2.clone(), b"slow".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(b"slow".to_vec(), resp);
+        assert_eq!(vec![bound2], listener1.connected_urls().await.unwrap());
+    }
+}
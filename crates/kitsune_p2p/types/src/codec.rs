@@ -224,7 +224,10 @@ macro_rules! write_codec_enum {
                                 Ok(Self::[< $var_name:camel >]($crate::codec::Codec::decode(r)?))
                             },
                         )*
-                        _ => Err(::std::io::Error::new(::std::io::ErrorKind::Other, "invalid protocol byte")),
+                        b => Err(::std::io::Error::new(
+                            ::std::io::ErrorKind::Other,
+                            format!("invalid protocol byte: {:#04x}", b),
+                        )),
                     }
                 }
             }
@@ -0,0 +1,347 @@
+//! Bound the connections a transport keeps open.
+//!
+//! Transports that hold one connection per remote keep them in a
+//! [ConnectionLru] so that the least recently used connection is closed
+//! when the limit is reached, and idle connections are closed after a timeout.
+//! Connections with channels still open are never closed this way.
+
+use crate::transport::KitsuneTransportMetrics;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Default maximum number of open connections per transport.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 256;
+
+/// Default time after which an unused connection is closed.
+pub const DEFAULT_CONNECTION_IDLE_TIMEOUT_MS: u64 = 1000 * 60 * 2; // 2 minutes
+
+/// Limits applied to a [ConnectionLru].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConnectionLimits {
+    /// Close the least recently used connection when opening
+    /// one more than this. 0 = unlimited.
+    pub max_connections: usize,
+
+    /// Close connections that haven't been used for this long.
+    /// 0 = never.
+    pub idle_timeout_ms: u64,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            idle_timeout_ms: DEFAULT_CONNECTION_IDLE_TIMEOUT_MS,
+        }
+    }
+}
+
+impl ConnectionLimits {
+    /// How often the owning transport should call [ConnectionLru::take_idle].
+    pub fn idle_check_interval(&self) -> Option<Duration> {
+        match self.idle_timeout_ms {
+            0 => None,
+            t => Some(Duration::from_millis((t / 2).max(100))),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TrackerInner {
+    open: AtomicUsize,
+    last_closed: Mutex<Option<Instant>>,
+}
+
+/// Counts the channels open on a connection,
+/// so that it isn't closed while they are in use.
+#[derive(Clone, Default)]
+pub struct ChannelTracker(Arc<TrackerInner>);
+
+impl ChannelTracker {
+    /// Count a newly opened channel as open
+    /// until the returned guard, and all its clones, are dropped.
+    pub fn open(&self) -> ChannelGuard {
+        self.0.open.fetch_add(1, Ordering::SeqCst);
+        ChannelGuard(Arc::new(GuardInner(self.0.clone())))
+    }
+
+    /// The number of channels open on this connection.
+    pub fn open_count(&self) -> usize {
+        self.0.open.load(Ordering::SeqCst)
+    }
+
+    /// When the last channel on this connection closed.
+    fn last_closed(&self) -> Option<Instant> {
+        *self.0.last_closed.lock().unwrap()
+    }
+}
+
+struct GuardInner(Arc<TrackerInner>);
+
+impl Drop for GuardInner {
+    fn drop(&mut self) {
+        *self.0.last_closed.lock().unwrap() = Some(Instant::now());
+        self.0.open.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Held by everything serving a channel, e.g. its read and write tasks,
+/// for as long as the channel is in use.
+#[derive(Clone)]
+pub struct ChannelGuard(Arc<GuardInner>);
+
+struct Entry<C> {
+    con: C,
+    last_used: Instant,
+    channels: ChannelTracker,
+}
+
+impl<C> Entry<C> {
+    fn is_busy(&self) -> bool {
+        self.channels.open_count() > 0
+    }
+
+    /// Idle time counts from the last use or the last channel closing,
+    /// whichever is later.
+    fn last_active(&self) -> Instant {
+        match self.channels.last_closed() {
+            Some(last_closed) if last_closed > self.last_used => last_closed,
+            _ => self.last_used,
+        }
+    }
+}
+
+/// Open connections keyed by remote url, in least recently used order.
+pub struct ConnectionLru<C> {
+    limits: ConnectionLimits,
+    connections: HashMap<url2::Url2, Entry<C>>,
+}
+
+impl<C> ConnectionLru<C> {
+    /// Create an empty set of connections with these limits.
+    pub fn new(limits: ConnectionLimits) -> Self {
+        Self {
+            limits,
+            connections: HashMap::new(),
+        }
+    }
+
+    /// The limits applied to these connections.
+    pub fn limits(&self) -> &ConnectionLimits {
+        &self.limits
+    }
+
+    /// The number of open connections.
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// True if there are no open connections.
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// The urls of the open connections.
+    pub fn urls(&self) -> impl Iterator<Item = &url2::Url2> {
+        self.connections.keys()
+    }
+
+    /// Get the connection to this url to reuse it,
+    /// marking it as the most recently used.
+    pub fn get(&mut self, url: &url2::Url2) -> Option<&C> {
+        match self.connections.get_mut(url) {
+            Some(entry) => {
+                entry.last_used = Instant::now();
+                KitsuneTransportMetrics::count(KitsuneTransportMetrics::ConnectionReuse, 1);
+                Some(&entry.con)
+            }
+            None => None,
+        }
+    }
+
+    /// The tracker for the channels open on the connection to this url.
+    pub fn channels(&self, url: &url2::Url2) -> Option<ChannelTracker> {
+        self.connections
+            .get(url)
+            .map(|entry| entry.channels.clone())
+    }
+
+    /// Mark the connection to this url as used without reusing it,
+    /// e.g. because the remote opened a channel on it.
+    pub fn touch(&mut self, url: &url2::Url2) {
+        if let Some(entry) = self.connections.get_mut(url) {
+            entry.last_used = Instant::now();
+        }
+    }
+
+    /// Get the connection to this url without marking it as used.
+    pub fn peek(&self, url: &url2::Url2) -> Option<&C> {
+        self.connections.get(url).map(|entry| &entry.con)
+    }
+
+    /// Add a newly opened connection, whose open channels are counted
+    /// by `channels`. Returns any connections the caller should now close -
+    /// one replaced for the same url, and the least recently used
+    /// without open channels if we are over the limit.
+    pub fn insert(&mut self, url: url2::Url2, con: C, channels: ChannelTracker) -> Vec<C> {
+        KitsuneTransportMetrics::count(KitsuneTransportMetrics::ConnectionOpen, 1);
+        let mut evicted = Vec::new();
+        let entry = Entry {
+            con,
+            last_used: Instant::now(),
+            channels,
+        };
+        if let Some(old) = self.connections.insert(url.clone(), entry) {
+            evicted.push(old.con);
+        }
+        while self.limits.max_connections > 0
+            && self.connections.len() > self.limits.max_connections
+        {
+            // if every other connection is busy we go over the limit for now
+            let lru = self
+                .connections
+                .iter()
+                .filter(|(u, entry)| **u != url && !entry.is_busy())
+                .min_by_key(|(_, entry)| entry.last_active())
+                .map(|(url, _)| url.clone());
+            match lru.and_then(|url| self.connections.remove(&url)) {
+                Some(entry) => evicted.push(entry.con),
+                None => break,
+            }
+        }
+        KitsuneTransportMetrics::count(KitsuneTransportMetrics::ConnectionClose, evicted.len());
+        evicted
+    }
+
+    /// Remove a connection that has closed or failed.
+    pub fn remove(&mut self, url: &url2::Url2) -> Option<C> {
+        let out = self.connections.remove(url).map(|entry| entry.con);
+        if out.is_some() {
+            KitsuneTransportMetrics::count(KitsuneTransportMetrics::ConnectionClose, 1);
+        }
+        out
+    }
+
+    /// Remove the connections that have had no open channels
+    /// for longer than the timeout, returning them for the caller to close.
+    pub fn take_idle(&mut self) -> Vec<C> {
+        if self.limits.idle_timeout_ms == 0 {
+            return Vec::new();
+        }
+        let timeout = Duration::from_millis(self.limits.idle_timeout_ms);
+        let now = Instant::now();
+        let idle = self
+            .connections
+            .iter()
+            .filter(|(_, entry)| {
+                !entry.is_busy() && now.duration_since(entry.last_active()) >= timeout
+            })
+            .map(|(url, _)| url.clone())
+            .collect::<Vec<_>>();
+        let out = idle
+            .into_iter()
+            .filter_map(|url| self.connections.remove(&url))
+            .map(|entry| entry.con)
+            .collect::<Vec<_>>();
+        KitsuneTransportMetrics::count(KitsuneTransportMetrics::ConnectionClose, out.len());
+        out
+    }
+
+    /// Remove all connections, returning them for the caller to close.
+    pub fn drain(&mut self) -> Vec<C> {
+        self.connections
+            .drain()
+            .map(|(_, entry)| entry.con)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_lru_evicts_least_recently_used() {
+        let mut lru = ConnectionLru::new(ConnectionLimits {
+            max_connections: 2,
+            idle_timeout_ms: 0,
+        });
+        let url = |n| url2::url2!("test://{}", n);
+
+        assert!(lru.insert(url(1), 1, ChannelTracker::default()).is_empty());
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(lru.insert(url(2), 2, ChannelTracker::default()).is_empty());
+        std::thread::sleep(Duration::from_millis(2));
+
+        // using 1 makes 2 the least recently used
+        assert_eq!(Some(&1), lru.get(&url(1)));
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(vec![2], lru.insert(url(3), 3, ChannelTracker::default()));
+        assert_eq!(2, lru.len());
+        assert!(lru.peek(&url(2)).is_none());
+
+        // replacing a connection returns the old one
+        assert_eq!(vec![1], lru.insert(url(1), 10, ChannelTracker::default()));
+        assert_eq!(Some(&10), lru.peek(&url(1)));
+
+        // idle timeout of 0 never evicts
+        assert!(lru.take_idle().is_empty());
+    }
+
+    #[test]
+    fn connection_lru_takes_idle() {
+        let mut lru = ConnectionLru::new(ConnectionLimits {
+            max_connections: 0,
+            idle_timeout_ms: 20,
+        });
+        let url = |n| url2::url2!("test://{}", n);
+
+        lru.insert(url(1), 1, ChannelTracker::default());
+        lru.insert(url(3), 3, ChannelTracker::default());
+        std::thread::sleep(Duration::from_millis(30));
+        lru.insert(url(2), 2, ChannelTracker::default());
+        // the remote is still using 3
+        lru.touch(&url(3));
+
+        assert_eq!(vec![1], lru.take_idle());
+        assert_eq!(Some(&2), lru.peek(&url(2)));
+        assert_eq!(Some(&3), lru.peek(&url(3)));
+    }
+
+    #[test]
+    fn connection_lru_keeps_connections_with_open_channels() {
+        let mut lru = ConnectionLru::new(ConnectionLimits {
+            max_connections: 1,
+            idle_timeout_ms: 20,
+        });
+        let url = |n| url2::url2!("test://{}", n);
+
+        let channels = ChannelTracker::default();
+        lru.insert(url(1), 1, channels.clone());
+        let guard = channels.open();
+        let read_guard = guard.clone();
+
+        // over the limit, but 1 is busy
+        assert!(lru.insert(url(2), 2, ChannelTracker::default()).is_empty());
+        assert_eq!(2, lru.len());
+
+        // nor is it idle while the channel is open
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(vec![2], lru.take_idle());
+        assert_eq!(Some(&1), lru.peek(&url(1)));
+
+        // idle time counts from the channel closing
+        drop(guard);
+        assert_eq!(1, channels.open_count());
+        drop(read_guard);
+        assert_eq!(0, channels.open_count());
+        assert!(lru.take_idle().is_empty());
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(vec![1], lru.take_idle());
+    }
+}
@@ -17,8 +17,10 @@ pub mod async_lazy;
 mod auto_stream_select;
 pub use auto_stream_select::*;
 pub mod codec;
+pub mod connection_lru;
 pub mod dht_arc;
 pub mod metrics;
 pub mod transport;
 pub mod transport_mem;
+pub mod transport_mem_sim;
 pub mod transport_pool;
@@ -4,7 +4,14 @@ use futures::future::FutureExt;
 use futures::sink::SinkExt;
 use futures::stream::StreamExt;
 
-observability::metrics!(KitsuneTransportMetrics, Write, Read);
+observability::metrics!(
+    KitsuneTransportMetrics,
+    Write,
+    Read,
+    ConnectionOpen,
+    ConnectionClose,
+    ConnectionReuse
+);
 
 /// Error related to remote communication.
 #[derive(Debug, thiserror::Error)]
@@ -14,6 +21,21 @@ pub enum TransportError {
     #[error(transparent)]
     GhostError(#[from] ghost_actor::GhostError),
 
+    /// The remote presented a tls certificate with a different digest
+    /// than the one we expected (both base64 url encoded).
+    #[error("tls certificate digest mismatch, expected: {expected}, received: {received}")]
+    CertDigestMismatch {
+        /// The digest we expected, usually from the remote's proxy url.
+        expected: String,
+        /// The digest of the certificate the remote presented.
+        received: String,
+    },
+
+    /// A client presented a tls certificate whose digest is not
+    /// in our list of allowed client digests (base64 url encoded).
+    #[error("tls client certificate digest not allowed: {0}")]
+    CertDigestRejected(String),
+
     /// Unspecified error.
     #[error(transparent)]
     Other(Box<dyn std::error::Error + Send + Sync>),
@@ -169,6 +191,9 @@ ghost_actor::ghost_chan! {
         /// Retrieve the current url (address) this listener is bound to.
         fn bound_url() -> url2::Url2;
 
+        /// The urls of the remotes this listener has open connections to.
+        fn connected_urls() -> Vec<url2::Url2>;
+
         /// Attempt to establish an outgoing channel to a remote.
         /// Channels are expected to be very light-weight.
         /// This API was designed around QUIC bi-streams.
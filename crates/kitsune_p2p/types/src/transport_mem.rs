@@ -1,6 +1,7 @@
 //! A mem-only transport - largely for testing
 
 use crate::transport::*;
+use crate::transport_mem_sim::MemNetwork;
 use futures::future::FutureExt;
 use futures::sink::SinkExt;
 
@@ -51,6 +52,27 @@ fn drop_core(url: url2::Url2) {
 pub async fn spawn_bind_transport_mem() -> TransportResult<(
     ghost_actor::GhostSender<TransportListener>,
     TransportEventReceiver,
+)> {
+    bind_transport_mem(None).await
+}
+
+/// Spawn / bind the listening side of a mem-only transport
+/// whose outgoing channels are subject to the conditions of this
+/// simulated network.
+pub async fn spawn_bind_transport_mem_sim(
+    network: MemNetwork,
+) -> TransportResult<(
+    ghost_actor::GhostSender<TransportListener>,
+    TransportEventReceiver,
+)> {
+    bind_transport_mem(Some(network)).await
+}
+
+async fn bind_transport_mem(
+    network: Option<MemNetwork>,
+) -> TransportResult<(
+    ghost_actor::GhostSender<TransportListener>,
+    TransportEventReceiver,
 )> {
     let url = url2::url2!("{}://{}", SCHEME, nanoid::nanoid!());
 
@@ -65,13 +87,18 @@ pub async fn spawn_bind_transport_mem() -> TransportResult<(
 
     put_core(url.clone(), evt_send).await?;
 
-    crate::metrics::metric_task(builder.spawn(InnerListen::new(url)));
+    if let Some(network) = &network {
+        network.add_listener(url.clone());
+    }
+
+    crate::metrics::metric_task(builder.spawn(InnerListen::new(url, network)));
 
     Ok((sender, evt_recv))
 }
 
 struct InnerListen {
     url: url2::Url2,
+    network: Option<MemNetwork>,
 }
 
 impl Drop for InnerListen {
@@ -81,8 +108,8 @@ impl Drop for InnerListen {
 }
 
 impl InnerListen {
-    pub fn new(url: url2::Url2) -> Self {
-        Self { url }
+    pub fn new(url: url2::Url2, network: Option<MemNetwork>) -> Self {
+        Self { url, network }
     }
 }
 
@@ -93,11 +120,13 @@ impl ghost_actor::GhostHandler<TransportListener> for InnerListen {}
 impl TransportListenerHandler for InnerListen {
     fn handle_debug(&mut self) -> TransportListenerHandlerResult<serde_json::Value> {
         let url = self.url.clone();
+        let network = self.network.as_ref().map(|n| n.name().to_string());
         let listeners = list_cores();
         Ok(async move {
             let listeners = listeners.await?;
             Ok(serde_json::json! {{
                 "url": url,
+                "network": network,
                 "listener_count": listeners.len(),
             }})
         }
@@ -110,28 +139,52 @@ impl TransportListenerHandler for InnerListen {
         Ok(async move { Ok(url) }.boxed().into())
     }
 
+    fn handle_connected_urls(&mut self) -> TransportListenerHandlerResult<Vec<url2::Url2>> {
+        // there are no connections to set up,
+        // every other mem listener is as good as connected
+        let url = self.url.clone();
+        let listeners = list_cores();
+        Ok(async move {
+            let mut listeners = listeners.await?;
+            listeners.retain(|l| l != &url);
+            Ok(listeners)
+        }
+        .boxed()
+        .into())
+    }
+
     fn handle_create_channel(
         &mut self,
         url: url2::Url2,
     ) -> TransportListenerHandlerResult<(url2::Url2, TransportChannelWrite, TransportChannelRead)>
     {
         let this_url = self.url.clone();
+        let network = self.network.clone();
         Ok(async move {
             let mut evt_send = get_core(url.clone()).await?;
 
-            let ((send1, recv1), (send2, recv2)) = create_transport_channel_pair();
-
-            // if we don't spawn here there can be a deadlock on
-            // incoming_channel trying to process all channel data
-            // before we've returned our halves here.
-            crate::metrics::metric_task(async move {
-                // it's ok if this errors... the channels will close.
-                let _ = evt_send
-                    .send(TransportEvent::IncomingChannel(this_url, send1, recv1))
-                    .await;
-
-                <Result<(), ()>>::Ok(())
-            });
+            let (remote, (send2, recv2)) = match network {
+                Some(network) => network.create_channel(&this_url, &url)?,
+                None => {
+                    let (remote, local) = create_transport_channel_pair();
+                    (Some(remote), local)
+                }
+            };
+
+            // a simulated network may have lost this channel
+            if let Some((send1, recv1)) = remote {
+                // if we don't spawn here there can be a deadlock on
+                // incoming_channel trying to process all channel data
+                // before we've returned our halves here.
+                crate::metrics::metric_task(async move {
+                    // it's ok if this errors... the channels will close.
+                    let _ = evt_send
+                        .send(TransportEvent::IncomingChannel(this_url, send1, recv1))
+                        .await;
+
+                    <Result<(), ()>>::Ok(())
+                });
+            }
             Ok((url, send2, recv2))
         }
         .boxed()
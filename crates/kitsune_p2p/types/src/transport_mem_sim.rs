@@ -0,0 +1,478 @@
+//! Simulated network conditions for the mem-only transport - for testing
+//!
+//! Mem transport listeners bound to a named [MemNetwork] share its
+//! link conditions. Tests can change latency, jitter, loss and bandwidth
+//! per link, or partition the network, while the listeners are running.
+//! Loss and jitter are drawn from a per-link rng derived from the network
+//! seed and the order the listeners bound in, and all delays are tokio
+//! timers, so under paused tokio time (`tokio::time::pause`) runs with the
+//! same seed are repeatable.
+
+use crate::transport::*;
+use futures::sink::SinkExt;
+use futures::stream::StreamExt;
+use once_cell::sync::Lazy;
+use rand::Rng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+
+/// The conditions applied to data sent over a simulated link.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LinkConditions {
+    /// One-way delay in milliseconds before data starts arriving.
+    #[serde(default)]
+    pub latency_ms: u64,
+
+    /// Up to this many milliseconds are randomly added to the latency
+    /// of each channel.
+    #[serde(default)]
+    pub jitter_ms: u64,
+
+    /// The chance (0.0 - 1.0) that a channel is lost and never
+    /// reaches the remote. The caller sees the channel close with no data.
+    #[serde(default)]
+    pub loss: f64,
+
+    /// Cap the data rate in each direction of a channel.
+    /// Default: None = unlimited.
+    #[serde(default)]
+    pub bandwidth_bytes_per_sec: Option<u64>,
+}
+
+/// Configuration for joining a simulated mem network.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MemNetworkConfig {
+    /// Listeners with the same network name share link conditions
+    /// and partitions.
+    pub name: String,
+
+    /// Seed for the rng deciding loss and jitter.
+    /// Listeners joining an existing network must use the same seed.
+    #[serde(default)]
+    pub seed: u64,
+
+    /// The conditions for any link without its own.
+    /// Listeners joining an existing network must use the same default.
+    #[serde(default)]
+    pub default_link: LinkConditions,
+}
+
+struct NetworkState {
+    default_link: LinkConditions,
+    links: HashMap<(url2::Url2, url2::Url2), LinkConditions>,
+    partitions: HashMap<String, HashSet<url2::Url2>>,
+    /// Listeners are numbered in the order they bind,
+    /// so link rngs don't depend on the random listener urls.
+    listeners: HashMap<url2::Url2, u64>,
+    /// One rng per link, so the draws on a link don't depend
+    /// on how channels on other links interleave with it.
+    link_rngs: HashMap<(u64, u64), rand::rngs::StdRng>,
+}
+
+struct NetworkInner {
+    config: MemNetworkConfig,
+    state: Mutex<NetworkState>,
+}
+
+impl Drop for NetworkInner {
+    fn drop(&mut self) {
+        let mut networks = NETWORKS.lock().unwrap();
+        // a new network may already have taken this name
+        if let Some(network) = networks.get(&self.config.name) {
+            if network.strong_count() == 0 {
+                networks.remove(&self.config.name);
+            }
+        }
+    }
+}
+
+/// The live networks by name. A network is removed
+/// once the last handle to it, and the last listener bound to it, is dropped.
+static NETWORKS: Lazy<Mutex<HashMap<String, Weak<NetworkInner>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Handle to a named simulated mem network.
+#[derive(Clone)]
+pub struct MemNetwork(Arc<NetworkInner>);
+
+impl std::fmt::Debug for MemNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemNetwork")
+            .field("name", &self.0.config.name)
+            .finish()
+    }
+}
+
+impl MemNetwork {
+    /// Get the network with this config's name, creating it if needed.
+    /// Errors if the network exists with a different seed or default link.
+    pub fn from_config(config: &MemNetworkConfig) -> TransportResult<Self> {
+        let mut networks = NETWORKS.lock().unwrap();
+        if let Some(network) = networks.get(&config.name).and_then(Weak::upgrade) {
+            // dropping the network may need the registry lock
+            drop(networks);
+            if &network.config != config {
+                return Err(format!(
+                    "mem network {} already exists with a different config",
+                    config.name
+                )
+                .into());
+            }
+            return Ok(Self(network));
+        }
+        let network = Self::create(config);
+        networks.insert(config.name.clone(), Arc::downgrade(&network.0));
+        Ok(network)
+    }
+
+    /// Get the network with this name, whatever its config,
+    /// or create it with perfect links and a zero seed.
+    pub fn get(name: &str) -> Self {
+        let mut networks = NETWORKS.lock().unwrap();
+        if let Some(network) = networks.get(name).and_then(Weak::upgrade) {
+            return Self(network);
+        }
+        let network = Self::create(&MemNetworkConfig {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        networks.insert(name.to_string(), Arc::downgrade(&network.0));
+        network
+    }
+
+    fn create(config: &MemNetworkConfig) -> Self {
+        Self(Arc::new(NetworkInner {
+            config: config.clone(),
+            state: Mutex::new(NetworkState {
+                default_link: config.default_link.clone(),
+                links: HashMap::new(),
+                partitions: HashMap::new(),
+                listeners: HashMap::new(),
+                link_rngs: HashMap::new(),
+            }),
+        }))
+    }
+
+    /// The name of this network.
+    pub fn name(&self) -> &str {
+        &self.0.config.name
+    }
+
+    /// Number a listener binding to this network.
+    pub(crate) fn add_listener(&self, url: url2::Url2) {
+        let mut state = self.0.state.lock().unwrap();
+        let index = state.listeners.len() as u64;
+        state.listeners.entry(url).or_insert(index);
+    }
+
+    /// Set the conditions for links without their own.
+    pub fn set_default_link(&self, conditions: LinkConditions) {
+        self.0.state.lock().unwrap().default_link = conditions;
+    }
+
+    /// Set the conditions for channels opened from one listener to another.
+    /// This only applies in that direction.
+    pub fn set_link(&self, from: url2::Url2, to: url2::Url2, conditions: LinkConditions) {
+        self.0
+            .state
+            .lock()
+            .unwrap()
+            .links
+            .insert((from, to), conditions);
+    }
+
+    /// Go back to the default conditions for this link.
+    pub fn clear_link(&self, from: &url2::Url2, to: &url2::Url2) {
+        self.0
+            .state
+            .lock()
+            .unwrap()
+            .links
+            .remove(&(from.clone(), to.clone()));
+    }
+
+    /// Cut these listeners off from everyone outside the partition.
+    /// Replaces any partition already using this name.
+    pub fn set_partition(&self, name: &str, members: impl IntoIterator<Item = url2::Url2>) {
+        self.0
+            .state
+            .lock()
+            .unwrap()
+            .partitions
+            .insert(name.to_string(), members.into_iter().collect());
+    }
+
+    /// Heal a partition, reconnecting its members to everyone else.
+    pub fn remove_partition(&self, name: &str) {
+        self.0.state.lock().unwrap().partitions.remove(name);
+    }
+
+    /// Open a simulated channel from one listener to another.
+    /// Returns the halves for the remote, or None if the channel was lost,
+    /// and the halves for the caller.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn create_channel(
+        &self,
+        from: &url2::Url2,
+        to: &url2::Url2,
+    ) -> TransportResult<(
+        Option<(TransportChannelWrite, TransportChannelRead)>,
+        (TransportChannelWrite, TransportChannelRead),
+    )> {
+        let (link, jitter_ms, lost) = {
+            let mut state = self.0.state.lock().unwrap();
+            if let Some((name, _)) = state
+                .partitions
+                .iter()
+                .find(|(_, members)| members.contains(from) != members.contains(to))
+            {
+                return Err(format!("{} is partitioned from {} by {}", from, to, name).into());
+            }
+            let link = state
+                .links
+                .get(&(from.clone(), to.clone()))
+                .unwrap_or(&state.default_link)
+                .clone();
+            let seed = self.0.config.seed;
+            let from_index = state.listeners.get(from).copied().unwrap_or(u64::MAX);
+            let to_index = state.listeners.get(to).copied().unwrap_or(u64::MAX);
+            let rng = state
+                .link_rngs
+                .entry((from_index, to_index))
+                .or_insert_with(|| link_rng(seed, from_index, to_index));
+            let jitter_ms = if link.jitter_ms > 0 {
+                rng.gen_range(0, link.jitter_ms + 1)
+            } else {
+                0
+            };
+            let lost = link.loss > 0.0 && rng.gen::<f64>() < link.loss;
+            (link, jitter_ms, lost)
+        };
+        let latency = std::time::Duration::from_millis(link.latency_ms + jitter_ms);
+
+        let (local_write, out_read) = futures::channel::mpsc::channel::<Vec<u8>>(10);
+        let (in_write, local_read) = futures::channel::mpsc::channel::<Vec<u8>>(10);
+        let local: (TransportChannelWrite, TransportChannelRead) = (
+            Box::new(local_write.sink_map_err(TransportError::other)),
+            Box::new(local_read),
+        );
+
+        if lost {
+            // swallow anything written, then close the caller's read half
+            crate::metrics::metric_task(async move {
+                out_read.for_each(|_| async {}).await;
+                tokio::time::delay_for(latency).await;
+                drop(in_write);
+                <Result<(), ()>>::Ok(())
+            });
+            return Ok((None, local));
+        }
+
+        let (remote_write, in_read) = futures::channel::mpsc::channel::<Vec<u8>>(10);
+        let (out_write, remote_read) = futures::channel::mpsc::channel::<Vec<u8>>(10);
+        let remote: (TransportChannelWrite, TransportChannelRead) = (
+            Box::new(remote_write.sink_map_err(TransportError::other)),
+            Box::new(remote_read),
+        );
+
+        relay(out_read, out_write, latency, link.bandwidth_bytes_per_sec);
+        relay(in_read, in_write, latency, link.bandwidth_bytes_per_sec);
+
+        Ok((Some(remote), local))
+    }
+}
+
+/// The rng for a link, seeded from the network seed and the
+/// order its listeners bound in.
+fn link_rng(seed: u64, from_index: u64, to_index: u64) -> rand::rngs::StdRng {
+    let mut link_seed = [0; 32];
+    link_seed[..8].copy_from_slice(&seed.to_le_bytes());
+    link_seed[8..16].copy_from_slice(&from_index.to_le_bytes());
+    link_seed[16..24].copy_from_slice(&to_index.to_le_bytes());
+    rand::rngs::StdRng::from_seed(link_seed)
+}
+
+/// Forward data from one half of a channel to the other,
+/// delaying the first chunk by the link latency and every chunk
+/// by the time it would take to send at the bandwidth cap.
+fn relay(
+    mut recv: futures::channel::mpsc::Receiver<Vec<u8>>,
+    mut send: futures::channel::mpsc::Sender<Vec<u8>>,
+    latency: std::time::Duration,
+    bandwidth_bytes_per_sec: Option<u64>,
+) {
+    crate::metrics::metric_task(async move {
+        let mut delay = latency;
+        while let Some(data) = recv.next().await {
+            if let Some(bps) = bandwidth_bytes_per_sec {
+                delay += std::time::Duration::from_millis(data.len() as u64 * 1000 / bps.max(1));
+            }
+            tokio::time::delay_for(delay).await;
+            delay = std::time::Duration::default();
+            if send.send(data).await.is_err() {
+                break;
+            }
+        }
+        <Result<(), ()>>::Ok(())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport_mem::*;
+    use ghost_actor::GhostControlSender;
+
+    fn echo_receiver(mut recv: TransportEventReceiver) {
+        crate::metrics::metric_task(async move {
+            while let Some(evt) = recv.next().await {
+                match evt {
+                    TransportEvent::IncomingChannel(_, mut write, read) => {
+                        let data = read.read_to_end().await;
+                        write.write_and_close(data).await?;
+                    }
+                }
+            }
+            TransportResult::Ok(())
+        });
+    }
+
+    #[tokio::test]
+    async fn mem_sim_latency_and_partitions() -> TransportResult<()> {
+        tokio::time::pause();
+        let net = MemNetwork::get("mem_sim_latency_and_partitions");
+        let (bind1, evt1) = spawn_bind_transport_mem_sim(net.clone()).await?;
+        echo_receiver(evt1);
+        let (bind2, evt2) = spawn_bind_transport_mem_sim(net.clone()).await?;
+        echo_receiver(evt2);
+
+        let url1 = bind1.bound_url().await?;
+        let url2 = bind2.bound_url().await?;
+
+        net.set_link(
+            url1.clone(),
+            url2.clone(),
+            LinkConditions {
+                latency_ms: 100,
+                ..Default::default()
+            },
+        );
+        let start = tokio::time::Instant::now();
+        let res = bind1.request(url2.clone(), b"test".to_vec()).await?;
+        assert_eq!(b"test".to_vec(), res);
+        // there and back again
+        assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+
+        net.set_partition("split", vec![url1.clone()]);
+        assert!(bind1.request(url2.clone(), b"test".to_vec()).await.is_err());
+        assert!(bind2.request(url1.clone(), b"test".to_vec()).await.is_err());
+
+        net.remove_partition("split");
+        net.clear_link(&url1, &url2);
+        let res = bind2.request(url1.clone(), b"test".to_vec()).await?;
+        assert_eq!(b"test".to_vec(), res);
+
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn mem_sim_loss() -> TransportResult<()> {
+        let net = MemNetwork::from_config(&MemNetworkConfig {
+            name: "mem_sim_loss".to_string(),
+            seed: 42,
+            default_link: LinkConditions {
+                loss: 1.0,
+                ..Default::default()
+            },
+        })?;
+        let (bind1, evt1) = spawn_bind_transport_mem_sim(net.clone()).await?;
+        echo_receiver(evt1);
+        let (bind2, evt2) = spawn_bind_transport_mem_sim(net.clone()).await?;
+        echo_receiver(evt2);
+
+        let url2 = bind2.bound_url().await?;
+        let res = bind1.request(url2, b"test".to_vec()).await?;
+        assert!(res.is_empty());
+
+        Ok(())
+    }
+
+    /// The round trip times of a few requests over a jittery link.
+    async fn jitter_round_trips(name: &str, seed: u64) -> TransportResult<Vec<u128>> {
+        let net = MemNetwork::from_config(&MemNetworkConfig {
+            name: name.to_string(),
+            seed,
+            default_link: LinkConditions {
+                jitter_ms: 1000,
+                ..Default::default()
+            },
+        })?;
+        let (bind1, evt1) = spawn_bind_transport_mem_sim(net.clone()).await?;
+        echo_receiver(evt1);
+        let (bind2, evt2) = spawn_bind_transport_mem_sim(net).await?;
+        echo_receiver(evt2);
+
+        let url2 = bind2.bound_url().await?;
+        let mut out = Vec::new();
+        for _ in 0..5 {
+            let start = tokio::time::Instant::now();
+            bind1.request(url2.clone(), b"test".to_vec()).await?;
+            out.push(start.elapsed().as_millis());
+        }
+        Ok(out)
+    }
+
+    #[tokio::test]
+    async fn mem_sim_seeded_jitter_is_repeatable() -> TransportResult<()> {
+        tokio::time::pause();
+        let first = jitter_round_trips("mem_sim_seeded_jitter_1", 7).await?;
+        let second = jitter_round_trips("mem_sim_seeded_jitter_2", 7).await?;
+        assert_eq!(first, second);
+        let other = jitter_round_trips("mem_sim_seeded_jitter_3", 8).await?;
+        assert_ne!(first, other);
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn mem_sim_registry() -> TransportResult<()> {
+        let config = MemNetworkConfig {
+            name: "mem_sim_registry".to_string(),
+            seed: 1,
+            ..Default::default()
+        };
+        let other_config = MemNetworkConfig {
+            seed: 2,
+            ..config.clone()
+        };
+
+        let net = MemNetwork::from_config(&config)?;
+        // the same config joins the network, a different one is refused
+        let (bind, _evt) = spawn_bind_transport_mem_sim(MemNetwork::from_config(&config)?).await?;
+        assert!(MemNetwork::from_config(&other_config).is_err());
+        // get joins whatever the config
+        drop(MemNetwork::get("mem_sim_registry"));
+
+        // the network lives on while a listener is bound to it
+        drop(net);
+        assert!(MemNetwork::from_config(&other_config).is_err());
+
+        // once all are gone the name is free again
+        bind.ghost_actor_shutdown_immediate().await?;
+        let mut freed = false;
+        for _ in 0..100 {
+            if MemNetwork::from_config(&other_config).is_ok() {
+                freed = true;
+                break;
+            }
+            tokio::time::delay_for(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(freed, "mem network was never removed");
+        assert!(!NETWORKS.lock().unwrap().contains_key("mem_sim_registry"));
+
+        Ok(())
+    }
+}
@@ -179,6 +179,20 @@ impl TransportListenerHandler for Inner {
         .into())
     }
 
+    fn handle_connected_urls(&mut self) -> TransportListenerHandlerResult<Vec<url2::Url2>> {
+        let urls = self
+            .sub_listeners
+            .values()
+            .map(|v| v.connected_urls())
+            .collect::<Vec<_>>();
+        Ok(async move {
+            let urls = futures::future::try_join_all(urls).await?;
+            Ok(urls.into_iter().flatten().collect())
+        }
+        .boxed()
+        .into())
+    }
+
     fn handle_create_channel(
         &mut self,
         url: url2::Url2,
@@ -135,6 +135,30 @@ fn call_create_entry(_: ()) -> ExternResult<HeaderHash> {
     }
 }
 
+/// Create a post only through a `call`
+#[hdk_extern]
+fn call_only_create_entry(_: ()) -> ExternResult<ZomeCallResponse> {
+    call(
+        None,
+        "create_entry".to_string().into(),
+        "create_entry".to_string().into(),
+        None,
+        &(),
+    )
+}
+
+/// Get a post through a `call`
+#[hdk_extern]
+fn call_get_entry(_: ()) -> ExternResult<ZomeCallResponse> {
+    call(
+        None,
+        "create_entry".to_string().into(),
+        "get_entry".to_string().into(),
+        None,
+        &(),
+    )
+}
+
 #[hdk_extern]
 fn call_create_entry_remotely(agent: AgentPubKey) -> ExternResult<HeaderHash> {
     let zome_call_response: ZomeCallResponse = call_remote(